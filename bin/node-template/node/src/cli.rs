@@ -24,18 +24,27 @@ pub enum Subcommand {
 	/// Export blocks.
 	ExportBlocks(sc_cli::ExportBlocksCmd),
 
-	/// Export the state of a given block into a chain spec.
+	/// Export the state of a given block into a chain spec or a state snapshot.
 	ExportState(sc_cli::ExportStateCmd),
 
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Import a state snapshot.
+	ImportState(sc_cli::ImportStateCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Write a consistent copy of the database.
+	BackupDb(sc_cli::BackupDbCmd),
+
+	/// Convert a RocksDB database into a ParityDB database.
+	ConvertDb(sc_cli::ConvertDbCmd),
+
 	/// Sub-commands concerned with benchmarking.
 	#[command(subcommand)]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
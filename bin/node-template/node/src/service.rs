@@ -7,7 +7,7 @@ use sc_consensus_grandpa::SharedVoterState;
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
 use sc_telemetry::{Telemetry, TelemetryWorker};
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_consensus_aura::sr25519::{AuthorityId as AuraId, AuthorityPair as AuraPair};
 use std::{sync::Arc, time::Duration};
 
 // Our native executor instance.
@@ -43,7 +43,7 @@ pub fn new_partial(
 		FullBackend,
 		FullSelectChain,
 		sc_consensus::DefaultImportQueue<Block, FullClient>,
-		sc_transaction_pool::FullPool<Block, FullClient>,
+		sc_transaction_pool::TransactionPoolWrapper<Block, FullClient>,
 		(
 			sc_consensus_grandpa::GrandpaBlockImport<
 				FullBackend,
@@ -74,7 +74,7 @@ pub fn new_partial(
 		sc_service::new_full_parts::<Block, RuntimeApi, _>(
 			config,
 			telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
-			executor,
+			executor.clone(),
 		)?;
 	let client = Arc::new(client);
 
@@ -83,9 +83,11 @@ pub fn new_partial(
 		telemetry
 	});
 
+	sc_service::spawn_runtime_precompiler(task_manager.spawn_handle(), client.clone(), executor);
+
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
-	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
+	let transaction_pool = sc_transaction_pool::TransactionPoolWrapper::new_full(
 		config.transaction_pool.clone(),
 		config.role.is_authority().into(),
 		config.prometheus_registry(),
@@ -228,6 +230,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 
 		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+		let client_clone = client.clone();
 
 		let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
 			StartAuraParams {
@@ -236,16 +239,24 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				select_chain,
 				block_import,
 				proposer_factory,
-				create_inherent_data_providers: move |_, ()| async move {
-					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-					let slot =
-						sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
-							*timestamp,
-							slot_duration,
-						);
-
-					Ok((slot, timestamp))
+				create_inherent_data_providers: move |parent, ()| {
+					let client_clone = client_clone.clone();
+					async move {
+						let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+						// the slot duration may have changed at the parent.
+						let slot_schedule = sc_consensus_aura::slot_schedule_at::<AuraId, _, _>(
+							&*client_clone,
+							parent,
+						)?;
+						let slot =
+							sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_schedule(
+								*timestamp,
+								&slot_schedule,
+							);
+
+						Ok((slot, timestamp))
+					}
 				},
 				force_authoring,
 				backoff_authoring_blocks,
@@ -256,6 +267,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				max_block_proposal_slot_portion: None,
 				telemetry: telemetry.as_ref().map(|x| x.handle()),
 				compatibility_mode: Default::default(),
+				slot_diagnostics: None,
 			},
 		)?;
 
@@ -60,7 +60,10 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 			ready: PoolLimit { count: 100_000, total_bytes: 100 * 1024 * 1024 },
 			future: PoolLimit { count: 100_000, total_bytes: 100 * 1024 * 1024 },
 			reject_future_transactions: false,
+			replacement_policy: Default::default(),
 			ban_time: Duration::from_secs(30 * 60),
+			persistence_path: None,
+			pool_type: Default::default(),
 		},
 		network: network_config,
 		keystore: KeystoreConfig::InMemory,
@@ -50,7 +50,7 @@ type FullGrandpaBlockImport =
 	grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
 
 /// The transaction pool type defintion.
-pub type TransactionPool = sc_transaction_pool::FullPool<Block, FullClient>;
+pub type TransactionPool = sc_transaction_pool::TransactionPoolWrapper<Block, FullClient>;
 
 /// Fetch the nonce of the given `account` from the chain state.
 ///
@@ -135,7 +135,7 @@ pub fn new_partial(
 		FullBackend,
 		FullSelectChain,
 		sc_consensus::DefaultImportQueue<Block, FullClient>,
-		sc_transaction_pool::FullPool<Block, FullClient>,
+		sc_transaction_pool::TransactionPoolWrapper<Block, FullClient>,
 		(
 			impl Fn(
 				node_rpc::DenyUnsafe,
@@ -146,7 +146,7 @@ pub fn new_partial(
 				grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
 				sc_consensus_babe::BabeLink<Block>,
 			),
-			grandpa::SharedVoterState,
+			(grandpa::SharedVoterState, sc_consensus_slots::SlotDiagnostics),
 			Option<Telemetry>,
 		),
 	>,
@@ -169,7 +169,7 @@ pub fn new_partial(
 		sc_service::new_full_parts::<Block, RuntimeApi, _>(
 			config,
 			telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
-			executor,
+			executor.clone(),
 		)?;
 	let client = Arc::new(client);
 
@@ -178,9 +178,11 @@ pub fn new_partial(
 		telemetry
 	});
 
+	sc_service::spawn_runtime_precompiler(task_manager.spawn_handle(), client.clone(), executor);
+
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
-	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
+	let transaction_pool = sc_transaction_pool::TransactionPoolWrapper::new_full(
 		config.transaction_pool.clone(),
 		config.role.is_authority().into(),
 		config.prometheus_registry(),
@@ -227,6 +229,11 @@ pub fn new_partial(
 
 	let import_setup = (block_import, grandpa_link, babe_link);
 
+	let slot_diagnostics = sc_consensus_slots::SlotDiagnostics::new(
+		sc_consensus_slots::DEFAULT_DIAGNOSTICS_CAPACITY,
+		config.prometheus_registry(),
+	)?;
+
 	let (rpc_extensions_builder, rpc_setup) = {
 		let (_, grandpa_link, _) = &import_setup;
 
@@ -245,6 +252,7 @@ pub fn new_partial(
 		let select_chain = select_chain.clone();
 		let keystore = keystore_container.keystore();
 		let chain_spec = config.chain_spec.cloned_box();
+		let rpc_slot_diagnostics = slot_diagnostics.clone();
 
 		let rpc_backend = backend.clone();
 		let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
@@ -257,6 +265,7 @@ pub fn new_partial(
 				babe: node_rpc::BabeDeps {
 					keystore: keystore.clone(),
 					babe_worker_handle: babe_worker_handle.clone(),
+					slot_diagnostics: rpc_slot_diagnostics.clone(),
 				},
 				grandpa: node_rpc::GrandpaDeps {
 					shared_voter_state: shared_voter_state.clone(),
@@ -270,7 +279,7 @@ pub fn new_partial(
 			node_rpc::create_full(deps, rpc_backend.clone()).map_err(Into::into)
 		};
 
-		(rpc_extensions_builder, shared_voter_state2)
+		(rpc_extensions_builder, (shared_voter_state2, slot_diagnostics))
 	};
 
 	Ok(sc_service::PartialComponents {
@@ -328,8 +337,9 @@ pub fn new_full_base(
 		other: (rpc_builder, import_setup, rpc_setup, mut telemetry),
 	} = new_partial(&config)?;
 
-	let shared_voter_state = rpc_setup;
+	let (shared_voter_state, slot_diagnostics) = rpc_setup;
 	let auth_disc_publish_non_global_ips = config.network.allow_non_globals_in_dht;
+	let auth_disc_persisted_cache_directory = config.network.net_config_path.clone();
 	let grandpa_protocol_name = grandpa::protocol_standard_name(
 		&client.block_hash(0).ok().flatten().expect("Genesis block exists; qed"),
 		&config.chain_spec,
@@ -455,6 +465,7 @@ pub fn new_full_base(
 			block_proposal_slot_portion: SlotProportion::new(0.5),
 			max_block_proposal_slot_portion: None,
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
+			slot_diagnostics: Some(slot_diagnostics),
 		};
 
 		let babe = sc_consensus_babe::start_babe(babe_config)?;
@@ -480,6 +491,7 @@ pub fn new_full_base(
 			sc_authority_discovery::new_worker_and_service_with_config(
 				sc_authority_discovery::WorkerConfig {
 					publish_non_global_ips: auth_disc_publish_non_global_ips,
+					persisted_cache_directory: auth_disc_persisted_cache_directory,
 					..Default::default()
 				},
 				client.clone(),
@@ -40,6 +40,7 @@ use sc_consensus_babe::BabeWorkerHandle;
 use sc_consensus_grandpa::{
 	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
 };
+use sc_consensus_slots::SlotDiagnostics;
 use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
@@ -56,6 +57,8 @@ pub struct BabeDeps {
 	pub babe_worker_handle: BabeWorkerHandle<Block>,
 	/// The keystore that manages the keys of the node.
 	pub keystore: KeystorePtr,
+	/// The outcomes of the slots handled by the BABE worker.
+	pub slot_diagnostics: SlotDiagnostics,
 }
 
 /// Extra dependencies for GRANDPA
@@ -118,6 +121,7 @@ where
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use sc_consensus_babe_rpc::{Babe, BabeApiServer};
 	use sc_consensus_grandpa_rpc::{Grandpa, GrandpaApiServer};
+	use sc_consensus_slots_rpc::{Slots, SlotsApiServer};
 	use sc_rpc::dev::{Dev, DevApiServer};
 	use sc_rpc_spec_v2::chain_spec::{ChainSpec, ChainSpecApiServer};
 	use sc_sync_state_rpc::{SyncState, SyncStateApiServer};
@@ -127,7 +131,7 @@ where
 	let mut io = RpcModule::new(());
 	let FullDeps { client, pool, select_chain, chain_spec, deny_unsafe, babe, grandpa } = deps;
 
-	let BabeDeps { keystore, babe_worker_handle } = babe;
+	let BabeDeps { keystore, babe_worker_handle, slot_diagnostics } = babe;
 	let GrandpaDeps {
 		shared_voter_state,
 		shared_authority_set,
@@ -151,6 +155,7 @@ where
 		Babe::new(client.clone(), babe_worker_handle.clone(), keystore, select_chain, deny_unsafe)
 			.into_rpc(),
 	)?;
+	io.merge(Slots::new(slot_diagnostics, deny_unsafe).into_rpc())?;
 	io.merge(
 		Grandpa::new(
 			subscription_executor,
@@ -73,6 +73,9 @@ pub enum Error {
 	#[error("Received authority record without a valid signature for the remote peer id.")]
 	MissingPeerIdSignature,
 
+	#[error("Failed to read or write the persisted address cache: {0}")]
+	PersistingAddrCache(#[from] std::io::Error),
+
 	#[error("Unable to fetch best block.")]
 	BestBlockFetchingError,
 }
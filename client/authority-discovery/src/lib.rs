@@ -33,7 +33,7 @@ pub use crate::{
 	worker::{AuthorityDiscovery, NetworkProvider, Role, Worker},
 };
 
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{collections::HashSet, path::PathBuf, sync::Arc, time::Duration};
 
 use futures::{
 	channel::{mpsc, oneshot},
@@ -84,6 +84,12 @@ pub struct WorkerConfig {
 	///
 	/// Defaults to `false` to provide compatibility with old versions
 	pub strict_record_validation: bool,
+
+	/// Directory in which the addresses of the discovered authorities are persisted, so that
+	/// they are known right away after a restart instead of after the first DHT lookups.
+	///
+	/// Defaults to `None`, in which case the addresses are only kept in memory.
+	pub persisted_cache_directory: Option<PathBuf>,
 }
 
 impl Default for WorkerConfig {
@@ -105,6 +111,7 @@ impl Default for WorkerConfig {
 			max_query_interval: Duration::from_secs(10 * 60),
 			publish_non_global_ips: true,
 			strict_record_validation: false,
+			persisted_cache_directory: None,
 		}
 	}
 }
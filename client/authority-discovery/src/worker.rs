@@ -25,6 +25,7 @@ use crate::{
 use std::{
 	collections::{HashMap, HashSet},
 	marker::PhantomData,
+	path::PathBuf,
 	sync::Arc,
 	time::Duration,
 };
@@ -39,7 +40,7 @@ use libp2p::{
 	multihash::{Multihash, MultihashDigest},
 	Multiaddr, PeerId,
 };
-use log::{debug, error, log_enabled};
+use log::{debug, error, info, log_enabled, warn};
 use prometheus_endpoint::{register, Counter, CounterVec, Gauge, Opts, U64};
 use prost::Message;
 use rand::{seq::SliceRandom, thread_rng};
@@ -57,6 +58,12 @@ use sp_keystore::{Keystore, KeystorePtr};
 use sp_runtime::traits::Block as BlockT;
 
 mod addr_cache;
+
+/// Name of the file, in [`WorkerConfig::persisted_cache_directory`], holding the address cache.
+const ADDR_CACHE_FILE_NAME: &str = "authority_discovery_addr_cache";
+
+/// Interval at which the address cache is written to disk, if it changed.
+const ADDR_CACHE_PERSIST_INTERVAL: Duration = Duration::from_secs(60);
 /// Dht payload schemas generated from Protobuf definitions via Prost crate in build.rs.
 mod schema {
 	#[cfg(test)]
@@ -142,6 +149,12 @@ pub struct Worker<Client, Network, Block, DhtEventStream> {
 	in_flight_lookups: HashMap<KademliaKey, AuthorityId>,
 
 	addr_cache: addr_cache::AddrCache,
+	/// File the address cache is persisted to, if any.
+	addr_cache_path: Option<PathBuf>,
+	/// Whether the address cache changed since it was last persisted.
+	addr_cache_changed: bool,
+	/// Interval at which the address cache is persisted.
+	persist_interval: ExpIncInterval,
 
 	metrics: Option<Metrics>,
 
@@ -213,7 +226,34 @@ where
 		let publish_if_changed_interval =
 			ExpIncInterval::new(config.keystore_refresh_interval, config.keystore_refresh_interval);
 
-		let addr_cache = AddrCache::new();
+		let persist_interval =
+			ExpIncInterval::new(ADDR_CACHE_PERSIST_INTERVAL, ADDR_CACHE_PERSIST_INTERVAL);
+
+		let addr_cache_path =
+			config.persisted_cache_directory.map(|dir| dir.join(ADDR_CACHE_FILE_NAME));
+		let addr_cache = match &addr_cache_path {
+			Some(path) => match AddrCache::load(path) {
+				Ok(addr_cache) => {
+					info!(
+						target: LOG_TARGET,
+						"Loaded the addresses of {} authorities from {}",
+						addr_cache.num_authority_ids(),
+						path.display(),
+					);
+					addr_cache
+				},
+				Err(e) => {
+					warn!(
+						target: LOG_TARGET,
+						"Failed to load the address cache from {}: {}",
+						path.display(),
+						e,
+					);
+					AddrCache::new()
+				},
+			},
+			None => AddrCache::new(),
+		};
 
 		let metrics = match prometheus_registry {
 			Some(registry) => match Metrics::register(&registry) {
@@ -226,6 +266,12 @@ where
 			None => None,
 		};
 
+		if let Some(metrics) = &metrics {
+			metrics
+				.known_authorities_count
+				.set(addr_cache.num_authority_ids().try_into().unwrap_or(std::u64::MAX));
+		}
+
 		Worker {
 			from_service: from_service.fuse(),
 			client,
@@ -240,6 +286,9 @@ where
 			pending_lookups: Vec::new(),
 			in_flight_lookups: HashMap::new(),
 			addr_cache,
+			addr_cache_path,
+			addr_cache_changed: false,
+			persist_interval,
 			role,
 			metrics,
 			phantom: PhantomData,
@@ -259,6 +308,7 @@ where
 					} else {
 						// This point is reached if the network has shut down, at which point there is not
 						// much else to do than to shut down the authority discovery as well.
+						self.persist_addr_cache();
 						return;
 					}
 				},
@@ -287,10 +337,32 @@ where
 						);
 					}
 				},
+				// Persist the addresses found so far.
+				_ = self.persist_interval.next().fuse() => {
+					self.persist_addr_cache();
+				},
 			}
 		}
 	}
 
+	/// Writes the address cache to disk if it changed since the last time.
+	fn persist_addr_cache(&mut self) {
+		let path = match &self.addr_cache_path {
+			Some(path) if self.addr_cache_changed => path,
+			_ => return,
+		};
+
+		match self.addr_cache.save(path) {
+			Ok(()) => self.addr_cache_changed = false,
+			Err(e) => warn!(
+				target: LOG_TARGET,
+				"Failed to persist the address cache to {}: {}",
+				path.display(),
+				e,
+			),
+		}
+	}
+
 	fn process_message_from_service(&self, msg: ServicetoWorkerMsg) {
 		match msg {
 			ServicetoWorkerMsg::GetAddressesByAuthorityId(authority, sender) => {
@@ -403,6 +475,7 @@ where
 			.collect::<Vec<_>>();
 
 		self.addr_cache.retain_ids(&authorities);
+		self.addr_cache_changed = true;
 
 		authorities.shuffle(&mut thread_rng());
 		self.pending_lookups = authorities;
@@ -578,6 +651,7 @@ where
 
 		if !remote_addresses.is_empty() {
 			self.addr_cache.insert(authority_id, remote_addresses);
+			self.addr_cache_changed = true;
 			if let Some(metrics) = &self.metrics {
 				metrics
 					.known_authorities_count
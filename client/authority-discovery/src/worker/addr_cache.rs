@@ -16,12 +16,38 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::error::Result;
+
+use codec::{Decode, Encode};
 use libp2p::{
 	core::multiaddr::{Multiaddr, Protocol},
 	PeerId,
 };
+use sc_utils::persistence::{unix_time, write_atomically};
 use sp_authority_discovery::AuthorityId;
-use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::{
+	collections::{hash_map::Entry, HashMap, HashSet},
+	fs,
+	io::ErrorKind,
+	path::Path,
+	time::Duration,
+};
+
+/// Addresses that haven't been refreshed for this long are not loaded back from disk.
+///
+/// Authorities republish their addresses at least every hour, and the DHT forgets records after
+/// 36 hours, so anything older is unlikely to still be valid.
+const PERSISTED_ADDRESSES_EXPIRY: Duration = Duration::from_secs(36 * 60 * 60);
+
+/// Entry of the cache as written to disk.
+#[derive(Encode, Decode)]
+struct PersistedAuthority {
+	authority_id: AuthorityId,
+	/// Binary representation of the addresses.
+	addresses: Vec<Vec<u8>>,
+	/// Seconds since the UNIX epoch at which the addresses were found on the DHT.
+	last_updated: u64,
+}
 
 /// Cache for [`AuthorityId`] -> [`HashSet<Multiaddr>`] and [`PeerId`] -> [`HashSet<AuthorityId>`]
 /// mappings.
@@ -35,6 +61,8 @@ pub(super) struct AddrCache {
 	/// it's not expected that a single `AuthorityId` can have multiple `PeerId`s.
 	authority_id_to_addresses: HashMap<AuthorityId, HashSet<Multiaddr>>,
 	peer_id_to_authority_ids: HashMap<PeerId, HashSet<AuthorityId>>,
+	/// Seconds since the UNIX epoch at which the addresses of each authority were last updated.
+	last_updated: HashMap<AuthorityId, u64>,
 }
 
 impl AddrCache {
@@ -42,12 +70,62 @@ impl AddrCache {
 		AddrCache {
 			authority_id_to_addresses: HashMap::new(),
 			peer_id_to_authority_ids: HashMap::new(),
+			last_updated: HashMap::new(),
+		}
+	}
+
+	/// Loads the cache written by [`AddrCache::save`], skipping the authorities whose addresses
+	/// have expired. Returns an empty cache if the file doesn't exist.
+	pub fn load(path: &Path) -> Result<Self> {
+		let mut cache = AddrCache::new();
+
+		let encoded = match fs::read(path) {
+			Ok(encoded) => encoded,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(cache),
+			Err(e) => return Err(e.into()),
+		};
+
+		let oldest = unix_time().saturating_sub(PERSISTED_ADDRESSES_EXPIRY.as_secs());
+		for entry in Vec::<PersistedAuthority>::decode(&mut &encoded[..])? {
+			if entry.last_updated < oldest {
+				continue
+			}
+
+			let addresses = entry
+				.addresses
+				.into_iter()
+				.filter_map(|address| Multiaddr::try_from(address).ok())
+				.collect();
+			cache.insert_at(entry.authority_id, addresses, entry.last_updated);
 		}
+
+		Ok(cache)
+	}
+
+	/// Writes the cache to `path`, replacing the previous content of the file.
+	pub fn save(&self, path: &Path) -> Result<()> {
+		let entries = self
+			.authority_id_to_addresses
+			.iter()
+			.map(|(authority_id, addresses)| PersistedAuthority {
+				authority_id: authority_id.clone(),
+				addresses: addresses.iter().map(|address| address.to_vec()).collect(),
+				last_updated: self.last_updated.get(authority_id).copied().unwrap_or_default(),
+			})
+			.collect::<Vec<_>>();
+
+		write_atomically(path, &entries.encode())?;
+
+		Ok(())
 	}
 
 	/// Inserts the given [`AuthorityId`] and [`Vec<Multiaddr>`] pair for future lookups by
 	/// [`AuthorityId`] or [`PeerId`].
 	pub fn insert(&mut self, authority_id: AuthorityId, addresses: Vec<Multiaddr>) {
+		self.insert_at(authority_id, addresses, unix_time())
+	}
+
+	fn insert_at(&mut self, authority_id: AuthorityId, addresses: Vec<Multiaddr>, timestamp: u64) {
 		let addresses = addresses.into_iter().collect::<HashSet<_>>();
 		let peer_ids = addresses_to_peer_ids(&addresses);
 
@@ -74,6 +152,7 @@ impl AddrCache {
 			"Found addresses for authority {authority_id:?}: {addresses:?}",
 		);
 
+		self.last_updated.insert(authority_id.clone(), timestamp);
 		let old_addresses = self.authority_id_to_addresses.insert(authority_id.clone(), addresses);
 		let old_peer_ids = addresses_to_peer_ids(&old_addresses.unwrap_or_default());
 
@@ -143,6 +222,8 @@ impl AddrCache {
 			.collect::<Vec<AuthorityId>>();
 
 		for authority_id_to_remove in authority_ids_to_remove {
+			self.last_updated.remove(&authority_id_to_remove);
+
 			// Remove other entries from `self.authority_id_to_addresses`.
 			let addresses = if let Some(addresses) =
 				self.authority_id_to_addresses.remove(&authority_id_to_remove)
@@ -385,4 +466,35 @@ mod tests {
 			addr_cache.get_addresses_by_authority_id(&authority_id1).unwrap()
 		);
 	}
+
+	#[test]
+	fn persisted_cache_is_reloaded_without_expired_entries() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("addr_cache");
+
+		let peer_id = PeerId::random();
+		let addr = Multiaddr::empty().with(Protocol::P2p(peer_id.into()));
+		let fresh = AuthorityPair::generate().0.public();
+		let expired = AuthorityPair::generate().0.public();
+
+		let mut addr_cache = AddrCache::load(&path).unwrap();
+		assert_eq!(0, addr_cache.num_authority_ids());
+
+		addr_cache.insert(fresh.clone(), vec![addr.clone()]);
+		let too_old = unix_time() - PERSISTED_ADDRESSES_EXPIRY.as_secs() - 1;
+		addr_cache.insert_at(expired.clone(), vec![addr.clone()], too_old);
+		addr_cache.save(&path).unwrap();
+
+		let addr_cache = AddrCache::load(&path).unwrap();
+		assert_eq!(1, addr_cache.num_authority_ids());
+		assert_eq!(
+			&HashSet::from([addr]),
+			addr_cache.get_addresses_by_authority_id(&fresh).unwrap()
+		);
+		assert!(addr_cache.get_addresses_by_authority_id(&expired).is_none());
+		assert_eq!(
+			Some(&HashSet::from([fresh])),
+			addr_cache.get_authority_ids_by_peer_id(&peer_id)
+		);
+	}
 }
@@ -0,0 +1,82 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::Backend;
+use sp_runtime::traits::Block as BlockT;
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+
+/// The `backup-db` command used to write a consistent copy of the database.
+///
+/// The node must not be running, as the database is opened by this command. Running nodes can be
+/// backed up through the unsafe `db_backup` RPC method instead.
+#[derive(Debug, Clone, Parser)]
+pub struct BackupDbCmd {
+	/// Directory to write the copy of the database to. It must not exist yet.
+	#[arg(value_name = "PATH")]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl BackupDbCmd {
+	/// Run the backup-db command
+	pub fn run<B, BA>(&self, backend: Arc<BA>) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: Backend<B>,
+	{
+		if self.output.exists() {
+			return Err(error::Error::Input(format!("{:?} already exists", self.output)))
+		}
+
+		backend.checkpoint(&self.output)?;
+		println!("Database written to {:?}.", self.output);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for BackupDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
@@ -0,0 +1,102 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	arg_enums::Database,
+	error,
+	params::{PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_service::DatabaseSource;
+use sp_runtime::traits::Block as BlockT;
+
+/// The `convert-db` command used to convert a RocksDB database into a ParityDB database.
+///
+/// The RocksDB database of the chain is left untouched and the ParityDB database is written to
+/// the location used by `--database paritydb`. The node must not be running.
+#[derive(Debug, Clone, Parser)]
+pub struct ConvertDbCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ConvertDbCmd {
+	/// Run the convert-db command
+	pub fn run<B: BlockT>(&self, database_config: DatabaseSource) -> error::Result<()> {
+		let DatabaseSource::Auto { rocksdb_path, paritydb_path, cache_size } = database_config
+		else {
+			return Err(error::Error::Input("Cannot convert custom database implementation".into()))
+		};
+
+		#[cfg(feature = "rocksdb")]
+		{
+			log::info!("Converting {:?} into {:?}", rocksdb_path, paritydb_path);
+			let summary = sc_client_db::convert_rocksdb_to_paritydb::<B>(
+				&rocksdb_path,
+				&paritydb_path,
+				cache_size,
+				|progress| {
+					log::info!(
+						"Copied {} entries ({} MiB), column {}",
+						progress.entries,
+						progress.bytes / 1024 / 1024,
+						progress.column,
+					)
+				},
+			)?;
+
+			match summary.verified_state {
+				Some((root, nodes)) =>
+					log::info!("Verified {} trie nodes of the finalized state {:?}", nodes, root),
+				None => log::info!("No finalized state to verify"),
+			}
+			println!(
+				"{:?} converted into {:?}. Start the node with `--database paritydb` or remove {:?} \
+				to use the new database.",
+				rocksdb_path, paritydb_path, rocksdb_path,
+			);
+			Ok(())
+		}
+
+		#[cfg(not(feature = "rocksdb"))]
+		{
+			let _ = (rocksdb_path, paritydb_path, cache_size);
+			Err(error::Error::Input("RocksDB support is not enabled".into()))
+		}
+	}
+}
+
+impl CliConfiguration for ConvertDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database(&self) -> error::Result<Option<Database>> {
+		Ok(Some(Database::Auto))
+	}
+}
@@ -23,18 +23,31 @@ use crate::{
 };
 use clap::Parser;
 use log::info;
-use sc_client_api::{HeaderBackend, StorageProvider, UsageProvider};
+use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider, StorageProvider, UsageProvider};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{fmt::Debug, io::Write, str::FromStr, sync::Arc};
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
 
 /// The `export-state` command used to export the state of a given block into
-/// a chain spec.
+/// a chain spec or a state snapshot.
 #[derive(Debug, Clone, Parser)]
 pub struct ExportStateCmd {
 	/// Block hash or number.
 	#[arg(value_name = "HASH or NUMBER")]
 	pub input: Option<BlockNumberOrHash>,
 
+	/// Write a verifiable state snapshot to the given file instead of a chain spec.
+	///
+	/// The snapshot can be loaded into a fresh database with `import-state`.
+	#[arg(long, value_name = "PATH")]
+	pub snapshot: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub shared_params: SharedParams,
@@ -57,18 +70,30 @@ impl ExportStateCmd {
 	) -> error::Result<()>
 	where
 		B: BlockT,
-		C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
+		C: UsageProvider<B>
+			+ StorageProvider<B, BA>
+			+ HeaderBackend<B>
+			+ BlockBackend<B>
+			+ ProofProvider<B>,
 		BA: sc_client_api::backend::Backend<B>,
 		B::Hash: FromStr,
 		<B::Hash as FromStr>::Err: Debug,
 		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
 	{
-		info!("Exporting raw state...");
 		let block_id = self.input.as_ref().map(|b| b.parse()).transpose()?;
 		let hash = match block_id {
 			Some(id) => client.expect_block_hash_from_id(&id)?,
 			None => client.usage_info().chain.best_hash,
 		};
+
+		if let Some(path) = &self.snapshot {
+			info!("Exporting state snapshot to {}...", path.display());
+			let file = io::BufWriter::new(fs::File::create(path)?);
+			sc_service::chain_ops::export_state_snapshot(client, hash, file)?;
+			return Ok(())
+		}
+
+		info!("Exporting raw state...");
 		let raw_state = sc_service::chain_ops::export_raw_state(client, hash)?;
 		input_spec.set_storage(raw_state);
 
@@ -20,7 +20,8 @@ use crate::{arg_enums::SyncMode, params::node_key_params::NodeKeyParams};
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		BandwidthLimit, NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode,
+		ProtocolBandwidthLimit, SetConfig, TransportConfig,
 	},
 	multiaddr::Protocol,
 };
@@ -28,7 +29,7 @@ use sc_service::{
 	config::{Multiaddr, MultiaddrWithPeerId},
 	ChainSpec, ChainType,
 };
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, num::NonZeroU64, path::PathBuf};
 
 /// Parameters used to create the network configuration.
 #[derive(Debug, Clone, Args)]
@@ -131,6 +132,13 @@ pub struct NetworkParams {
 	#[arg(long)]
 	pub ipfs_server: bool,
 
+	/// Persist peer reputations and the addresses of reachable peers across restarts.
+	///
+	/// They are stored in the network directory of the chain's base path. Restored peers are
+	/// dialed at startup besides the bootnodes, and peers banned before a restart stay banned.
+	#[arg(long)]
+	pub persist_peers: bool,
+
 	/// Blockchain syncing mode.
 	#[arg(
 		long,
@@ -148,6 +156,57 @@ pub struct NetworkParams {
 	/// and observe block requests timing out.
 	#[arg(long, value_name = "COUNT", default_value_t = 64)]
 	pub max_blocks_per_request: u32,
+
+	/// Maximum number of bytes per second received over all connections.
+	///
+	/// This includes requests and responses, which time out if they can't get through in time.
+	#[arg(long, value_name = "BYTES")]
+	pub max_inbound_bandwidth: Option<NonZeroU64>,
+
+	/// Maximum number of bytes per second sent over all connections.
+	///
+	/// This includes requests and responses, which time out if they can't get through in time.
+	#[arg(long, value_name = "BYTES")]
+	pub max_outbound_bandwidth: Option<NonZeroU64>,
+
+	/// Limit the throughput of notification or request-response protocols.
+	///
+	/// Takes `<PROTOCOL>=<INBOUND>:<OUTBOUND>` in bytes per second, `0` meaning unlimited. The
+	/// limit applies to the protocol with the full name `<PROTOCOL>`, and is shared by all peers.
+	/// For example, `/<genesis hash>/sync/2=0:1048576` serves block requests at no more than
+	/// 1 MiB/s.
+	///
+	/// Only received notifications are limited, the outbound limit of notification protocols is
+	/// ignored.
+	///
+	/// Requests and responses waiting for bandwidth can time out, so limits on request-response
+	/// protocols should leave room for a full response within the request timeout.
+	#[arg(long, value_name = "LIMIT", value_parser = parse_protocol_bandwidth_limit)]
+	pub protocol_bandwidth_limit: Vec<ProtocolBandwidthLimit>,
+}
+
+/// Parses a `<PROTOCOL>=<INBOUND>:<OUTBOUND>` protocol bandwidth limit.
+fn parse_protocol_bandwidth_limit(s: &str) -> Result<ProtocolBandwidthLimit, String> {
+	let (protocol, limits) = s
+		.rsplit_once('=')
+		.ok_or_else(|| format!("Expected `<PROTOCOL>=<INBOUND>:<OUTBOUND>`, got `{}`", s))?;
+	let (inbound, outbound) = limits
+		.split_once(':')
+		.ok_or_else(|| format!("Expected `<INBOUND>:<OUTBOUND>`, got `{}`", limits))?;
+	let parse = |rate: &str| {
+		rate.parse::<u64>()
+			.map(NonZeroU64::new)
+			.map_err(|e| format!("Invalid bandwidth `{}`: {}", rate, e))
+	};
+
+	if protocol.is_empty() {
+		return Err("Protocol name can't be empty".into())
+	}
+
+	Ok(ProtocolBandwidthLimit {
+		protocol: protocol.to_string().into(),
+		limit: BandwidthLimit { inbound: parse(inbound)?, outbound: parse(outbound)? },
+	})
 }
 
 impl NetworkParams {
@@ -243,7 +302,13 @@ impl NetworkParams {
 			kademlia_disjoint_query_paths: self.kademlia_disjoint_query_paths,
 			yamux_window_size: None,
 			ipfs_server: self.ipfs_server,
+			persist_peers: self.persist_peers,
 			sync_mode: self.sync.into(),
+			bandwidth_limit: BandwidthLimit {
+				inbound: self.max_inbound_bandwidth,
+				outbound: self.max_outbound_bandwidth,
+			},
+			protocol_bandwidth_limits: self.protocol_bandwidth_limit.clone(),
 		}
 	}
 }
@@ -298,4 +363,33 @@ mod tests {
 
 		assert_eq!(SyncMode::Warp, params.network_params.sync);
 	}
+
+	#[test]
+	fn protocol_bandwidth_limits_are_parsed() {
+		let params = Cli::try_parse_from([
+			"",
+			"--protocol-bandwidth-limit",
+			"/cafe/sync/2=0:1048576",
+			"--protocol-bandwidth-limit",
+			"/cafe/state/2=4096:0",
+		])
+		.expect("Parses network params");
+
+		assert_eq!(
+			params.network_params.protocol_bandwidth_limit,
+			vec![
+				ProtocolBandwidthLimit {
+					protocol: "/cafe/sync/2".into(),
+					limit: BandwidthLimit { inbound: None, outbound: NonZeroU64::new(1048576) },
+				},
+				ProtocolBandwidthLimit {
+					protocol: "/cafe/state/2".into(),
+					limit: BandwidthLimit { inbound: NonZeroU64::new(4096), outbound: None },
+				},
+			],
+		);
+
+		assert!(Cli::try_parse_from(["", "--protocol-bandwidth-limit", "/sync/2=1024"]).is_err());
+		assert!(Cli::try_parse_from(["", "--protocol-bandwidth-limit", "=1:1"]).is_err());
+	}
 }
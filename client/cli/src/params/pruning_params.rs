@@ -46,6 +46,11 @@ pub struct PruningParams {
 	///
 	///    Keep the state of the last number of finalized blocks.
 	///
+	///  - 'snapshots:number:period'
+	///
+	///    Keep the state of the last number of finalized blocks, and the state of every
+	///    finalized block whose number is a multiple of period. Requires ParityDB.
+	///
 	/// [default: 256]
 	#[arg(alias = "pruning", long, value_name = "PRUNING_MODE")]
 	pub state_pruning: Option<DatabasePruningMode>,
@@ -84,6 +89,12 @@ impl PruningParams {
 
 	/// Get the block pruning value from the parameters
 	pub fn blocks_pruning(&self) -> error::Result<BlocksPruning> {
+		if let DatabasePruningMode::PeriodicSnapshots { .. } = self.blocks_pruning {
+			return Err(error::Error::Input(
+				"Periodic snapshots are only supported by `--state-pruning`".into(),
+			))
+		}
+
 		Ok(self.blocks_pruning.into())
 	}
 }
@@ -101,6 +112,14 @@ pub enum DatabasePruningMode {
 	ArchiveCanonical,
 	/// Keep the data of the last number of finalized blocks.
 	Custom(u32),
+	/// Keep the data of the last `window` finalized blocks and of every finalized block whose
+	/// number is a multiple of `period`.
+	PeriodicSnapshots {
+		/// Number of the last finalized blocks to keep.
+		window: u32,
+		/// Number of blocks between two kept blocks.
+		period: u32,
+	},
 }
 
 impl std::str::FromStr for DatabasePruningMode {
@@ -110,6 +129,17 @@ impl std::str::FromStr for DatabasePruningMode {
 		match input {
 			"archive" => Ok(Self::Archive),
 			"archive-canonical" => Ok(Self::ArchiveCanonical),
+			snapshots if snapshots.starts_with("snapshots:") => {
+				let invalid = || "Invalid periodic snapshots pruning mode specified".to_string();
+				let (window, period) =
+					snapshots["snapshots:".len()..].split_once(':').ok_or_else(invalid)?;
+				let window = window.parse().map_err(|_| invalid())?;
+				let period = period.parse().map_err(|_| invalid())?;
+				if period == 0 {
+					return Err("The snapshot period must be greater than zero".to_string())
+				}
+				Ok(Self::PeriodicSnapshots { window, period })
+			},
 			bc => bc
 				.parse()
 				.map_err(|_| "Invalid pruning mode specified".to_string())
@@ -124,6 +154,8 @@ impl Into<PruningMode> for DatabasePruningMode {
 			DatabasePruningMode::Archive => PruningMode::ArchiveAll,
 			DatabasePruningMode::ArchiveCanonical => PruningMode::ArchiveCanonical,
 			DatabasePruningMode::Custom(n) => PruningMode::blocks_pruning(n),
+			DatabasePruningMode::PeriodicSnapshots { window, period } =>
+				PruningMode::periodic_snapshots(window, period),
 		}
 	}
 }
@@ -133,7 +165,8 @@ impl Into<BlocksPruning> for DatabasePruningMode {
 		match self {
 			DatabasePruningMode::Archive => BlocksPruning::KeepAll,
 			DatabasePruningMode::ArchiveCanonical => BlocksPruning::KeepFinalized,
-			DatabasePruningMode::Custom(n) => BlocksPruning::Some(n),
+			DatabasePruningMode::Custom(n) |
+			DatabasePruningMode::PeriodicSnapshots { window: n, .. } => BlocksPruning::Some(n),
 		}
 	}
 }
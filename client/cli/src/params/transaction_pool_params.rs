@@ -16,8 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::{arg_enums::TransactionPoolType, config::DEFAULT_TXPOOL_PERSISTENCE_PATH};
 use clap::Args;
 use sc_service::config::TransactionPoolOptions;
+use sp_runtime::Percent;
+use std::path::PathBuf;
 
 /// Parameters used to create the pool configuration.
 #[derive(Debug, Clone, Args)]
@@ -33,6 +36,28 @@ pub struct TransactionPoolParams {
 	/// How long a transaction is banned for, if it is considered invalid. Defaults to 1800s.
 	#[arg(long, value_name = "SECONDS")]
 	pub tx_ban_seconds: Option<u64>,
+
+	/// Minimum priority increase, in percent, a transaction needs to replace the transactions
+	/// providing the same tags, e.g. a transaction of the same sender with the same nonce.
+	#[arg(long, value_name = "PERCENT", default_value_t = 10, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub pool_replacement_bump: u8,
+
+	/// Maximum number of times the transactions of a sender with a given nonce can be replaced.
+	///
+	/// The count is only reset once the nonce got included in a block.
+	#[arg(long, value_name = "COUNT", default_value_t = 10)]
+	pub pool_max_replacements: usize,
+
+	/// Persist the transaction pool across restarts.
+	///
+	/// Pending transactions are periodically written to the chain's config directory and
+	/// revalidated against the best block on startup.
+	#[arg(long)]
+	pub pool_persist: bool,
+
+	/// The transaction pool implementation to use.
+	#[arg(long, value_name = "TYPE", value_enum, ignore_case = true, default_value_t = TransactionPoolType::SingleState)]
+	pub pool_type: TransactionPoolType,
 }
 
 impl TransactionPoolParams {
@@ -57,6 +82,16 @@ impl TransactionPoolParams {
 			std::time::Duration::from_secs(30 * 60)
 		};
 
+		opts.replacement_policy.min_priority_bump =
+			Percent::from_percent(self.pool_replacement_bump);
+		opts.replacement_policy.max_replacements = Some(self.pool_max_replacements);
+
+		opts.pool_type = self.pool_type.into();
+
+		if self.pool_persist {
+			opts.persistence_path = Some(PathBuf::from(DEFAULT_TXPOOL_PERSISTENCE_PATH));
+		}
+
 		opts
 	}
 }
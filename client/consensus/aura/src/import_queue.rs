@@ -19,8 +19,9 @@
 //! Module implementing the logic for verifying and importing AuRa blocks.
 
 use crate::{
-	authorities, standalone::SealVerificationError, AuthorityId, CompatibilityMode, Error,
-	LOG_TARGET,
+	authorities,
+	standalone::{runtime_slot_schedule_at, SealVerificationError},
+	AuthorityId, CompatibilityMode, Error, LOG_TARGET,
 };
 use codec::{Codec, Decode, Encode};
 use log::{debug, info, trace};
@@ -44,6 +45,7 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	DigestItem,
 };
+use sp_timestamp::TimestampInherentData;
 use std::{fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc};
 
 /// check a header has been signed by the right key. If the slot is too far in the future, an error
@@ -212,7 +214,17 @@ where
 			.await
 			.map_err(Error::<B>::Inherent)?;
 
-		let slot_now = create_inherent_data_providers.slot();
+		// if the runtime supports slot duration changes, check the slot against the slot schedule
+		// of the parent rather than the slot duration the inherent data providers assume.
+		let slot_schedule =
+			runtime_slot_schedule_at::<AuthorityId<P>, _, _>(self.client.as_ref(), parent_hash)
+				.map_err(|e| {
+					format!("Could not fetch slot schedule at {:?}: {}", parent_hash, e)
+				})?;
+		let slot_now = match (slot_schedule, inherent_data.timestamp_inherent_data()) {
+			(Some(schedule), Ok(Some(timestamp))) => schedule.slot_at(timestamp),
+			_ => create_inherent_data_providers.slot(),
+		};
 
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
@@ -419,3 +431,212 @@ pub fn build_verifier<P, C, CIDP, N>(
 		compatibility_mode,
 	)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+	use sc_consensus::BlockImportParams;
+	use sp_api::{ApiError, ApiRef, Core, RuntimeApiInfo};
+	use sp_consensus::BlockOrigin;
+	use sp_consensus_aura::{
+		digests::CompatibleDigestItem,
+		inherents::InherentDataProvider as AuraInherentDataProvider,
+		sr25519::{AuthorityId, AuthorityPair, AuthoritySignature},
+		SlotDuration, SlotSchedule,
+	};
+	use sp_keyring::sr25519::Keyring;
+	use sp_runtime::{traits::Header as _, Digest};
+	use sp_timestamp::{InherentDataProvider as TimestampInherentDataProvider, Timestamp};
+	use std::collections::HashMap;
+	use substrate_test_runtime_client::runtime::{Block, Hash, Header};
+
+	// The slot duration the inherent data providers assume.
+	const GENESIS_SLOT_DURATION: u64 = 1_000;
+	// The slot duration enacted at `ANCHOR_SLOT`.
+	const CHANGED_SLOT_DURATION: u64 = 6_000;
+	const ANCHOR_SLOT: u64 = 1_000_000_000;
+	const ANCHOR_TIMESTAMP: u64 = ANCHOR_SLOT * GENESIS_SLOT_DURATION;
+
+	#[derive(Default)]
+	struct TestClient {
+		aux: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+	}
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = RuntimeApi;
+
+		fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+			RuntimeApi.into()
+		}
+	}
+
+	impl AuxStore for TestClient {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(
+			&self,
+			insert: I,
+			delete: D,
+		) -> sp_blockchain::Result<()> {
+			let mut aux = self.aux.lock();
+			for (key, value) in insert {
+				aux.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				aux.remove(*key);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.aux.lock().get(key).cloned())
+		}
+	}
+
+	// The runtime API of a runtime at version 2 of the Aura API.
+	//
+	// `sp_api::mock_impl_runtime_apis!` always reports the base version of an API, while the
+	// verifier only reads the slot schedule from runtimes that support slot duration changes.
+	struct RuntimeApi;
+
+	impl ApiExt<Block> for RuntimeApi {
+		type StateBackend = sp_api::InMemoryBackend<sp_api::HashFor<Block>>;
+
+		fn execute_in_transaction<F: FnOnce(&Self) -> sp_api::TransactionOutcome<R>, R>(
+			&self,
+			call: F,
+		) -> R {
+			call(self).into_inner()
+		}
+
+		fn has_api<A: RuntimeApiInfo + ?Sized>(&self, at: Hash) -> Result<bool, ApiError> {
+			self.api_version::<A>(at).map(|version| version.is_some())
+		}
+
+		fn has_api_with<A: RuntimeApiInfo + ?Sized, P: Fn(u32) -> bool>(
+			&self,
+			at: Hash,
+			pred: P,
+		) -> Result<bool, ApiError> {
+			self.api_version::<A>(at).map(|version| version.map_or(false, pred))
+		}
+
+		fn api_version<A: RuntimeApiInfo + ?Sized>(
+			&self,
+			_: Hash,
+		) -> Result<Option<u32>, ApiError> {
+			if A::ID == <dyn AuraApi<Block, AuthorityId>>::ID {
+				Ok(Some(2))
+			} else {
+				Ok(Some(A::VERSION))
+			}
+		}
+
+		fn record_proof(&mut self) {
+			unimplemented!()
+		}
+
+		fn extract_proof(&mut self) -> Option<sp_api::StorageProof> {
+			unimplemented!()
+		}
+
+		fn proof_recorder(&self) -> Option<sp_api::ProofRecorder<Block>> {
+			unimplemented!()
+		}
+
+		fn into_storage_changes(
+			&self,
+			_: &Self::StateBackend,
+			_: Hash,
+		) -> Result<sp_api::StorageChanges<Self::StateBackend, Block>, String> {
+			unimplemented!()
+		}
+	}
+
+	impl RuntimeApi {
+		fn call(&self, function: &str) -> Result<Vec<u8>, ApiError> {
+			Ok(match function {
+				"AuraApi_authorities" => vec![AuthorityId::from(Keyring::Alice.public())].encode(),
+				"AuraApi_slot_schedule" => SlotSchedule {
+					duration: SlotDuration::from_millis(CHANGED_SLOT_DURATION),
+					anchor_slot: ANCHOR_SLOT.into(),
+					anchor_timestamp: ANCHOR_TIMESTAMP,
+				}
+				.encode(),
+				_ => unimplemented!("`{}` is not called when verifying a header", function),
+			})
+		}
+	}
+
+	macro_rules! impl_runtime_api {
+		($( $api:path ),*) => {
+			$(
+				impl $api for RuntimeApi {
+					fn __runtime_api_internal_call_api_at(
+						&self,
+						_: Hash,
+						_: ExecutionContext,
+						_: Vec<u8>,
+						fn_name: &dyn Fn(sp_api::RuntimeVersion) -> &'static str,
+					) -> Result<Vec<u8>, ApiError> {
+						self.call(fn_name(Default::default()))
+					}
+				}
+			)*
+		};
+	}
+
+	impl_runtime_api!(Core<Block>, BlockBuilderApi<Block>, AuraApi<Block, AuthorityId>);
+
+	// A child of genesis authored by Alice in `slot`.
+	fn block(slot: u64) -> BlockImportParams<Block, ()> {
+		let pre_digest =
+			<DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_pre_digest(slot.into());
+		let mut header = Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Digest { logs: vec![pre_digest] },
+		);
+		let signature = Keyring::Alice.pair().sign(header.hash().as_ref());
+		header.digest_mut().push(
+			<DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_seal(signature.into()),
+		);
+
+		BlockImportParams::new(BlockOrigin::NetworkBroadcast, header)
+	}
+
+	#[tokio::test]
+	async fn slots_are_checked_against_changed_schedule() {
+		// five slots of the changed schedule after the anchor.
+		let now = Timestamp::new(ANCHOR_TIMESTAMP + 5 * CHANGED_SLOT_DURATION);
+		let mut verifier = build_verifier::<AuthorityPair, _, _, _>(BuildVerifierParams {
+			client: Arc::new(TestClient::default()),
+			create_inherent_data_providers: move |_: Hash, _| async move {
+				let slot = AuraInherentDataProvider::from_timestamp_and_slot_duration(
+					now,
+					SlotDuration::from_millis(GENESIS_SLOT_DURATION),
+				);
+				Ok((slot, TimestampInherentDataProvider::new(now)))
+			},
+			check_for_equivocation: CheckForEquivocation::No,
+			telemetry: None,
+			compatibility_mode: CompatibilityMode::None,
+		});
+
+		// the current slot of the changed schedule, with one slot of drift, is accepted.
+		assert!(verifier.verify(block(ANCHOR_SLOT + 5)).await.is_ok());
+		assert!(verifier.verify(block(ANCHOR_SLOT + 6)).await.is_ok());
+
+		// later slots are in the future, even though they are far behind the slot the inherent
+		// data providers derive from the genesis slot duration.
+		let err = verifier.verify(block(ANCHOR_SLOT + 7)).await.err().unwrap();
+		assert!(err.contains("too far in the future"), "{}", err);
+	}
+}
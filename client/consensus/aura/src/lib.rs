@@ -40,7 +40,7 @@ use sc_client_api::{backend::AuxStore, BlockOf};
 use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction};
 use sc_consensus_slots::{
 	BackoffAuthoringBlocksStrategy, InherentDataProviderExt, SimpleSlotWorkerToSlotWorker,
-	SlotInfo, StorageChanges,
+	SlotDiagnostics, SlotDurationProvider, SlotInfo, StorageChanges,
 };
 use sc_telemetry::TelemetryHandle;
 use sp_api::{Core, ProvideRuntimeApi};
@@ -56,7 +56,7 @@ use sp_runtime::traits::{Block as BlockT, Header, Member, NumberFor};
 mod import_queue;
 pub mod standalone;
 
-pub use crate::standalone::{find_pre_digest, slot_duration};
+pub use crate::standalone::{find_pre_digest, slot_duration, slot_schedule_at};
 pub use import_queue::{
 	build_verifier, import_queue, AuraVerifier, BuildVerifierParams, CheckForEquivocation,
 	ImportQueueParams,
@@ -66,7 +66,7 @@ pub use sp_consensus::SyncOracle;
 pub use sp_consensus_aura::{
 	digests::CompatibleDigestItem,
 	inherents::{InherentDataProvider, InherentType as AuraInherent, INHERENT_IDENTIFIER},
-	AuraApi, ConsensusLog, SlotDuration, AURA_ENGINE_ID,
+	AuraApi, ConsensusLog, SlotDuration, SlotSchedule, AURA_ENGINE_ID,
 };
 
 const LOG_TARGET: &str = "aura";
@@ -113,6 +113,9 @@ impl<N> Default for CompatibilityMode<N> {
 /// Parameters of [`start_aura`].
 pub struct StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, N> {
 	/// The duration of a slot.
+	///
+	/// If the runtime supports slot duration changes, the duration is read from the chain head
+	/// at every slot instead.
 	pub slot_duration: SlotDuration,
 	/// The client to interact with the chain.
 	pub client: Arc<C>,
@@ -149,6 +152,8 @@ pub struct StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, N> {
 	///
 	/// If in doubt, use `Default::default()`.
 	pub compatibility_mode: CompatibilityMode<N>,
+	/// Records the outcome of every slot, if given.
+	pub slot_diagnostics: Option<SlotDiagnostics>,
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
@@ -169,6 +174,7 @@ pub fn start_aura<P, B, C, SC, I, PF, SO, L, CIDP, BS, Error>(
 		max_block_proposal_slot_portion,
 		telemetry,
 		compatibility_mode,
+		slot_diagnostics,
 	}: StartAuraParams<C, SC, I, PF, SO, L, CIDP, BS, NumberFor<B>>,
 ) -> Result<impl Future<Output = ()>, ConsensusError>
 where
@@ -189,6 +195,12 @@ where
 	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 	Error: std::error::Error + Send + From<ConsensusError> + 'static,
 {
+	let slot_duration = AuraSlotDuration {
+		client: client.clone(),
+		initial: slot_duration,
+		_key_type: PhantomData::<P>,
+	};
+
 	let worker = build_aura_worker::<P, _, _, _, _, _, _, _, _>(BuildAuraWorkerParams {
 		client,
 		block_import,
@@ -202,6 +214,7 @@ where
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		compatibility_mode,
+		slot_diagnostics,
 	});
 
 	Ok(sc_consensus_slots::start_slot_worker(
@@ -213,6 +226,42 @@ where
 	))
 }
 
+/// Reads the slot duration for the slot worker from the runtime at the chain head.
+struct AuraSlotDuration<C, P> {
+	client: Arc<C>,
+	initial: SlotDuration,
+	_key_type: PhantomData<P>,
+}
+
+impl<B, C, P> SlotDurationProvider<B> for AuraSlotDuration<C, P>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + AuxStore + Send + Sync,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	P: Pair + Send + Sync,
+	P::Public: Codec,
+{
+	fn initial_slot_duration(&self) -> SlotDuration {
+		self.initial
+	}
+
+	fn slot_duration(&self, chain_head: &B::Header) -> SlotDuration {
+		match slot_schedule_at::<AuthorityId<P>, _, _>(&*self.client, chain_head.hash()) {
+			Ok(schedule) => schedule.duration,
+			Err(err) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Unable to fetch the slot schedule at {:?}, using the initial slot duration: {}",
+					chain_head.hash(),
+					err,
+				);
+
+				self.initial
+			},
+		}
+	}
+}
+
 /// Parameters of [`build_aura_worker`].
 pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N> {
 	/// The client to interact with the chain.
@@ -246,6 +295,8 @@ pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N> {
 	///
 	/// If in doubt, use `Default::default()`.
 	pub compatibility_mode: CompatibilityMode<N>,
+	/// Records the outcome of every slot, if given.
+	pub slot_diagnostics: Option<SlotDiagnostics>,
 }
 
 /// Build the aura worker.
@@ -265,6 +316,7 @@ pub fn build_aura_worker<P, B, C, PF, I, SO, L, BS, Error>(
 		telemetry,
 		force_authoring,
 		compatibility_mode,
+		slot_diagnostics,
 	}: BuildAuraWorkerParams<C, I, PF, SO, L, BS, NumberFor<B>>,
 ) -> impl sc_consensus_slots::SimpleSlotWorker<
 	B,
@@ -303,6 +355,7 @@ where
 		block_proposal_slot_portion,
 		max_block_proposal_slot_portion,
 		compatibility_mode,
+		slot_diagnostics,
 		_key_type: PhantomData::<P>,
 	}
 }
@@ -320,6 +373,7 @@ struct AuraWorker<C, E, I, P, SO, L, BS, N> {
 	max_block_proposal_slot_portion: Option<SlotProportion>,
 	telemetry: Option<TelemetryHandle>,
 	compatibility_mode: CompatibilityMode<N>,
+	slot_diagnostics: Option<SlotDiagnostics>,
 	_key_type: PhantomData<P>,
 }
 
@@ -447,6 +501,10 @@ where
 		self.telemetry.clone()
 	}
 
+	fn slot_diagnostics(&self) -> Option<SlotDiagnostics> {
+		self.slot_diagnostics.clone()
+	}
+
 	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> std::time::Duration {
 		let parent_slot = find_pre_digest::<B, P::Signature>(&slot_info.chain_head).ok();
 
@@ -555,7 +613,10 @@ mod tests {
 	use sc_block_builder::BlockBuilderProvider;
 	use sc_client_api::BlockchainEvents;
 	use sc_consensus::BoxJustificationImport;
-	use sc_consensus_slots::{BackoffAuthoringOnFinalizedHeadLagging, SimpleSlotWorker};
+	use sc_consensus_slots::{
+		BackoffAuthoringOnFinalizedHeadLagging, SimpleSlotWorker, SlotOutcome,
+		DEFAULT_DIAGNOSTICS_CAPACITY,
+	};
 	use sc_keystore::LocalKeystore;
 	use sc_network_test::{Block as TestBlock, *};
 	use sp_application_crypto::{key_types::AURA, AppCrypto};
@@ -757,6 +818,7 @@ mod tests {
 					max_block_proposal_slot_portion: None,
 					telemetry: None,
 					compatibility_mode: CompatibilityMode::None,
+					slot_diagnostics: None,
 				})
 				.expect("Starts aura"),
 			);
@@ -810,6 +872,7 @@ mod tests {
 			block_proposal_slot_portion: SlotProportion::new(0.5),
 			max_block_proposal_slot_portion: None,
 			compatibility_mode: Default::default(),
+			slot_diagnostics: None,
 		};
 
 		let head = Header::new(
@@ -845,6 +908,7 @@ mod tests {
 		let peer = net.peer(3);
 		let client = peer.client().as_client();
 		let environ = DummyFactory(client.clone());
+		let diagnostics = SlotDiagnostics::new(DEFAULT_DIAGNOSTICS_CAPACITY, None).unwrap();
 
 		let mut worker = AuraWorker {
 			client: client.clone(),
@@ -860,6 +924,7 @@ mod tests {
 			block_proposal_slot_portion: SlotProportion::new(0.5),
 			max_block_proposal_slot_portion: None,
 			compatibility_mode: Default::default(),
+			slot_diagnostics: Some(diagnostics.clone()),
 		};
 
 		let head = client.expect_header(client.info().genesis_hash).unwrap();
@@ -878,5 +943,13 @@ mod tests {
 
 		// The returned block should be imported and we should be able to get its header by now.
 		assert!(client.header(res.block.hash()).unwrap().is_some());
+
+		let records = diagnostics.last(1);
+		assert_eq!(records[0].slot, Slot::from(0));
+		assert_eq!(
+			records[0].outcome,
+			SlotOutcome::Authored { hash: res.block.hash().as_bytes().to_vec().into() }
+		);
+		assert!(records[0].proposal_time_ms.is_some());
 	}
 }
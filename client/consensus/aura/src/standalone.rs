@@ -25,7 +25,7 @@ use log::trace;
 use codec::Codec;
 
 use sc_client_api::{backend::AuxStore, UsageProvider};
-use sp_api::{Core, ProvideRuntimeApi};
+use sp_api::{ApiExt, Core, ProvideRuntimeApi};
 use sp_application_crypto::{AppCrypto, AppPublic};
 use sp_blockchain::Result as CResult;
 use sp_consensus::Error as ConsensusError;
@@ -40,10 +40,14 @@ use sp_runtime::{
 pub use sc_consensus_slots::check_equivocation;
 
 use super::{
-	AuraApi, AuthorityId, CompatibilityMode, CompatibleDigestItem, SlotDuration, LOG_TARGET,
+	AuraApi, AuthorityId, CompatibilityMode, CompatibleDigestItem, SlotDuration, SlotSchedule,
+	LOG_TARGET,
 };
 
 /// Get the slot duration for Aura by reading from a runtime API at the best block's state.
+///
+/// If the slot duration changed since genesis, this is the current duration. Use
+/// [`slot_schedule_at`] to map timestamps to slots.
 pub fn slot_duration<A, B, C>(client: &C) -> CResult<SlotDuration>
 where
 	A: Codec,
@@ -65,6 +69,46 @@ where
 	client.runtime_api().slot_duration(block_hash).map_err(|err| err.into())
 }
 
+/// Get the slot schedule for the children of a given block by reading from a runtime API at its
+/// state.
+///
+/// For runtimes that don't support slot duration changes, this is a schedule with the slot duration
+/// of the runtime.
+pub fn slot_schedule_at<A, B, C>(client: &C, block_hash: B::Hash) -> CResult<SlotSchedule>
+where
+	A: Codec,
+	B: BlockT,
+	C: AuxStore + ProvideRuntimeApi<B>,
+	C::Api: AuraApi<B, A>,
+{
+	match runtime_slot_schedule_at(client, block_hash)? {
+		Some(schedule) => Ok(schedule),
+		None => slot_duration_at(client, block_hash).map(SlotSchedule::new),
+	}
+}
+
+/// Get the slot schedule for the children of a given block, if the runtime supports slot duration
+/// changes.
+pub(crate) fn runtime_slot_schedule_at<A, B, C>(
+	client: &C,
+	block_hash: B::Hash,
+) -> CResult<Option<SlotSchedule>>
+where
+	A: Codec,
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: AuraApi<B, A>,
+{
+	let runtime_api = client.runtime_api();
+	let version = runtime_api.api_version::<dyn AuraApi<B, A>>(block_hash)?;
+
+	if version.map_or(false, |version| version >= 2) {
+		runtime_api.slot_schedule(block_hash).map(Some).map_err(|err| err.into())
+	} else {
+		Ok(None)
+	}
+}
+
 /// Get the slot author for given block along with authorities.
 pub fn slot_author<P: Pair>(slot: Slot, authorities: &[AuthorityId<P>]) -> Option<&AuthorityId<P>> {
 	if authorities.is_empty() {
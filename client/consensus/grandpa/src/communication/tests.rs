@@ -653,6 +653,98 @@ fn peer_with_higher_view_leads_to_catch_up_request() {
 	futures::executor::block_on(test);
 }
 
+#[test]
+fn commit_is_gossiped_over_simulated_network() {
+	use sc_network::NetworkStateInfo;
+	use sc_network_test::simulator::{LinkConfig, SimulatedNetwork, SimulatedNode};
+	use std::time::Duration;
+
+	let private = [Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie];
+	let public = make_ids(&private[..]);
+	let voter_set = Arc::new(VoterSet::new(public.iter().cloned()).unwrap());
+	let set_id = 1;
+
+	let commit = |round, target_number| {
+		let target_hash: Hash = [target_number as u8; 32].into();
+		let precommit = finality_grandpa::Precommit { target_hash, target_number };
+		let payload = sp_consensus_grandpa::localized_payload(
+			round,
+			set_id,
+			&finality_grandpa::Message::Precommit(precommit.clone()),
+		);
+		let precommits = private
+			.iter()
+			.zip(&public)
+			.map(|(key, (id, _))| finality_grandpa::SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: key.sign(&payload[..]).into(),
+				id: id.clone(),
+			})
+			.collect();
+
+		finality_grandpa::voter::CommunicationOut::Commit(
+			round,
+			finality_grandpa::Commit { target_hash, target_number, precommits },
+		)
+	};
+
+	let network = SimulatedNetwork::new(0);
+	network
+		.set_default_link(LinkConfig { latency: Duration::from_millis(100), ..Default::default() });
+	let nodes = [(); 2]
+		.map(|_| network.add_node(ObservedRole::Authority, [grandpa_protocol_name::NAME.into()]));
+	let mut bridges = nodes.clone().map(|node| {
+		super::NetworkBridge::<Block, SimulatedNode, SimulatedNode>::new(
+			node.clone(),
+			node,
+			config(),
+			voter_set_state(),
+			None,
+			None,
+		)
+	});
+	let (_, mut commits_out) =
+		bridges[0].global_communication(SetId(set_id), voter_set.clone(), true);
+	let (commits_in, _) = bridges[1].global_communication(SetId(set_id), voter_set, false);
+	let mut commits_in = Box::pin(commits_in);
+	network.connect(nodes[0].local_peer_id(), nodes[1].local_peer_id());
+
+	// Advances the virtual clock and returns the target numbers of the commits received since.
+	let mut advance = |duration| {
+		network.advance(duration);
+		let mut received = Vec::new();
+		futures::executor::block_on(future::poll_fn(|cx| {
+			for _ in 0..2 {
+				for bridge in &mut bridges {
+					assert!(bridge.poll_unpin(cx).is_pending());
+				}
+				while let Poll::Ready(Some(message)) = commits_in.poll_next_unpin(cx) {
+					if let finality_grandpa::voter::CommunicationIn::Commit(_, commit, _) = message
+					{
+						received.push(commit.target_number);
+					}
+				}
+			}
+			Poll::Ready(())
+		}));
+		received
+	};
+
+	// Exchange the neighbor packets, so that the nodes know which commits the other accepts.
+	assert!(advance(Duration::ZERO).is_empty());
+	assert!(advance(Duration::from_millis(100)).is_empty());
+
+	// The commit is received once the latency of the link has elapsed.
+	futures::executor::block_on(commits_out.send(commit(1, 100))).unwrap();
+	assert!(advance(Duration::from_millis(50)).is_empty());
+	assert_eq!(advance(Duration::from_millis(50)), vec![100]);
+
+	// Commits don't cross a partition.
+	network.partition(&[&[nodes[0].local_peer_id()], &[nodes[1].local_peer_id()]]);
+	futures::executor::block_on(commits_out.send(commit(2, 200))).unwrap();
+	assert!(advance(Duration::from_secs(1)).is_empty());
+}
+
 fn local_chain_spec() -> Box<dyn sc_chain_spec::ChainSpec> {
 	use sc_chain_spec::{ChainSpec, GenericChainSpec};
 	use serde::{Deserialize, Serialize};
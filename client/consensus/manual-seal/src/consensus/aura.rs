@@ -27,19 +27,22 @@ use sp_blockchain::{HeaderBackend, HeaderMetadata};
 use sp_consensus_aura::{
 	digests::CompatibleDigestItem,
 	sr25519::{AuthorityId, AuthoritySignature},
-	AuraApi, Slot, SlotDuration,
+	AuraApi,
 };
 use sp_inherents::InherentData;
-use sp_runtime::{traits::Block as BlockT, Digest, DigestItem};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as _},
+	Digest, DigestItem,
+};
 use sp_timestamp::TimestampInherentData;
 use std::{marker::PhantomData, sync::Arc};
 
 /// Consensus data provider for Aura.
 pub struct AuraConsensusDataProvider<B, C, P> {
-	// slot duration
-	slot_duration: SlotDuration,
+	// the client, to read the slot schedule of the parent block
+	client: Arc<C>,
 	// phantom data for required generics
-	_phantom: PhantomData<(B, C, P)>,
+	_phantom: PhantomData<(B, P)>,
 }
 
 impl<B, C, P> AuraConsensusDataProvider<B, C, P>
@@ -51,10 +54,7 @@ where
 	/// Creates a new instance of the [`AuraConsensusDataProvider`], requires that `client`
 	/// implements [`sp_consensus_aura::AuraApi`]
 	pub fn new(client: Arc<C>) -> Self {
-		let slot_duration = sc_consensus_aura::slot_duration(&*client)
-			.expect("slot_duration is always present; qed.");
-
-		Self { slot_duration, _phantom: PhantomData }
+		Self { client, _phantom: PhantomData }
 	}
 }
 
@@ -72,18 +72,16 @@ where
 	type Transaction = TransactionFor<C, B>;
 	type Proof = P;
 
-	fn create_digest(
-		&self,
-		_parent: &B::Header,
-		inherents: &InherentData,
-	) -> Result<Digest, Error> {
+	fn create_digest(&self, parent: &B::Header, inherents: &InherentData) -> Result<Digest, Error> {
 		let timestamp =
 			inherents.timestamp_inherent_data()?.expect("Timestamp is always present; qed");
 
 		// we always calculate the new slot number based on the current time-stamp and the slot
-		// duration.
+		// schedule of the parent, which accounts for any slot duration change.
+		let slot_schedule =
+			sc_consensus_aura::slot_schedule_at::<AuthorityId, _, _>(&*self.client, parent.hash())?;
 		let digest_item = <DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_pre_digest(
-			Slot::from_timestamp(timestamp, self.slot_duration),
+			slot_schedule.slot_at(timestamp),
 		);
 
 		Ok(Digest { logs: vec![digest_item] })
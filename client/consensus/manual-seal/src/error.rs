@@ -38,6 +38,7 @@ mod codes {
 	pub const CONSENSUS_ERROR: i32 = 14_000;
 	pub const INHERENTS_ERROR: i32 = 15_000;
 	pub const BLOCKCHAIN_ERROR: i32 = 16_000;
+	pub const TIMESTAMP_IN_PAST: i32 = 17_000;
 	pub const UNKNOWN_ERROR: i32 = 20_000;
 }
 
@@ -64,6 +65,14 @@ pub enum Error {
 	/// Supplied parent_hash doesn't exist in chain
 	#[error("Supplied parent_hash: {0} doesn't exist in chain")]
 	BlockNotFound(String),
+	/// Requested timestamp is earlier than the timestamp of the best block
+	#[error("Timestamp {timestamp} is earlier than the best block's timestamp {best}")]
+	TimestampInPast {
+		/// the requested timestamp.
+		timestamp: u64,
+		/// timestamp of the best block.
+		best: u64,
+	},
 	/// Some string error
 	#[error("{0}")]
 	StringError(String),
@@ -100,6 +109,7 @@ impl Error {
 			ConsensusError(_) => codes::CONSENSUS_ERROR,
 			InherentError(_) => codes::INHERENTS_ERROR,
 			BlockchainError(_) => codes::BLOCKCHAIN_ERROR,
+			TimestampInPast { .. } => codes::TIMESTAMP_IN_PAST,
 			SendError(_) | Canceled(_) => codes::SERVER_SHUTTING_DOWN,
 			_ => codes::UNKNOWN_ERROR,
 		}
@@ -34,7 +34,9 @@ use std::{marker::PhantomData, sync::Arc};
 
 mod error;
 mod finalize_block;
+mod revert_block;
 mod seal_block;
+mod time_travel;
 
 pub mod consensus;
 pub mod rpc;
@@ -43,10 +45,12 @@ pub use self::{
 	consensus::ConsensusDataProvider,
 	error::Error,
 	finalize_block::{finalize_block, FinalizeBlockParams},
+	revert_block::{revert_block, RevertBlockParams},
 	rpc::{CreatedBlock, EngineCommand},
 	seal_block::{seal_block, SealBlockParams, MAX_PROPOSAL_DURATION},
+	time_travel::TimeTravel,
 };
-use sc_transaction_pool_api::TransactionPool;
+use sc_transaction_pool_api::MaintainedTransactionPool;
 use sp_api::{ProvideRuntimeApi, TransactionFor};
 
 const LOG_TARGET: &str = "manual-seal";
@@ -83,7 +87,7 @@ where
 }
 
 /// Params required to start the instant sealing authorship task.
-pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, TP, SC, CS, CIDP, P> {
+pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, CB, TP, SC, CS, CIDP, P> {
 	/// Block import instance for well. importing blocks.
 	pub block_import: BI,
 
@@ -93,6 +97,9 @@ pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, TP, SC, C
 	/// Client instance
 	pub client: Arc<C>,
 
+	/// Client backend, required to revert blocks with `EngineCommand::RevertTo`.
+	pub backend: Option<Arc<CB>>,
+
 	/// Shared reference to the transaction pool.
 	pub pool: Arc<TP>,
 
@@ -142,12 +149,13 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 		mut block_import,
 		mut env,
 		client,
+		backend,
 		pool,
 		mut commands_stream,
 		select_chain,
 		consensus_data_provider,
 		create_inherent_data_providers,
-	}: ManualSealParams<B, BI, E, C, TP, SC, CS, CIDP, P>,
+	}: ManualSealParams<B, BI, E, C, CB, TP, SC, CS, CIDP, P>,
 ) where
 	B: BlockT + 'static,
 	BI: BlockImport<B, Error = sp_consensus::Error, Transaction = sp_api::TransactionFor<C, B>>
@@ -161,10 +169,12 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 	CS: Stream<Item = EngineCommand<<B as BlockT>::Hash>> + Unpin + 'static,
 	SC: SelectChain<B> + 'static,
 	TransactionFor<C, B>: 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: Send + Sync + 'static,
 {
+	let mut time_travel = TimeTravel::default();
+
 	while let Some(command) = commands_stream.next().await {
 		match command {
 			EngineCommand::SealNewBlock { create_empty, finalize, parent_hash, sender } => {
@@ -180,8 +190,10 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 					pool: pool.clone(),
 					client: client.clone(),
 					create_inherent_data_providers: &create_inherent_data_providers,
+					time_travel: &mut time_travel,
 				})
 				.await;
+				time_travel.finalized(client.info().finalized_number);
 			},
 			EngineCommand::FinalizeBlock { hash, sender, justification } => {
 				let justification = justification.map(|j| (MANUAL_SEAL_ENGINE_ID, j));
@@ -192,8 +204,28 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 					finalizer: client.clone(),
 					_phantom: PhantomData,
 				})
-				.await
+				.await;
+				time_travel.finalized(client.info().finalized_number);
+			},
+			EngineCommand::SetTimestamp { timestamp, mut sender } => {
+				let best = client.info().best_hash;
+				rpc::send_result(&mut sender, time_travel.set_timestamp(timestamp, &best))
+			},
+			EngineCommand::AdvanceTimestamp { by, mut sender } => {
+				time_travel.advance(by);
+				rpc::send_result(&mut sender, Ok(()))
 			},
+			EngineCommand::RevertTo { hash, sender } =>
+				revert_block(RevertBlockParams {
+					hash,
+					sender,
+					client: client.clone(),
+					backend: backend.clone(),
+					pool: pool.clone(),
+					time_travel: &mut time_travel,
+					_phantom: PhantomData,
+				})
+				.await,
 		}
 	}
 }
@@ -223,7 +255,7 @@ pub async fn run_instant_seal<B, BI, CB, E, C, TP, SC, CIDP, P>(
 	E::Proposer: Proposer<B, Proof = P, Transaction = TransactionFor<C, B>>,
 	SC: SelectChain<B> + 'static,
 	TransactionFor<C, B>: 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: Send + Sync + 'static,
 {
@@ -240,6 +272,7 @@ pub async fn run_instant_seal<B, BI, CB, E, C, TP, SC, CIDP, P>(
 		block_import,
 		env,
 		client,
+		backend: None,
 		pool,
 		commands_stream,
 		select_chain,
@@ -277,7 +310,7 @@ pub async fn run_instant_seal_and_finalize<B, BI, CB, E, C, TP, SC, CIDP, P>(
 	E::Proposer: Proposer<B, Proof = P, Transaction = TransactionFor<C, B>>,
 	SC: SelectChain<B> + 'static,
 	TransactionFor<C, B>: 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: Send + Sync + 'static,
 {
@@ -294,6 +327,7 @@ pub async fn run_instant_seal_and_finalize<B, BI, CB, E, C, TP, SC, CIDP, P>(
 		block_import,
 		env,
 		client,
+		backend: None,
 		pool,
 		commands_stream,
 		select_chain,
@@ -306,14 +340,18 @@ pub async fn run_instant_seal_and_finalize<B, BI, CB, E, C, TP, SC, CIDP, P>(
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use codec::{Decode, Encode};
+	use rpc::ManualSealApiServer;
 	use sc_basic_authorship::ProposerFactory;
 	use sc_consensus::ImportedAux;
 	use sc_transaction_pool::{BasicPool, FullChainApi, Options, RevalidationType};
 	use sc_transaction_pool_api::{MaintainedTransactionPool, TransactionPool, TransactionSource};
 	use sp_inherents::InherentData;
 	use sp_runtime::generic::{BlockId, Digest, DigestItem};
+	use sp_timestamp::TimestampInherentData;
 	use substrate_test_runtime_client::{
-		AccountKeyring::*, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+		runtime::Block, AccountKeyring::*, DefaultTestClientBuilderExt, TestClient,
+		TestClientBuilder, TestClientBuilderExt,
 	};
 	use substrate_test_runtime_transaction_pool::{uxt, TestApi};
 
@@ -354,6 +392,32 @@ mod tests {
 		}
 	}
 
+	// records the timestamp inherent of every block in its digest.
+	struct TimestampDigestProvider;
+	impl ConsensusDataProvider<Block> for TimestampDigestProvider {
+		type Transaction = TransactionFor<TestClient, Block>;
+		type Proof = ();
+
+		fn create_digest(
+			&self,
+			_parent: &<Block as BlockT>::Header,
+			inherents: &InherentData,
+		) -> Result<Digest, Error> {
+			let timestamp = inherents.timestamp_inherent_data()?.expect("provided by the test");
+			Ok(Digest { logs: vec![DigestItem::Other(timestamp.encode())] })
+		}
+
+		fn append_block_import(
+			&self,
+			_parent: &<Block as BlockT>::Header,
+			_params: &mut BlockImportParams<Block, Self::Transaction>,
+			_inherents: &InherentData,
+			_proof: Self::Proof,
+		) -> Result<(), Error> {
+			Ok(())
+		}
+	}
+
 	#[tokio::test]
 	async fn instant_seal() {
 		let builder = TestClientBuilder::new();
@@ -393,6 +457,7 @@ mod tests {
 			block_import: client.clone(),
 			env,
 			client: client.clone(),
+			backend: None,
 			pool: pool.clone(),
 			commands_stream,
 			select_chain,
@@ -454,6 +519,7 @@ mod tests {
 			block_import: client.clone(),
 			env,
 			client: client.clone(),
+			backend: None,
 			pool: pool.clone(),
 			commands_stream,
 			select_chain,
@@ -539,6 +605,7 @@ mod tests {
 			block_import: client.clone(),
 			env,
 			client: client.clone(),
+			backend: None,
 			pool: pool.clone(),
 			commands_stream,
 			select_chain,
@@ -644,6 +711,7 @@ mod tests {
 			block_import: client.clone(),
 			env,
 			client: client.clone(),
+			backend: None,
 			pool: pool.clone(),
 			commands_stream,
 			select_chain,
@@ -670,4 +738,88 @@ mod tests {
 		let header = client.header(created_block.hash).unwrap().unwrap();
 		assert_eq!(header.number, 1);
 	}
+
+	#[tokio::test]
+	async fn time_travel_batches_and_revert() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let genesis_hash = client.info().genesis_hash;
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(),
+			true.into(),
+			api(),
+			None,
+			RevalidationType::Full,
+			spawner.clone(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		));
+		let env = ProposerFactory::new(spawner.clone(), client.clone(), pool.clone(), None, None);
+
+		let (sink, commands_stream) = futures::channel::mpsc::channel(1024);
+		let future = run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env,
+			client: client.clone(),
+			backend: Some(backend),
+			pool: pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(TimestampDigestProvider)),
+			// the provided timestamp never moves, only time travel changes it.
+			create_inherent_data_providers: |_, _| async {
+				Ok(sp_timestamp::InherentDataProvider::new(1_000.into()))
+			},
+		});
+		std::thread::spawn(|| {
+			let rt = tokio::runtime::Runtime::new().unwrap();
+			rt.block_on(future);
+		});
+		let rpc = rpc::ManualSeal::new(sink);
+		let timestamp_of = |hash| {
+			let header = client.header(hash).unwrap().unwrap();
+			match header.digest.logs() {
+				[DigestItem::Other(timestamp)] => u64::decode(&mut &timestamp[..]).unwrap(),
+				logs => panic!("unexpected digest: {:?}", logs),
+			}
+		};
+
+		let first = rpc.create_blocks(2, false).await.unwrap();
+		assert_eq!(first.len(), 2);
+		assert_eq!(timestamp_of(first[1]), 1_000);
+
+		assert!(rpc.set_timestamp(50_000).await.unwrap());
+		assert!(rpc.advance_timestamp(1_000).await.unwrap());
+		let second = rpc.create_blocks(3, true).await.unwrap();
+		assert_eq!(timestamp_of(second[0]), 51_000);
+		assert_eq!(timestamp_of(second[2]), 51_000);
+		assert_eq!(client.info().best_number, 5);
+		assert_eq!(client.info().finalized_hash, second[2]);
+
+		// the best block's timestamp is the lower bound for pinned timestamps.
+		assert!(rpc.set_timestamp(50_999).await.is_err());
+
+		// finalized blocks are reverted as well.
+		assert!(rpc.revert_to(first[0]).await.unwrap());
+		assert_eq!(client.info().best_hash, first[0]);
+		assert_eq!(client.info().best_number, 1);
+
+		// unknown blocks can't be reverted to.
+		assert!(rpc.revert_to(Default::default()).await.is_err());
+
+		// older blocks can be given their timestamps again once reverted to.
+		assert!(rpc.set_timestamp(1_000).await.unwrap());
+		assert!(rpc.advance_timestamp(50_000).await.unwrap());
+
+		let third = rpc.create_blocks(1, false).await.unwrap();
+		assert_eq!(client.header(third[0]).unwrap().unwrap().parent_hash, first[0]);
+		assert_eq!(timestamp_of(third[0]), 51_000);
+
+		assert!(rpc.create_blocks(rpc::MAX_CREATE_BLOCKS + 1, false).await.is_err());
+		assert_eq!(client.info().best_hash, third[0]);
+	}
 }
@@ -0,0 +1,107 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Block reverting utilities
+
+use crate::{rpc, Error, TimeTravel};
+use sc_client_api::backend::Backend as ClientBackend;
+use sc_transaction_pool_api::{ChainEvent, MaintainedTransactionPool};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use std::{marker::PhantomData, sync::Arc};
+
+/// params for reverting the best chain.
+pub struct RevertBlockParams<'a, B: BlockT, C, CB, TP> {
+	/// hash of the block that becomes the new best block
+	pub hash: <B as BlockT>::Hash,
+	/// sender to report errors/success to the rpc.
+	pub sender: rpc::Sender<()>,
+	/// header backend
+	pub client: Arc<C>,
+	/// backend the blocks are removed from, reverting fails without one.
+	pub backend: Option<Arc<CB>>,
+	/// transaction pool, moved to the new best block.
+	pub pool: Arc<TP>,
+	/// Timestamp adjustments, which forget about the reverted blocks.
+	pub time_travel: &'a mut TimeTravel<<B as BlockT>::Hash, NumberFor<B>>,
+	/// phantom type to pin the Block type
+	pub _phantom: PhantomData<B>,
+}
+
+/// reverts the best chain to the given block, finalized blocks included.
+///
+/// The transaction pool is moved to the new best block before reverting, so that it resubmits
+/// the transactions of the reverted blocks. The pool doesn't retract finalized blocks though:
+/// the transactions of reverted finalized blocks are not resubmitted, and have to be submitted
+/// again to be included in a new block.
+pub async fn revert_block<B, C, CB, TP>(params: RevertBlockParams<'_, B, C, CB, TP>)
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+	CB: ClientBackend<B>,
+	TP: MaintainedTransactionPool<Block = B>,
+{
+	let RevertBlockParams { hash, mut sender, client, backend, pool, time_travel, .. } = params;
+
+	let result = async {
+		let backend = backend.ok_or_else(|| {
+			Error::StringError("Reverting blocks is not enabled for this node".into())
+		})?;
+		let number = *client
+			.header(hash)?
+			.ok_or_else(|| Error::BlockNotFound(format!("{}", hash)))?
+			.number();
+		if client.hash(number)? != Some(hash) {
+			return Err(Error::StringError(format!("Block {} is not part of the best chain", hash)))
+		}
+
+		let info = client.info();
+		pool.maintain(ChainEvent::NewBestBlock { hash, tree_route: None }).await;
+
+		let (reverted, reverted_finalized) = match backend.revert(info.best_number - number, true) {
+			Ok(reverted) => reverted,
+			Err(e) => {
+				// Move the pool back to the chain that is still there.
+				pool.maintain(ChainEvent::NewBestBlock { hash: info.best_hash, tree_route: None })
+					.await;
+				return Err(e.into())
+			},
+		};
+		if !reverted_finalized.is_empty() {
+			// The pool can't tell that the finalized block is gone, make it start over from the
+			// new one.
+			pool.maintain(ChainEvent::Finalized { hash, tree_route: Arc::from(Vec::new()) })
+				.await;
+		}
+		time_travel.reverted(number);
+
+		log::info!(
+			"⏪ Reverted {} blocks, {} of them finalized, best block is now: {}",
+			reverted,
+			reverted_finalized.len(),
+			hash,
+		);
+		Ok(())
+	}
+	.await;
+
+	if let Err(e) = &result {
+		log::warn!("Failed to revert to block {}: {}", hash, e);
+	}
+	rpc::send_result(&mut sender, result)
+}
@@ -31,6 +31,9 @@ use sc_consensus::ImportedAux;
 use serde::{Deserialize, Serialize};
 use sp_runtime::EncodedJustification;
 
+/// Maximum number of blocks `engine_createBlocks` creates in one call.
+pub const MAX_CREATE_BLOCKS: u32 = 1_000;
+
 /// Sender passed to the authorship task to report errors or successes.
 pub type Sender<T> = Option<oneshot::Sender<std::result::Result<T, Error>>>;
 
@@ -62,6 +65,27 @@ pub enum EngineCommand<Hash> {
 		/// finalization justification
 		justification: Option<EncodedJustification>,
 	},
+	/// Pins the timestamp of the next sealed block, later blocks keep the resulting offset.
+	SetTimestamp {
+		/// unix timestamp in milliseconds
+		timestamp: u64,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Moves the timestamp of all blocks sealed from now on forward.
+	AdvanceTimestamp {
+		/// duration in milliseconds
+		by: u64,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Reverts the best chain back to the block with the supplied hash.
+	RevertTo {
+		/// hash of the block that becomes the new best block
+		hash: Hash,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
 }
 
 /// RPC trait that provides methods for interacting with the manual-seal authorship task over rpc.
@@ -83,6 +107,27 @@ pub trait ManualSealApi<Hash> {
 		hash: Hash,
 		justification: Option<EncodedJustification>,
 	) -> RpcResult<bool>;
+
+	/// Instructs the manual-seal authorship task to create `count` empty blocks on top of the
+	/// best block, returns their hashes. At most [`MAX_CREATE_BLOCKS`] blocks are created per call.
+	#[method(name = "engine_createBlocks")]
+	async fn create_blocks(&self, count: u32, finalize: bool) -> RpcResult<Vec<Hash>>;
+
+	/// Sets the timestamp (in milliseconds) of the next block, blocks after it continue from
+	/// there.
+	#[method(name = "engine_setTimestamp")]
+	async fn set_timestamp(&self, timestamp: u64) -> RpcResult<bool>;
+
+	/// Advances the timestamp of all following blocks by the given amount of milliseconds.
+	#[method(name = "engine_advanceTimestamp")]
+	async fn advance_timestamp(&self, by: u64) -> RpcResult<bool>;
+
+	/// Reverts the best chain to the block with the supplied hash, finalized blocks included.
+	///
+	/// Transactions of the reverted blocks are resubmitted to the pool, except for those of
+	/// finalized blocks.
+	#[method(name = "engine_revertTo")]
+	async fn revert_to(&self, hash: Hash) -> RpcResult<bool>;
 }
 
 /// A struct that implements the [`ManualSealApiServer`].
@@ -144,6 +189,54 @@ impl<Hash: Send + 'static> ManualSealApiServer<Hash> for ManualSeal<Hash> {
 		sink.send(command).await?;
 		receiver.await.map(|_| true).map_err(|e| JsonRpseeError::to_call_error(e))
 	}
+
+	async fn create_blocks(&self, count: u32, finalize: bool) -> RpcResult<Vec<Hash>> {
+		if count > MAX_CREATE_BLOCKS {
+			return Err(Error::StringError(format!(
+				"Can't create {} blocks at once, the limit is {}",
+				count, MAX_CREATE_BLOCKS
+			))
+			.into())
+		}
+
+		let mut hashes = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			let created = self.create_block(true, finalize, None).await?;
+			hashes.push(created.hash);
+		}
+		Ok(hashes)
+	}
+
+	async fn set_timestamp(&self, timestamp: u64) -> RpcResult<bool> {
+		self.send_command(|sender| EngineCommand::SetTimestamp { timestamp, sender })
+			.await
+	}
+
+	async fn advance_timestamp(&self, by: u64) -> RpcResult<bool> {
+		self.send_command(|sender| EngineCommand::AdvanceTimestamp { by, sender }).await
+	}
+
+	async fn revert_to(&self, hash: Hash) -> RpcResult<bool> {
+		self.send_command(|sender| EngineCommand::RevertTo { hash, sender }).await
+	}
+}
+
+impl<Hash> ManualSeal<Hash> {
+	/// Sends a command that only reports success or failure to the authorship task.
+	async fn send_command(
+		&self,
+		command: impl FnOnce(Sender<()>) -> EngineCommand<Hash>,
+	) -> RpcResult<bool> {
+		let mut sink = self.import_block_channel.clone();
+		let (sender, receiver) = oneshot::channel();
+		sink.send(command(Some(sender))).await?;
+
+		match receiver.await {
+			Ok(Ok(())) => Ok(true),
+			Ok(Err(e)) => Err(e.into()),
+			Err(e) => Err(JsonRpseeError::to_call_error(e)),
+		}
+	}
 }
 
 /// report any errors or successes encountered by the authorship task back
@@ -0,0 +1,181 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Timestamp adjustments requested over rpc
+
+use crate::Error;
+use sp_inherents::InherentData;
+use sp_timestamp::{InherentType, TimestampInherentData, INHERENT_IDENTIFIER};
+use std::collections::HashMap;
+
+/// Shifts the timestamp inherent of sealed blocks, so that tests can move the chain through time
+/// without sealing a block for every slot in between.
+///
+/// The offset is applied on top of the timestamp provided by the inherent data providers and is
+/// kept until the authorship task stops. Pinned timestamps can't go back past the best block, as
+/// far as it was sealed by this task and isn't older than the finalized block.
+#[derive(Debug, Clone)]
+pub struct TimeTravel<Hash, Number> {
+	// timestamp the next sealed block should have, turned into an offset once it is sealed.
+	pending: Option<u64>,
+	// milliseconds added to the provided timestamp.
+	offset: i64,
+	// numbers and timestamps of the blocks sealed by this task, down to the finalized block.
+	sealed: HashMap<Hash, (Number, u64)>,
+}
+
+impl<Hash, Number> Default for TimeTravel<Hash, Number> {
+	fn default() -> Self {
+		Self { pending: None, offset: 0, sealed: HashMap::new() }
+	}
+}
+
+impl<Hash: std::hash::Hash + Eq, Number: Ord + Copy> TimeTravel<Hash, Number> {
+	/// Pin the timestamp of the next sealed block, which must not be earlier than the timestamp
+	/// of the `best` block.
+	pub fn set_timestamp(&mut self, timestamp: u64, best: &Hash) -> Result<(), Error> {
+		if let Some(&(_, best)) = self.sealed.get(best) {
+			if timestamp < best {
+				return Err(Error::TimestampInPast { timestamp, best })
+			}
+		}
+		self.pending = Some(timestamp);
+		Ok(())
+	}
+
+	/// Move the timestamp of all following blocks forward by `by` milliseconds.
+	pub fn advance(&mut self, by: u64) {
+		match self.pending.as_mut() {
+			Some(pending) => *pending = pending.saturating_add(by),
+			None => self.offset = self.offset.saturating_add(by.min(i64::MAX as u64) as i64),
+		}
+	}
+
+	/// Rewrite the timestamp inherent in `inherent_data`, if there is one, and return it.
+	pub fn apply(&mut self, inherent_data: &mut InherentData) -> Result<Option<u64>, Error> {
+		let provided = match inherent_data.timestamp_inherent_data()? {
+			Some(timestamp) => *timestamp,
+			None => return Ok(None),
+		};
+
+		if let Some(pending) = self.pending.take() {
+			self.offset = (pending as i128 - provided as i128) as i64;
+		}
+
+		let timestamp = provided.saturating_add_signed(self.offset);
+		inherent_data.replace_data(INHERENT_IDENTIFIER, &InherentType::from(timestamp));
+		Ok(Some(timestamp))
+	}
+
+	/// Remember the timestamp of a block sealed with the data passed to [`Self::apply`].
+	pub fn sealed(&mut self, hash: Hash, number: Number, timestamp: u64) {
+		self.sealed.insert(hash, (number, timestamp));
+	}
+
+	/// Forget the blocks above `best`, which were removed when reverting to it.
+	pub fn reverted(&mut self, best: Number) {
+		self.sealed.retain(|_, (number, _)| *number <= best);
+	}
+
+	/// Forget the blocks below the `finalized` one. Reverting to them is still possible, but the
+	/// timestamp pinned afterwards isn't checked against theirs.
+	pub fn finalized(&mut self, finalized: Number) {
+		self.sealed.retain(|_, (number, _)| *number >= finalized);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn inherent_data(timestamp: u64) -> InherentData {
+		let mut data = InherentData::new();
+		data.put_data(INHERENT_IDENTIFIER, &InherentType::from(timestamp)).unwrap();
+		data
+	}
+
+	fn applied(time_travel: &mut TimeTravel<u32, u32>, provided: u64) -> u64 {
+		let mut data = inherent_data(provided);
+		let timestamp = time_travel.apply(&mut data).unwrap().unwrap();
+		assert_eq!(*data.timestamp_inherent_data().unwrap().unwrap(), timestamp);
+		timestamp
+	}
+
+	#[test]
+	fn timestamp_is_shifted() {
+		let mut time_travel = TimeTravel::default();
+		assert_eq!(applied(&mut time_travel, 1_000), 1_000);
+
+		time_travel.set_timestamp(10_000, &0).unwrap();
+		time_travel.advance(500);
+		assert_eq!(applied(&mut time_travel, 2_000), 10_500);
+		// the offset sticks for the following blocks.
+		assert_eq!(applied(&mut time_travel, 3_000), 11_500);
+
+		time_travel.advance(1_000);
+		assert_eq!(applied(&mut time_travel, 4_000), 13_500);
+	}
+
+	#[test]
+	fn timestamp_cannot_go_back_past_best_block() {
+		let mut time_travel = TimeTravel::default();
+		time_travel.set_timestamp(10_000, &0).unwrap();
+		let timestamp = applied(&mut time_travel, 1_000);
+		time_travel.sealed(1, 1, timestamp);
+
+		assert!(matches!(
+			time_travel.set_timestamp(9_999, &1),
+			Err(Error::TimestampInPast { timestamp: 9_999, best: 10_000 })
+		));
+		// the rejected timestamp is not pinned.
+		assert_eq!(applied(&mut time_travel, 2_000), 11_000);
+
+		// the same timestamp is fine, and so is an earlier one once reverted to an older block.
+		time_travel.set_timestamp(10_000, &1).unwrap();
+		time_travel.set_timestamp(500, &0).unwrap();
+		assert_eq!(applied(&mut time_travel, 3_000), 500);
+	}
+
+	#[test]
+	fn missing_timestamp_is_left_alone() {
+		let mut time_travel = TimeTravel::<u32, u32>::default();
+		time_travel.set_timestamp(10_000, &0).unwrap();
+		let mut data = InherentData::new();
+		assert_eq!(time_travel.apply(&mut data).unwrap(), None);
+		assert!(data.timestamp_inherent_data().unwrap().is_none());
+		// the pinned timestamp is kept for a block that has one.
+		assert_eq!(applied(&mut time_travel, 1_000), 10_000);
+	}
+
+	#[test]
+	fn reverted_and_finalized_blocks_are_forgotten() {
+		let mut time_travel = TimeTravel::<u32, u32>::default();
+		for number in 1..=4 {
+			time_travel.sealed(number, number, number as u64 * 1_000);
+		}
+
+		time_travel.reverted(3);
+		assert!(time_travel.set_timestamp(500, &4).is_ok());
+		assert!(time_travel.set_timestamp(500, &3).is_err());
+
+		time_travel.finalized(2);
+		assert!(time_travel.set_timestamp(500, &1).is_ok());
+		assert!(time_travel.set_timestamp(500, &2).is_err());
+		assert_eq!(time_travel.sealed.len(), 2);
+	}
+}
@@ -29,7 +29,13 @@
 //! the worker handle, you can pull the metadata needed to start the
 //! mining process via [`MiningHandle::metadata`], and then do the actual
 //! mining on a standalone thread. Finally, when a seal is found, call
-//! [`MiningHandle::submit`] to build the block.
+//! [`MiningHandle::submit`] to build the block. For algorithms that can be
+//! expressed as a search over nonces, [`CpuMiner`] takes care of the mining
+//! threads, given a [`NonceSearch`] implementation.
+//!
+//! [`LwmaDifficulty`] can be wrapped around any [`PowAlgorithm`] with a
+//! [`U256`](sp_core::U256) difficulty to adjust it based on the recent solve
+//! times.
 //!
 //! The auxiliary storage for PoW engine only stores the total difficulty.
 //! For other storage requirements for particular PoW algorithm (such as
@@ -39,9 +45,15 @@
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
 
+mod lwma;
+mod miner;
 mod worker;
 
-pub use crate::worker::{MiningBuild, MiningHandle, MiningMetadata};
+pub use crate::{
+	lwma::LwmaDifficulty,
+	miner::{CpuMiner, CpuMinerParams, NonceSearch},
+	worker::{MiningBuild, MiningHandle, MiningMetadata},
+};
 
 use crate::worker::UntilImportedOrTimeout;
 use codec::{Decode, Encode};
@@ -170,6 +182,9 @@ where
 	}
 }
 
+/// Auxiliary data to insert, or to delete when the value is `None`.
+pub type AuxiliaryData = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
 /// Algorithm used for proof of work.
 pub trait PowAlgorithm<B: BlockT> {
 	/// Difficulty for the algorithm.
@@ -180,6 +195,14 @@ pub trait PowAlgorithm<B: BlockT> {
 	/// This function will be called twice during the import process, so the implementation
 	/// should be properly cached.
 	fn difficulty(&self, parent: B::Hash) -> Result<Self::Difficulty, Error<B>>;
+	/// Auxiliary data to insert, or to delete when `None`, along with a block imported on top of
+	/// `parent`.
+	///
+	/// Called by the block import while the state of `parent` is available. The data is written
+	/// together with the block.
+	fn import_auxiliary(&self, _parent: B::Hash) -> Result<AuxiliaryData, Error<B>> {
+		Ok(Vec::new())
+	}
 	/// Verify that the seal is valid against given pre hash when parent block is not yet imported.
 	///
 	/// None means that preliminary verify is not available for this algorithm.
@@ -383,6 +406,7 @@ where
 
 		let key = aux_key(&block.post_hash());
 		block.auxiliary.push((key, Some(aux.encode())));
+		block.auxiliary.extend(self.algorithm.import_auxiliary(parent_hash)?);
 		if block.fork_choice.is_none() {
 			block.fork_choice = Some(ForkChoiceStrategy::Custom(
 				match aux.total_difficulty.cmp(&best_aux.total_difficulty) {
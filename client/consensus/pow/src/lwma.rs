@@ -0,0 +1,478 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Difficulty adjustment with a linearly weighted moving average.
+
+use crate::{aux_key, AuxiliaryData, Error, PowAlgorithm, PowAux, Seal};
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sc_client_api::backend::AuxStore;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_pow::{lwma, TimestampApi};
+use sp_core::U256;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Saturating, Zero},
+};
+use std::{sync::Arc, time::Duration};
+
+/// A [`PowAlgorithm`] that adjusts the difficulty with [`lwma::next_difficulty`] and leaves seal
+/// verification to the wrapped algorithm.
+///
+/// The solve times are taken from the timestamps the runtime reports through [`TimestampApi`],
+/// the difficulties from the auxiliary data stored by the PoW block import. The timestamp of a
+/// block is read from its state when its first child is imported, and kept in the auxiliary
+/// storage from then on. Older blocks can hence be pruned. Timestamps of finalized blocks are
+/// removed once they are more than `window` blocks below the last finalized block, as only their
+/// descendants can still be imported.
+///
+/// The whole window must have been imported by this node: after a warp or state sync the
+/// difficulty can't be computed and an error is returned.
+pub struct LwmaDifficulty<B: BlockT, C, A> {
+	inner: A,
+	client: Arc<C>,
+	window: u32,
+	target_block_time: u64,
+	initial_difficulty: U256,
+	// `difficulty` is called twice for every imported block.
+	cache: Arc<Mutex<Option<(B::Hash, U256)>>>,
+}
+
+/// Auxiliary storage prefix of the block timestamps.
+const TIMESTAMP_AUX_PREFIX: [u8; 8] = *b"PoWLwma:";
+
+/// Auxiliary storage key of the number up to which finalized timestamps were removed.
+const PRUNED_AUX_KEY: &[u8] = b"PoWLwma:pruned";
+
+/// Maximum number of timestamps removed along with a single block.
+const MAX_PRUNED_PER_IMPORT: u32 = 128;
+
+fn timestamp_aux_key<T: AsRef<[u8]>>(hash: &T) -> Vec<u8> {
+	TIMESTAMP_AUX_PREFIX.iter().chain(hash.as_ref()).copied().collect()
+}
+
+impl<B: BlockT, C, A> LwmaDifficulty<B, C, A> {
+	/// Wrap `inner`, averaging over the solve times of the last `window` blocks.
+	///
+	/// `initial_difficulty` is used until there are at least two blocks after genesis.
+	pub fn new(
+		inner: A,
+		client: Arc<C>,
+		window: u32,
+		target_block_time: Duration,
+		initial_difficulty: U256,
+	) -> Self {
+		Self {
+			inner,
+			client,
+			window: window.max(1),
+			target_block_time: target_block_time.as_millis() as u64,
+			initial_difficulty,
+			cache: Arc::new(Mutex::new(None)),
+		}
+	}
+}
+
+impl<B: BlockT, C, A: Clone> Clone for LwmaDifficulty<B, C, A> {
+	fn clone(&self) -> Self {
+		Self {
+			inner: self.inner.clone(),
+			client: self.client.clone(),
+			window: self.window,
+			target_block_time: self.target_block_time,
+			initial_difficulty: self.initial_difficulty,
+			cache: self.cache.clone(),
+		}
+	}
+}
+
+impl<B, C, A> LwmaDifficulty<B, C, A>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + AuxStore,
+	C::Api: TimestampApi<B, u64>,
+{
+	/// The timestamp of `hash`, read from the auxiliary storage or from its state if not stored
+	/// yet.
+	fn timestamp(&self, hash: B::Hash) -> Result<u64, Error<B>> {
+		if let Some(bytes) =
+			self.client.get_aux(&timestamp_aux_key(&hash)).map_err(Error::Client)?
+		{
+			return u64::decode(&mut &bytes[..]).map_err(Error::Codec)
+		}
+
+		self.client
+			.runtime_api()
+			.timestamp(hash)
+			.map_err(|e| Error::Other(format!("Fetching timestamp of {} failed: {}", hash, e)))
+	}
+
+	/// The difficulty of `hash`, as stored by the PoW block import.
+	fn block_difficulty(&self, hash: B::Hash) -> Result<U256, Error<B>> {
+		match self.client.get_aux(&aux_key(&hash)).map_err(Error::Client)? {
+			Some(bytes) => PowAux::<U256>::decode(&mut &bytes[..])
+				.map(|aux| aux.difficulty)
+				.map_err(Error::Codec),
+			None => Err(Error::Other(format!("Missing PoW auxiliary data of {}", hash))),
+		}
+	}
+}
+
+impl<B, C, A> PowAlgorithm<B> for LwmaDifficulty<B, C, A>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + AuxStore,
+	C::Api: TimestampApi<B, u64>,
+	A: PowAlgorithm<B, Difficulty = U256>,
+{
+	type Difficulty = U256;
+
+	fn difficulty(&self, parent: B::Hash) -> Result<U256, Error<B>> {
+		if let Some((hash, difficulty)) = *self.cache.lock() {
+			if hash == parent {
+				return Ok(difficulty)
+			}
+		}
+
+		// `(timestamp, difficulty)` of the last `window + 1` blocks, genesis excluded.
+		let mut blocks = Vec::with_capacity(self.window as usize + 1);
+		let mut hash = parent;
+		while blocks.len() <= self.window as usize {
+			let header = self.client.header(hash).map_err(Error::Client)?.ok_or_else(|| {
+				Error::Client(sp_blockchain::Error::UnknownBlock(hash.to_string()))
+			})?;
+			if header.number().is_zero() {
+				break
+			}
+
+			blocks.push((self.timestamp(hash)?, self.block_difficulty(hash)?));
+			hash = *header.parent_hash();
+		}
+		blocks.reverse();
+
+		let difficulty = lwma::next_difficulty(&blocks, self.target_block_time)
+			.unwrap_or(self.initial_difficulty);
+		*self.cache.lock() = Some((parent, difficulty));
+		Ok(difficulty)
+	}
+
+	fn import_auxiliary(&self, parent: B::Hash) -> Result<AuxiliaryData, Error<B>> {
+		let mut auxiliary = self.inner.import_auxiliary(parent)?;
+
+		let key = timestamp_aux_key(&parent);
+		let number =
+			self.client.number(parent).map_err(Error::Client)?.ok_or_else(|| {
+				Error::Client(sp_blockchain::Error::UnknownBlock(parent.to_string()))
+			})?;
+		if !number.is_zero() && self.client.get_aux(&key).map_err(Error::Client)?.is_none() {
+			auxiliary.push((key, Some(self.timestamp(parent)?.encode())));
+		}
+
+		let prune_to = self.client.info().finalized_number.saturating_sub((self.window + 1).into());
+		let mut pruned = match self.client.get_aux(PRUNED_AUX_KEY).map_err(Error::Client)? {
+			Some(bytes) => NumberFor::<B>::decode(&mut &bytes[..]).map_err(Error::Codec)?,
+			None => Zero::zero(),
+		};
+		let mut count = 0;
+		while pruned < prune_to && count < MAX_PRUNED_PER_IMPORT {
+			pruned += One::one();
+			count += 1;
+			if let Some(hash) = self.client.hash(pruned).map_err(Error::Client)? {
+				auxiliary.push((timestamp_aux_key(&hash), None));
+			}
+		}
+		if count > 0 {
+			auxiliary.push((PRUNED_AUX_KEY.to_vec(), Some(pruned.encode())));
+		}
+
+		Ok(auxiliary)
+	}
+
+	fn preliminary_verify(
+		&self,
+		pre_hash: &B::Hash,
+		seal: &Seal,
+	) -> Result<Option<bool>, Error<B>> {
+		self.inner.preliminary_verify(pre_hash, seal)
+	}
+
+	fn break_tie(&self, own_seal: &Seal, new_seal: &Seal) -> bool {
+		self.inner.break_tie(own_seal, new_seal)
+	}
+
+	fn verify(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		pre_digest: Option<&[u8]>,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, Error<B>> {
+		self.inner.verify(parent, pre_hash, pre_digest, seal, difficulty)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_api::{ApiError, ApiRef};
+	use sp_blockchain::{BlockStatus, Info};
+	use sp_runtime::{traits::NumberFor, Digest};
+	use std::collections::HashMap;
+	use substrate_test_runtime_client::runtime::{Block, Hash, Header};
+
+	const TARGET: u64 = 6_000;
+
+	struct NoSeal;
+
+	impl PowAlgorithm<Block> for NoSeal {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: Hash) -> Result<U256, Error<Block>> {
+			unreachable!("The difficulty is computed by `LwmaDifficulty`")
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &Hash,
+			_pre_digest: Option<&[u8]>,
+			_seal: &Seal,
+			_difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			Ok(true)
+		}
+	}
+
+	#[derive(Default)]
+	struct TestClient {
+		headers: HashMap<Hash, Header>,
+		hashes: Vec<Hash>,
+		finalized: Mutex<u64>,
+		// Timestamps of the blocks whose state was not pruned.
+		state: Mutex<HashMap<Hash, u64>>,
+		aux: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+	}
+
+	impl TestClient {
+		// A chain of `len` blocks after genesis, mined every `solve_time` at `difficulty`.
+		fn new(len: u64, solve_time: u64, difficulty: u64) -> (Self, Vec<Hash>) {
+			let mut client = Self::default();
+			let mut parent_hash = Default::default();
+			let mut hashes = Vec::new();
+			for number in 0..=len {
+				let header = Header::new(
+					number,
+					Default::default(),
+					Default::default(),
+					parent_hash,
+					Digest::default(),
+				);
+				let hash = header.hash();
+				client.headers.insert(hash, header);
+				client.state.lock().insert(hash, 1_000_000 + number * solve_time);
+				let aux =
+					PowAux { difficulty: U256::from(difficulty), total_difficulty: U256::zero() };
+				client.aux.lock().insert(aux_key(&hash), aux.encode());
+				hashes.push(hash);
+				parent_hash = hash;
+			}
+			client.hashes = hashes.clone();
+			(client, hashes)
+		}
+	}
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = RuntimeApi;
+
+		fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+			RuntimeApi { state: self.state.lock().clone() }.into()
+		}
+	}
+
+	impl HeaderBackend<Block> for TestClient {
+		fn header(&self, hash: Hash) -> sp_blockchain::Result<Option<Header>> {
+			Ok(self.headers.get(&hash).cloned())
+		}
+
+		fn info(&self) -> Info<Block> {
+			let finalized = *self.finalized.lock();
+			Info {
+				best_hash: *self.hashes.last().unwrap(),
+				best_number: self.hashes.len() as u64 - 1,
+				genesis_hash: self.hashes[0],
+				finalized_hash: self.hashes[finalized as usize],
+				finalized_number: finalized,
+				finalized_state: None,
+				number_leaves: 1,
+				block_gap: None,
+			}
+		}
+
+		fn status(&self, _hash: Hash) -> sp_blockchain::Result<BlockStatus> {
+			unimplemented!()
+		}
+
+		fn number(&self, hash: Hash) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+			Ok(self.headers.get(&hash).map(|header| *header.number()))
+		}
+
+		fn hash(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Option<Hash>> {
+			Ok(self.hashes.get(number as usize).copied())
+		}
+	}
+
+	impl AuxStore for TestClient {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(
+			&self,
+			insert: I,
+			delete: D,
+		) -> sp_blockchain::Result<()> {
+			let mut aux = self.aux.lock();
+			for (key, value) in insert {
+				aux.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				aux.remove(*key);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.aux.lock().get(key).cloned())
+		}
+	}
+
+	struct RuntimeApi {
+		state: HashMap<Hash, u64>,
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl TimestampApi<Block, u64> for RuntimeApi {
+			#[advanced]
+			fn timestamp(&self, at: Hash) -> Result<u64, ApiError> {
+				self.state.get(&at).copied().ok_or_else(|| ApiError::UnknownBlock(at.to_string()))
+			}
+		}
+	}
+
+	fn lwma(client: &Arc<TestClient>) -> LwmaDifficulty<Block, TestClient, NoSeal> {
+		LwmaDifficulty::new(
+			NoSeal,
+			client.clone(),
+			10,
+			Duration::from_millis(TARGET),
+			U256::from(42),
+		)
+	}
+
+	// Write the auxiliary data of a block imported on top of `parent`.
+	fn import(client: &Arc<TestClient>, parent: Hash) {
+		for (key, value) in lwma(client).import_auxiliary(parent).unwrap() {
+			match value {
+				Some(value) => client.aux.lock().insert(key, value),
+				None => client.aux.lock().remove(&key),
+			};
+		}
+	}
+
+	#[test]
+	fn follows_solve_times() {
+		let (client, hashes) = TestClient::new(20, TARGET / 2, 1_000);
+		let client = Arc::new(client);
+
+		assert_eq!(lwma(&client).difficulty(hashes[20]).unwrap(), U256::from(2_000));
+	}
+
+	#[test]
+	fn uses_initial_difficulty_on_short_chains() {
+		let (client, hashes) = TestClient::new(1, TARGET / 2, 1_000);
+		let client = Arc::new(client);
+
+		assert_eq!(lwma(&client).difficulty(hashes[0]).unwrap(), U256::from(42));
+		assert_eq!(lwma(&client).difficulty(hashes[1]).unwrap(), U256::from(42));
+	}
+
+	#[test]
+	fn keeps_timestamps_of_pruned_blocks() {
+		let (client, hashes) = TestClient::new(20, TARGET / 2, 1_000);
+		let client = Arc::new(client);
+		for hash in &hashes[9..20] {
+			import(&client, *hash);
+		}
+
+		// Only the state of the last block is left.
+		client.state.lock().retain(|hash, _| *hash == hashes[20]);
+
+		assert_eq!(lwma(&client).difficulty(hashes[20]).unwrap(), U256::from(2_000));
+		// Blocks without imported children can't be recovered.
+		assert!(lwma(&client).difficulty(hashes[5]).is_err());
+	}
+
+	#[test]
+	fn difficulty_does_not_write_aux() {
+		let (client, hashes) = TestClient::new(20, TARGET / 2, 1_000);
+		let client = Arc::new(client);
+		let aux = client.aux.lock().clone();
+
+		lwma(&client).difficulty(hashes[20]).unwrap();
+
+		assert_eq!(*client.aux.lock(), aux);
+	}
+
+	#[test]
+	fn prunes_timestamps_below_finalized_window() {
+		let (client, hashes) = TestClient::new(20, TARGET / 2, 1_000);
+		let client = Arc::new(client);
+		for hash in &hashes[..20] {
+			import(&client, *hash);
+		}
+		assert!(client.get_aux(&timestamp_aux_key(&hashes[1])).unwrap().is_some());
+
+		*client.finalized.lock() = 15;
+		import(&client, hashes[20]);
+
+		// The window of the children of the finalized block starts at block 5.
+		for hash in &hashes[..5] {
+			assert!(client.get_aux(&timestamp_aux_key(hash)).unwrap().is_none());
+		}
+		for hash in &hashes[5..] {
+			assert!(client.get_aux(&timestamp_aux_key(hash)).unwrap().is_some());
+		}
+		client.state.lock().retain(|hash, _| *hash == hashes[15]);
+		assert_eq!(lwma(&client).difficulty(hashes[15]).unwrap(), U256::from(2_000));
+	}
+
+	#[test]
+	fn missing_difficulty_is_an_error() {
+		let (client, hashes) = TestClient::new(20, TARGET / 2, 1_000);
+		client.aux.lock().remove(&aux_key(&hashes[15]));
+		let client = Arc::new(client);
+
+		assert!(lwma(&client).difficulty(hashes[20]).is_err());
+		// Blocks out of the window are not needed.
+		assert!(lwma(&client).difficulty(hashes[14]).is_ok());
+	}
+}
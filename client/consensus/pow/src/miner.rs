@@ -0,0 +1,370 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Multi-threaded CPU mining on top of a [`MiningHandle`].
+
+use crate::{MiningHandle, MiningMetadata, PowAlgorithm, Seal, LOG_TARGET};
+use log::*;
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// How long an idle mining thread waits before checking for new mining metadata.
+const IDLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Minimal time between two hashrate updates.
+const HASHRATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A proof of work function searching for a seal one nonce at a time.
+pub trait NonceSearch<Hash, Difficulty>: Send + Sync + 'static {
+	/// Compute the seal for `nonce` and return it if it satisfies `metadata.difficulty`.
+	fn try_nonce(&self, metadata: &MiningMetadata<Hash, Difficulty>, nonce: u64) -> Option<Seal>;
+}
+
+/// Parameters of a [`CpuMiner`].
+#[derive(Debug, Clone)]
+pub struct CpuMinerParams {
+	/// Number of mining threads.
+	pub threads: usize,
+	/// Nonces a thread tries before checking whether the mining metadata changed.
+	pub batch_size: u64,
+}
+
+impl Default for CpuMinerParams {
+	fn default() -> Self {
+		Self {
+			threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+			batch_size: 10_000,
+		}
+	}
+}
+
+#[derive(Clone)]
+struct Metrics {
+	hashes: Counter<U64>,
+	hashrate: Gauge<U64>,
+	threads: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			hashes: register(
+				Counter::new("substrate_pow_mining_hashes_total", "Number of nonces tried")?,
+				registry,
+			)?,
+			hashrate: register(
+				Gauge::new("substrate_pow_mining_hashrate", "Nonces tried per second")?,
+				registry,
+			)?,
+			threads: register(
+				Gauge::new("substrate_pow_mining_threads", "Number of mining threads")?,
+				registry,
+			)?,
+		})
+	}
+}
+
+// Shared between all mining threads of a `CpuMiner`.
+struct Shared {
+	running: AtomicBool,
+	hashes: AtomicU64,
+	hashrate: AtomicU64,
+	// when the hashrate was last computed, and the number of hashes at that time.
+	last_update: Mutex<(Instant, u64)>,
+	metrics: Option<Metrics>,
+}
+
+impl Shared {
+	fn record_hashes(&self, count: u64) {
+		let hashes = self.hashes.fetch_add(count, Ordering::Relaxed) + count;
+		if let Some(metrics) = &self.metrics {
+			metrics.hashes.inc_by(count);
+		}
+
+		// only one thread needs to do the update.
+		let mut last_update = match self.last_update.try_lock() {
+			Some(last_update) => last_update,
+			None => return,
+		};
+		let elapsed = last_update.0.elapsed();
+		if elapsed >= HASHRATE_INTERVAL {
+			let hashrate = ((hashes - last_update.1) as f64 / elapsed.as_secs_f64()) as u64;
+			*last_update = (Instant::now(), hashes);
+			self.hashrate.store(hashrate, Ordering::Relaxed);
+			if let Some(metrics) = &self.metrics {
+				metrics.hashrate.set(hashrate);
+			}
+		}
+	}
+}
+
+/// Mines blocks on a [`MiningHandle`] with a number of CPU threads.
+///
+/// Every thread searches its own share of the nonces until a seal is found or the mining build
+/// changes. Found seals are submitted through the handle. The threads stop once the `CpuMiner` is
+/// dropped.
+pub struct CpuMiner {
+	shared: Arc<Shared>,
+}
+
+impl CpuMiner {
+	/// Spawn the mining threads.
+	pub fn start<Block, Algorithm, C, L, Proof, S>(
+		handle: MiningHandle<Block, Algorithm, C, L, Proof>,
+		search: S,
+		params: CpuMinerParams,
+		registry: Option<&Registry>,
+	) -> Self
+	where
+		Block: BlockT,
+		C: sp_api::ProvideRuntimeApi<Block> + 'static,
+		Algorithm: PowAlgorithm<Block> + Send + Sync + 'static,
+		Algorithm::Difficulty: Send + 'static,
+		L: sc_consensus::JustificationSyncLink<Block> + 'static,
+		Proof: Send + 'static,
+		sp_api::TransactionFor<C, Block>: Send + 'static,
+		S: NonceSearch<Block::Hash, Algorithm::Difficulty>,
+	{
+		let metrics = registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|err| warn!(target: LOG_TARGET, "Failed to register metrics: {}", err))
+				.ok()
+		});
+		let threads = params.threads.max(1);
+		if let Some(metrics) = &metrics {
+			metrics.threads.set(threads as u64);
+		}
+
+		let shared = Arc::new(Shared {
+			running: AtomicBool::new(true),
+			hashes: AtomicU64::new(0),
+			hashrate: AtomicU64::new(0),
+			last_update: Mutex::new((Instant::now(), 0)),
+			metrics,
+		});
+		let search = Arc::new(search);
+
+		for index in 0..threads {
+			let handle = handle.clone();
+			let search = search.clone();
+			let shared = shared.clone();
+			let batch_size = params.batch_size.max(1);
+			let spawned =
+				thread::Builder::new().name(format!("pow-miner-{}", index)).spawn(move || {
+					mine(handle, &*search, &shared, index as u64, threads as u64, batch_size)
+				});
+			if let Err(err) = spawned {
+				warn!(target: LOG_TARGET, "Failed to spawn mining thread: {}", err);
+			}
+		}
+
+		Self { shared }
+	}
+
+	/// Total number of nonces tried so far.
+	pub fn hashes(&self) -> u64 {
+		self.shared.hashes.load(Ordering::Relaxed)
+	}
+
+	/// Nonces tried per second, updated about every second.
+	pub fn hashrate(&self) -> u64 {
+		self.shared.hashrate.load(Ordering::Relaxed)
+	}
+}
+
+impl Drop for CpuMiner {
+	fn drop(&mut self) {
+		self.shared.running.store(false, Ordering::Relaxed);
+	}
+}
+
+// Mining loop of a single thread, trying the nonces `first_nonce + k * stride`.
+fn mine<Block, Algorithm, C, L, Proof, S>(
+	handle: MiningHandle<Block, Algorithm, C, L, Proof>,
+	search: &S,
+	shared: &Shared,
+	first_nonce: u64,
+	stride: u64,
+	batch_size: u64,
+) where
+	Block: BlockT,
+	C: sp_api::ProvideRuntimeApi<Block>,
+	Algorithm: PowAlgorithm<Block>,
+	Algorithm::Difficulty: Send + 'static,
+	L: sc_consensus::JustificationSyncLink<Block>,
+	sp_api::TransactionFor<C, Block>: Send + 'static,
+	S: NonceSearch<Block::Hash, Algorithm::Difficulty>,
+{
+	while shared.running.load(Ordering::Relaxed) {
+		let version = handle.version();
+		let metadata = match handle.metadata() {
+			Some(metadata) => metadata,
+			None => {
+				thread::sleep(IDLE_INTERVAL);
+				continue
+			},
+		};
+
+		let mut nonce = first_nonce;
+		while shared.running.load(Ordering::Relaxed) && handle.version() == version {
+			let mut tried = 0;
+			while tried < batch_size {
+				let seal = search.try_nonce(&metadata, nonce);
+				tried += 1;
+				nonce = nonce.wrapping_add(stride);
+
+				// a successful submission changes the version, a failed one goes on searching.
+				if let Some(seal) = seal {
+					if futures::executor::block_on(handle.submit(seal)) {
+						break
+					}
+				}
+			}
+			shared.record_hashes(tried);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Error, MiningBuild, PowBlockImport};
+	use codec::{Decode, Encode};
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_blockchain::HeaderBackend;
+	use sp_consensus::Proposal;
+	use sp_core::U256;
+	use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+	use substrate_test_runtime_client::{
+		runtime::{Block, Hash},
+		DefaultTestClientBuilderExt, TestClient, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	const DIFFICULTY: u64 = 1_000;
+
+	// Seals are nonces whose hash with the pre-hash, times the difficulty, does not overflow.
+	#[derive(Clone)]
+	struct Blake2Pow;
+
+	fn meets_difficulty(pre_hash: &Hash, nonce: u64, difficulty: U256) -> bool {
+		let hash = sp_core::blake2_256(&(pre_hash, nonce).encode());
+		!U256::from_big_endian(&hash).overflowing_mul(difficulty).1
+	}
+
+	impl PowAlgorithm<Block> for Blake2Pow {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: Hash) -> Result<U256, Error<Block>> {
+			Ok(U256::from(DIFFICULTY))
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			pre_hash: &Hash,
+			_pre_digest: Option<&[u8]>,
+			seal: &Seal,
+			difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			let nonce = u64::decode(&mut &seal[..]).map_err(Error::Codec)?;
+			Ok(meets_difficulty(pre_hash, nonce, difficulty))
+		}
+	}
+
+	impl NonceSearch<Hash, U256> for Blake2Pow {
+		fn try_nonce(&self, metadata: &MiningMetadata<Hash, U256>, nonce: u64) -> Option<Seal> {
+			meets_difficulty(&metadata.pre_hash, nonce, metadata.difficulty).then(|| nonce.encode())
+		}
+	}
+
+	fn wait_for(condition: impl Fn() -> bool) {
+		let started = Instant::now();
+		while !condition() {
+			assert!(started.elapsed() < Duration::from_secs(30), "Timed out");
+			thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	#[test]
+	fn mines_and_imports_blocks() {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let block_import = PowBlockImport::new(
+			client.clone(),
+			client.clone(),
+			Blake2Pow,
+			u64::MAX,
+			select_chain,
+			|_, _| async { Ok(()) },
+		);
+		let handle: MiningHandle<_, _, TestClient, _, ()> =
+			MiningHandle::new(Blake2Pow, Box::new(block_import), ());
+		let miner = CpuMiner::start(
+			handle.clone(),
+			Blake2Pow,
+			CpuMinerParams { threads: 4, batch_size: 100 },
+			None,
+		);
+
+		// Without a build the threads are idle.
+		thread::sleep(Duration::from_millis(200));
+		assert_eq!(miner.hashes(), 0);
+
+		for number in 1..=3 {
+			let best_hash = client.info().best_hash;
+			let built = client
+				.new_block_at(best_hash, Default::default(), false)
+				.unwrap()
+				.build()
+				.unwrap();
+			handle.on_build(MiningBuild {
+				metadata: MiningMetadata {
+					best_hash,
+					pre_hash: built.block.header.hash(),
+					pre_runtime: None,
+					difficulty: U256::from(DIFFICULTY),
+				},
+				proposal: Proposal {
+					block: built.block,
+					proof: (),
+					storage_changes: built.storage_changes,
+				},
+			});
+
+			wait_for(|| client.info().best_number == number);
+			assert!(handle.metadata().is_none());
+		}
+
+		let best = client.header(client.info().best_hash).unwrap().unwrap();
+		let seal = best.digest().logs().last().and_then(|log| log.as_seal()).unwrap().1.to_vec();
+		let nonce = u64::decode(&mut &seal[..]).unwrap();
+		let mut pre_header = best.clone();
+		pre_header.digest_mut().pop();
+		assert!(meets_difficulty(&pre_header.hash(), nonce, U256::from(DIFFICULTY)));
+		assert!(miner.hashes() > 0);
+	}
+}
@@ -0,0 +1,109 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for slot-based consensus.
+//!
+//! Serves the outcomes recorded by the [`SlotDiagnostics`] of the local slot worker.
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+use sc_consensus_slots::{SlotDiagnostics, SlotRecord};
+use sc_rpc_api::DenyUnsafe;
+use sp_core::Bytes;
+
+/// Provides rpc methods for inspecting the local slot worker.
+#[rpc(client, server)]
+pub trait SlotsApi {
+	/// Returns the outcomes of up to `count` of the most recent slots handled by the local slot
+	/// worker, oldest first.
+	///
+	/// Slots that could not be claimed by any of the local keys are not returned.
+	#[method(name = "slots_lastOutcomes")]
+	fn last_outcomes(&self, count: u32) -> RpcResult<Vec<SlotRecord<Bytes>>>;
+}
+
+/// Provides RPC methods for inspecting the local slot worker.
+pub struct Slots {
+	/// The slot outcomes recorded by the slot worker.
+	diagnostics: SlotDiagnostics,
+	/// Whether to deny unsafe calls
+	deny_unsafe: DenyUnsafe,
+}
+
+impl Slots {
+	/// Creates a new instance of the Slots Rpc handler.
+	pub fn new(diagnostics: SlotDiagnostics, deny_unsafe: DenyUnsafe) -> Self {
+		Self { diagnostics, deny_unsafe }
+	}
+}
+
+impl SlotsApiServer for Slots {
+	fn last_outcomes(&self, count: u32) -> RpcResult<Vec<SlotRecord<Bytes>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.diagnostics.last(count as usize))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_consensus_slots::SlotOutcome;
+	use std::time::Duration;
+
+	fn test_slots_rpc_module(deny_unsafe: DenyUnsafe) -> Slots {
+		let diagnostics = SlotDiagnostics::new(10, None).unwrap();
+		diagnostics.record(SlotRecord::<[u8; 1]>::new(
+			1.into(),
+			SlotOutcome::Backoff,
+			Duration::from_millis(1000),
+		));
+		diagnostics.record(
+			SlotRecord::new(
+				2.into(),
+				SlotOutcome::Authored { hash: [42u8] },
+				Duration::from_millis(1000),
+			)
+			.with_proposal_time(Duration::from_millis(250)),
+		);
+
+		Slots::new(diagnostics, deny_unsafe)
+	}
+
+	#[tokio::test]
+	async fn last_outcomes_works() {
+		let api = test_slots_rpc_module(DenyUnsafe::No).into_rpc();
+
+		let request = r#"{"jsonrpc":"2.0","method":"slots_lastOutcomes","params":[1],"id":1}"#;
+		let (response, _) = api.raw_json_request(request).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","result":[{"slot":2,"outcome":{"type":"authored","hash":"0x2a"},"budgetMs":1000,"proposalTimeMs":250}],"id":1}"#;
+
+		assert_eq!(&response.result, expected);
+	}
+
+	#[tokio::test]
+	async fn last_outcomes_is_unsafe() {
+		let api = test_slots_rpc_module(DenyUnsafe::Yes).into_rpc();
+
+		let request = r#"{"jsonrpc":"2.0","method":"slots_lastOutcomes","params":[1],"id":1}"#;
+		let (response, _) = api.raw_json_request(request).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"RPC call is unsafe to be called externally"},"id":1}"#;
+
+		assert_eq!(&response.result, expected);
+	}
+}
@@ -0,0 +1,324 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Per-slot authoring diagnostics.
+//!
+//! Every slot handled by a [`SimpleSlotWorker`](crate::SimpleSlotWorker) ends with a
+//! [`SlotOutcome`]. When the worker exposes a [`SlotDiagnostics`] handle the outcomes are reported
+//! to Prometheus and, except for slots that were not ours, kept in a bounded buffer, so that they
+//! can be served over RPC. The buffer keeps block hashes as raw bytes, so that the same handle can
+//! be passed around without knowing the block type.
+
+use parking_lot::Mutex;
+use prometheus_endpoint::{
+	register, CounterVec, Histogram, HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
+use serde::{Deserialize, Serialize};
+use sp_consensus_slots::Slot;
+use sp_core::Bytes;
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+/// Default number of slot records kept by [`SlotDiagnostics`].
+pub const DEFAULT_DIAGNOSTICS_CAPACITY: usize = 256;
+
+/// What happened in a slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum SlotOutcome<Hash> {
+	/// There was no time left to propose in the slot.
+	NoTimeLeft,
+	/// The auxiliary data needed for authoring could not be fetched.
+	AuxDataUnavailable {
+		/// The error returned when fetching the auxiliary data.
+		error: String,
+	},
+	/// Authoring was skipped because the node is offline.
+	Offline,
+	/// The slot could not be claimed by any of our keys.
+	NotOurSlot,
+	/// Authoring was skipped by the backoff strategy.
+	Backoff,
+	/// The proposer could not be created.
+	ProposerUnavailable {
+		/// The error returned when creating the proposer.
+		error: String,
+	},
+	/// The inherent data could not be created in time.
+	InherentDataUnavailable,
+	/// The proposer returned an error.
+	ProposalFailed {
+		/// The error returned by the proposer.
+		error: String,
+	},
+	/// Building the block took longer than the slot allowed.
+	ProposalTimeout,
+	/// The block import params could not be created for the proposed block.
+	BlockImportParamsFailed {
+		/// The error returned when creating the import params.
+		error: String,
+	},
+	/// The authored block failed to import.
+	ImportFailed {
+		/// Hash of the authored block.
+		hash: Hash,
+		/// The error returned by the block import.
+		error: String,
+	},
+	/// A block was authored and imported.
+	Authored {
+		/// Hash of the authored block.
+		hash: Hash,
+	},
+}
+
+impl<Hash> SlotOutcome<Hash> {
+	/// A short name of the outcome, used as the Prometheus label.
+	pub fn label(&self) -> &'static str {
+		match self {
+			Self::NoTimeLeft => "no_time_left",
+			Self::AuxDataUnavailable { .. } => "aux_data_unavailable",
+			Self::Offline => "offline",
+			Self::NotOurSlot => "not_our_slot",
+			Self::Backoff => "backoff",
+			Self::ProposerUnavailable { .. } => "proposer_unavailable",
+			Self::InherentDataUnavailable => "inherent_data_unavailable",
+			Self::ProposalFailed { .. } => "proposal_failed",
+			Self::ProposalTimeout => "proposal_timeout",
+			Self::BlockImportParamsFailed { .. } => "block_import_params_failed",
+			Self::ImportFailed { .. } => "import_failed",
+			Self::Authored { .. } => "authored",
+		}
+	}
+
+	/// Convert the block hash of the outcome, if any.
+	pub fn map_hash<T>(self, f: impl FnOnce(Hash) -> T) -> SlotOutcome<T> {
+		match self {
+			Self::NoTimeLeft => SlotOutcome::NoTimeLeft,
+			Self::AuxDataUnavailable { error } => SlotOutcome::AuxDataUnavailable { error },
+			Self::Offline => SlotOutcome::Offline,
+			Self::NotOurSlot => SlotOutcome::NotOurSlot,
+			Self::Backoff => SlotOutcome::Backoff,
+			Self::ProposerUnavailable { error } => SlotOutcome::ProposerUnavailable { error },
+			Self::InherentDataUnavailable => SlotOutcome::InherentDataUnavailable,
+			Self::ProposalFailed { error } => SlotOutcome::ProposalFailed { error },
+			Self::ProposalTimeout => SlotOutcome::ProposalTimeout,
+			Self::BlockImportParamsFailed { error } =>
+				SlotOutcome::BlockImportParamsFailed { error },
+			Self::ImportFailed { hash, error } =>
+				SlotOutcome::ImportFailed { hash: f(hash), error },
+			Self::Authored { hash } => SlotOutcome::Authored { hash: f(hash) },
+		}
+	}
+}
+
+/// The record of a single slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotRecord<Hash> {
+	/// The slot.
+	pub slot: Slot,
+	/// What happened in the slot.
+	pub outcome: SlotOutcome<Hash>,
+	/// Time that was left for proposing, in milliseconds.
+	///
+	/// For slots that reached the proposer this is measured when proposing started.
+	pub budget_ms: u64,
+	/// Time spent proposing, in milliseconds, if a proposal was attempted.
+	pub proposal_time_ms: Option<u64>,
+}
+
+impl<Hash> SlotRecord<Hash> {
+	/// Create a record for a slot in which no proposal was attempted.
+	pub fn new(slot: Slot, outcome: SlotOutcome<Hash>, budget: Duration) -> Self {
+		Self { slot, outcome, budget_ms: budget.as_millis() as u64, proposal_time_ms: None }
+	}
+
+	/// Set the time spent proposing.
+	pub fn with_proposal_time(mut self, proposal_time: Duration) -> Self {
+		self.proposal_time_ms = Some(proposal_time.as_millis() as u64);
+		self
+	}
+
+	/// Convert the block hash of the outcome, if any.
+	pub fn map_hash<T>(self, f: impl FnOnce(Hash) -> T) -> SlotRecord<T> {
+		let Self { slot, outcome, budget_ms, proposal_time_ms } = self;
+		SlotRecord { slot, outcome: outcome.map_hash(f), budget_ms, proposal_time_ms }
+	}
+}
+
+#[derive(Clone)]
+struct Metrics {
+	outcomes: CounterVec<U64>,
+	proposal_time: Histogram,
+	budget_used: Histogram,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			outcomes: register(
+				CounterVec::new(
+					Opts::new("substrate_slots_outcomes_total", "Number of slots per outcome"),
+					&["outcome"],
+				)?,
+				registry,
+			)?,
+			proposal_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"substrate_slots_proposal_time",
+					"Time spent proposing a block in a slot, in seconds",
+				))?,
+				registry,
+			)?,
+			budget_used: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_slots_proposal_budget_used",
+						"Time spent proposing as a fraction of the time left for proposing",
+					)
+					.buckets(vec![0.1, 0.25, 0.5, 0.75, 0.9, 0.95, 0.98, 1.0, 1.5]),
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+struct Inner {
+	records: Mutex<VecDeque<SlotRecord<Bytes>>>,
+	capacity: usize,
+	metrics: Option<Metrics>,
+}
+
+/// Keeps the outcomes of the most recent slots.
+///
+/// This is a cheap handle; clones share the same records.
+#[derive(Clone)]
+pub struct SlotDiagnostics {
+	inner: Arc<Inner>,
+}
+
+impl SlotDiagnostics {
+	/// Create a new instance keeping the last `capacity` slot records.
+	///
+	/// Metrics are registered if a `registry` is given.
+	pub fn new(capacity: usize, registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		let metrics = registry.map(Metrics::register).transpose()?;
+		let capacity = capacity.max(1);
+
+		Ok(Self {
+			inner: Arc::new(Inner {
+				records: Mutex::new(VecDeque::with_capacity(capacity)),
+				capacity,
+				metrics,
+			}),
+		})
+	}
+
+	/// Record the outcome of a slot, evicting the oldest record if full.
+	///
+	/// [`SlotOutcome::NotOurSlot`] is only counted in the metrics: it is the outcome of most slots
+	/// and would otherwise push all the interesting records out of the buffer.
+	pub fn record<Hash: AsRef<[u8]>>(&self, record: SlotRecord<Hash>) {
+		if let Some(metrics) = &self.inner.metrics {
+			metrics.outcomes.with_label_values(&[record.outcome.label()]).inc();
+			if let Some(proposal_time) = record.proposal_time_ms {
+				metrics.proposal_time.observe(proposal_time as f64 / 1000.0);
+				if record.budget_ms > 0 {
+					metrics.budget_used.observe(proposal_time as f64 / record.budget_ms as f64);
+				}
+			}
+		}
+
+		if matches!(record.outcome, SlotOutcome::NotOurSlot) {
+			return
+		}
+
+		let mut records = self.inner.records.lock();
+		if records.len() == self.inner.capacity {
+			records.pop_front();
+		}
+		records.push_back(record.map_hash(|hash| Bytes(hash.as_ref().to_vec())));
+	}
+
+	/// Returns up to `count` of the most recent records, oldest first.
+	pub fn last(&self, count: usize) -> Vec<SlotRecord<Bytes>> {
+		let records = self.inner.records.lock();
+		records.iter().skip(records.len().saturating_sub(count)).cloned().collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn record<Hash>(slot: u64) -> SlotRecord<Hash> {
+		SlotRecord::new(slot.into(), SlotOutcome::Backoff, Duration::from_secs(1))
+	}
+
+	#[test]
+	fn keeps_most_recent_records() {
+		let diagnostics = SlotDiagnostics::new(3, None).unwrap();
+		for slot in 0..5 {
+			diagnostics.record(record::<[u8; 32]>(slot));
+		}
+
+		assert_eq!(diagnostics.last(10), vec![record(2), record(3), record(4)]);
+		assert_eq!(diagnostics.last(2), vec![record(3), record(4)]);
+		assert!(diagnostics.last(0).is_empty());
+	}
+
+	#[test]
+	fn does_not_keep_slots_that_were_not_ours() {
+		let registry = Registry::new();
+		let diagnostics = SlotDiagnostics::new(3, Some(&registry)).unwrap();
+		diagnostics.record(record::<[u8; 32]>(1));
+		for slot in 2..10 {
+			diagnostics.record(SlotRecord::<[u8; 32]>::new(
+				slot.into(),
+				SlotOutcome::NotOurSlot,
+				Duration::from_secs(1),
+			));
+		}
+
+		assert_eq!(diagnostics.last(10), vec![record(1)]);
+		let metrics = diagnostics.inner.metrics.as_ref().unwrap();
+		assert_eq!(metrics.outcomes.with_label_values(&["not_our_slot"]).get(), 8);
+	}
+
+	#[test]
+	fn records_metrics() {
+		let registry = Registry::new();
+		let diagnostics = SlotDiagnostics::new(3, Some(&registry)).unwrap();
+		diagnostics.record(
+			SlotRecord::new(
+				1.into(),
+				SlotOutcome::Authored { hash: [1u8; 32] },
+				Duration::from_millis(1000),
+			)
+			.with_proposal_time(Duration::from_millis(500)),
+		);
+		diagnostics.record(record::<[u8; 32]>(2));
+
+		let metrics = diagnostics.inner.metrics.as_ref().unwrap();
+		assert_eq!(metrics.outcomes.with_label_values(&["authored"]).get(), 1);
+		assert_eq!(metrics.outcomes.with_label_values(&["backoff"]).get(), 1);
+		assert_eq!(metrics.proposal_time.get_sample_count(), 1);
+		assert_eq!(metrics.budget_used.get_sample_sum(), 0.5);
+	}
+}
@@ -26,9 +26,11 @@
 #![warn(missing_docs)]
 
 mod aux_schema;
+mod diagnostics;
 mod slots;
 
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use diagnostics::{SlotDiagnostics, SlotOutcome, SlotRecord, DEFAULT_DIAGNOSTICS_CAPACITY};
 pub use slots::SlotInfo;
 use slots::Slots;
 
@@ -181,6 +183,13 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Returns a [`TelemetryHandle`] if any.
 	fn telemetry(&self) -> Option<TelemetryHandle>;
 
+	/// Returns the [`SlotDiagnostics`] to record the outcome of every slot in, if any.
+	///
+	/// By default this function returns `None`.
+	fn slot_diagnostics(&self) -> Option<SlotDiagnostics> {
+		None
+	}
+
 	/// Remaining duration for proposing.
 	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> Duration;
 
@@ -202,8 +211,27 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		let telemetry = self.telemetry();
 		let log_target = self.logging_target();
 
+		let proposing_started = Instant::now();
+		let diagnostics = self.slot_diagnostics();
+		let record = |outcome: SlotOutcome<B::Hash>| {
+			if let Some(diagnostics) = &diagnostics {
+				let budget = end_proposing_at.saturating_duration_since(proposing_started);
+				diagnostics.record(
+					SlotRecord::new(slot, outcome, budget)
+						.with_proposal_time(proposing_started.elapsed()),
+				);
+			}
+		};
+
 		let inherent_data =
-			Self::create_inherent_data(&slot_info, &log_target, end_proposing_at).await?;
+			match Self::create_inherent_data(&slot_info, &log_target, end_proposing_at).await {
+				Some(inherent_data) => inherent_data,
+				None => {
+					record(SlotOutcome::InherentDataUnavailable);
+
+					return None
+				},
+			};
 
 		let proposing_remaining_duration =
 			end_proposing_at.saturating_duration_since(Instant::now());
@@ -230,6 +258,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Either::Left((Ok(p), _)) => p,
 			Either::Left((Err(err), _)) => {
 				warn!(target: log_target, "Proposing failed: {}", err);
+				record(SlotOutcome::ProposalFailed { error: err.to_string() });
 
 				return None
 			},
@@ -250,6 +279,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slots.discarding_proposal_took_too_long";
 					"slot" => *slot,
 				);
+				record(SlotOutcome::ProposalTimeout);
 
 				return None
 			},
@@ -308,11 +338,19 @@ pub trait SimpleSlotWorker<B: BlockT> {
 
 		let proposing_remaining_duration = self.proposing_remaining_duration(&slot_info);
 
+		let diagnostics = self.slot_diagnostics();
+		let record = |outcome: SlotOutcome<B::Hash>| {
+			if let Some(diagnostics) = &diagnostics {
+				diagnostics.record(SlotRecord::new(slot, outcome, proposing_remaining_duration));
+			}
+		};
+
 		let end_proposing_at = if proposing_remaining_duration == Duration::default() {
 			debug!(
 				target: logging_target,
 				"Skipping proposal slot {} since there's no time left to propose", slot,
 			);
+			record(SlotOutcome::NoTimeLeft);
 
 			return None
 		} else {
@@ -336,6 +374,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slot" => ?slot_info.chain_head.hash(),
 					"err" => ?err,
 				);
+				record(SlotOutcome::AuxDataUnavailable { error: err.to_string() });
 
 				return None
 			},
@@ -356,13 +395,23 @@ pub trait SimpleSlotWorker<B: BlockT> {
 				"slots.skipping_proposal_slot";
 				"authorities_len" => authorities_len,
 			);
+			record(SlotOutcome::Offline);
 
 			return None
 		}
 
-		let claim = self.claim_slot(&slot_info.chain_head, slot, &aux_data).await?;
+		let claim = match self.claim_slot(&slot_info.chain_head, slot, &aux_data).await {
+			Some(claim) => claim,
+			None => {
+				record(SlotOutcome::NotOurSlot);
+
+				return None
+			},
+		};
 
 		if self.should_backoff(slot, &slot_info.chain_head) {
+			record(SlotOutcome::Backoff);
+
 			return None
 		}
 
@@ -382,12 +431,24 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"slot" => *slot,
 					"err" => ?err
 				);
+				record(SlotOutcome::ProposerUnavailable { error: err.to_string() });
 
 				return None
 			},
 		};
 
+		// failed proposals are recorded by `propose` itself.
+		let proposing_started = Instant::now();
 		let proposal = self.propose(proposer, &claim, slot_info, end_proposing_at).await?;
+		let proposal_time = proposing_started.elapsed();
+		let record = |outcome: SlotOutcome<B::Hash>| {
+			if let Some(diagnostics) = &diagnostics {
+				let budget = end_proposing_at.saturating_duration_since(proposing_started);
+				diagnostics.record(
+					SlotRecord::new(slot, outcome, budget).with_proposal_time(proposal_time),
+				);
+			}
+		};
 
 		let (block, storage_proof) = (proposal.block, proposal.proof);
 		let (header, body) = block.deconstruct();
@@ -409,6 +470,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Ok(bi) => bi,
 			Err(err) => {
 				warn!(target: logging_target, "Failed to create block import params: {}", err);
+				record(SlotOutcome::BlockImportParamsFailed { error: err.to_string() });
 
 				return None
 			},
@@ -439,6 +501,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					*header.number(),
 					self.justification_sync_link(),
 				);
+				record(SlotOutcome::Authored { hash: header.hash() });
 			},
 			Err(err) => {
 				warn!(
@@ -453,6 +516,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 					"hash" => ?parent_hash,
 					"err" => ?err,
 				);
+				record(SlotOutcome::ImportFailed { hash: header.hash(), error: err.to_string() });
 			},
 		}
 
@@ -511,12 +575,35 @@ impl_inherent_data_provider_ext_tuple!(S, A, B, C, D, E, F, G, H);
 impl_inherent_data_provider_ext_tuple!(S, A, B, C, D, E, F, G, H, I);
 impl_inherent_data_provider_ext_tuple!(S, A, B, C, D, E, F, G, H, I, J);
 
+/// Provides the slot duration to the slot worker.
+///
+/// Implemented by [`SlotDuration`] for a slot duration that never changes.
+pub trait SlotDurationProvider<B: BlockT>: Send {
+	/// The slot duration to use until the chain head is known.
+	fn initial_slot_duration(&self) -> SlotDuration;
+
+	/// The slot duration of the slots following `chain_head`.
+	///
+	/// Slots of any duration are expected to start at a multiple of it since the unix epoch.
+	fn slot_duration(&self, chain_head: &B::Header) -> SlotDuration;
+}
+
+impl<B: BlockT> SlotDurationProvider<B> for SlotDuration {
+	fn initial_slot_duration(&self) -> SlotDuration {
+		*self
+	}
+
+	fn slot_duration(&self, _chain_head: &B::Header) -> SlotDuration {
+		*self
+	}
+}
+
 /// Start a new slot worker.
 ///
 /// Every time a new slot is triggered, `worker.on_slot` is called and the future it returns is
 /// polled until completion, unless we are major syncing.
-pub async fn start_slot_worker<B, C, W, SO, CIDP, Proof>(
-	slot_duration: SlotDuration,
+pub async fn start_slot_worker<B, C, W, SO, CIDP, Proof, D>(
+	slot_duration: D,
 	client: C,
 	mut worker: W,
 	sync_oracle: SO,
@@ -528,8 +615,9 @@ pub async fn start_slot_worker<B, C, W, SO, CIDP, Proof>(
 	SO: SyncOracle + Send,
 	CIDP: CreateInherentDataProviders<B, ()> + Send + 'static,
 	CIDP::InherentDataProviders: InherentDataProviderExt + Send,
+	D: SlotDurationProvider<B>,
 {
-	let mut slots = Slots::new(slot_duration.as_duration(), create_inherent_data_providers, client);
+	let mut slots = Slots::new(slot_duration, create_inherent_data_providers, client);
 
 	loop {
 		let slot_info = slots.next_slot().await;
@@ -20,7 +20,7 @@
 //!
 //! This is used instead of `futures_timer::Interval` because it was unreliable.
 
-use super::{InherentDataProviderExt, Slot, LOG_TARGET};
+use super::{InherentDataProviderExt, Slot, SlotDurationProvider, LOG_TARGET};
 use sp_consensus::SelectChain;
 use sp_inherents::{CreateInherentDataProviders, InherentDataProvider};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
@@ -87,25 +87,31 @@ impl<B: BlockT> SlotInfo<B> {
 }
 
 /// A stream that returns every time there is a new slot.
-pub(crate) struct Slots<Block, SC, IDP> {
+pub(crate) struct Slots<Block, SC, IDP, D> {
 	last_slot: Slot,
 	slot_duration: Duration,
+	slot_duration_provider: D,
 	until_next_slot: Option<Delay>,
 	create_inherent_data_providers: IDP,
 	select_chain: SC,
 	_phantom: std::marker::PhantomData<Block>,
 }
 
-impl<Block, SC, IDP> Slots<Block, SC, IDP> {
+impl<Block, SC, IDP, D> Slots<Block, SC, IDP, D>
+where
+	Block: BlockT,
+	D: SlotDurationProvider<Block>,
+{
 	/// Create a new `Slots` stream.
 	pub fn new(
-		slot_duration: Duration,
+		slot_duration_provider: D,
 		create_inherent_data_providers: IDP,
 		select_chain: SC,
 	) -> Self {
 		Slots {
 			last_slot: 0.into(),
-			slot_duration,
+			slot_duration: slot_duration_provider.initial_slot_duration().as_duration(),
+			slot_duration_provider,
 			until_next_slot: None,
 			create_inherent_data_providers,
 			select_chain,
@@ -114,12 +120,13 @@ impl<Block, SC, IDP> Slots<Block, SC, IDP> {
 	}
 }
 
-impl<Block, SC, IDP> Slots<Block, SC, IDP>
+impl<Block, SC, IDP, D> Slots<Block, SC, IDP, D>
 where
 	Block: BlockT,
 	SC: SelectChain<Block>,
 	IDP: CreateInherentDataProviders<Block, ()> + 'static,
 	IDP::InherentDataProviders: crate::InherentDataProviderExt,
+	D: SlotDurationProvider<Block>,
 {
 	/// Returns a future that fires when the next slot starts.
 	pub async fn next_slot(&mut self) -> SlotInfo<Block> {
@@ -134,10 +141,6 @@ where
 				})
 				.await;
 
-			// Schedule delay for next slot.
-			let wait_dur = time_until_next_slot(self.slot_duration);
-			self.until_next_slot = Some(Delay::new(wait_dur));
-
 			let chain_head = match self.select_chain.best_chain().await {
 				Ok(x) => x,
 				Err(e) => {
@@ -151,6 +154,14 @@ where
 				},
 			};
 
+			// The slot duration may change from one block to the next.
+			self.slot_duration =
+				self.slot_duration_provider.slot_duration(&chain_head).as_duration();
+
+			// Schedule delay for next slot.
+			let wait_dur = time_until_next_slot(self.slot_duration);
+			self.until_next_slot = Some(Delay::new(wait_dur));
+
 			let inherent_data_providers = match self
 				.create_inherent_data_providers
 				.create_inherent_data_providers(chain_head.hash(), ())
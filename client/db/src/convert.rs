@@ -0,0 +1,323 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Conversion of a RocksDB database into a ParityDB database.
+//!
+//! Every column is streamed from the RocksDB database into a new ParityDB database. Most columns
+//! are copied as they are, except for the ones that ParityDB reference counts:
+//!
+//! - Trie nodes are stored under their full prefixed key in RocksDB and under their hash only in
+//!   ParityDB. Every stored node counts as one reference.
+//! - Indexed transactions are reference counted by [`sp_database::as_database`] with a counter
+//!   stored next to the value. The counter becomes the ParityDB reference count.
+//!
+//! The state database journals, including the nodes pending deletion by the blocks in the pruning
+//! window, are then converted with [`sc_state_db::convert_journals`] and the state of the last
+//! finalized block is verified by walking its tries in the new database.
+
+use crate::{
+	apply_state_commit, columns,
+	utils::{self, DatabaseType, NUM_COLUMNS},
+	DbHash, StateMetaDb, DB_HASH_LEN,
+};
+use hash_db::{HashDBRef, Hasher, Prefix};
+use kvdb::KeyValueDB;
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys};
+use sp_database::{Database, Transaction};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, HashFor, Header as HeaderT},
+};
+use sp_trie::{trie_types::TrieDBBuilder, DBValue, Trie};
+use std::{cell::Cell, marker::PhantomData, path::Path, sync::Arc};
+
+/// Approximate amount of data written to the new database in a single transaction.
+const BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Progress of a conversion, reported after every written batch.
+#[derive(Debug, Clone, Copy)]
+pub struct ConversionProgress {
+	/// Column being copied.
+	pub column: u32,
+	/// Number of entries copied so far, over all columns.
+	pub entries: u64,
+	/// Number of bytes copied so far, over all columns.
+	pub bytes: u64,
+}
+
+/// Result of a finished conversion.
+#[derive(Debug, Clone)]
+pub struct ConversionSummary<Hash> {
+	/// Number of entries copied.
+	pub entries: u64,
+	/// Number of bytes copied.
+	pub bytes: u64,
+	/// State root of the last finalized block and the number of trie nodes verified under it,
+	/// if the database has a finalized state.
+	pub verified_state: Option<(Hash, u64)>,
+}
+
+/// Convert the RocksDB database at `source` into a new ParityDB database at `target`.
+///
+/// `source` is only read from and must not be used by a running node. `target` must not exist.
+pub fn convert_rocksdb_to_paritydb<Block: BlockT>(
+	source: &Path,
+	target: &Path,
+	cache_size: usize,
+	mut progress: impl FnMut(ConversionProgress),
+) -> ClientResult<ConversionSummary<Block::Hash>> {
+	if target.exists() {
+		return Err(ClientError::Backend(format!("{} already exists", target.display())))
+	}
+
+	let (source_db, _) =
+		utils::open_raw_kvdb_rocksdb::<Block>(source, DatabaseType::Full, false, cache_size)?;
+	let target_db: Arc<dyn Database<DbHash>> =
+		crate::parity_db::open(target, DatabaseType::Full, true, false)
+			.map_err(|e| ClientError::Backend(format!("Error opening ParityDB: {}", e)))?;
+
+	let mut batch = Batch { db: &*target_db, transaction: Transaction::new(), size: 0 };
+	let mut entries = 0;
+	let mut bytes = 0;
+	for column in 0..NUM_COLUMNS {
+		// Last indexed transaction seen, waiting for its reference counter.
+		let mut pending_transaction: Option<(Vec<u8>, Vec<u8>)> = None;
+		for item in source_db.iter(column) {
+			let (key, value) = item.map_err(db_err)?;
+			let size = key.len() + value.len();
+			entries += 1;
+			bytes += size as u64;
+
+			match column {
+				columns::STATE => {
+					let mut key = key.to_vec();
+					target_db.sanitize_key(&mut key);
+					batch.transaction.set_from_vec(columns::STATE, &key, value);
+				},
+				columns::TRANSACTION if key.len() == DB_HASH_LEN => {
+					if let Some((hash, value)) = pending_transaction.take() {
+						store_transaction(&mut batch.transaction, &hash, value, 1);
+					}
+					pending_transaction = Some((key.to_vec(), value));
+				},
+				columns::TRANSACTION => {
+					let counter = <[u8; 4]>::try_from(value.as_slice())
+						.map(u32::from_le_bytes)
+						.map_err(|_| {
+							ClientError::Backend(format!(
+								"Unexpected transaction counter 0x{:?}",
+								HexDisplay::from(&&key[..])
+							))
+						})?;
+					match pending_transaction.take() {
+						Some((hash, value)) if key[..] == [&hash[..], &[0]].concat()[..] =>
+							store_transaction(&mut batch.transaction, &hash, value, counter),
+						_ =>
+							return Err(ClientError::Backend(format!(
+								"Transaction counter 0x{:?} without a value",
+								HexDisplay::from(&&key[..])
+							))),
+					}
+				},
+				_ => batch.transaction.set_from_vec(column, &key, value),
+			}
+
+			batch.size += size;
+			if batch.size >= BATCH_SIZE {
+				batch.commit()?;
+				progress(ConversionProgress { column, entries, bytes });
+			}
+		}
+		if let Some((hash, value)) = pending_transaction.take() {
+			store_transaction(&mut batch.transaction, &hash, value, 1);
+		}
+		batch.commit()?;
+		progress(ConversionProgress { column, entries, bytes });
+	}
+	drop(source_db);
+
+	let commit = sc_state_db::convert_journals::<Block::Hash, Vec<u8>, _>(
+		&StateMetaDb(target_db.clone()),
+		|key| target_db.sanitize_key(key),
+	)
+	.map_err(ClientError::from_state_db)?;
+	let mut transaction = Transaction::new();
+	apply_state_commit(&mut transaction, commit);
+	target_db.commit(transaction)?;
+
+	let verified_state = verify_finalized_state::<Block>(&*target_db)?;
+	Ok(ConversionSummary { entries, bytes, verified_state })
+}
+
+struct Batch<'a> {
+	db: &'a dyn Database<DbHash>,
+	transaction: Transaction<DbHash>,
+	size: usize,
+}
+
+impl<'a> Batch<'a> {
+	fn commit(&mut self) -> ClientResult<()> {
+		self.size = 0;
+		Ok(self.db.commit(std::mem::take(&mut self.transaction))?)
+	}
+}
+
+fn store_transaction(
+	transaction: &mut Transaction<DbHash>,
+	hash: &[u8],
+	value: Vec<u8>,
+	references: u32,
+) {
+	for _ in 0..references {
+		transaction.store(columns::TRANSACTION, DbHash::from_slice(hash), value.clone());
+	}
+}
+
+fn db_err(e: std::io::Error) -> ClientError {
+	ClientError::Backend(format!("Error reading RocksDB: {}", e))
+}
+
+/// Walk all tries of the last finalized state, checking that every node is present and matches
+/// its hash. Returns the state root and the number of nodes.
+fn verify_finalized_state<Block: BlockT>(
+	db: &dyn Database<DbHash>,
+) -> ClientResult<Option<(Block::Hash, u64)>> {
+	let meta = utils::read_meta::<Block>(db, columns::HEADER)?;
+	let Some((hash, _)) = meta.finalized_state else { return Ok(None) };
+	let header =
+		utils::read_header::<Block>(db, columns::KEY_LOOKUP, columns::HEADER, BlockId::Hash(hash))?
+			.ok_or_else(|| ClientError::UnknownBlock(format!("{:?}", hash)))?;
+	let root = *header.state_root();
+
+	let nodes = VerifyingDb::<HashFor<Block>> { db, nodes: Cell::new(0), _phantom: PhantomData };
+	let mut child_roots = Vec::new();
+	verify_trie(&nodes, &root, |key, value| {
+		if key.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			let mut child_root = Block::Hash::default();
+			if value.len() == child_root.as_ref().len() {
+				child_root.as_mut().copy_from_slice(value);
+				child_roots.push(child_root);
+			}
+		}
+	})?;
+	for child_root in child_roots {
+		verify_trie(&nodes, &child_root, |_, _| ())?;
+	}
+	Ok(Some((root, nodes.nodes.get())))
+}
+
+fn verify_trie<H: Hasher>(
+	db: &VerifyingDb<H>,
+	root: &H::Out,
+	mut f: impl FnMut(&[u8], &[u8]),
+) -> ClientResult<()> {
+	let trie_err = |e| ClientError::Backend(format!("State verification failed: {:?}", e));
+	let trie = TrieDBBuilder::<H>::new(db, root).build();
+	for item in trie.iter().map_err(trie_err)? {
+		let (key, value) = item.map_err(trie_err)?;
+		f(&key, &value);
+	}
+	Ok(())
+}
+
+/// Trie node storage that only returns nodes matching their hash.
+struct VerifyingDb<'a, H> {
+	db: &'a dyn Database<DbHash>,
+	nodes: Cell<u64>,
+	_phantom: PhantomData<H>,
+}
+
+impl<'a, H: Hasher> HashDBRef<H, DBValue> for VerifyingDb<'a, H> {
+	fn get(&self, key: &H::Out, _prefix: Prefix) -> Option<DBValue> {
+		let node = self.db.get(columns::STATE, key.as_ref())?;
+		// A corrupted node is reported as missing by the trie.
+		(H::hash(&node) == *key).then(|| {
+			self.nodes.set(self.nodes.get() + 1);
+			node
+		})
+	}
+
+	fn contains(&self, key: &H::Out, prefix: Prefix) -> bool {
+		self.get(key, prefix).is_some()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		tests::{insert_header, Block},
+		Backend, BlocksPruning, DatabaseSettings, DatabaseSource, PruningMode,
+	};
+	use sc_client_api::backend::Backend as _;
+	use sp_blockchain::HeaderBackend;
+	use sp_core::H256;
+
+	fn settings(source: DatabaseSource) -> DatabaseSettings {
+		DatabaseSettings {
+			trie_cache_maximum_size: None,
+			state_pruning: Some(PruningMode::blocks_pruning(2)),
+			source,
+			blocks_pruning: BlocksPruning::Some(2),
+		}
+	}
+
+	#[test]
+	fn convert_rocksdb_to_paritydb_works() {
+		let dir = tempfile::tempdir().unwrap();
+		let rocksdb_path = dir.path().join("rocksdb");
+		let paritydb_path = dir.path().join("paritydb");
+
+		let best = {
+			let source = DatabaseSource::RocksDb { path: rocksdb_path.clone(), cache_size: 16 };
+			let backend = Backend::<Block>::new(settings(source), 1).unwrap();
+			let mut hash = insert_header(&backend, 0, Default::default(), None, Default::default());
+			for number in 1..5 {
+				hash = insert_header(&backend, number, hash, None, Default::default());
+			}
+			backend.finalize_block(hash, None).unwrap();
+			insert_header(&backend, 5, hash, None, H256::from_low_u64_be(5))
+		};
+
+		let summary =
+			convert_rocksdb_to_paritydb::<Block>(&rocksdb_path, &paritydb_path, 16, |_| ())
+				.unwrap();
+		assert!(summary.entries > 0);
+		assert!(matches!(summary.verified_state, Some((_, nodes)) if nodes > 0));
+		assert!(
+			convert_rocksdb_to_paritydb::<Block>(&rocksdb_path, &paritydb_path, 16, |_| ())
+				.is_err(),
+			"Existing target must not be overwritten"
+		);
+
+		let backend =
+			Backend::<Block>::new(settings(DatabaseSource::ParityDb { path: paritydb_path }), 1)
+				.unwrap();
+		assert_eq!(backend.blockchain().info().best_hash, best);
+		assert!(backend.state_at(best).is_ok());
+
+		// The converted database keeps importing and pruning blocks.
+		let mut hash = best;
+		for number in 6..10 {
+			hash = insert_header(&backend, number, hash, None, Default::default());
+		}
+		backend.finalize_block(hash, None).unwrap();
+		assert!(backend.state_at(hash).is_ok());
+	}
+}
@@ -33,6 +33,8 @@ pub mod offchain;
 pub mod bench;
 
 mod children;
+#[cfg(any(feature = "rocksdb", test))]
+mod convert;
 mod parity_db;
 mod pinned_blocks_cache;
 mod record_stats_state;
@@ -90,13 +92,15 @@ use sp_state_machine::{
 	StateMachineStats, StorageCollection, StorageIterator, StorageKey, StorageValue,
 	UsageInfo as StateUsageInfo,
 };
-use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, PrefixedMemoryDB};
+use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, MerkleValue, PrefixedMemoryDB};
 
 // Re-export the Database trait so that one can pass an implementation of it.
 pub use sc_state_db::PruningMode;
 pub use sp_database::Database;
 
 pub use bench::BenchmarkingState;
+#[cfg(any(feature = "rocksdb", test))]
+pub use convert::{convert_rocksdb_to_paritydb, ConversionProgress, ConversionSummary};
 
 const CACHE_HEADERS: usize = 8;
 
@@ -215,6 +219,21 @@ impl<B: BlockT> StateBackend<HashFor<B>> for RefTrackingState<B> {
 		self.state.child_storage_hash(child_info, key)
 	}
 
+	fn closest_merkle_value(
+		&self,
+		key: &[u8],
+	) -> Result<Option<MerkleValue<B::Hash>>, Self::Error> {
+		self.state.closest_merkle_value(key)
+	}
+
+	fn child_closest_merkle_value(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<MerkleValue<B::Hash>>, Self::Error> {
+		self.state.child_closest_merkle_value(child_info, key)
+	}
+
 	fn exists_storage(&self, key: &[u8]) -> Result<bool, Self::Error> {
 		self.state.exists_storage(key)
 	}
@@ -312,6 +331,16 @@ pub enum BlocksPruning {
 	Some(u32),
 }
 
+impl BlocksPruning {
+	/// True if this is an archive pruning mode (either KeepAll or KeepFinalized).
+	pub fn is_archive(&self) -> bool {
+		match *self {
+			BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => true,
+			BlocksPruning::Some(_) => false,
+		}
+	}
+}
+
 /// Where to find the database..
 #[derive(Debug, Clone)]
 pub enum DatabaseSource {
@@ -2479,6 +2508,12 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		)
 	}
 
+	fn checkpoint(&self, path: &std::path::Path) -> ClientResult<()> {
+		self.storage.db.checkpoint(path).map_err(|e| {
+			sp_blockchain::Error::Backend(format!("Error writing database checkpoint: {}", e))
+		})
+	}
+
 	fn pin_block(&self, hash: <Block as BlockT>::Hash) -> sp_blockchain::Result<()> {
 		let hint = || {
 			let header_metadata = self.blockchain.header_metadata(hash);
@@ -22,7 +22,21 @@ use crate::{
 /// A `Database` adapter for parity-db.
 use sp_database::{error::DatabaseError, Change, ColumnId, Database, Transaction};
 
-struct DbAdapter(parity_db::Db);
+use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+
+struct OpenDb {
+	db: parity_db::Db,
+	writable: bool,
+}
+
+struct DbAdapter {
+	/// Only `None` while the database is being reopened, or if it could not be reopened at all.
+	db: RwLock<Option<OpenDb>>,
+	options: parity_db::Options,
+	/// Held for writing while a checkpoint is taken. Commits hold it for reading, so they wait
+	/// until the database is open for writing again.
+	checkpoint: RwLock<()>,
+}
 
 fn handle_err<T>(result: parity_db::Result<T>) -> T {
 	match result {
@@ -40,6 +54,15 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 	create: bool,
 	upgrade: bool,
 ) -> parity_db::Result<std::sync::Arc<dyn Database<H>>> {
+	Ok(std::sync::Arc::new(open_adapter(path, db_type, create, upgrade)?))
+}
+
+fn open_adapter(
+	path: &std::path::Path,
+	db_type: DatabaseType,
+	create: bool,
+	upgrade: bool,
+) -> parity_db::Result<DbAdapter> {
 	let mut config = parity_db::Options::with_columns(path, NUM_COLUMNS as u8);
 
 	match db_type {
@@ -83,7 +106,11 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 		parity_db::Db::open(&config)?
 	};
 
-	Ok(std::sync::Arc::new(DbAdapter(db)))
+	Ok(DbAdapter {
+		db: RwLock::new(Some(OpenDb { db, writable: true })),
+		options: config,
+		checkpoint: RwLock::new(()),
+	})
 }
 
 fn ref_counted_column(col: u32) -> bool {
@@ -93,42 +120,47 @@ fn ref_counted_column(col: u32) -> bool {
 impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 	fn commit(&self, transaction: Transaction<H>) -> Result<(), DatabaseError> {
 		let mut not_ref_counted_column = Vec::new();
-		let result = self.0.commit(transaction.0.into_iter().filter_map(|change| {
-			Some(match change {
-				Change::Set(col, key, value) => (col as u8, key, Some(value)),
-				Change::Remove(col, key) => (col as u8, key, None),
-				Change::Store(col, key, value) =>
-					if ref_counted_column(col) {
-						(col as u8, key.as_ref().to_vec(), Some(value))
-					} else {
-						if !not_ref_counted_column.contains(&col) {
-							not_ref_counted_column.push(col);
+		let ops = transaction
+			.0
+			.into_iter()
+			.filter_map(|change| {
+				Some(match change {
+					Change::Set(col, key, value) => (col as u8, key, Some(value)),
+					Change::Remove(col, key) => (col as u8, key, None),
+					Change::Store(col, key, value) =>
+						if ref_counted_column(col) {
+							(col as u8, key.as_ref().to_vec(), Some(value))
+						} else {
+							if !not_ref_counted_column.contains(&col) {
+								not_ref_counted_column.push(col);
+							}
+							return None
+						},
+					Change::Reference(col, key) => {
+						if ref_counted_column(col) {
+							// FIXME accessing value is not strictly needed, optimize this in
+							// parity-db.
+							let value = <Self as Database<H>>::get(self, col, key.as_ref());
+							(col as u8, key.as_ref().to_vec(), value)
+						} else {
+							if !not_ref_counted_column.contains(&col) {
+								not_ref_counted_column.push(col);
+							}
+							return None
 						}
-						return None
-					},
-				Change::Reference(col, key) => {
-					if ref_counted_column(col) {
-						// FIXME accessing value is not strictly needed, optimize this in parity-db.
-						let value = <Self as Database<H>>::get(self, col, key.as_ref());
-						(col as u8, key.as_ref().to_vec(), value)
-					} else {
-						if !not_ref_counted_column.contains(&col) {
-							not_ref_counted_column.push(col);
-						}
-						return None
-					}
-				},
-				Change::Release(col, key) =>
-					if ref_counted_column(col) {
-						(col as u8, key.as_ref().to_vec(), None)
-					} else {
-						if !not_ref_counted_column.contains(&col) {
-							not_ref_counted_column.push(col);
-						}
-						return None
 					},
+					Change::Release(col, key) =>
+						if ref_counted_column(col) {
+							(col as u8, key.as_ref().to_vec(), None)
+						} else {
+							if !not_ref_counted_column.contains(&col) {
+								not_ref_counted_column.push(col);
+							}
+							return None
+						},
+				})
 			})
-		}));
+			.collect::<Vec<_>>();
 
 		if not_ref_counted_column.len() > 0 {
 			return Err(DatabaseError(Box::new(parity_db::Error::InvalidInput(format!(
@@ -137,19 +169,24 @@ impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 			)))))
 		}
 
-		result.map_err(|e| DatabaseError(Box::new(e)))
+		let _checkpoint = self.checkpoint.read();
+		match &*self.db.read() {
+			Some(OpenDb { db, writable: true }) => db.commit(ops),
+			_ => Err(parity_db::Error::InvalidInput("Database is not open for writing".into())),
+		}
+		.map_err(|e| DatabaseError(Box::new(e)))
 	}
 
 	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
-		handle_err(self.0.get(col as u8, key))
+		handle_err(self.db().get(col as u8, key))
 	}
 
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
-		handle_err(self.0.get_size(col as u8, key)).is_some()
+		<Self as Database<H>>::value_size(self, col, key).is_some()
 	}
 
 	fn value_size(&self, col: ColumnId, key: &[u8]) -> Option<usize> {
-		handle_err(self.0.get_size(col as u8, key)).map(|s| s as usize)
+		handle_err(self.db().get_size(col as u8, key)).map(|s| s as usize)
 	}
 
 	fn supports_ref_counting(&self) -> bool {
@@ -159,4 +196,106 @@ impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 	fn sanitize_key(&self, key: &mut Vec<u8>) {
 		let _prefix = key.drain(0..key.len() - crate::DB_HASH_LEN);
 	}
+
+	/// The database is closed, which lets parity-db write everything committed so far to its
+	/// tables and stops its background workers, and is reopened read-only while its files are
+	/// copied. Commits wait until the database is reopened for writing, so block import stalls
+	/// for the duration of the copy.
+	fn checkpoint(&self, path: &std::path::Path) -> Result<(), DatabaseError> {
+		let _checkpoint = self.checkpoint.write();
+		let to_db_err = |e: parity_db::Error| DatabaseError(Box::new(e));
+		if path.exists() {
+			return Err(to_db_err(parity_db::Error::InvalidInput(format!(
+				"Checkpoint path {} already exists",
+				path.display()
+			))))
+		}
+
+		let copied = self.reopen(false).and_then(|()| {
+			copy_database_files(&self.options.path, path).map_err(parity_db::Error::Io)
+		});
+		self.reopen(true).map_err(to_db_err)?;
+		copied.map_err(to_db_err)
+	}
+}
+
+impl DbAdapter {
+	fn db(&self) -> MappedRwLockReadGuard<'_, parity_db::Db> {
+		RwLockReadGuard::map(self.db.read(), |db| {
+			&db.as_ref().expect("The database could not be reopened after a checkpoint").db
+		})
+	}
+
+	/// Close the database and open it again, for writing or read-only.
+	///
+	/// If that fails, the database is left open read-only if possible.
+	fn reopen(&self, writable: bool) -> parity_db::Result<()> {
+		let mut db = self.db.write();
+		// Waits for the background workers, which write all queued commits to the tables.
+		drop(db.take());
+		let reopened = if writable {
+			parity_db::Db::open(&self.options)
+		} else {
+			parity_db::Db::open_read_only(&self.options)
+		};
+		match reopened {
+			Ok(reopened) => {
+				*db = Some(OpenDb { db: reopened, writable });
+				Ok(())
+			},
+			Err(e) => {
+				*db = parity_db::Db::open_read_only(&self.options)
+					.ok()
+					.map(|db| OpenDb { db, writable: false });
+				Err(e)
+			},
+		}
+	}
+}
+
+/// Copy the files of a closed database from `from` to the new directory `to`.
+fn copy_database_files(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+	std::fs::create_dir_all(to)?;
+	for entry in std::fs::read_dir(from)? {
+		let entry = entry?;
+		// The lock file belongs to the running instance.
+		if !entry.file_type()?.is_file() || entry.file_name() == "lock" {
+			continue
+		}
+		std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn checkpoint_keeps_database_writable() {
+		let dir = tempfile::tempdir().unwrap();
+		let db_path = dir.path().join("db");
+		let checkpoint_path = dir.path().join("checkpoint");
+		let adapter = open_adapter(&db_path, DatabaseType::Full, true, false).unwrap();
+
+		let mut tx = Transaction::<H256>::new();
+		tx.set(columns::AUX, b"before", b"1");
+		adapter.commit(tx).unwrap();
+
+		Database::<H256>::checkpoint(&adapter, &checkpoint_path).unwrap();
+		assert!(Database::<H256>::checkpoint(&adapter, &checkpoint_path).is_err());
+
+		let mut tx = Transaction::<H256>::new();
+		tx.set(columns::AUX, b"after", b"2");
+		tx.remove(columns::AUX, b"before");
+		adapter.commit(tx).unwrap();
+		assert_eq!(Database::<H256>::get(&adapter, columns::AUX, b"after"), Some(b"2".to_vec()));
+		assert!(!Database::<H256>::contains(&adapter, columns::AUX, b"before"));
+		drop(adapter);
+
+		let checkpoint = open::<H256>(&checkpoint_path, DatabaseType::Full, false, false).unwrap();
+		assert_eq!(checkpoint.get(columns::AUX, b"before"), Some(b"1".to_vec()));
+		assert_eq!(checkpoint.get(columns::AUX, b"after"), None);
+	}
 }
@@ -300,6 +300,69 @@ fn open_kvdb_rocksdb<Block: BlockT>(
 	create: bool,
 	cache_size: usize,
 ) -> OpenDbResult {
+	let (db, config) = open_raw_kvdb_rocksdb::<Block>(path, db_type, create, cache_size)?;
+
+	let db_path = path.to_path_buf();
+	let create_checkpoint = move |checkpoint_path: &Path| {
+		let secondary_path = checkpoint_path.with_extension("secondary");
+		let copied = copy_rocksdb(&db_path, &secondary_path, &config, checkpoint_path);
+		let _ = fs::remove_dir_all(&secondary_path);
+		copied?;
+		crate::upgrade::update_version(checkpoint_path)
+	};
+	Ok(sp_database::as_database_with_checkpoint(db, create_checkpoint))
+}
+
+/// Copy the RocksDB database at `path`, which is open, to a new database at `target`.
+///
+/// RocksDB only creates checkpoints through the primary instance, which is not exposed by
+/// `kvdb_rocksdb`. The database is read through a secondary instance instead, whose logs are kept
+/// at `secondary_path`: once it caught up with the primary, it is a consistent view of the
+/// database that is not affected by the writes to the primary.
+#[cfg(any(feature = "rocksdb", test))]
+fn copy_rocksdb(
+	path: &Path,
+	secondary_path: &Path,
+	config: &kvdb_rocksdb::DatabaseConfig,
+	target: &Path,
+) -> io::Result<()> {
+	/// Approximate amount of data written to the new database in a single transaction.
+	const BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+	let mut secondary_config = config.clone();
+	secondary_config.secondary = Some(secondary_path.to_path_buf());
+	let source = kvdb_rocksdb::Database::open(&secondary_config, path)?;
+	source.try_catch_up_with_primary()?;
+
+	let mut target_config = config.clone();
+	target_config.create_if_missing = true;
+	let target = kvdb_rocksdb::Database::open(&target_config, target)?;
+	for column in 0..NUM_COLUMNS {
+		let mut transaction = target.transaction();
+		let mut size = 0;
+		for entry in source.iter(column) {
+			let (key, value) = entry?;
+			size += key.len() + value.len();
+			transaction.put_vec(column, &key, value);
+			if size >= BATCH_SIZE {
+				target.write(std::mem::take(&mut transaction))?;
+				size = 0;
+			}
+		}
+		target.write(transaction)?;
+	}
+	Ok(())
+}
+
+/// Open the RocksDB database at `path` without wrapping it into a [`Database`]. Returns the
+/// database together with the configuration it was opened with.
+#[cfg(any(feature = "rocksdb", test))]
+pub(crate) fn open_raw_kvdb_rocksdb<Block: BlockT>(
+	path: &Path,
+	db_type: DatabaseType,
+	create: bool,
+	cache_size: usize,
+) -> Result<(kvdb_rocksdb::Database, kvdb_rocksdb::DatabaseConfig), OpenDbError> {
 	// first upgrade database to required version
 	match crate::upgrade::upgrade_db::<Block>(path, db_type) {
 		// in case of missing version file, assume that database simply does not exist at given
@@ -340,7 +403,7 @@ fn open_kvdb_rocksdb<Block: BlockT>(
 	let db = kvdb_rocksdb::Database::open(&db_config, path)?;
 	// write database version only after the database is succesfully opened
 	crate::upgrade::update_version(path)?;
-	Ok(sp_database::as_database(db))
+	Ok((db, db_config))
 }
 
 #[cfg(not(any(feature = "rocksdb", test)))]
@@ -824,4 +887,45 @@ mod tests {
 			assert!(db_res.is_ok(), "Existing parity database should be reopened");
 		}
 	}
+
+	fn assert_checkpoint_works(source: DatabaseSource, checkpoint_source: DatabaseSource) {
+		let checkpoint_path = checkpoint_source.path().unwrap().to_owned();
+		{
+			let db = open_database::<Block>(&source, DatabaseType::Full, true).unwrap();
+			let mut tx = Transaction::new();
+			tx.set(crate::columns::AUX, b"before", b"value");
+			db.commit(tx).unwrap();
+
+			db.checkpoint(&checkpoint_path).unwrap();
+			assert!(db.checkpoint(&checkpoint_path).is_err(), "Existing path must not be reused");
+
+			let mut tx = Transaction::new();
+			tx.set(crate::columns::AUX, b"after", b"value");
+			db.commit(tx).unwrap();
+		}
+
+		let checkpoint = open_database::<Block>(&checkpoint_source, DatabaseType::Full, false)
+			.expect("Checkpoint should be opened as a database");
+		assert_eq!(checkpoint.get(crate::columns::AUX, b"before"), Some(b"value".to_vec()));
+		assert_eq!(checkpoint.get(crate::columns::AUX, b"after"), None);
+	}
+
+	#[cfg(feature = "rocksdb")]
+	#[test]
+	fn rocksdb_checkpoint_works() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		assert_checkpoint_works(
+			DatabaseSource::RocksDb { path: db_dir.path().join("db"), cache_size: 128 },
+			DatabaseSource::RocksDb { path: db_dir.path().join("checkpoint"), cache_size: 128 },
+		);
+	}
+
+	#[test]
+	fn paritydb_checkpoint_works() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		assert_checkpoint_works(
+			DatabaseSource::ParityDb { path: db_dir.path().join("db") },
+			DatabaseSource::ParityDb { path: db_dir.path().join("checkpoint") },
+		);
+	}
 }
@@ -19,7 +19,7 @@
 use crate::{
 	error::{Error, Result},
 	wasm_runtime::{RuntimeCache, WasmExecutionMethod},
-	RuntimeVersionOf,
+	PrecompileRuntime, RuntimeVersionOf,
 };
 
 use std::{
@@ -534,6 +534,30 @@ where
 	}
 }
 
+impl<H> PrecompileRuntime for WasmExecutor<H>
+where
+	H: HostFunctions,
+{
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &RuntimeCode,
+	) -> Result<()> {
+		let on_chain_heap_alloc_strategy = runtime_code
+			.heap_pages
+			.map(|h| HeapAllocStrategy::Static { extra_pages: h as _ })
+			.unwrap_or_else(|| self.default_onchain_heap_alloc_strategy);
+
+		self.cache.precompile::<H>(
+			runtime_code,
+			ext,
+			self.method,
+			on_chain_heap_alloc_strategy,
+			self.allow_missing_host_functions,
+		)
+	}
+}
+
 /// A generic `CodeExecutor` implementation that uses a delegate to determine wasm code equivalence
 /// and dispatch to native code when possible, falling back on `WasmExecutor` when not.
 pub struct NativeElseWasmExecutor<D: NativeExecutionDispatch> {
@@ -607,6 +631,16 @@ impl<D: NativeExecutionDispatch> RuntimeVersionOf for NativeElseWasmExecutor<D>
 	}
 }
 
+impl<D: NativeExecutionDispatch> PrecompileRuntime for NativeElseWasmExecutor<D> {
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &RuntimeCode,
+	) -> Result<()> {
+		self.wasm.precompile_runtime(ext, runtime_code)
+	}
+}
+
 impl<D: NativeExecutionDispatch> GetNativeVersion for NativeElseWasmExecutor<D> {
 	fn native_version(&self) -> &NativeVersion {
 		&self.native_version
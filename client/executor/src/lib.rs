@@ -67,6 +67,19 @@ pub trait RuntimeVersionOf {
 	) -> error::Result<RuntimeVersion>;
 }
 
+/// Prepares a runtime ahead of its first use.
+pub trait PrecompileRuntime {
+	/// Compile the given `runtime_code` and keep it cached, so that the first call into it does not
+	/// have to wait for the compilation.
+	///
+	/// This can take a long time and should not be called from an async context.
+	fn precompile_runtime(
+		&self,
+		ext: &mut dyn Externalities,
+		runtime_code: &sp_core::traits::RuntimeCode,
+	) -> error::Result<()>;
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -285,6 +285,77 @@ impl RuntimeCache {
 
 		Ok(versioned_runtime.with_instance(ext, f))
 	}
+
+	/// Prepares the runtime given by `runtime_code` and puts it into the cache without executing
+	/// anything.
+	///
+	/// Unlike [`Self::with_instance`], the cache lock is not held while the runtime is being
+	/// prepared, so calls into the runtimes already in the cache can proceed meanwhile. This makes
+	/// it suitable for compiling a runtime in the background ahead of its first use.
+	///
+	/// Does nothing if the runtime is already cached or if the cache can only hold a single
+	/// runtime, as inserting it would evict the one currently in use.
+	pub fn precompile<H>(
+		&self,
+		runtime_code: &RuntimeCode,
+		ext: &mut dyn Externalities,
+		wasm_method: WasmExecutionMethod,
+		heap_alloc_strategy: HeapAllocStrategy,
+		allow_missing_func_imports: bool,
+	) -> Result<(), Error>
+	where
+		H: HostFunctions,
+	{
+		let versioned_runtime_id = VersionedRuntimeId {
+			code_hash: runtime_code.hash.clone(),
+			heap_alloc_strategy,
+			wasm_method,
+		};
+
+		{
+			let runtimes = self.runtimes.lock();
+			if runtimes.cap().get() < 2 {
+				tracing::debug!(
+					target: "wasm-runtime",
+					"Not precompiling runtime, the runtime cache can only hold one runtime.",
+				);
+				return Ok(())
+			}
+
+			if runtimes.contains(&versioned_runtime_id) {
+				return Ok(())
+			}
+		}
+
+		let code = runtime_code.fetch_runtime_code().ok_or(WasmError::CodeNotFound)?;
+
+		let time = std::time::Instant::now();
+
+		let versioned_runtime = create_versioned_wasm_runtime::<H>(
+			&code,
+			ext,
+			wasm_method,
+			heap_alloc_strategy,
+			allow_missing_func_imports,
+			self.max_runtime_instances,
+			self.cache_path.as_deref(),
+		)?;
+
+		tracing::debug!(
+			target: "wasm-runtime",
+			"Precompiled runtime version {:?} in {} ms.",
+			versioned_runtime.version,
+			time.elapsed().as_millis(),
+		);
+
+		let mut runtimes = self.runtimes.lock();
+		// The runtime may have been prepared by a call in the meantime.
+		if !runtimes.contains(&versioned_runtime_id) {
+			runtimes.put(versioned_runtime_id, Arc::new(versioned_runtime));
+		}
+
+		Ok(())
+	}
 }
 
 /// Create a wasm runtime with the given `code`.
@@ -570,4 +641,51 @@ mod tests {
 
 		assert_eq!(runtime_version, read_version);
 	}
+
+	#[test]
+	fn precompiled_runtime_is_used_without_fetching_code() {
+		struct NoCode;
+
+		impl FetchRuntimeCode for NoCode {
+			fn fetch_runtime_code(&self) -> Option<std::borrow::Cow<[u8]>> {
+				None
+			}
+		}
+
+		let cache = RuntimeCache::new(1, None, 2);
+		let mut ext = sp_state_machine::BasicExternalities::default();
+		let hash = vec![1, 2, 3];
+
+		let code = sp_core::traits::WrappedRuntimeCode(
+			substrate_test_runtime::wasm_binary_unwrap().into(),
+		);
+		let runtime_code =
+			RuntimeCode { code_fetcher: &code, heap_pages: None, hash: hash.clone() };
+		cache
+			.precompile::<sp_io::SubstrateHostFunctions>(
+				&runtime_code,
+				&mut ext,
+				WasmExecutionMethod::Interpreted,
+				sc_executor_common::wasm_runtime::DEFAULT_HEAP_ALLOC_STRATEGY,
+				true,
+			)
+			.unwrap();
+
+		// The code can no longer be fetched, so this only works if the runtime is cached.
+		let runtime_code = RuntimeCode { code_fetcher: &NoCode, heap_pages: None, hash };
+		let version = cache
+			.with_instance::<sp_io::SubstrateHostFunctions, _, _>(
+				&runtime_code,
+				&mut ext,
+				WasmExecutionMethod::Interpreted,
+				sc_executor_common::wasm_runtime::DEFAULT_HEAP_ALLOC_STRATEGY,
+				true,
+				|_, _, version, _| Ok(version.cloned()),
+			)
+			.unwrap()
+			.unwrap()
+			.expect("Test runtime has a version");
+
+		assert_eq!(version.spec_name, "test".into());
+	}
 }
@@ -121,9 +121,10 @@ impl<B: BlockT> InformantDisplay<B> {
 					"⚙️ ",
 					"Downloading state".into(),
 					format!(
-						", {}%, {:.2} Mib",
+						", {}%, {:.2} Mib from {} peers",
 						state.percentage,
-						(state.size as f32) / (1024f32 * 1024f32)
+						(state.size as f32) / (1024f32 * 1024f32),
+						state.peers.iter().filter(|peer| peer.downloading).count(),
 					),
 				),
 				(SyncState::Idle, _, _) => ("💤", "Idle".into(), "".into()),
@@ -1,4 +1,5 @@
-const PROTOS: &[&str] = &["src/schema/bitswap.v1.2.0.proto"];
+const PROTOS: &[&str] =
+	&["src/schema/bitswap.v1.2.0.proto", "src/schema/dag-pb.proto", "src/schema/unixfs.proto"];
 
 fn main() {
 	prost_build::compile_protos(PROTOS, &["src/schema"]).unwrap();
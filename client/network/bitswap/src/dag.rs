@@ -0,0 +1,178 @@
+// Copyright Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Files stored as UnixFS DAGs.
+//!
+//! The bitswap server returns any indexed transaction by the CID of its content. A file too large
+//! for a single transaction can still be fetched by standard IPFS clients if it is stored as a
+//! DAG:
+//!
+//! 1. Split the file into chunks and store each of them with its own transaction.
+//! 2. Store the root node returned by [`encode_file_root`] in a last transaction.
+//! 3. Share the CID of the root node, [`blake2b_cid`] with the [`DAG_PB_CODEC`] codec.
+//!
+//! [`encode_file`] does all of the above, returning the payloads of the
+//! `pallet_transaction_storage::Call::store` transactions along with the CID of the file.
+//!
+//! Clients fetching the root node find the CIDs of the chunks in it, request them in a single
+//! wantlist, and concatenate them into the original file.
+
+use crate::{
+	schema::{
+		merkledag::{PbLink, PbNode},
+		unixfs::{self, data::DataType},
+	},
+	BitswapError,
+};
+use cid::{
+	multihash::{Code, Multihash},
+	Cid,
+};
+use prost::Message;
+use sp_core::hashing::blake2_256;
+
+/// Multicodec of blocks holding raw binary data.
+pub const RAW_CODEC: u64 = 0x55;
+
+/// Multicodec of blocks holding a dag-pb node.
+pub const DAG_PB_CODEC: u64 = 0x70;
+
+/// Chunk of a file, stored in its own transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChunk {
+	/// Blake2b-256 hash of the chunk, which is also the hash of the indexed transaction.
+	pub hash: [u8; 32],
+	/// Size of the chunk in bytes.
+	pub size: u64,
+}
+
+/// Returns the CID of the block with the given codec and Blake2b-256 hash.
+pub fn blake2b_cid(codec: u64, hash: &[u8; 32]) -> Cid {
+	let multihash = Multihash::wrap(Code::Blake2b256.into(), hash)
+		.expect("32 bytes always fit in a multihash; qed");
+	Cid::new_v1(codec, multihash)
+}
+
+/// Encodes the root node of the file made of `chunks`, in order.
+///
+/// The chunks are linked as raw blocks, as with `ipfs add --raw-leaves`.
+pub fn encode_file_root(chunks: &[FileChunk]) -> Vec<u8> {
+	let data = unixfs::Data {
+		r#type: DataType::File as i32,
+		filesize: Some(chunks.iter().map(|chunk| chunk.size).sum()),
+		blocksizes: chunks.iter().map(|chunk| chunk.size).collect(),
+		..Default::default()
+	}
+	.encode_to_vec();
+
+	// Canonical dag-pb puts the links before the data, while `prost` would encode the fields in
+	// tag order. Strict decoders reject the latter, so the node is assembled by hand.
+	let mut node = Vec::new();
+	for chunk in chunks {
+		let link = PbLink {
+			hash: Some(blake2b_cid(RAW_CODEC, &chunk.hash).to_bytes()),
+			name: Some(String::new()),
+			tsize: Some(chunk.size),
+		};
+		prost::encoding::message::encode(2, &link, &mut node);
+	}
+	prost::encoding::bytes::encode(1, &data, &mut node);
+
+	node
+}
+
+/// Splits `data` into chunks of at most `chunk_size` bytes and encodes the root node linking them.
+///
+/// Returns the payloads to store, one transaction each, and the CID of the file. The root node is
+/// the last payload. `chunk_size` must not exceed the `MaxTransactionSize` of
+/// `pallet-transaction-storage`, and neither must the root node, which takes around 50 bytes per
+/// chunk.
+pub fn encode_file(data: &[u8], chunk_size: usize) -> (Vec<Vec<u8>>, Cid) {
+	let mut payloads = data.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect::<Vec<_>>();
+	let chunks = payloads
+		.iter()
+		.map(|chunk| FileChunk { hash: blake2_256(chunk), size: chunk.len() as u64 })
+		.collect::<Vec<_>>();
+
+	let root = encode_file_root(&chunks);
+	let cid = blake2b_cid(DAG_PB_CODEC, &blake2_256(&root));
+	payloads.push(root);
+
+	(payloads, cid)
+}
+
+/// Decodes a root node produced by [`encode_file_root`], returning the CIDs of the chunks in
+/// order.
+pub fn decode_file_root(node: &[u8]) -> Result<Vec<Cid>, BitswapError> {
+	let node = PbNode::decode(node)?;
+	let data = unixfs::Data::decode(node.data.unwrap_or_default().as_slice())?;
+	if data.r#type != DataType::File as i32 {
+		return Err(BitswapError::InvalidDagNode)
+	}
+
+	node.links
+		.into_iter()
+		.map(|link| {
+			let hash = link.hash.ok_or(BitswapError::InvalidDagNode)?;
+			Ok(Cid::read_bytes(hash.as_slice())?)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn file_root_is_canonical_dag_pb() {
+		let chunks =
+			vec![FileChunk { hash: [1; 32], size: 1024 }, FileChunk { hash: [2; 32], size: 512 }];
+		let root = encode_file_root(&chunks);
+
+		// Links (field 2) come first and data (field 1) last.
+		assert_eq!(root[0], 2 << 3 | 2);
+		let node = PbNode::decode(root.as_slice()).unwrap();
+		let links_len = node.links.iter().map(|link| link.encoded_len()).sum::<usize>();
+		assert_eq!(root[links_len + node.links.len() * 2], 1 << 3 | 2);
+
+		let data = unixfs::Data::decode(node.data.unwrap().as_slice()).unwrap();
+		assert_eq!(data.filesize, Some(1536));
+		assert_eq!(data.blocksizes, vec![1024, 512]);
+
+		assert_eq!(
+			decode_file_root(&root).unwrap(),
+			vec![blake2b_cid(RAW_CODEC, &[1; 32]), blake2b_cid(RAW_CODEC, &[2; 32])],
+		);
+	}
+
+	#[test]
+	fn file_is_split_into_chunks_and_root() {
+		let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+		let (payloads, cid) = encode_file(&data, 384);
+
+		assert_eq!(payloads.len(), 4);
+		assert_eq!(payloads[..3].concat(), data);
+		assert_eq!(payloads[2].len(), 1000 - 2 * 384);
+		assert_eq!(cid, blake2b_cid(DAG_PB_CODEC, &blake2_256(&payloads[3])));
+		assert_eq!(
+			decode_file_root(&payloads[3]).unwrap(),
+			payloads[..3]
+				.iter()
+				.map(|chunk| blake2b_cid(RAW_CODEC, &blake2_256(chunk)))
+				.collect::<Vec<_>>(),
+		);
+	}
+}
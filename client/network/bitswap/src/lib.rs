@@ -19,6 +19,9 @@
 //! Allows querying transactions by hash over standard bitswap protocol
 //! Only supports bitswap 1.2.0.
 //! CID is expected to reference 256-bit Blake2b transaction hash.
+//!
+//! Files larger than a single transaction can be stored and served as UnixFS DAGs, see the
+//! [`dag`] module.
 
 use cid::{self, Version};
 use futures::{channel::mpsc, StreamExt};
@@ -38,6 +41,7 @@ use sp_runtime::traits::Block as BlockT;
 use std::{io, sync::Arc, time::Duration};
 use unsigned_varint::encode as varint_encode;
 
+pub mod dag;
 mod schema;
 
 const LOG_TARGET: &str = "bitswap";
@@ -52,7 +56,10 @@ const MAX_PACKET_SIZE: u64 = 16 * 1024 * 1024;
 const MAX_REQUEST_QUEUE: usize = 20;
 
 /// Max number of blocks per wantlist
-const MAX_WANTED_BLOCKS: usize = 16;
+const MAX_WANTED_BLOCKS: usize = 128;
+
+/// Upper bound of the bytes needed to add a block to a response, on top of the encoded block.
+const BLOCK_OVERHEAD: usize = 16;
 
 /// Bitswap protocol name
 const PROTOCOL_NAME: &'static str = "/ipfs/bitswap/1.2.0";
@@ -185,7 +192,16 @@ impl<B: BlockT> BitswapRequestHandler<B> {
 			return Err(BitswapError::TooManyEntries)
 		}
 
+		// Set once a block didn't fit in the response. From then on, the remaining blocks are only
+		// looked up, so that a long wantlist never makes us read more than a response can carry.
+		let mut response_full = false;
+
 		for entry in wantlist.entries {
+			// Requests are answered right away, there is nothing to cancel.
+			if entry.cancel {
+				continue
+			}
+
 			let cid = match cid::Cid::read_bytes(entry.block.as_slice()) {
 				Ok(cid) => cid,
 				Err(e) => {
@@ -204,45 +220,64 @@ impl<B: BlockT> BitswapRequestHandler<B> {
 
 			let mut hash = B::Hash::default();
 			hash.as_mut().copy_from_slice(&cid.hash().digest()[0..32]);
-			let transaction = match self.client.indexed_transaction(hash) {
-				Ok(ex) => ex,
-				Err(e) => {
-					error!(target: LOG_TARGET, "Error retrieving transaction {}: {}", hash, e);
-					None
-				},
-			};
 
-			match transaction {
-				Some(transaction) => {
-					trace!(target: LOG_TARGET, "Found CID {:?}, hash {:?}", cid, hash);
+			let found = if entry.want_type == WantType::Block as i32 && !response_full {
+				match self.client.indexed_transaction(hash) {
+					Ok(Some(transaction)) => {
+						trace!(target: LOG_TARGET, "Found CID {:?}, hash {:?}", cid, hash);
 
-					if entry.want_type == WantType::Block as i32 {
 						let prefix = Prefix {
 							version: cid.version(),
 							codec: cid.codec(),
 							mh_type: cid.hash().code(),
 							mh_len: cid.hash().size(),
 						};
-						response
-							.payload
-							.push(MessageBlock { prefix: prefix.to_bytes(), data: transaction });
-					} else {
-						response.block_presences.push(BlockPresence {
-							r#type: BlockPresenceType::Have as i32,
-							cid: cid.to_bytes(),
-						});
-					}
-				},
-				None => {
-					trace!(target: LOG_TARGET, "Missing CID {:?}, hash {:?}", cid, hash);
-
-					if entry.send_dont_have {
-						response.block_presences.push(BlockPresence {
-							r#type: BlockPresenceType::DontHave as i32,
-							cid: cid.to_bytes(),
-						});
-					}
-				},
+						let block = MessageBlock { prefix: prefix.to_bytes(), data: transaction };
+
+						if response.encoded_len() + block.encoded_len() + BLOCK_OVERHEAD <=
+							MAX_PACKET_SIZE as usize
+						{
+							response.payload.push(block);
+							continue
+						}
+
+						// The block is announced instead, so that the client asks for it again in
+						// its next request.
+						response_full = true;
+						true
+					},
+					Ok(None) => false,
+					Err(e) => {
+						error!(target: LOG_TARGET, "Error retrieving transaction {}: {}", hash, e);
+						false
+					},
+				}
+			} else {
+				match self.client.has_indexed_transaction(hash) {
+					Ok(found) => found,
+					Err(e) => {
+						error!(target: LOG_TARGET, "Error retrieving transaction {}: {}", hash, e);
+						false
+					},
+				}
+			};
+
+			if found {
+				trace!(target: LOG_TARGET, "Found CID {:?}, hash {:?}", cid, hash);
+
+				response.block_presences.push(BlockPresence {
+					r#type: BlockPresenceType::Have as i32,
+					cid: cid.to_bytes(),
+				});
+			} else {
+				trace!(target: LOG_TARGET, "Missing CID {:?}, hash {:?}", cid, hash);
+
+				if entry.send_dont_have {
+					response.block_presences.push(BlockPresence {
+						r#type: BlockPresenceType::DontHave as i32,
+						cid: cid.to_bytes(),
+					});
+				}
 			}
 		}
 
@@ -284,6 +319,10 @@ pub enum BitswapError {
 	/// Too many blocks requested.
 	#[error("Too many block entries in the request.")]
 	TooManyEntries,
+
+	/// DAG node that isn't a UnixFS file root.
+	#[error("Invalid UnixFS file node.")]
+	InvalidDagNode,
 }
 
 #[cfg(test)]
@@ -522,4 +561,81 @@ mod tests {
 			panic!("invalid event received");
 		}
 	}
+
+	#[tokio::test]
+	async fn file_dag_is_served() {
+		let mut client = TestClientBuilder::with_tx_storage(u32::MAX).build();
+		let mut block_builder = client.new_block(Default::default()).unwrap();
+
+		let file = [vec![0x13; 100], vec![0x37; 100], vec![0x42; 10]].concat();
+		let (payloads, root_cid) = dag::encode_file(&file, 100);
+
+		for data in &payloads {
+			block_builder.push(Extrinsic::Store(data.clone())).unwrap();
+		}
+		let block = block_builder.build().unwrap().block;
+		client.import(BlockOrigin::File, block).await.unwrap();
+
+		let (bitswap, config) = BitswapRequestHandler::new(Arc::new(client));
+		tokio::spawn(async move { bitswap.run().await });
+		let mut inbound_queue = config.inbound_queue.unwrap();
+
+		let mut request = |entries: Vec<Entry>| {
+			let (tx, rx) = oneshot::channel();
+			let request = IncomingRequest {
+				peer: PeerId::random(),
+				payload: BitswapMessage {
+					wantlist: Some(Wantlist { entries, full: false }),
+					..Default::default()
+				}
+				.encode_to_vec(),
+				pending_response: tx,
+			};
+			inbound_queue.try_send(request).unwrap();
+			async move {
+				let response = rx.await.unwrap().result.expect("fetch to succeed");
+				schema::bitswap::Message::decode(&response[..]).unwrap()
+			}
+		};
+
+		// Fetch the root node and find the chunks in it.
+		let response =
+			request(vec![Entry { block: root_cid.to_bytes(), ..Default::default() }]).await;
+		let links = dag::decode_file_root(&response.payload[0].data).unwrap();
+		assert_eq!(links.len(), 3);
+
+		// Fetch all the chunks at once, and ask whether an unknown block is available.
+		let missing = dag::blake2b_cid(dag::RAW_CODEC, &[0u8; 32]);
+		let mut entries = links
+			.iter()
+			.map(|cid| Entry { block: cid.to_bytes(), ..Default::default() })
+			.collect::<Vec<_>>();
+		entries.push(Entry {
+			block: missing.to_bytes(),
+			want_type: WantType::Have as i32,
+			send_dont_have: true,
+			..Default::default()
+		});
+		let response = request(entries).await;
+
+		let fetched = response.payload.into_iter().flat_map(|block| block.data).collect::<Vec<_>>();
+		assert_eq!(fetched, file);
+		assert_eq!(
+			response.block_presences,
+			vec![BlockPresence {
+				r#type: BlockPresenceType::DontHave as i32,
+				cid: missing.to_bytes(),
+			}],
+		);
+
+		// Availability of a chunk is reported without sending it.
+		let response = request(vec![Entry {
+			block: links[0].to_bytes(),
+			want_type: WantType::Have as i32,
+			..Default::default()
+		}])
+		.await;
+		assert!(response.payload.is_empty());
+		assert_eq!(response.block_presences[0].r#type, BlockPresenceType::Have as i32);
+	}
 }
@@ -21,3 +21,11 @@
 pub(crate) mod bitswap {
 	include!(concat!(env!("OUT_DIR"), "/bitswap.message.rs"));
 }
+
+pub(crate) mod merkledag {
+	include!(concat!(env!("OUT_DIR"), "/merkledag.rs"));
+}
+
+pub(crate) mod unixfs {
+	include!(concat!(env!("OUT_DIR"), "/unixfs.rs"));
+}
@@ -83,6 +83,19 @@ pub struct StateDownloadProgress {
 	pub percentage: u32,
 	/// Total state size in bytes downloaded so far.
 	pub size: u64,
+	/// Progress of the download from each peer state was requested from.
+	pub peers: Vec<PeerStateDownloadProgress>,
+}
+
+/// State download progress from a single peer.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PeerStateDownloadProgress {
+	/// Peer the state is downloaded from.
+	pub peer_id: PeerId,
+	/// State size in bytes downloaded from this peer so far.
+	pub size: u64,
+	/// Whether a state request to this peer is in flight.
+	pub downloading: bool,
 }
 
 /// Syncing status and statistics.
@@ -17,6 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+	config::ProtocolBandwidthLimit,
 	discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
 	event::DhtEvent,
 	peer_info,
@@ -171,6 +172,7 @@ impl<B: BlockT> Behaviour<B> {
 		local_public_key: PublicKey,
 		disco_config: DiscoveryConfig,
 		request_response_protocols: Vec<ProtocolConfig>,
+		protocol_bandwidth_limits: &[ProtocolBandwidthLimit],
 		peerset: PeersetHandle,
 	) -> Result<Self, request_responses::RegisterError> {
 		Ok(Self {
@@ -179,6 +181,7 @@ impl<B: BlockT> Behaviour<B> {
 			discovery: disco_config.finish(),
 			request_responses: request_responses::RequestResponsesBehaviour::new(
 				request_response_protocols.into_iter(),
+				protocol_bandwidth_limits,
 				peerset,
 			)?,
 		})
@@ -48,6 +48,7 @@ use std::{
 	io::{self, Write},
 	iter,
 	net::Ipv4Addr,
+	num::NonZeroU64,
 	path::{Path, PathBuf},
 	pin::Pin,
 	str::{self, FromStr},
@@ -546,6 +547,46 @@ impl NonDefaultSetConfig {
 	}
 }
 
+/// Limit on the throughput of the node or of a protocol.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthLimit {
+	/// Maximum number of bytes received per second. `None` for no limit.
+	pub inbound: Option<NonZeroU64>,
+	/// Maximum number of bytes sent per second. `None` for no limit.
+	pub outbound: Option<NonZeroU64>,
+}
+
+/// Bandwidth limit applying to notification or request-response protocols.
+///
+/// Only the received notifications are limited: sent notifications that waited for bandwidth
+/// would fill the queue of the peer they are sent to and get it disconnected.
+///
+/// Requests and responses are not exempt: the time spent waiting for bandwidth counts against
+/// the `request_timeout` of the protocol, and each payload is let through in one go, after which
+/// the following ones wait for the limit to catch up. A limit much lower than the typical response
+/// size divided by the request timeout therefore makes requests time out, and the peers asking
+/// them may end up disconnecting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtocolBandwidthLimit {
+	/// Full name of the protocol the limit applies to, e.g. `/<genesis hash>/sync/2` for block
+	/// requests.
+	pub protocol: ProtocolName,
+	/// The limit, shared by all the substreams of a matching protocol, with all peers.
+	pub limit: BandwidthLimit,
+}
+
+impl ProtocolBandwidthLimit {
+	/// Returns the limit of the first entry of `limits` matching `protocol`, or no limit if none
+	/// does.
+	pub fn find(limits: &[Self], protocol: &ProtocolName) -> BandwidthLimit {
+		limits
+			.iter()
+			.find(|l| l.protocol == *protocol)
+			.map(|l| l.limit)
+			.unwrap_or_default()
+	}
+}
+
 /// Network service configuration.
 #[derive(Clone, Debug)]
 pub struct NetworkConfiguration {
@@ -612,6 +653,10 @@ pub struct NetworkConfiguration {
 	/// Enable serving block data over IPFS bitswap.
 	pub ipfs_server: bool,
 
+	/// Persist peer reputations and the addresses of reachable peers in `net_config_path`, so that
+	/// they survive restarts.
+	pub persist_peers: bool,
+
 	/// Size of Yamux receive window of all substreams. `None` for the default (256kiB).
 	/// Any value less than 256kiB is invalid.
 	///
@@ -632,6 +677,18 @@ pub struct NetworkConfiguration {
 	/// a modification of the way the implementation works. Different nodes with different
 	/// configured values remain compatible with each other.
 	pub yamux_window_size: Option<u32>,
+
+	/// Limit on the total throughput of all connections, including the transport overhead.
+	///
+	/// This applies to request-response protocols as well, see [`ProtocolBandwidthLimit`] for the
+	/// effect on their timeouts.
+	pub bandwidth_limit: BandwidthLimit,
+
+	/// Limits on the throughput of individual protocols, counting the payload of notifications,
+	/// requests and responses.
+	///
+	/// Protocols that match no entry are not limited.
+	pub protocol_bandwidth_limits: Vec<ProtocolBandwidthLimit>,
 }
 
 impl NetworkConfiguration {
@@ -664,6 +721,9 @@ impl NetworkConfiguration {
 			kademlia_disjoint_query_paths: false,
 			yamux_window_size: None,
 			ipfs_server: false,
+			persist_peers: false,
+			bandwidth_limit: Default::default(),
+			protocol_bandwidth_limits: Vec::new(),
 		}
 	}
 
@@ -249,6 +249,7 @@ mod discovery;
 mod peer_info;
 mod protocol;
 mod service;
+mod throttle;
 mod transport;
 
 pub mod config;
@@ -267,7 +268,8 @@ pub use sc_network_common::{
 	role::ObservedRole,
 	sync::{
 		warp::{WarpSyncPhase, WarpSyncProgress},
-		ExtendedPeerInfo, StateDownloadProgress, SyncEventStream, SyncState, SyncStatusProvider,
+		ExtendedPeerInfo, PeerStateDownloadProgress, StateDownloadProgress, SyncEventStream,
+		SyncState, SyncStatusProvider,
 	},
 };
 pub use service::{
@@ -154,7 +154,20 @@ impl<B: BlockT> Protocol<B> {
 				});
 			}
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			let (peerset, peerset_handle) =
+				sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
+					sets,
+					peer_store: network_config
+						.net_config_path
+						.as_ref()
+						.filter(|_| network_config.persist_peers)
+						.map(|p| p.join("peers.json")),
+				});
+
+			// Peers that we reached before the last restart are good bootstrap candidates.
+			known_addresses.extend(peerset.persisted_addresses());
+
+			(peerset, peerset_handle)
 		};
 
 		let behaviour = {
@@ -168,12 +181,22 @@ impl<B: BlockT> Protocol<B> {
 					fallback_names: block_announces_protocol.fallback_names.clone(),
 					handshake: block_announces_protocol.handshake.as_ref().unwrap().to_vec(),
 					max_notification_size: block_announces_protocol.max_notification_size,
+					bandwidth_limit: config::ProtocolBandwidthLimit::find(
+						&network_config.protocol_bandwidth_limits,
+						&block_announces_protocol.notifications_protocol,
+					),
 				})
-				.chain(network_config.extra_sets.iter().map(|s| notifications::ProtocolConfig {
-					name: s.notifications_protocol.clone(),
-					fallback_names: s.fallback_names.clone(),
-					handshake: s.handshake.as_ref().map_or(roles.encode(), |h| (*h).to_vec()),
-					max_notification_size: s.max_notification_size,
+				.chain(network_config.extra_sets.iter().map(|s| {
+					notifications::ProtocolConfig {
+						name: s.notifications_protocol.clone(),
+						fallback_names: s.fallback_names.clone(),
+						handshake: s.handshake.as_ref().map_or(roles.encode(), |h| (*h).to_vec()),
+						max_notification_size: s.max_notification_size,
+						bandwidth_limit: config::ProtocolBandwidthLimit::find(
+							&network_config.protocol_bandwidth_limits,
+							&s.notifications_protocol,
+						),
+					}
 				})),
 			)
 		};
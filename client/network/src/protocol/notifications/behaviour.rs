@@ -17,9 +17,11 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+	config::BandwidthLimit,
 	protocol::notifications::handler::{
 		self, NotificationsSink, NotifsHandlerIn, NotifsHandlerOut, NotifsHandlerProto,
 	},
+	throttle::Limiters,
 	types::ProtocolName,
 };
 
@@ -150,6 +152,11 @@ pub struct ProtocolConfig {
 	pub handshake: Vec<u8>,
 	/// Maximum allowed size for a notification.
 	pub max_notification_size: u64,
+	/// Limit on the throughput of the received notifications, shared by all peers.
+	///
+	/// The outbound limit is ignored, as notifications waiting for bandwidth would fill the
+	/// queue of the peer and get it disconnected.
+	pub bandwidth_limit: BandwidthLimit,
 }
 
 /// Identifier for a delay firing.
@@ -368,6 +375,7 @@ impl Notifications {
 				fallback_names: cfg.fallback_names,
 				handshake: Arc::new(RwLock::new(cfg.handshake)),
 				max_notification_size: cfg.max_notification_size,
+				limiters: Limiters::new(&BandwidthLimit { outbound: None, ..cfg.bandwidth_limit }),
 			})
 			.collect::<Vec<_>>();
 
@@ -2119,7 +2127,7 @@ mod tests {
 				reserved_only: false,
 			});
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets, peer_store: None })
 		};
 
 		(
@@ -2130,6 +2138,7 @@ mod tests {
 					fallback_names: Vec::new(),
 					handshake: vec![1, 2, 3, 4],
 					max_notification_size: u64::MAX,
+					bandwidth_limit: Default::default(),
 				}),
 			),
 			peerset_handle,
@@ -2158,6 +2167,38 @@ mod tests {
 		notif.set_notif_protocol_handshake(1337.into(), vec![5, 6, 7, 8]);
 	}
 
+	#[test]
+	fn only_received_notifications_are_throttled() {
+		let (peerset, _peerset_handle) =
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
+				sets: vec![sc_peerset::SetConfig {
+					in_peers: 25,
+					out_peers: 25,
+					bootnodes: Vec::new(),
+					reserved_nodes: HashSet::new(),
+					reserved_only: false,
+				}],
+				peer_store: None,
+			});
+		let limit = std::num::NonZeroU64::new(1024);
+
+		let notif = Notifications::new(
+			peerset,
+			iter::once(ProtocolConfig {
+				name: "/foo".into(),
+				fallback_names: Vec::new(),
+				handshake: vec![1, 2, 3, 4],
+				max_notification_size: u64::MAX,
+				bandwidth_limit: BandwidthLimit { inbound: limit, outbound: limit },
+			}),
+		);
+
+		// Throttled notifications would pile up in the sink of the peer and get it disconnected.
+		let limiters = &notif.notif_protocols[0].limiters;
+		assert!(limiters.inbound.is_some());
+		assert!(limiters.outbound.is_none());
+	}
+
 	#[test]
 	fn disconnect_backoff_peer() {
 		let (mut notif, _peerset) = development_notifs();
@@ -62,6 +62,7 @@ use crate::{
 		NotificationsIn, NotificationsInSubstream, NotificationsOut, NotificationsOutSubstream,
 		UpgradeCollec,
 	},
+	throttle::{Limiters, Throttle},
 	types::ProtocolName,
 };
 
@@ -151,6 +152,8 @@ pub struct ProtocolConfig {
 	pub handshake: Arc<RwLock<Vec<u8>>>,
 	/// Maximum allowed size for a notification.
 	pub max_notification_size: u64,
+	/// Bandwidth limiters shared by the handlers of all connections. Only the inbound one applies.
+	pub limiters: Limiters,
 }
 
 /// Fields specific for each individual protocol.
@@ -161,6 +164,12 @@ struct Protocol {
 	/// Prototype for the inbound upgrade.
 	in_upgrade: NotificationsIn,
 
+	/// Throttle for the received notifications, if limited.
+	///
+	/// Sent notifications are not throttled: they would pile up in `notifications_sink_rx` and
+	/// get the peer disconnected once it is full.
+	inbound_throttle: Option<Throttle>,
+
 	/// Current state of the substreams for this protocol.
 	state: State,
 }
@@ -251,7 +260,12 @@ impl IntoConnectionHandler for NotifsHandlerProto {
 						config.max_notification_size,
 					);
 
-					Protocol { config, in_upgrade, state: State::Closed { pending_opening: false } }
+					Protocol {
+						inbound_throttle: config.limiters.inbound_throttle(),
+						config,
+						in_upgrade,
+						state: State::Closed { pending_opening: false },
+					}
 				})
 				.collect(),
 			peer_id: *peer_id,
@@ -808,28 +822,39 @@ impl ConnectionHandler for NotifsHandler {
 		for protocol_index in 0..self.protocols.len() {
 			// Inbound substreams being closed is always tolerated, except for the
 			// `OpenDesiredByRemote` state which might need to be switched back to `Closed`.
-			match &mut self.protocols[protocol_index].state {
+			let protocol = &mut self.protocols[protocol_index];
+			match &mut protocol.state {
 				State::Closed { .. } |
 				State::Open { in_substream: None, .. } |
 				State::Opening { in_substream: None } => {},
 
-				State::Open { in_substream: in_substream @ Some(_), .. } =>
+				State::Open { in_substream: in_substream @ Some(_), .. } => {
+					// Leave the notifications in the substream while over the bandwidth limit.
+					if let Some(throttle) = protocol.inbound_throttle.as_mut() {
+						if throttle.poll_ready(cx).is_pending() {
+							continue
+						}
+					}
+
 					match Stream::poll_next(Pin::new(in_substream.as_mut().unwrap()), cx) {
 						Poll::Pending => {},
 						Poll::Ready(Some(Ok(message))) => {
+							if let Some(throttle) = protocol.inbound_throttle.as_ref() {
+								throttle.consume(message.len());
+							}
 							let event = NotifsHandlerOut::Notification { protocol_index, message };
 							return Poll::Ready(ConnectionHandlerEvent::Custom(event))
 						},
 						Poll::Ready(None) | Poll::Ready(Some(Err(_))) => *in_substream = None,
-					},
+					}
+				},
 
 				State::OpenDesiredByRemote { in_substream, pending_opening } =>
 					match NotificationsInSubstream::poll_process(Pin::new(in_substream), cx) {
 						Poll::Pending => {},
 						Poll::Ready(Ok(void)) => match void {},
 						Poll::Ready(Err(_)) => {
-							self.protocols[protocol_index].state =
-								State::Closed { pending_opening: *pending_opening };
+							protocol.state = State::Closed { pending_opening: *pending_opening };
 							return Poll::Ready(ConnectionHandlerEvent::Custom(
 								NotifsHandlerOut::CloseDesired { protocol_index },
 							))
@@ -1059,8 +1084,10 @@ pub mod tests {
 				fallback_names: vec![],
 				handshake: Arc::new(RwLock::new(b"hello, world".to_vec())),
 				max_notification_size: u64::MAX,
+				limiters: Default::default(),
 			},
 			in_upgrade: NotificationsIn::new("/foo", Vec::new(), u64::MAX),
+			inbound_throttle: None,
 			state: State::Closed { pending_opening: false },
 		};
 
@@ -79,6 +79,7 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		});
 
 		let behaviour = CustomProtoWithAddr {
@@ -89,6 +90,7 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 					fallback_names: Vec::new(),
 					handshake: Vec::new(),
 					max_notification_size: 1024 * 1024,
+					bandwidth_limit: Default::default(),
 				}),
 			),
 			addrs: addrs
@@ -34,7 +34,9 @@
 //! - If provided, a ["requests processing"](ProtocolConfig::inbound_queue) channel
 //! is used to handle incoming requests.
 
-use crate::{types::ProtocolName, ReputationChange};
+use crate::{
+	config::ProtocolBandwidthLimit, throttle::Limiters, types::ProtocolName, ReputationChange,
+};
 
 use futures::{
 	channel::{mpsc, oneshot},
@@ -318,8 +320,13 @@ struct RequestProcessingOutcome {
 impl RequestResponsesBehaviour {
 	/// Creates a new behaviour. Must be passed a list of supported protocols. Returns an error if
 	/// the same protocol is passed twice.
+	///
+	/// The requests and responses of each protocol are throttled according to the first entry of
+	/// `bandwidth_limits` matching its name. Waiting for bandwidth counts against the
+	/// `request_timeout` of the protocol.
 	pub fn new(
 		list: impl Iterator<Item = ProtocolConfig>,
+		bandwidth_limits: &[ProtocolBandwidthLimit],
 		peerset: PeersetHandle,
 	) -> Result<Self, RegisterError> {
 		let mut protocols = HashMap::new();
@@ -338,6 +345,10 @@ impl RequestResponsesBehaviour {
 				GenericCodec {
 					max_request_size: protocol.max_request_size,
 					max_response_size: protocol.max_response_size,
+					limiters: Limiters::new(&ProtocolBandwidthLimit::find(
+						bandwidth_limits,
+						&protocol.name,
+					)),
 				},
 				iter::once(protocol.name.as_bytes().to_vec())
 					.chain(protocol.fallback_names.iter().map(|name| name.as_bytes().to_vec()))
@@ -920,6 +931,24 @@ pub enum ResponseFailure {
 pub struct GenericCodec {
 	max_request_size: u64,
 	max_response_size: u64,
+	/// Bandwidth limiters shared by all the substreams of the protocol.
+	limiters: Limiters,
+}
+
+impl GenericCodec {
+	/// Waits until `len` more bytes can be received according to the inbound limit, if any.
+	async fn throttle_inbound(&self, len: usize) {
+		if let Some(mut throttle) = self.limiters.inbound_throttle() {
+			throttle.acquire(len).await;
+		}
+	}
+
+	/// Waits until `len` more bytes can be sent according to the outbound limit, if any.
+	async fn throttle_outbound(&self, len: usize) {
+		if let Some(mut throttle) = self.limiters.outbound_throttle() {
+			throttle.acquire(len).await;
+		}
+	}
 }
 
 #[async_trait::async_trait]
@@ -948,6 +977,7 @@ impl RequestResponseCodec for GenericCodec {
 		}
 
 		// Read the payload.
+		self.throttle_inbound(length).await;
 		let mut buffer = vec![0; length];
 		io.read_exact(&mut buffer).await?;
 		Ok(buffer)
@@ -983,6 +1013,7 @@ impl RequestResponseCodec for GenericCodec {
 		}
 
 		// Read the payload.
+		self.throttle_inbound(length).await;
 		let mut buffer = vec![0; length];
 		io.read_exact(&mut buffer).await?;
 		Ok(Ok(buffer))
@@ -997,6 +1028,8 @@ impl RequestResponseCodec for GenericCodec {
 	where
 		T: AsyncWrite + Unpin + Send,
 	{
+		self.throttle_outbound(req.len()).await;
+
 		// TODO: check the length?
 		// Write the length.
 		{
@@ -1022,6 +1055,8 @@ impl RequestResponseCodec for GenericCodec {
 	{
 		// If `res` is an `Err`, we jump to closing the substream without writing anything on it.
 		if let Ok(res) = res {
+			self.throttle_outbound(res.len()).await;
+
 			// TODO: check the length?
 			// Write the length.
 			{
@@ -1089,11 +1124,12 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
 
-		let behaviour = RequestResponsesBehaviour::new(list, handle).unwrap();
+		let behaviour = RequestResponsesBehaviour::new(list, &[], handle).unwrap();
 
 		let runtime = tokio::runtime::Runtime::new().unwrap();
 		let mut swarm = Swarm::with_executor(
@@ -353,6 +353,7 @@ where
 					config_mem,
 					params.network_config.yamux_window_size,
 					yamux_maximum_buffer_size,
+					&params.network_config.bandwidth_limit,
 				)
 			};
 
@@ -363,6 +364,7 @@ where
 					local_public,
 					discovery_config,
 					params.network_config.request_response_protocols,
+					&params.network_config.protocol_bandwidth_limits,
 					peerset_handle.clone(),
 				);
 
@@ -1592,6 +1594,11 @@ where
 					debug!(target: "sub-libp2p", "Libp2p => Connected({:?})", peer_id);
 				}
 
+				// Only addresses we dialed are known to be reachable.
+				if let ConnectedPoint::Dialer { address, .. } = &endpoint {
+					self.service.peerset.add_known_address(peer_id, address.clone());
+				}
+
 				if let Some(metrics) = self.metrics.as_ref() {
 					let direction = match endpoint {
 						ConnectedPoint::Dialer { .. } => "out",
@@ -0,0 +1,297 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Token-bucket bandwidth limiting.
+//!
+//! A [`RateLimiter`] is a bucket that refills at a fixed number of bytes per second and holds at
+//! most one second worth of bytes. Users wait until the bucket holds at least one byte, then
+//! transfer as much as they want and subtract the number of bytes actually transferred. The bucket
+//! can therefore go into debt, which the following transfers have to wait for, and the average
+//! throughput never exceeds the configured rate.
+//!
+//! Limiters are shared, either between all the connections of the node or between all the
+//! substreams of a protocol, while each user holds its own [`Throttle`].
+
+use crate::config::BandwidthLimit;
+
+use futures::{prelude::*, ready};
+use futures_timer::Delay;
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+	io,
+	num::NonZeroU64,
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+	time::{Duration, Instant},
+};
+
+/// Bucket of bytes shared between the users of a limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+	/// Number of bytes added to the bucket every second. Also the capacity of the bucket.
+	bytes_per_sec: u64,
+	/// Current state of the bucket.
+	bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+	/// Number of bytes that can be transferred. Negative if in debt.
+	balance: i64,
+	/// Last time `balance` was refilled.
+	last_refill: Instant,
+}
+
+impl RateLimiter {
+	/// Creates a limiter allowing `bytes_per_sec` bytes per second, starting with a full bucket.
+	pub fn new(bytes_per_sec: NonZeroU64) -> Arc<Self> {
+		Self::new_at(bytes_per_sec, Instant::now())
+	}
+
+	/// Creates a limiter whose bucket is full at `now`.
+	fn new_at(bytes_per_sec: NonZeroU64, now: Instant) -> Arc<Self> {
+		let bytes_per_sec = bytes_per_sec.get().min(i64::MAX as u64);
+		Arc::new(Self {
+			bytes_per_sec,
+			bucket: Mutex::new(Bucket { balance: bytes_per_sec as i64, last_refill: now }),
+		})
+	}
+
+	/// Returns how long to wait after `now` before the bucket holds at least one byte, or `None`
+	/// if it already does.
+	fn wait_time(&self, now: Instant) -> Option<Duration> {
+		let mut bucket = self.bucket.lock();
+
+		let elapsed = now.saturating_duration_since(bucket.last_refill);
+		let refill = elapsed.as_micros() * u128::from(self.bytes_per_sec) / 1_000_000;
+		// Only move `last_refill` forward if bytes were actually added, so that frequent calls
+		// don't lose the fractional bytes.
+		if refill > 0 {
+			bucket.balance = bucket
+				.balance
+				.saturating_add(i64::try_from(refill).unwrap_or(i64::MAX))
+				.min(self.bytes_per_sec as i64);
+			bucket.last_refill = now;
+		}
+
+		if bucket.balance > 0 {
+			return None
+		}
+
+		let missing = 1 + bucket.balance.unsigned_abs();
+		Some(Duration::from_micros(
+			u64::try_from(u128::from(missing) * 1_000_000 / u128::from(self.bytes_per_sec))
+				.unwrap_or(u64::MAX),
+		))
+	}
+
+	/// Removes `bytes` from the bucket.
+	fn consume(&self, bytes: usize) {
+		let mut bucket = self.bucket.lock();
+		bucket.balance = bucket.balance.saturating_sub(i64::try_from(bytes).unwrap_or(i64::MAX));
+	}
+}
+
+/// Limiters for both directions of a connection or a protocol.
+#[derive(Debug, Clone, Default)]
+pub struct Limiters {
+	/// Limiter for the received bytes.
+	pub inbound: Option<Arc<RateLimiter>>,
+	/// Limiter for the sent bytes.
+	pub outbound: Option<Arc<RateLimiter>>,
+}
+
+impl Limiters {
+	/// Creates new limiters enforcing `limit`.
+	pub fn new(limit: &BandwidthLimit) -> Self {
+		Self {
+			inbound: limit.inbound.map(RateLimiter::new),
+			outbound: limit.outbound.map(RateLimiter::new),
+		}
+	}
+
+	/// Returns a throttle for the inbound direction, if it is limited.
+	pub fn inbound_throttle(&self) -> Option<Throttle> {
+		self.inbound.clone().map(Throttle::new)
+	}
+
+	/// Returns a throttle for the outbound direction, if it is limited.
+	pub fn outbound_throttle(&self) -> Option<Throttle> {
+		self.outbound.clone().map(Throttle::new)
+	}
+}
+
+/// Handle to a [`RateLimiter`] owned by a single user.
+#[derive(Debug)]
+pub struct Throttle {
+	limiter: Arc<RateLimiter>,
+	/// Timer to wait for the bucket to refill.
+	delay: Option<Delay>,
+}
+
+impl Throttle {
+	/// Creates a new throttle drawing from `limiter`.
+	pub fn new(limiter: Arc<RateLimiter>) -> Self {
+		Self { limiter, delay: None }
+	}
+
+	/// Returns `Ready` once the bucket is no longer empty.
+	pub fn poll_ready(&mut self, cx: &mut Context) -> Poll<()> {
+		loop {
+			if let Some(delay) = self.delay.as_mut() {
+				ready!(delay.poll_unpin(cx));
+				self.delay = None;
+			}
+
+			match self.limiter.wait_time(Instant::now()) {
+				None => return Poll::Ready(()),
+				Some(wait) => self.delay = Some(Delay::new(wait)),
+			}
+		}
+	}
+
+	/// Records that `bytes` bytes have been transferred.
+	pub fn consume(&self, bytes: usize) {
+		self.limiter.consume(bytes);
+	}
+
+	/// Waits for the bucket to no longer be empty, then records the transfer of `bytes` bytes.
+	pub async fn acquire(&mut self, bytes: usize) {
+		future::poll_fn(|cx| self.poll_ready(cx)).await;
+		self.consume(bytes);
+	}
+}
+
+/// Connection whose reads and writes are throttled.
+#[pin_project]
+pub struct ThrottledConnection<C> {
+	#[pin]
+	inner: C,
+	inbound: Option<Throttle>,
+	outbound: Option<Throttle>,
+}
+
+impl<C> ThrottledConnection<C> {
+	/// Wraps `inner`, drawing from `limiters` for each read and write.
+	pub fn new(inner: C, limiters: &Limiters) -> Self {
+		Self { inner, inbound: limiters.inbound_throttle(), outbound: limiters.outbound_throttle() }
+	}
+}
+
+impl<C: AsyncRead> AsyncRead for ThrottledConnection<C> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		let this = self.project();
+		if let Some(throttle) = this.inbound.as_mut() {
+			ready!(throttle.poll_ready(cx));
+		}
+
+		let read = ready!(this.inner.poll_read(cx, buf))?;
+		if let Some(throttle) = this.inbound.as_ref() {
+			throttle.consume(read);
+		}
+		Poll::Ready(Ok(read))
+	}
+}
+
+impl<C: AsyncWrite> AsyncWrite for ThrottledConnection<C> {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let this = self.project();
+		if let Some(throttle) = this.outbound.as_mut() {
+			ready!(throttle.poll_ready(cx));
+		}
+
+		let written = ready!(this.inner.poll_write(cx, buf))?;
+		if let Some(throttle) = this.outbound.as_ref() {
+			throttle.consume(written);
+		}
+		Poll::Ready(Ok(written))
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		self.project().inner.poll_flush(cx)
+	}
+
+	fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		self.project().inner.poll_close(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::task::noop_waker_ref;
+
+	#[test]
+	fn limiter_caps_throughput() {
+		let start = Instant::now();
+		let limiter = RateLimiter::new_at(NonZeroU64::new(1000).unwrap(), start);
+
+		// The first second worth of bytes is available right away.
+		for _ in 0..10 {
+			assert_eq!(limiter.wait_time(start), None);
+			limiter.consume(100);
+		}
+		assert_eq!(limiter.wait_time(start), Some(Duration::from_millis(1)));
+
+		// Going into debt delays the following transfers until it is paid back.
+		limiter.consume(500);
+		assert_eq!(limiter.wait_time(start), Some(Duration::from_millis(501)));
+		assert_eq!(
+			limiter.wait_time(start + Duration::from_millis(500)),
+			Some(Duration::from_millis(1)),
+		);
+		assert_eq!(limiter.wait_time(start + Duration::from_millis(501)), None);
+	}
+
+	#[test]
+	fn limiter_refills_up_to_one_second_of_bytes() {
+		let start = Instant::now();
+		let limiter = RateLimiter::new_at(NonZeroU64::new(1000).unwrap(), start);
+		limiter.consume(1000);
+
+		// Idle time beyond one second is not accumulated.
+		let later = start + Duration::from_secs(10);
+		assert_eq!(limiter.wait_time(later), None);
+		limiter.consume(1000);
+		assert_eq!(limiter.wait_time(later), Some(Duration::from_millis(1)));
+
+		// Refilling less than a byte doesn't lose the elapsed time.
+		assert!(limiter.wait_time(later + Duration::from_micros(500)).is_some());
+		assert_eq!(limiter.wait_time(later + Duration::from_millis(1)), None);
+	}
+
+	#[test]
+	fn throttled_connection_stops_reading_when_over_limit() {
+		let limiters =
+			Limiters::new(&BandwidthLimit { inbound: NonZeroU64::new(1), outbound: None });
+		let data = vec![0u8; 4096];
+		let mut conn = ThrottledConnection::new(futures::io::Cursor::new(data), &limiters);
+		let mut cx = Context::from_waker(noop_waker_ref());
+		let mut buf = [0u8; 1024];
+
+		// The single byte in the bucket allows one read, which puts the bucket deep into debt.
+		assert!(matches!(Pin::new(&mut conn).poll_read(&mut cx, &mut buf), Poll::Ready(Ok(1024))));
+		assert!(Pin::new(&mut conn).poll_read(&mut cx, &mut buf).is_pending());
+	}
+}
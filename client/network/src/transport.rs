@@ -16,6 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::{
+	config::BandwidthLimit,
+	throttle::{Limiters, ThrottledConnection},
+};
+
 use libp2p::{
 	bandwidth,
 	core::{
@@ -44,6 +49,9 @@ pub use self::bandwidth::BandwidthSinks;
 /// high-level protocols combined, or to some generously high value if you are sure that a maximum
 /// size is enforced on all high-level protocols.
 ///
+/// `bandwidth_limit` caps the throughput of all the connections combined, including the overhead
+/// of encryption and multiplexing.
+///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
 /// the connections spawned with this transport.
 pub fn build_transport(
@@ -51,6 +59,7 @@ pub fn build_transport(
 	memory_only: bool,
 	yamux_window_size: Option<u32>,
 	yamux_maximum_buffer_size: usize,
+	bandwidth_limit: &BandwidthLimit,
 ) -> (Boxed<(PeerId, StreamMuxerBox)>, Arc<BandwidthSinks>) {
 	// Build the base layer of the transport.
 	let transport = if !memory_only {
@@ -82,6 +91,10 @@ pub fn build_transport(
 		))
 	};
 
+	let limiters = Limiters::new(bandwidth_limit);
+	let transport =
+		transport.map(move |connection, _| ThrottledConnection::new(connection, &limiters));
+
 	let (transport, bandwidth) = bandwidth::BandwidthLogging::new(transport);
 
 	let authentication_config =
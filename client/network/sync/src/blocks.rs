@@ -24,8 +24,16 @@ use std::{
 	cmp,
 	collections::{BTreeMap, HashMap},
 	ops::Range,
+	time::{Duration, Instant},
 };
 
+/// Time after which the first range still being downloaded is considered stalled and may be
+/// requested from another peer. Nothing after that range can be imported until it is downloaded.
+const STALLED_RANGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of additional peers a stalled range is requested from.
+const MAX_RANGE_REASSIGNMENTS: u32 = 2;
+
 /// Block data with origin.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BlockData<B: BlockT> {
@@ -37,7 +45,7 @@ pub struct BlockData<B: BlockT> {
 
 #[derive(Debug)]
 enum BlockRangeState<B: BlockT> {
-	Downloading { len: NumberFor<B>, downloading: u32 },
+	Downloading { len: NumberFor<B>, downloading: u32, requested_at: Instant },
 	Complete(Vec<BlockData<B>>),
 	Queued { len: NumberFor<B> },
 }
@@ -119,6 +127,9 @@ impl<B: BlockT> BlockCollection<B> {
 			// Bail out early
 			return None
 		}
+		if let Some(range) = self.reassign_stalled(who, peer_best, common, max_parallel) {
+			return Some(range)
+		}
 		// First block number that we need to download
 		let first_different = common + <NumberFor<B>>::one();
 		let count = (count as u32).into();
@@ -128,9 +139,10 @@ impl<B: BlockT> BlockCollection<B> {
 			loop {
 				let next = downloading_iter.next();
 				break match (prev, next) {
-					(Some((start, &BlockRangeState::Downloading { ref len, downloading })), _)
-						if downloading < max_parallel =>
-						(*start..*start + *len, downloading),
+					(
+						Some((start, &BlockRangeState::Downloading { ref len, downloading, .. })),
+						_,
+					) if downloading < max_parallel => (*start..*start + *len, downloading),
 					(Some((start, r)), Some((next_start, _))) if *start + r.len() < *next_start =>
 						(*start + r.len()..cmp::min(*next_start, *start + r.len() + count), 0), // gap
 					(Some((start, r)), None) => (*start + r.len()..*start + r.len() + count, 0), /* last range */
@@ -167,6 +179,7 @@ impl<B: BlockT> BlockCollection<B> {
 			BlockRangeState::Downloading {
 				len: range.end - range.start,
 				downloading: downloading + 1,
+				requested_at: Instant::now(),
 			},
 		);
 		if range.end <= range.start {
@@ -178,6 +191,42 @@ impl<B: BlockT> BlockCollection<B> {
 		Some(range)
 	}
 
+	/// Request the first range that is still being downloaded from `who` as well, if it has been
+	/// requested more than [`STALLED_RANGE_TIMEOUT`] ago. Whichever peer delivers it first wins,
+	/// the response of the other one is discarded.
+	fn reassign_stalled(
+		&mut self,
+		who: PeerId,
+		peer_best: NumberFor<B>,
+		common: NumberFor<B>,
+		max_parallel: u32,
+	) -> Option<Range<NumberFor<B>>> {
+		let (start, range) = self
+			.blocks
+			.iter_mut()
+			.find(|(_, range)| !matches!(range, BlockRangeState::Queued { .. }))?;
+		match range {
+			BlockRangeState::Downloading { len, downloading, requested_at }
+				if *downloading < max_parallel + MAX_RANGE_REASSIGNMENTS &&
+					requested_at.elapsed() >= STALLED_RANGE_TIMEOUT &&
+					*start > common && *start + *len <= peer_best + One::one() =>
+			{
+				trace!(
+					target: "sync",
+					"Range {}..{} stalled, requesting it from {} as well",
+					start,
+					*start + *len,
+					who,
+				);
+				*downloading += 1;
+				*requested_at = Instant::now();
+				self.peer_requests.insert(who, *start);
+				Some(*start..*start + *len)
+			},
+			_ => None,
+		}
+	}
+
 	/// Get a valid chain of blocks ordered in descending order and ready for importing into
 	/// the blockchain.
 	/// `from` is the maximum block number for the start of the range that we are interested in.
@@ -223,32 +272,38 @@ impl<B: BlockT> BlockCollection<B> {
 		}
 	}
 
-	pub fn clear_peer_download(&mut self, who: &PeerId) {
+	/// Clear the pending download of `who`. Returns `false` if the range requested from `who` was
+	/// meanwhile delivered by another peer, in which case the response should be discarded.
+	pub fn clear_peer_download(&mut self, who: &PeerId) -> bool {
 		if let Some(start) = self.peer_requests.remove(who) {
-			let remove = match self.blocks.get_mut(&start) {
+			let (remove, needed) = match self.blocks.get_mut(&start) {
 				Some(&mut BlockRangeState::Downloading { ref mut downloading, .. })
 					if *downloading > 1 =>
 				{
 					*downloading -= 1;
-					false
+					(false, true)
 				},
-				Some(&mut BlockRangeState::Downloading { .. }) => true,
-				_ => false,
+				Some(&mut BlockRangeState::Downloading { .. }) => (true, true),
+				_ => (false, false),
 			};
 			if remove {
 				self.blocks.remove(&start);
 			}
+			needed
+		} else {
+			true
 		}
 	}
 }
 
 #[cfg(test)]
 mod test {
-	use super::{BlockCollection, BlockData, BlockRangeState};
+	use super::{BlockCollection, BlockData, BlockRangeState, STALLED_RANGE_TIMEOUT};
 	use libp2p::PeerId;
 	use sc_network_common::sync::message;
 	use sp_core::H256;
 	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+	use std::time::Instant;
 
 	type Block = RawBlock<ExtrinsicWrapper<u64>>;
 
@@ -357,7 +412,10 @@ mod test {
 	#[test]
 	fn large_gap() {
 		let mut bc: BlockCollection<Block> = BlockCollection::new();
-		bc.blocks.insert(100, BlockRangeState::Downloading { len: 128, downloading: 1 });
+		bc.blocks.insert(
+			100,
+			BlockRangeState::Downloading { len: 128, downloading: 1, requested_at: Instant::now() },
+		);
 		let blocks = generate_blocks(10)
 			.into_iter()
 			.map(|b| BlockData { block: b, origin: None })
@@ -431,4 +489,42 @@ mod test {
 		assert!(bc.blocks.is_empty());
 		assert!(bc.queued_blocks.is_empty());
 	}
+
+	#[test]
+	fn stalled_range_is_requested_from_another_peer() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		let peer0 = PeerId::random();
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+
+		let blocks = generate_blocks(150);
+		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0, 1, 200), Some(1..41));
+		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0, 1, 200), Some(41..81));
+
+		// Ranges are requested ahead while the first one is not stalled.
+		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0, 1, 200), Some(81..121));
+		bc.clear_peer_download(&peer2);
+
+		match bc.blocks.get_mut(&1) {
+			Some(BlockRangeState::Downloading { requested_at, .. }) =>
+				*requested_at = Instant::now().checked_sub(STALLED_RANGE_TIMEOUT).unwrap(),
+			_ => panic!("Range is being downloaded"),
+		}
+		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0, 1, 200), Some(1..41));
+		// The stalled range was just requested again.
+		assert_eq!(bc.needed_blocks(PeerId::random(), 40, 150, 0, 1, 200), Some(81..121));
+
+		assert!(bc.clear_peer_download(&peer2));
+		bc.insert(1, blocks[1..41].to_vec(), peer2);
+		assert_eq!(
+			bc.ready_blocks(1),
+			blocks[1..41]
+				.iter()
+				.map(|b| BlockData { block: b.clone(), origin: Some(peer2) })
+				.collect::<Vec<_>>()
+		);
+
+		// The late response of the original peer is not needed anymore.
+		assert!(!bc.clear_peer_download(&peer0));
+	}
 }
@@ -31,7 +31,7 @@
 use crate::{
 	blocks::BlockCollection,
 	schema::v1::{StateRequest, StateResponse},
-	state::StateSync,
+	state::{persisted_target, StateSync},
 	warp::{WarpProofImportResult, WarpSync},
 };
 
@@ -45,7 +45,7 @@ use log::{debug, error, info, trace, warn};
 use prost::Message;
 
 use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{
 	import_queue::ImportQueueService, BlockImportError, BlockImportStatus, IncomingBlock,
 };
@@ -476,6 +476,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -759,23 +760,33 @@ where
 			if let Some(request) = request {
 				match &mut peer.state {
 					PeerSyncState::DownloadingNew(_) => {
-						self.blocks.clear_peer_download(who);
+						let needed = self.blocks.clear_peer_download(who);
 						peer.state = PeerSyncState::Available;
 						if let Some(start_block) =
 							validate_blocks::<B>(&blocks, who, Some(request))?
 						{
-							self.blocks.insert(start_block, blocks, *who);
+							if needed {
+								self.blocks.insert(start_block, blocks, *who);
+							} else {
+								trace!(
+									target: "sync",
+									"Discarding blocks from {} already downloaded from another peer",
+									who,
+								);
+							}
 						}
 						self.ready_blocks()
 					},
 					PeerSyncState::DownloadingGap(_) => {
 						peer.state = PeerSyncState::Available;
 						if let Some(gap_sync) = &mut self.gap_sync {
-							gap_sync.blocks.clear_peer_download(who);
+							let needed = gap_sync.blocks.clear_peer_download(who);
 							if let Some(start_block) =
 								validate_blocks::<B>(&blocks, who, Some(request))?
 							{
-								gap_sync.blocks.insert(start_block, blocks, *who);
+								if needed {
+									gap_sync.blocks.insert(start_block, blocks, *who);
+								}
 							}
 							gap = true;
 							let blocks: Vec<_> = gap_sync
@@ -1087,12 +1098,24 @@ where
 				heads.sort();
 				let median = heads[heads.len() / 2];
 				if number + STATE_SYNC_FINALITY_THRESHOLD.saturated_into() >= median {
-					if let Ok(Some(header)) = self.client.header(*hash) {
+					// Resume an interrupted state sync if its target is still recent enough for
+					// peers to serve its state.
+					let resumed = persisted_target::<B, _>(&*self.client)
+						.and_then(|target| self.client.header(target).ok().flatten())
+						.filter(|header| {
+							*header.number() + STATE_SYNC_FINALITY_THRESHOLD.saturated_into() >=
+								median
+						});
+					let header = match resumed {
+						Some(header) => Some(header),
+						None => self.client.header(*hash).ok().flatten(),
+					};
+					if let Some(header) = header {
 						log::debug!(
 							target: "sync",
 							"Starting state sync for #{} ({})",
-							number,
-							hash,
+							header.number(),
+							header.hash(),
 						);
 						self.state_sync = Some(StateSync::new(
 							self.client.clone(),
@@ -1235,11 +1258,17 @@ where
 	fn peer_disconnected(&mut self, who: &PeerId) {
 		self.blocks.clear_peer_download(who);
 		if let Some(gap_sync) = &mut self.gap_sync {
-			gap_sync.blocks.clear_peer_download(who)
+			gap_sync.blocks.clear_peer_download(who);
 		}
 		self.peers.remove(who);
 		self.pending_responses.remove(who);
 		self.extra_justifications.peer_disconnected(who);
+		if let Some(state_sync) = &mut self.state_sync {
+			state_sync.peer_disconnected(who);
+		}
+		if let Some(warp_sync) = &mut self.warp_sync {
+			warp_sync.peer_disconnected(who);
+		}
 		self.allowed_requests.set_all();
 		self.fork_targets.retain(|_, target| {
 			target.peers.remove(who);
@@ -1393,6 +1422,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -2165,7 +2195,7 @@ where
 			self.send_block_request(id, request);
 		}
 
-		if let Some((id, request)) = self.state_request() {
+		for (id, request) in self.state_requests() {
 			self.send_state_request(id, request);
 		}
 
@@ -2485,49 +2515,46 @@ where
 		// Box::new(iter)
 	}
 
-	fn state_request(&mut self) -> Option<(PeerId, OpaqueStateRequest)> {
+	fn state_requests(&mut self) -> Vec<(PeerId, OpaqueStateRequest)> {
+		let mut requests = Vec::new();
 		if self.allowed_requests.is_empty() {
-			return None
-		}
-		if (self.state_sync.is_some() || self.warp_sync.is_some()) &&
-			self.peers.iter().any(|(_, peer)| peer.state == PeerSyncState::DownloadingState)
-		{
-			// Only one pending state request is allowed.
-			return None
+			return requests
 		}
-		if let Some(sync) = &self.state_sync {
+		if let Some(sync) = &mut self.state_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
 
 			for (id, peer) in self.peers.iter_mut() {
 				if peer.state.is_available() && peer.common_number >= sync.target_block_num() {
-					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
-					trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
-					self.allowed_requests.clear();
-					return Some((*id, OpaqueStateRequest(Box::new(request))))
+					if let Some(request) = sync.next_request(id) {
+						trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
+						peer.state = PeerSyncState::DownloadingState;
+						requests.push((*id, OpaqueStateRequest(Box::new(request))));
+					}
 				}
 			}
 		}
-		if let Some(sync) = &self.warp_sync {
+		if let Some(sync) = &mut self.warp_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
-			if let (Some(request), Some(target)) =
-				(sync.next_state_request(), sync.target_block_number())
-			{
+			if let Some(target) = sync.target_block_number() {
 				for (id, peer) in self.peers.iter_mut() {
 					if peer.state.is_available() && peer.best_number >= target {
-						trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
-						peer.state = PeerSyncState::DownloadingState;
-						self.allowed_requests.clear();
-						return Some((*id, OpaqueStateRequest(Box::new(request))))
+						if let Some(request) = sync.next_state_request(id) {
+							trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
+							peer.state = PeerSyncState::DownloadingState;
+							requests.push((*id, OpaqueStateRequest(Box::new(request))));
+						}
 					}
 				}
 			}
 		}
-		None
+		if !requests.is_empty() {
+			self.allowed_requests.clear();
+		}
+		requests
 	}
 
 	fn warp_sync_request(&mut self) -> Option<(PeerId, WarpProofRequest<B>)> {
@@ -2589,7 +2616,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(*response)
+			sync.import(who, *response)
 		} else if let Some(sync) = &mut self.warp_sync {
 			debug!(
 				target: "sync",
@@ -2598,7 +2625,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import_state(*response)
+			sync.import_state(who, *response)
 		} else {
 			debug!(target: "sync", "Ignored obsolete state response from {}", who);
 			return Err(BadPeer(*who, rep::NOT_REQUESTED))
@@ -2756,7 +2783,9 @@ where
 							"State sync is complete ({} MiB), restarting block sync.",
 							self.state_sync.as_ref().map_or(0, |s| s.progress().size / (1024 * 1024)),
 						);
-						self.state_sync = None;
+						if let Some(mut state_sync) = self.state_sync.take() {
+							state_sync.clear_persisted();
+						}
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -2770,7 +2799,9 @@ where
 							"Warp sync is complete ({} MiB), restarting block sync.",
 							self.warp_sync.as_ref().map_or(0, |s| s.progress().total_bytes / (1024 * 1024)),
 						);
-						self.warp_sync = None;
+						if let Some(mut warp_sync) = self.warp_sync.take() {
+							warp_sync.clear_persisted();
+						}
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -2823,8 +2854,12 @@ where
 				},
 				e @ Err(BlockImportError::UnknownParent) | e @ Err(BlockImportError::Other(_)) => {
 					warn!(target: "sync", "💔 Error importing block {:?}: {}", hash, e.unwrap_err());
-					self.state_sync = None;
-					self.warp_sync = None;
+					if let Some(mut state_sync) = self.state_sync.take() {
+						state_sync.clear_persisted();
+					}
+					if let Some(mut warp_sync) = self.warp_sync.take() {
+						warp_sync.clear_persisted();
+					}
 					output.extend(self.restart());
 				},
 				Err(BlockImportError::Cancelled) => {},
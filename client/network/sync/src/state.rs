@@ -15,24 +15,314 @@
 
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
-
 //! State sync support.
+//!
+//! The top trie key space is initially split into [`STATE_SYNC_RANGES`] disjoint ranges by the
+//! first byte of the key. Each range is downloaded sequentially, but different ranges are
+//! requested from different peers concurrently. When a peer is idle while all the remaining
+//! ranges are being downloaded, a range is split in two inside the key prefix its last chunk was
+//! downloaded from, so that a large storage map such as `System::Account` is downloaded from
+//! several peers too. Child tries are downloaded along with the top trie key that references them.
+//!
+//! Verified chunks are written to aux storage instead of being kept in memory, along with the
+//! position of each range, so that a state sync interrupted by a restart resumes from the last
+//! persisted position of each range instead of from scratch. The chunks are only read back to
+//! assemble the state once it is complete.
 
 use crate::schema::v1::{StateEntry, StateRequest, StateResponse};
 use codec::{Decode, Encode};
-use log::debug;
-use sc_client_api::{CompactProof, ProofProvider};
+use libp2p::PeerId;
+use log::{debug, info, warn};
+use sc_client_api::{AuxStore, CompactProof, KeyValueStates, ProofProvider};
 use sc_consensus::ImportedState;
-use sc_network_common::sync::StateDownloadProgress;
+use sc_network_common::sync::{PeerStateDownloadProgress, StateDownloadProgress};
 use smallvec::SmallVec;
 use sp_core::storage::well_known_keys;
 use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+/// Number of disjoint ranges the top trie key space is initially split into.
+pub const STATE_SYNC_RANGES: usize = 16;
+
+/// Maximum number of ranges the top trie key space is split into. This is also the maximum
+/// number of peers state is downloaded from concurrently.
+const MAX_STATE_SYNC_RANGES: usize = 64;
+
+/// Number of distinct first key bytes covered by an initial range.
+const RANGE_WIDTH: usize = 256 / STATE_SYNC_RANGES;
+
+/// Aux storage key of the persisted state sync progress.
+const STATE_SYNC_PROGRESS_KEY: &[u8] = b"sync_state_progress";
+
+/// Prefix of the aux storage keys of the persisted state sync chunks.
+const STATE_SYNC_CHUNK_PREFIX: &[u8] = b"sync_state_chunk";
+
+/// Key values of a chunk, grouped by trie root. The top trie has an empty root.
+type Levels = Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>;
+
+/// Accumulated key values and child trie storage keys, by trie root.
+type State = HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>;
+
+/// A range of the top trie key space, downloaded from one peer at a time.
+#[derive(Encode, Decode)]
+struct KeyRange {
+	/// Exclusive lower bound. `None` for the first range.
+	start: Option<Vec<u8>>,
+	/// Inclusive upper bound. `None` for the last range.
+	end: Option<Vec<u8>>,
+	/// Last key downloaded in this range.
+	cursor: Vec<Vec<u8>>,
+	/// Whether all the keys of the range were downloaded.
+	complete: bool,
+	/// Peer the range is currently requested from.
+	#[codec(skip)]
+	peer: Option<PeerId>,
+	/// Number of chunks downloaded since the range was created or last split.
+	#[codec(skip)]
+	chunks: u32,
+	/// Length of the key prefix shared by the top trie keys of the last downloaded chunk.
+	#[codec(skip)]
+	prefix: usize,
+}
+
+impl KeyRange {
+	fn new(index: usize) -> Self {
+		let start = (index > 0).then(|| vec![(index * RANGE_WIDTH) as u8]);
+		let end = (index + 1 < STATE_SYNC_RANGES).then(|| vec![((index + 1) * RANGE_WIDTH) as u8]);
+		Self::between(start, end)
+	}
+
+	fn between(start: Option<Vec<u8>>, end: Option<Vec<u8>>) -> Self {
+		let cursor = start.iter().cloned().collect();
+		Self { start, end, cursor, complete: false, peer: None, chunks: 0, prefix: 0 }
+	}
+
+	/// Fraction of the key space downloaded in this range.
+	fn downloaded(&self) -> f64 {
+		let lower = self.start.as_deref().map_or(0.0, key_position);
+		let upper = self.end.as_deref().map_or(1.0, key_position);
+		if self.complete {
+			return upper - lower
+		}
+		let position = self.cursor.first().map_or(lower, |key| key_position(key));
+		(position - lower).clamp(0.0, upper - lower)
+	}
+
+	/// Split off the keys of the range past a key halfway through the prefix the last chunk was
+	/// downloaded from, or through the rest of the range if that prefix is past its end. Returns
+	/// `None` if the range can not be split.
+	fn split(&mut self) -> Option<KeyRange> {
+		// Child tries are not split, neither are ranges nothing is known about yet.
+		let last = match self.cursor.as_slice() {
+			[last] if self.chunks > 0 && !self.complete => last,
+			_ => return None,
+		};
+		let prefix = &last[..self.prefix.min(last.len())];
+		let high = match (prefix_end(prefix), &self.end) {
+			(Some(prefix_end), Some(end)) => Some(prefix_end.min(end.clone())),
+			(prefix_end, end) => prefix_end.or_else(|| end.clone()),
+		};
+		let middle = middle_key(last, high.as_deref())?;
+		let end = self.end.replace(middle.clone());
+		self.chunks = 0;
+		Some(KeyRange::between(Some(middle), end))
+	}
+
+	/// Drop the top trie keys past the end of the range from `levels`, along with the child
+	/// tries they reference. Returns `true` if anything was dropped, meaning that the end of the
+	/// range was reached.
+	fn clip(&self, levels: &mut Levels) -> bool {
+		let end = match &self.end {
+			Some(end) => end,
+			None => return false,
+		};
+		let mut clipped = false;
+		let mut dropped_roots = HashSet::new();
+		let mut kept_roots = HashSet::new();
+		for (_, key_values) in levels.iter_mut().filter(|(root, _)| root.is_empty()) {
+			key_values.retain(|(key, value)| {
+				let keep = key <= end;
+				if well_known_keys::is_child_storage_key(key) {
+					if keep {
+						kept_roots.insert(value.clone());
+					} else {
+						dropped_roots.insert(value.clone());
+					}
+				}
+				clipped |= !keep;
+				keep
+			});
+		}
+		levels.retain(|(root, _)| {
+			root.is_empty() || !dropped_roots.contains(root) || kept_roots.contains(root)
+		});
+		clipped
+	}
+}
+
+/// Position of `key` in the key space, as a fraction of it.
+fn key_position(key: &[u8]) -> f64 {
+	key.iter()
+		.take(8)
+		.rev()
+		.fold(0.0, |position, byte| (position + *byte as f64) / 256.0)
+}
+
+/// Smallest key greater than all the keys starting with `prefix`, if any.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+	let mut end = prefix.to_vec();
+	while let Some(last) = end.pop() {
+		if last < u8::MAX {
+			end.push(last + 1);
+			return Some(end)
+		}
+	}
+	None
+}
+
+/// Key halfway between `low` and `high`, or between `low` and the end of the key space if `high`
+/// is `None`. Returns `None` if there is no key strictly between them.
+fn middle_key(low: &[u8], high: Option<&[u8]>) -> Option<Vec<u8>> {
+	// Keys are added as big-endian numbers one byte longer than the longest of them.
+	let width = low.len().max(high.map_or(0, <[u8]>::len)) + 1;
+	let byte = |key: &[u8], i: usize| key.get(i).copied().unwrap_or(0) as u32;
+	let mut sum = vec![0; width];
+	let mut carry = 0;
+	for i in (0..width).rev() {
+		let digit = byte(low, i) + high.map_or(u8::MAX as u32, |high| byte(high, i)) + carry;
+		sum[i] = digit & 0xff;
+		carry = digit >> 8;
+	}
+	let mut middle = Vec::with_capacity(width);
+	let mut remainder = carry;
+	for digit in sum {
+		let value = (remainder << 8) | digit;
+		middle.push((value / 2) as u8);
+		remainder = value % 2;
+	}
+	(middle.as_slice() > low && high.map_or(true, |high| middle.as_slice() < high))
+		.then_some(middle)
+}
+
+/// Length of the longest common prefix of `a` and `b`.
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+	a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// Size of the keys of `levels`, excluding child trie roots.
+fn levels_size(levels: &Levels) -> u64 {
+	levels
+		.iter()
+		.flat_map(|(_, key_values)| key_values)
+		.filter(|(key, _)| !well_known_keys::is_child_storage_key(key))
+		.map(|(key, _)| key.len() as u64)
+		.sum()
+}
+
+/// Add the key values of a chunk to `state`.
+fn insert_levels(state: &mut State, levels: Levels) {
+	for (root, key_values) in levels {
+		if root.is_empty() {
+			let mut child_roots = Vec::new();
+			let entry = state.entry(root).or_default();
+			for (key, value) in key_values {
+				// Skip all child key root (will be recalculated on import).
+				if well_known_keys::is_child_storage_key(key.as_slice()) {
+					child_roots.push((value, key));
+				} else {
+					entry.0.push((key, value))
+				}
+			}
+			for (root, storage_key) in child_roots {
+				state.entry(root).or_default().1.push(storage_key);
+			}
+		} else {
+			let entry = state.entry(root).or_default();
+			if entry.0.len() > 0 && entry.1.len() > 1 {
+				// Already imported child trie with same root. All child tries live in the
+				// range of the `:child_storage:` prefix, so they are never downloaded in parallel.
+			} else {
+				entry.0.extend(key_values);
+			}
+		}
+	}
+}
+
+/// A verified chunk of state.
+struct StateChunk {
+	/// Index of the range the chunk belongs to.
+	range: usize,
+	/// Cursor of the range after the chunk.
+	cursor: Vec<Vec<u8>>,
+	/// Whether the chunk completes the range.
+	complete: bool,
+	/// Downloaded key values.
+	levels: Levels,
+	/// Size of the proof the chunk was verified with, if any.
+	proof_size: u64,
+}
 
-/// State sync state machine. Accumulates partial state data until it
+/// State sync progress, as persisted to aux storage.
+#[derive(Encode, Decode)]
+struct PersistedProgress<H> {
+	/// Target block of the state sync.
+	target: H,
+	/// Ranges of the key space, as of the last persisted chunk.
+	ranges: Vec<KeyRange>,
+	/// Number of persisted chunks.
+	chunks: u32,
+	/// Total size of the persisted chunks.
+	imported_bytes: u64,
+}
+
+/// Aux storage key of the persisted chunk at `index`.
+fn chunk_key(index: u32) -> Vec<u8> {
+	let mut key = STATE_SYNC_CHUNK_PREFIX.to_vec();
+	key.extend_from_slice(&index.to_be_bytes());
+	key
+}
+
+/// Read the persisted state sync progress, if any.
+fn load_progress<B: BlockT, Client: AuxStore>(
+	client: &Client,
+) -> Option<PersistedProgress<B::Hash>> {
+	match client.get_aux(STATE_SYNC_PROGRESS_KEY) {
+		Ok(Some(encoded)) => match PersistedProgress::decode(&mut &encoded[..]) {
+			Ok(progress) => Some(progress),
+			Err(e) => {
+				warn!(target: "sync", "Failed to decode persisted state sync progress: {}", e);
+				None
+			},
+		},
+		Ok(None) => None,
+		Err(e) => {
+			warn!(target: "sync", "Failed to read persisted state sync progress: {}", e);
+			None
+		},
+	}
+}
+
+/// Remove the persisted state sync progress, along with its first `chunks` chunks.
+fn clear_progress<Client: AuxStore>(client: &Client, chunks: u32) {
+	let keys = (0..chunks).map(chunk_key).collect::<Vec<_>>();
+	let deleted = keys.iter().map(Vec::as_slice).chain(std::iter::once(STATE_SYNC_PROGRESS_KEY));
+	if let Err(e) = client.insert_aux(&[], &deleted.collect::<Vec<_>>()) {
+		warn!(target: "sync", "Failed to remove persisted state sync progress: {}", e);
+	}
+}
+
+/// Returns the target block of the state sync persisted to aux storage, if any.
+pub fn persisted_target<B: BlockT, Client: AuxStore>(client: &Client) -> Option<B::Hash> {
+	load_progress::<B, _>(client).map(|progress| progress.target)
+}
+
+/// State sync state machine. Persists partial state data until it
 /// is ready to be imported.
 pub struct StateSync<B: BlockT, Client> {
 	target_block: B::Hash,
@@ -40,12 +330,16 @@ pub struct StateSync<B: BlockT, Client> {
 	target_root: B::Hash,
 	target_body: Option<Vec<B::Extrinsic>>,
 	target_justifications: Option<Justifications>,
-	last_key: SmallVec<[Vec<u8>; 2]>,
-	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
+	ranges: Vec<KeyRange>,
+	/// Number of chunks persisted to aux storage.
+	persisted_chunks: u32,
+	/// Chunks kept in memory because they could not be persisted.
+	unpersisted: Vec<Levels>,
 	complete: bool,
 	client: Arc<Client>,
 	imported_bytes: u64,
 	skip_proof: bool,
+	peer_bytes: HashMap<PeerId, u64>,
 }
 
 /// Import state chunk result.
@@ -61,9 +355,12 @@ pub enum ImportResult<B: BlockT> {
 impl<B, Client> StateSync<B, Client>
 where
 	B: BlockT,
-	Client: ProofProvider<B> + Send + Sync + 'static,
+	Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
 {
 	///  Create a new instance.
+	///
+	/// The progress persisted for the same target is restored, while the progress persisted for
+	/// any other target is discarded.
 	pub fn new(
 		client: Arc<Client>,
 		target_header: B::Header,
@@ -71,116 +368,150 @@ where
 		target_justifications: Option<Justifications>,
 		skip_proof: bool,
 	) -> Self {
-		Self {
+		let mut sync = Self {
 			client,
 			target_block: target_header.hash(),
 			target_root: *target_header.state_root(),
 			target_header,
 			target_body,
 			target_justifications,
-			last_key: SmallVec::default(),
-			state: HashMap::default(),
+			ranges: (0..STATE_SYNC_RANGES).map(KeyRange::new).collect(),
+			persisted_chunks: 0,
+			unpersisted: Vec::new(),
 			complete: false,
 			imported_bytes: 0,
 			skip_proof,
+			peer_bytes: HashMap::default(),
+		};
+
+		match load_progress::<B, _>(&*sync.client) {
+			Some(progress) if progress.target == sync.target_block => {
+				sync.ranges = progress.ranges;
+				sync.persisted_chunks = progress.chunks;
+				sync.imported_bytes = progress.imported_bytes;
+				info!(
+					target: "sync",
+					"Resuming state sync of {} from {} persisted chunks ({}%)",
+					sync.target_block,
+					progress.chunks,
+					sync.progress().percentage,
+				);
+			},
+			Some(progress) => clear_progress(&*sync.client, progress.chunks),
+			None => {},
 		}
+		sync
 	}
 
-	///  Validate and import a state response.
-	pub fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from `who`.
+	pub fn import(&mut self, who: &PeerId, response: StateResponse) -> ImportResult<B> {
+		let index = match self.ranges.iter().position(|range| range.peer.as_ref() == Some(who)) {
+			Some(index) => index,
+			None => {
+				debug!(target: "sync", "Unexpected state response from {}", who);
+				return ImportResult::BadResponse
+			},
+		};
+		self.ranges[index].peer = None;
+
+		let chunk = match self.verify(index, response) {
+			Some(chunk) => chunk,
+			None => return ImportResult::BadResponse,
+		};
+
+		let imported_bytes = self.imported_bytes;
+		let levels = self.apply(chunk);
+		*self.peer_bytes.entry(*who).or_default() += self.imported_bytes - imported_bytes;
+
+		if !self.ranges.iter().all(|range| range.complete) {
+			self.persist(levels);
+			return ImportResult::Continue
+		}
+
+		// The chunk completing the state is not persisted: if the node is stopped before the
+		// state is imported, it is downloaded again.
+		self.unpersisted.push(levels);
+		match self.assemble() {
+			Some(state) => {
+				self.complete = true;
+				ImportResult::Import(
+					self.target_block,
+					self.target_header.clone(),
+					ImportedState { block: self.target_block, state },
+					self.target_body.clone(),
+					self.target_justifications.clone(),
+				)
+			},
+			None => {
+				warn!(target: "sync", "Persisted state sync chunks are missing, starting over");
+				self.clear_persisted();
+				self.ranges = (0..STATE_SYNC_RANGES).map(KeyRange::new).collect();
+				self.imported_bytes = 0;
+				ImportResult::Continue
+			},
+		}
+	}
+
+	/// Validate a state response for the range at `index`.
+	fn verify(&self, index: usize, response: StateResponse) -> Option<StateChunk> {
 		if response.entries.is_empty() && response.proof.is_empty() {
 			debug!(target: "sync", "Bad state response");
-			return ImportResult::BadResponse
+			return None
 		}
 		if !self.skip_proof && response.proof.is_empty() {
 			debug!(target: "sync", "Missing proof");
-			return ImportResult::BadResponse
+			return None
 		}
-		let complete = if !self.skip_proof {
+		let range = &self.ranges[index];
+		let mut cursor: SmallVec<[Vec<u8>; 2]> = range.cursor.iter().cloned().collect();
+		let (mut levels, complete, proof_size): (Levels, _, _) = if !self.skip_proof {
 			debug!(target: "sync", "Importing state from {} trie nodes", response.proof.len());
 			let proof_size = response.proof.len() as u64;
 			let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
 				Ok(proof) => proof,
 				Err(e) => {
 					debug!(target: "sync", "Error decoding proof: {:?}", e);
-					return ImportResult::BadResponse
-				},
-			};
-			let (values, completed) = match self.client.verify_range_proof(
-				self.target_root,
-				proof,
-				self.last_key.as_slice(),
-			) {
-				Err(e) => {
-					debug!(
-						target: "sync",
-						"StateResponse failed proof verification: {}",
-						e,
-					);
-					return ImportResult::BadResponse
+					return None
 				},
-				Ok(values) => values,
 			};
+			let (values, completed) =
+				match self.client.verify_range_proof(self.target_root, proof, cursor.as_slice()) {
+					Err(e) => {
+						debug!(
+							target: "sync",
+							"StateResponse failed proof verification: {}",
+							e,
+						);
+						return None
+					},
+					Ok(values) => values,
+				};
 			debug!(target: "sync", "Imported with {} keys", values.len());
 
 			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.last_key) {
+			if !complete && !values.update_last_key(completed, &mut cursor) {
 				debug!(target: "sync", "Error updating key cursor, depth: {}", completed);
 			};
 
-			for values in values.0 {
-				let key_values = if values.state_root.is_empty() {
-					// Read child trie roots.
-					values
-						.key_values
-						.into_iter()
-						.filter(|key_value| {
-							if well_known_keys::is_child_storage_key(key_value.0.as_slice()) {
-								self.state
-									.entry(key_value.1.clone())
-									.or_default()
-									.1
-									.push(key_value.0.clone());
-								false
-							} else {
-								true
-							}
-						})
-						.collect()
-				} else {
-					values.key_values
-				};
-				let mut entry = self.state.entry(values.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child_trie with same root.
-					// Warning this will not work with parallel download.
-				} else if entry.0.is_empty() {
-					for (key, _value) in key_values.iter() {
-						self.imported_bytes += key.len() as u64;
-					}
-
-					entry.0 = key_values;
-				} else {
-					for (key, value) in key_values {
-						self.imported_bytes += key.len() as u64;
-						entry.0.push((key, value))
-					}
-				}
-			}
-			self.imported_bytes += proof_size;
-			complete
+			let levels = values
+				.0
+				.into_iter()
+				.map(|values| (values.state_root, values.key_values))
+				.collect();
+			(levels, complete, proof_size)
 		} else {
 			let mut complete = true;
 			// if the trie is a child trie and one of its parent trie is empty,
 			// the parent cursor stays valid.
 			// Empty parent trie content only happens when all the response content
 			// is part of a single child trie.
-			if self.last_key.len() == 2 && response.entries[0].entries.is_empty() {
+			if cursor.len() == 2 && response.entries[0].entries.is_empty() {
 				// Do not remove the parent trie position.
-				self.last_key.pop();
+				cursor.pop();
 			} else {
-				self.last_key.clear();
+				cursor.clear();
 			}
+			let mut levels = Vec::with_capacity(response.entries.len());
 			for state in response.entries {
 				debug!(
 					target: "sync",
@@ -191,58 +522,150 @@ where
 
 				if !state.complete {
 					if let Some(e) = state.entries.last() {
-						self.last_key.push(e.key.clone());
+						cursor.push(e.key.clone());
 					}
 					complete = false;
 				}
-				let is_top = state.state_root.is_empty();
-				let entry = self.state.entry(state.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child trie with same root.
-				} else {
-					let mut child_roots = Vec::new();
-					for StateEntry { key, value } in state.entries {
-						// Skip all child key root (will be recalculated on import).
-						if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
-							child_roots.push((value, key));
-						} else {
-							self.imported_bytes += key.len() as u64;
-							entry.0.push((key, value))
-						}
-					}
-					for (root, storage_key) in child_roots {
-						self.state.entry(root).or_default().1.push(storage_key);
-					}
-				}
+				let key_values = state
+					.entries
+					.into_iter()
+					.map(|StateEntry { key, value }| (key, value))
+					.collect();
+				levels.push((state.state_root, key_values));
 			}
-			complete
+			(levels, complete, 0)
 		};
+
+		// Responses are not bounded by the end of the range, anything past it is downloaded
+		// as part of the next range.
+		let complete = range.clip(&mut levels) || complete;
 		if complete {
-			self.complete = true;
-			ImportResult::Import(
-				self.target_block,
-				self.target_header.clone(),
-				ImportedState {
-					block: self.target_block,
-					state: std::mem::take(&mut self.state).into(),
+			cursor.clear();
+		}
+		Some(StateChunk { range: index, cursor: cursor.into_vec(), complete, levels, proof_size })
+	}
+
+	/// Advance the range of a verified chunk, returning the key values of the chunk.
+	fn apply(&mut self, chunk: StateChunk) -> Levels {
+		let range = &mut self.ranges[chunk.range];
+		range.cursor = chunk.cursor;
+		range.complete = chunk.complete;
+		range.chunks += 1;
+		// A single key tells nothing about the prefix the following keys share.
+		if let Some((_, key_values)) = chunk
+			.levels
+			.iter()
+			.find(|(root, key_values)| root.is_empty() && key_values.len() > 1)
+		{
+			range.prefix = common_prefix_len(&key_values[0].0, &key_values[key_values.len() - 1].0);
+		}
+		self.imported_bytes += chunk.proof_size + levels_size(&chunk.levels);
+		chunk.levels
+	}
+
+	/// Persist a chunk to aux storage, along with the ranges it was applied to.
+	///
+	/// Once a chunk fails to be persisted, it and all the later chunks are kept in memory
+	/// instead, so that the persisted progress stays consistent with the persisted chunks.
+	fn persist(&mut self, levels: Levels) {
+		if !self.unpersisted.is_empty() {
+			self.unpersisted.push(levels);
+			return
+		}
+		let progress = PersistedProgress {
+			target: self.target_block,
+			ranges: std::mem::take(&mut self.ranges),
+			chunks: self.persisted_chunks + 1,
+			imported_bytes: self.imported_bytes,
+		};
+		let encoded_progress = progress.encode();
+		self.ranges = progress.ranges;
+		let key = chunk_key(self.persisted_chunks);
+		match self.client.insert_aux(
+			&[(&key[..], &levels.encode()[..]), (STATE_SYNC_PROGRESS_KEY, &encoded_progress[..])],
+			&[],
+		) {
+			Ok(()) => self.persisted_chunks += 1,
+			Err(e) => {
+				warn!(
+					target: "sync",
+					"Failed to persist state sync chunk, state sync will not be resumable: {}",
+					e,
+				);
+				self.unpersisted.push(levels);
+			},
+		}
+	}
+
+	/// Read the persisted chunks back and assemble them with the chunks kept in memory.
+	/// Returns `None` if a persisted chunk is missing.
+	fn assemble(&mut self) -> Option<KeyValueStates> {
+		let mut state = State::default();
+		for index in 0..self.persisted_chunks {
+			let levels = match self.client.get_aux(&chunk_key(index)) {
+				Ok(Some(encoded)) => Levels::decode(&mut &encoded[..]).ok(),
+				Ok(None) => None,
+				Err(e) => {
+					warn!(target: "sync", "Failed to read persisted state sync chunk: {}", e);
+					None
 				},
-				self.target_body.clone(),
-				self.target_justifications.clone(),
-			)
-		} else {
-			ImportResult::Continue
+			};
+			insert_levels(&mut state, levels?);
+		}
+		for levels in std::mem::take(&mut self.unpersisted) {
+			insert_levels(&mut state, levels);
 		}
+		Some(state.into())
 	}
 
-	/// Produce next state request.
-	pub fn next_request(&self) -> StateRequest {
-		StateRequest {
+	/// Produce the next state request for `who`. A range is split to produce it if all the
+	/// remaining ranges are already requested from other peers.
+	pub fn next_request(&mut self, who: &PeerId) -> Option<StateRequest> {
+		if self.complete || self.ranges.iter().any(|range| range.peer.as_ref() == Some(who)) {
+			return None
+		}
+		let index =
+			match self.ranges.iter().position(|range| !range.complete && range.peer.is_none()) {
+				Some(index) => index,
+				None => self.split_range()?,
+			};
+		let range = &mut self.ranges[index];
+		range.peer = Some(*who);
+		Some(StateRequest {
 			block: self.target_block.encode(),
-			start: self.last_key.clone().into_vec(),
+			start: range.cursor.clone(),
 			no_proof: self.skip_proof,
+		})
+	}
+
+	/// Split the range that most chunks were downloaded from since it was last split, returning
+	/// the index of the new range.
+	fn split_range(&mut self) -> Option<usize> {
+		if self.ranges.len() >= MAX_STATE_SYNC_RANGES {
+			return None
+		}
+		let mut candidates = (0..self.ranges.len()).collect::<Vec<_>>();
+		candidates.sort_by_key(|index| std::cmp::Reverse(self.ranges[*index].chunks));
+		let range = candidates.into_iter().find_map(|index| self.ranges[index].split())?;
+		self.ranges.push(range);
+		debug!(target: "sync", "Split a state range, downloading {} ranges", self.ranges.len());
+		Some(self.ranges.len() - 1)
+	}
+
+	/// Release the range requested from a disconnected peer, so that it can be requested from
+	/// another one.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		for range in self.ranges.iter_mut().filter(|range| range.peer.as_ref() == Some(who)) {
+			range.peer = None;
 		}
 	}
 
+	/// Remove the persisted progress of this state sync, once the state is imported.
+	pub fn clear_persisted(&mut self) {
+		clear_progress(&*self.client, self.persisted_chunks);
+		self.persisted_chunks = 0;
+	}
+
 	/// Check if the state is complete.
 	pub fn is_complete(&self) -> bool {
 		self.complete
@@ -260,8 +683,206 @@ where
 
 	/// Returns state sync estimated progress.
 	pub fn progress(&self) -> StateDownloadProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
-		StateDownloadProgress { percentage: percent_done, size: self.imported_bytes }
+		let percent_done = if self.ranges.iter().all(|range| range.complete) {
+			100
+		} else {
+			let downloaded: f64 = self.ranges.iter().map(KeyRange::downloaded).sum();
+			((downloaded * 100.0) as u32).min(99)
+		};
+		let mut peers = self
+			.peer_bytes
+			.iter()
+			.map(|(peer_id, size)| PeerStateDownloadProgress {
+				peer_id: *peer_id,
+				size: *size,
+				downloading: self.ranges.iter().any(|range| range.peer.as_ref() == Some(peer_id)),
+			})
+			.collect::<Vec<_>>();
+		peers.extend(
+			self.ranges
+				.iter()
+				.filter_map(|range| range.peer)
+				.filter(|peer_id| !self.peer_bytes.contains_key(peer_id))
+				.map(|peer_id| PeerStateDownloadProgress { peer_id, size: 0, downloading: true }),
+		);
+		StateDownloadProgress { percentage: percent_done, size: self.imported_bytes, peers }
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use sc_client_api::StorageProvider;
+	use sp_blockchain::HeaderBackend;
+	use sp_core::storage::ChildInfo;
+	use substrate_test_runtime_client::{
+		runtime::Block, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
+	};
+
+	/// Prefix shared by the keys of a large storage map in the test state.
+	const MAP_PREFIX: [u8; 4] = [0x26, 0xaa, 0x39, 0x4e];
+
+	fn test_client() -> Arc<TestClient> {
+		let mut builder = TestClientBuilder::new()
+			.add_extra_child_storage(&ChildInfo::new_default(b"child"), vec![1], vec![2])
+			// Key at the boundary between the first two ranges.
+			.add_extra_storage(vec![RANGE_WIDTH as u8], vec![1]);
+		for first in (0..=255u8).step_by(5) {
+			builder = builder.add_extra_storage(vec![first, 1], vec![first; 64]);
+		}
+		for index in 0..=255u8 {
+			builder =
+				builder.add_extra_storage([&MAP_PREFIX[..], &[index]].concat(), vec![index; 32]);
+		}
+		Arc::new(builder.build())
+	}
+
+	fn state_sync(client: &Arc<TestClient>) -> StateSync<Block, TestClient> {
+		let header = client.header(client.info().genesis_hash).unwrap().unwrap();
+		StateSync::new(client.clone(), header, None, None, false)
+	}
+
+	fn response(client: &TestClient, request: &StateRequest) -> StateResponse {
+		let block = Decode::decode(&mut request.block.as_ref()).unwrap();
+		let (proof, _) = client.read_proof_collection(block, &request.start, 256).unwrap();
+		StateResponse { entries: Vec::new(), proof: proof.encode() }
+	}
+
+	/// Download from all `peers` in parallel, for at most `rounds` requests per peer.
+	fn download(
+		sync: &mut StateSync<Block, TestClient>,
+		client: &TestClient,
+		peers: &[PeerId],
+		rounds: usize,
+	) -> Option<ImportedState<Block>> {
+		for _ in 0..rounds {
+			let requests = peers
+				.iter()
+				.filter_map(|peer| sync.next_request(peer).map(|request| (*peer, request)))
+				.collect::<Vec<_>>();
+			for (peer, request) in requests {
+				match sync.import(&peer, response(client, &request)) {
+					ImportResult::Import(_, _, state, _, _) => return Some(state),
+					ImportResult::Continue => {},
+					ImportResult::BadResponse => panic!("Unexpected bad response"),
+				}
+			}
+		}
+		None
+	}
+
+	fn assert_complete_state(client: &TestClient, state: ImportedState<Block>) {
+		let mut expected = client
+			.storage_keys(client.info().genesis_hash, None, None)
+			.unwrap()
+			.map(|key| key.0)
+			.filter(|key| !well_known_keys::is_child_storage_key(key))
+			.collect::<Vec<_>>();
+		expected.sort();
+
+		let mut top = Vec::new();
+		let mut child = Vec::new();
+		for level in state.state.0 {
+			if level.state_root.is_empty() {
+				top.extend(level.key_values.into_iter().map(|(key, _)| key));
+			} else {
+				child.extend(level.key_values);
+			}
+		}
+		top.sort();
+		assert_eq!(top, expected);
+		assert_eq!(child, vec![(vec![1], vec![2])]);
+	}
+
+	#[test]
+	fn middle_key_is_strictly_between_bounds() {
+		assert_eq!(middle_key(&[0x26], Some(&[0x27])), Some(vec![0x26, 0x80]));
+		assert_eq!(middle_key(&[0xff], None), Some(vec![0xff, 0x7f]));
+		assert_eq!(middle_key(&[], Some(&[0x00, 0x01])), Some(vec![0x00, 0x00, 0x80]));
+		// There is no key between a key and the same key followed by a zero byte.
+		assert_eq!(middle_key(&[0x01], Some(&[0x01, 0x00])), None);
+
+		assert_eq!(prefix_end(&[0x26, 0xff]), Some(vec![0x27]));
+		assert_eq!(prefix_end(&[0xff]), None);
+	}
+
+	#[test]
+	fn ranges_are_split_inside_the_prefix_of_the_last_chunk() {
+		let mut range = KeyRange::between(Some(vec![0x20]), Some(vec![0x30]));
+		// Nothing is known about the range before a chunk was downloaded from it.
+		assert!(range.split().is_none());
+
+		range.cursor = vec![[&MAP_PREFIX[..], &[0x10]].concat()];
+		range.chunks = 1;
+		range.prefix = MAP_PREFIX.len();
+		let split = range.split().unwrap();
+		let middle = range.end.clone().unwrap();
+		assert_eq!(split.start, Some(middle.clone()));
+		assert_eq!(split.end, Some(vec![0x30]));
+		assert_eq!(split.cursor, vec![middle.clone()]);
+		assert!(middle > range.cursor[0] && middle < prefix_end(&MAP_PREFIX).unwrap());
+		assert_eq!(range.chunks, 0);
+	}
+
+	#[test]
+	fn ranges_are_downloaded_from_peers_in_parallel() {
+		let client = test_client();
+		let mut sync = state_sync(&client);
+		let peers = (0..4).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+		let first = sync.next_request(&peers[0]).unwrap();
+		let second = sync.next_request(&peers[1]).unwrap();
+		assert_ne!(first.start, second.start);
+		// A peer is only assigned one range at a time.
+		assert!(sync.next_request(&peers[0]).is_none());
+		assert_eq!(sync.progress().peers.iter().filter(|peer| peer.downloading).count(), 2);
+
+		// Released ranges are requested again.
+		sync.peer_disconnected(&peers[0]);
+		sync.peer_disconnected(&peers[1]);
+		assert_eq!(sync.next_request(&peers[2]).unwrap().start, first.start);
+
+		sync.peer_disconnected(&peers[2]);
+		let state = download(&mut sync, &client, &peers, usize::MAX).unwrap();
+		assert!(sync.is_complete());
+		assert_eq!(sync.progress().percentage, 100);
+		assert!(sync.progress().peers.iter().filter(|peer| peer.size > 0).count() > 1);
+		assert_complete_state(&client, state);
+	}
+
+	#[test]
+	fn idle_peers_download_split_ranges() {
+		let client = test_client();
+		let mut sync = state_sync(&client);
+		let peers = (0..2 * STATE_SYNC_RANGES).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+		let state = download(&mut sync, &client, &peers, usize::MAX).unwrap();
+		assert!(sync.ranges.len() > STATE_SYNC_RANGES);
+		assert_complete_state(&client, state);
+	}
+
+	#[test]
+	fn interrupted_state_sync_resumes_from_persisted_chunks() {
+		let client = test_client();
+		let peers = (0..2).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+		let mut sync = state_sync(&client);
+		assert!(download(&mut sync, &client, &peers, 4).is_none());
+		let progress = sync.progress();
+		assert!(progress.size > 0);
+		drop(sync);
+
+		assert_eq!(persisted_target::<Block, _>(&*client), Some(client.info().genesis_hash));
+		let mut sync = state_sync(&client);
+		let resumed = sync.progress();
+		assert_eq!((resumed.percentage, resumed.size), (progress.percentage, progress.size));
+
+		let state = download(&mut sync, &client, &peers, usize::MAX).unwrap();
+		assert_complete_state(&client, state);
+
+		sync.clear_persisted();
+		assert_eq!(persisted_target::<Block, _>(&*client), None);
+		assert_eq!(client.get_aux(&chunk_key(0)).unwrap(), None);
 	}
 }
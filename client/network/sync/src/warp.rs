@@ -17,15 +17,22 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Warp sync support.
+//!
+//! Verified warp sync progress is persisted to aux storage, so that a restarted node resumes
+//! from the last verified warp proof fragment, or from the target block once all proofs were
+//! verified, instead of starting over from the genesis authorities. A target block persisted too
+//! long ago is likely pruned by peers: warp proofs are then requested again, starting from it.
 
 use crate::{
 	oneshot,
 	schema::v1::{StateRequest, StateResponse},
 	state::{ImportResult, StateSync},
 };
+use codec::{Decode, Encode};
 use futures::FutureExt;
-use log::error;
-use sc_client_api::ProofProvider;
+use libp2p::PeerId;
+use log::{error, info, warn};
+use sc_client_api::{AuxStore, ProofProvider};
 use sc_network_common::sync::{
 	message::{BlockAttributes, BlockData, BlockRequest, Direction, FromBlock},
 	warp::{
@@ -33,10 +40,19 @@ use sc_network_common::sync::{
 		WarpSyncProgress, WarpSyncProvider,
 	},
 };
+use sc_utils::persistence::unix_time;
 use sp_blockchain::HeaderBackend;
 use sp_consensus_grandpa::{AuthorityList, SetId};
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor, Zero};
-use std::{sync::Arc, task::Poll};
+use std::{sync::Arc, task::Poll, time::Duration};
+
+/// Aux storage key of the persisted warp sync progress.
+const WARP_SYNC_PROGRESS_KEY: &[u8] = b"sync_warp_progress";
+/// Maximum age of a persisted target block to resume state sync from.
+///
+/// Peers keep the state of the last 256 blocks by default, which takes roughly 25 minutes to
+/// produce on a chain with 6 second blocks.
+const MAX_TARGET_BLOCK_AGE: Duration = Duration::from_secs(10 * 60);
 
 enum Phase<B: BlockT, Client> {
 	WarpProof {
@@ -52,6 +68,43 @@ enum Phase<B: BlockT, Client> {
 	State(StateSync<B, Client>),
 }
 
+/// Warp sync progress, as persisted to aux storage.
+#[derive(Encode, Decode)]
+enum PersistedPhase<B: BlockT> {
+	/// Proofs were verified up to `last_hash`, which was finalized by `authorities`.
+	WarpProof { set_id: SetId, authorities: AuthorityList, last_hash: B::Hash },
+	/// All proofs were verified, the target block is known.
+	///
+	/// `set_id` and `authorities` are those that finalize the children of the target block, and
+	/// `persisted_at` is the UNIX time in seconds at which the target block was persisted.
+	TargetBlock { header: B::Header, set_id: SetId, authorities: AuthorityList, persisted_at: u64 },
+}
+
+/// Read the persisted warp sync progress, if any.
+fn load_progress<B: BlockT, Client: AuxStore>(client: &Client) -> Option<PersistedPhase<B>> {
+	match client.get_aux(WARP_SYNC_PROGRESS_KEY) {
+		Ok(Some(encoded)) => match PersistedPhase::decode(&mut &encoded[..]) {
+			Ok(phase) => Some(phase),
+			Err(e) => {
+				warn!(target: "sync", "Failed to decode persisted warp sync progress: {}", e);
+				None
+			},
+		},
+		Ok(None) => None,
+		Err(e) => {
+			warn!(target: "sync", "Failed to read persisted warp sync progress: {}", e);
+			None
+		},
+	}
+}
+
+/// Persist the warp sync progress.
+fn persist_progress<B: BlockT, Client: AuxStore>(client: &Client, phase: &PersistedPhase<B>) {
+	if let Err(e) = client.insert_aux(&[(WARP_SYNC_PROGRESS_KEY, &phase.encode()[..])], &[]) {
+		warn!(target: "sync", "Failed to persist warp sync progress: {}", e);
+	}
+}
+
 /// Import warp proof result.
 pub enum WarpProofImportResult {
 	/// Import was successful.
@@ -78,20 +131,67 @@ pub struct WarpSync<B: BlockT, Client> {
 impl<B, Client> WarpSync<B, Client>
 where
 	B: BlockT,
-	Client: HeaderBackend<B> + ProofProvider<B> + 'static,
+	Client: HeaderBackend<B> + ProofProvider<B> + AuxStore + 'static,
 {
 	/// Create a new instance. When passing a warp sync provider we will be checking for proof and
-	/// authorities. Alternatively we can pass a target block when we want to skip downloading
-	/// proofs, in this case we will continue polling until the target block is known.
+	/// authorities, resuming from the progress persisted by a previous run if any. Alternatively
+	/// we can pass a target block when we want to skip downloading proofs, in this case we will
+	/// continue polling until the target block is known.
 	pub fn new(client: Arc<Client>, warp_sync_params: WarpSyncParams<B>) -> Self {
-		let last_hash = client.hash(Zero::zero()).unwrap().expect("Genesis header always exists");
 		match warp_sync_params {
 			WarpSyncParams::WithProvider(warp_sync_provider) => {
-				let phase = Phase::WarpProof {
-					set_id: 0,
-					authorities: warp_sync_provider.current_authorities(),
-					last_hash,
-					warp_sync_provider: warp_sync_provider.clone(),
+				let phase = match load_progress::<B, _>(&*client) {
+					Some(PersistedPhase::WarpProof { set_id, authorities, last_hash }) => {
+						info!(
+							target: "sync",
+							"Resuming warp sync from {} (set_id={})",
+							last_hash,
+							set_id,
+						);
+						Phase::WarpProof { set_id, authorities, last_hash, warp_sync_provider }
+					},
+					Some(PersistedPhase::TargetBlock {
+						header,
+						set_id,
+						authorities,
+						persisted_at,
+					}) =>
+						if unix_time().saturating_sub(persisted_at) <=
+							MAX_TARGET_BLOCK_AGE.as_secs()
+						{
+							info!(
+								target: "sync",
+								"Resuming warp sync at target block #{} ({})",
+								header.number(),
+								header.hash(),
+							);
+							Phase::TargetBlock(header)
+						} else {
+							info!(
+								target: "sync",
+								"Warp sync target block #{} ({}) is stale, resuming warp sync from it",
+								header.number(),
+								header.hash(),
+							);
+							Phase::WarpProof {
+								set_id,
+								authorities,
+								last_hash: header.hash(),
+								warp_sync_provider,
+							}
+						},
+					None => {
+						let last_hash = client
+							.hash(Zero::zero())
+							.unwrap()
+							.expect("Genesis header always exists");
+						Phase::WarpProof {
+							set_id: 0,
+							authorities: warp_sync_provider.current_authorities(),
+							last_hash,
+							warp_sync_provider: warp_sync_provider.clone(),
+						}
+					},
 				};
 				Self { client, phase, total_proof_bytes: 0 }
 			},
@@ -126,14 +226,14 @@ where
 		self.phase = new_phase;
 	}
 
-	///  Validate and import a state response.
-	pub fn import_state(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from `who`.
+	pub fn import_state(&mut self, who: &PeerId, response: StateResponse) -> ImportResult<B> {
 		match &mut self.phase {
 			Phase::WarpProof { .. } | Phase::TargetBlock(_) | Phase::PendingTargetBlock { .. } => {
 				log::debug!(target: "sync", "Unexpected state response");
 				ImportResult::BadResponse
 			},
-			Phase::State(sync) => sync.import(response),
+			Phase::State(sync) => sync.import(who, response),
 		}
 	}
 
@@ -152,15 +252,32 @@ where
 					},
 					Ok(VerificationResult::Partial(new_set_id, new_authorities, new_last_hash)) => {
 						log::debug!(target: "sync", "Verified partial proof, set_id={:?}", new_set_id);
+						persist_progress(
+							&*self.client,
+							&PersistedPhase::<B>::WarpProof {
+								set_id: new_set_id,
+								authorities: new_authorities.clone(),
+								last_hash: new_last_hash,
+							},
+						);
 						*set_id = new_set_id;
 						*authorities = new_authorities;
 						*last_hash = new_last_hash;
 						self.total_proof_bytes += response.0.len() as u64;
 						WarpProofImportResult::Success
 					},
-					Ok(VerificationResult::Complete(new_set_id, _, header)) => {
+					Ok(VerificationResult::Complete(new_set_id, new_authorities, header)) => {
 						log::debug!(target: "sync", "Verified complete proof, set_id={:?}", new_set_id);
 						self.total_proof_bytes += response.0.len() as u64;
+						persist_progress(
+							&*self.client,
+							&PersistedPhase::<B>::TargetBlock {
+								header: header.clone(),
+								set_id: new_set_id,
+								authorities: new_authorities,
+								persisted_at: unix_time(),
+							},
+						);
 						self.phase = Phase::TargetBlock(header);
 						WarpProofImportResult::Success
 					},
@@ -209,12 +326,29 @@ where
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_state_request(&self) -> Option<StateRequest> {
-		match &self.phase {
+	/// Produce next state request for `who`.
+	pub fn next_state_request(&mut self, who: &PeerId) -> Option<StateRequest> {
+		match &mut self.phase {
 			Phase::WarpProof { .. } | Phase::TargetBlock(_) | Phase::PendingTargetBlock { .. } =>
 				None,
-			Phase::State(sync) => Some(sync.next_request()),
+			Phase::State(sync) => sync.next_request(who),
+		}
+	}
+
+	/// Remove the persisted progress, once warp sync is over.
+	pub fn clear_persisted(&mut self) {
+		if let Err(e) = self.client.insert_aux(&[], &[WARP_SYNC_PROGRESS_KEY]) {
+			warn!(target: "sync", "Failed to remove persisted warp sync progress: {}", e);
+		}
+		if let Phase::State(sync) = &mut self.phase {
+			sync.clear_persisted();
+		}
+	}
+
+	/// Release the state range requested from a disconnected peer.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		if let Phase::State(sync) = &mut self.phase {
+			sync.peer_disconnected(who);
 		}
 	}
 
@@ -297,3 +431,125 @@ where
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::traits::Header as _;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Header},
+		DefaultTestClientBuilderExt, TestClient, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	/// Accepts `[1]` as a partial proof up to `last_hash` and `[2]` as a complete proof up to
+	/// `target`.
+	struct TestProvider {
+		last_hash: H256,
+		target: Header,
+	}
+
+	impl WarpSyncProvider<Block> for TestProvider {
+		fn generate(
+			&self,
+			_start: H256,
+		) -> Result<EncodedProof, Box<dyn std::error::Error + Send + Sync>> {
+			Err("Proofs are only verified in these tests".into())
+		}
+
+		fn verify(
+			&self,
+			proof: &EncodedProof,
+			set_id: SetId,
+			authorities: AuthorityList,
+		) -> Result<VerificationResult<Block>, Box<dyn std::error::Error + Send + Sync>> {
+			match proof.0[..] {
+				[1] => Ok(VerificationResult::Partial(set_id + 1, authorities, self.last_hash)),
+				[2] =>
+					Ok(VerificationResult::Complete(set_id + 1, authorities, self.target.clone())),
+				_ => Err("Bad proof".into()),
+			}
+		}
+
+		fn current_authorities(&self) -> AuthorityList {
+			Vec::new()
+		}
+	}
+
+	fn target(client: &Arc<TestClient>) -> Header {
+		Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			client.info().genesis_hash,
+			Default::default(),
+		)
+	}
+
+	fn warp_sync(client: &Arc<TestClient>) -> WarpSync<Block, TestClient> {
+		let provider = TestProvider { last_hash: H256::repeat_byte(1), target: target(client) };
+		WarpSync::new(client.clone(), WarpSyncParams::WithProvider(Arc::new(provider)))
+	}
+
+	#[test]
+	fn warp_sync_resumes_from_persisted_progress() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let genesis_hash = client.info().genesis_hash;
+
+		let mut sync = warp_sync(&client);
+		assert_eq!(sync.next_warp_proof_request().unwrap().begin, genesis_hash);
+		assert!(matches!(
+			sync.import_warp_proof(EncodedProof(vec![1])),
+			WarpProofImportResult::Success
+		));
+
+		// Restarting resumes from the last verified fragment.
+		let mut sync = warp_sync(&client);
+		assert_eq!(sync.next_warp_proof_request().unwrap().begin, H256::repeat_byte(1));
+		assert!(matches!(
+			sync.import_warp_proof(EncodedProof(vec![2])),
+			WarpProofImportResult::Success
+		));
+
+		// Once all proofs are verified, restarting resumes with the target block.
+		let mut sync = warp_sync(&client);
+		assert!(sync.next_warp_proof_request().is_none());
+		let (number, request) = sync.next_target_block_request().unwrap();
+		assert_eq!(number, 1);
+		assert_eq!(request.from, FromBlock::Hash(target(&client).hash()));
+
+		sync.clear_persisted();
+		let sync = warp_sync(&client);
+		assert_eq!(sync.next_warp_proof_request().unwrap().begin, genesis_hash);
+	}
+
+	#[test]
+	fn warp_sync_proves_again_from_stale_target_block() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target_hash = target(&client).hash();
+
+		let mut sync = warp_sync(&client);
+		assert!(matches!(
+			sync.import_warp_proof(EncodedProof(vec![2])),
+			WarpProofImportResult::Success
+		));
+
+		// Pretend that the target block was persisted long ago.
+		let Some(PersistedPhase::TargetBlock { header, set_id, authorities, persisted_at }) =
+			load_progress::<Block, _>(&*client)
+		else {
+			panic!("The target block is persisted")
+		};
+		assert_eq!(set_id, 1);
+		let persisted_at = persisted_at - MAX_TARGET_BLOCK_AGE.as_secs() - 1;
+		persist_progress(
+			&*client,
+			&PersistedPhase::<Block>::TargetBlock { header, set_id, authorities, persisted_at },
+		);
+
+		// Warp proofs are requested again, starting from the stale target block.
+		let sync = warp_sync(&client);
+		assert!(sync.next_target_block_request().is_none());
+		assert_eq!(sync.next_warp_proof_request().unwrap().begin, target_hash);
+	}
+}
@@ -24,6 +24,8 @@ mod service;
 #[cfg(test)]
 mod sync;
 
+pub mod simulator;
+
 use std::{
 	collections::HashMap,
 	marker::PhantomData,
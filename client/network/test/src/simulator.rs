@@ -0,0 +1,1014 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Deterministic in-memory network simulator.
+//!
+//! A [`SimulatedNetwork`] connects any number of [`SimulatedNode`]s. Nodes implement the same
+//! traits as [`NetworkService`](sc_network::NetworkService), and can therefore be handed to the
+//! networking code under test, for example GRANDPA or BEEFY gossip.
+//!
+//! Nothing goes through a real transport. Notifications and requests are queued, and are only
+//! delivered when the test advances the virtual clock with [`SimulatedNetwork::advance`]. The
+//! test controls the latency of each link, the probability of losing messages and partitions of
+//! the network. All randomness comes from a seeded RNG, so that a given seed always produces the
+//! same sequence of deliveries.
+//!
+//! Request handlers registered with [`SimulatedNode::register_request_handler`] run in the
+//! test's own tasks. Their responses are picked up on the next call to `advance` and are then
+//! delivered like any other message. Requests that don't get a response, because the request or
+//! the response was lost or the handler never answered, fail with
+//! [`OutboundFailure::Timeout`] once the virtual clock reaches their timeout.
+//!
+//! Only the network is simulated. Timers of the code under test, such as the GRANDPA and BEEFY
+//! round timers or the periodic gossip rebroadcasts, still run on the wall clock, so tests should
+//! make progress through message deliveries rather than by waiting for these timers.
+
+use bytes::Bytes;
+use futures::{
+	channel::{mpsc, oneshot},
+	prelude::*,
+};
+use libp2p::identity::{ed25519, error::SigningError, Keypair};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sc_network::{
+	config::{IncomingRequest, MultiaddrWithPeerId, OutgoingResponse},
+	event::Event,
+	types::ProtocolName,
+	IfDisconnected, Multiaddr, NetworkBlock, NetworkEventStream, NetworkNotification, NetworkPeers,
+	NetworkRequest, NetworkSigner, NetworkStateInfo, NetworkSyncForkRequest,
+	NotificationSenderError, NotificationSenderReady, NotificationSenderT, ObservedRole,
+	OutboundFailure, PeerId, ReputationChange, RequestFailure, Signature,
+};
+use sc_network_common::sync::{SyncEvent, SyncEventStream};
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	pin::Pin,
+	sync::Arc,
+	time::Duration,
+};
+
+/// Characteristics of the link from one node to another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkConfig {
+	/// Minimum time it takes for a message to be delivered.
+	pub latency: Duration,
+	/// Maximum random delay added to `latency`. Messages sent over the same link are still
+	/// delivered in order, like over a real substream.
+	pub jitter: Duration,
+	/// Probability, between 0 and 1, that a message is lost.
+	pub drop_probability: f64,
+}
+
+/// Time after which requests without a response fail, unless changed with
+/// [`SimulatedNetwork::set_request_timeout`].
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+impl Default for LinkConfig {
+	fn default() -> Self {
+		Self { latency: Duration::from_millis(10), jitter: Duration::ZERO, drop_probability: 0.0 }
+	}
+}
+
+/// Message in flight.
+enum Message {
+	Notification {
+		protocol: ProtocolName,
+		message: Vec<u8>,
+	},
+	Request {
+		protocol: ProtocolName,
+		payload: Vec<u8>,
+		request_id: u64,
+	},
+	Response {
+		result: Result<Vec<u8>, RequestFailure>,
+		request_id: u64,
+	},
+	/// Fails the request if it hasn't been answered yet.
+	Timeout {
+		request_id: u64,
+	},
+}
+
+/// Request delivered to a handler and waiting for its response.
+struct PendingResponse {
+	/// Node that received the request.
+	responder: PeerId,
+	/// Node that sent the request.
+	requester: PeerId,
+	response_rx: oneshot::Receiver<OutgoingResponse>,
+	request_id: u64,
+}
+
+struct Node {
+	role: ObservedRole,
+	protocols: HashSet<ProtocolName>,
+	request_handlers: HashMap<ProtocolName, mpsc::Sender<IncomingRequest>>,
+	event_streams: Vec<TracingUnboundedSender<Event>>,
+	sync_event_streams: Vec<TracingUnboundedSender<SyncEvent>>,
+	/// Reputation changes reported by this node.
+	reports: Vec<(PeerId, ReputationChange)>,
+}
+
+impl Node {
+	fn emit(&mut self, event: Event) {
+		self.event_streams.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+	}
+
+	fn emit_sync(&mut self, event: impl Fn() -> SyncEvent) {
+		self.sync_event_streams.retain(|tx| tx.unbounded_send(event()).is_ok());
+	}
+}
+
+struct Inner {
+	/// Virtual time elapsed since the start of the simulation.
+	now: Duration,
+	rng: StdRng,
+	nodes: BTreeMap<PeerId, Node>,
+	default_link: LinkConfig,
+	links: HashMap<(PeerId, PeerId), LinkConfig>,
+	/// Pairs of nodes that the test wants connected, smallest `PeerId` first.
+	wanted: BTreeSet<(PeerId, PeerId)>,
+	/// Pairs of nodes that are currently connected, smallest `PeerId` first.
+	connected: BTreeSet<(PeerId, PeerId)>,
+	/// Index of the group of each node while the network is partitioned.
+	partition: Option<HashMap<PeerId, usize>>,
+	/// Messages in flight, by delivery time and then sending order.
+	in_flight: BTreeMap<(Duration, u64), (PeerId, PeerId, Message)>,
+	next_sequence: u64,
+	/// Delivery time of the last message sent over each link, to keep links ordered.
+	last_delivery: HashMap<(PeerId, PeerId), Duration>,
+	pending_responses: Vec<PendingResponse>,
+	/// Senders of the requests waiting for a response or their timeout, by request id.
+	requests: HashMap<u64, oneshot::Sender<Result<Vec<u8>, RequestFailure>>>,
+	next_request_id: u64,
+	request_timeout: Duration,
+}
+
+fn pair(a: PeerId, b: PeerId) -> (PeerId, PeerId) {
+	if a < b {
+		(a, b)
+	} else {
+		(b, a)
+	}
+}
+
+impl Inner {
+	fn is_reachable(&self, a: &PeerId, b: &PeerId) -> bool {
+		match &self.partition {
+			None => true,
+			Some(groups) => match (groups.get(a), groups.get(b)) {
+				(Some(a), Some(b)) => a == b,
+				_ => false,
+			},
+		}
+	}
+
+	fn common_protocols(&self, a: &PeerId, b: &PeerId) -> Vec<ProtocolName> {
+		let mut protocols = self.nodes[a]
+			.protocols
+			.intersection(&self.nodes[b].protocols)
+			.cloned()
+			.collect::<Vec<_>>();
+		protocols.sort_by(|x, y| (**x).cmp(&**y));
+		protocols
+	}
+
+	fn open(&mut self, link: (PeerId, PeerId)) {
+		if self.connected.contains(&link) || !self.is_reachable(&link.0, &link.1) {
+			return
+		}
+		self.connected.insert(link);
+
+		let protocols = self.common_protocols(&link.0, &link.1);
+		for (local, remote) in [(link.0, link.1), (link.1, link.0)] {
+			let role = self.nodes[&remote].role.clone();
+			let node = self.nodes.get_mut(&local).expect("nodes are never removed; qed");
+			node.emit_sync(|| SyncEvent::PeerConnected(remote));
+			for protocol in &protocols {
+				node.emit(Event::NotificationStreamOpened {
+					remote,
+					protocol: protocol.clone(),
+					negotiated_fallback: None,
+					role: role.clone(),
+					received_handshake: Vec::new(),
+				});
+			}
+		}
+	}
+
+	fn close(&mut self, link: (PeerId, PeerId)) {
+		if !self.connected.remove(&link) {
+			return
+		}
+
+		let protocols = self.common_protocols(&link.0, &link.1);
+		for (local, remote) in [(link.0, link.1), (link.1, link.0)] {
+			let node = self.nodes.get_mut(&local).expect("nodes are never removed; qed");
+			for protocol in &protocols {
+				node.emit(Event::NotificationStreamClosed { remote, protocol: protocol.clone() });
+			}
+			node.emit_sync(|| SyncEvent::PeerDisconnected(remote));
+		}
+	}
+
+	/// Queues `message` from `from` to `to`, applying the configuration of the link. Returns the
+	/// message back if the nodes aren't connected.
+	fn send(&mut self, from: PeerId, to: PeerId, message: Message) -> Result<(), Message> {
+		if !self.connected.contains(&pair(from, to)) {
+			return Err(message)
+		}
+
+		let link = self.links.get(&(from, to)).unwrap_or(&self.default_link).clone();
+		if link.drop_probability > 0.0 && self.rng.gen_bool(link.drop_probability.min(1.0)) {
+			log::trace!(target: "simulator", "Dropping message from {} to {}", from, to);
+			return Ok(())
+		}
+
+		let jitter = if link.jitter.is_zero() {
+			Duration::ZERO
+		} else {
+			Duration::from_micros(self.rng.gen_range(0..=link.jitter.as_micros() as u64))
+		};
+		let last = self.last_delivery.get(&(from, to)).copied().unwrap_or_default();
+		let deliver_at = (self.now + link.latency + jitter).max(last);
+		self.last_delivery.insert((from, to), deliver_at);
+
+		self.in_flight.insert((deliver_at, self.next_sequence), (from, to, message));
+		self.next_sequence += 1;
+		Ok(())
+	}
+
+	/// Queues a request from `from` to `to`, failing it right away if they aren't connected, and
+	/// schedules its timeout otherwise.
+	fn send_request(
+		&mut self,
+		from: PeerId,
+		to: PeerId,
+		protocol: ProtocolName,
+		payload: Vec<u8>,
+		response_tx: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+	) {
+		let request_id = self.next_request_id;
+		self.next_request_id += 1;
+
+		if self.send(from, to, Message::Request { protocol, payload, request_id }).is_err() {
+			let _ = response_tx.send(Err(RequestFailure::NotConnected));
+			return
+		}
+
+		self.requests.insert(request_id, response_tx);
+		let timeout_at = self.now + self.request_timeout;
+		self.in_flight
+			.insert((timeout_at, self.next_sequence), (from, to, Message::Timeout { request_id }));
+		self.next_sequence += 1;
+	}
+
+	/// Completes the request with the given id, unless it already completed.
+	fn complete_request(&mut self, request_id: u64, result: Result<Vec<u8>, RequestFailure>) {
+		if let Some(response_tx) = self.requests.remove(&request_id) {
+			let _ = response_tx.send(result);
+		}
+	}
+
+	fn deliver(&mut self, from: PeerId, to: PeerId, message: Message) {
+		if let Message::Timeout { request_id } = message {
+			self.complete_request(
+				request_id,
+				Err(RequestFailure::Network(OutboundFailure::Timeout)),
+			);
+			return
+		}
+
+		// Messages in flight when the nodes got disconnected are lost.
+		if !self.connected.contains(&pair(from, to)) {
+			return
+		}
+
+		match message {
+			Message::Notification { protocol, message } => {
+				let node = self.nodes.get_mut(&to).expect("nodes are never removed; qed");
+				node.emit(Event::NotificationsReceived {
+					remote: from,
+					messages: vec![(protocol, Bytes::from(message))],
+				});
+			},
+			Message::Request { protocol, payload, request_id } => {
+				let node = self.nodes.get_mut(&to).expect("nodes are never removed; qed");
+				let handler = match node.request_handlers.get_mut(&protocol) {
+					Some(handler) => handler,
+					None => {
+						self.complete_request(request_id, Err(RequestFailure::UnknownProtocol));
+						return
+					},
+				};
+
+				let (pending_response, response_rx) = oneshot::channel();
+				let request = IncomingRequest { peer: from, payload, pending_response };
+				if handler.try_send(request).is_ok() {
+					self.pending_responses.push(PendingResponse {
+						responder: to,
+						requester: from,
+						response_rx,
+						request_id,
+					});
+				} else {
+					self.complete_request(request_id, Err(RequestFailure::Refused));
+				}
+			},
+			Message::Response { result, request_id } => self.complete_request(request_id, result),
+			Message::Timeout { .. } => unreachable!("timeouts are handled above; qed"),
+		}
+	}
+
+	/// Sends the responses that the request handlers produced since the last call.
+	fn collect_responses(&mut self) {
+		for mut pending in std::mem::take(&mut self.pending_responses) {
+			let result = match pending.response_rx.try_recv() {
+				Ok(None) => {
+					self.pending_responses.push(pending);
+					continue
+				},
+				Ok(Some(response)) => {
+					let node = self
+						.nodes
+						.get_mut(&pending.responder)
+						.expect("nodes are never removed; qed");
+					for change in response.reputation_changes {
+						node.reports.push((pending.requester, change));
+					}
+					if let Some(feedback) = response.sent_feedback {
+						let _ = feedback.send(());
+					}
+					response.result.map_err(|()| RequestFailure::Refused)
+				},
+				Err(_) => Err(RequestFailure::Refused),
+			};
+
+			// A response that can't be sent is lost, and the request eventually times out.
+			let response = Message::Response { result, request_id: pending.request_id };
+			let _ = self.send(pending.responder, pending.requester, response);
+		}
+	}
+}
+
+/// Simulated network shared by a set of [`SimulatedNode`]s.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+	inner: Arc<Mutex<Inner>>,
+}
+
+impl SimulatedNetwork {
+	/// Creates an empty network. All the random decisions are derived from `seed`.
+	pub fn new(seed: u64) -> Self {
+		Self {
+			inner: Arc::new(Mutex::new(Inner {
+				now: Duration::ZERO,
+				rng: StdRng::seed_from_u64(seed),
+				nodes: BTreeMap::new(),
+				default_link: LinkConfig::default(),
+				links: HashMap::new(),
+				wanted: BTreeSet::new(),
+				connected: BTreeSet::new(),
+				partition: None,
+				in_flight: BTreeMap::new(),
+				next_sequence: 0,
+				last_delivery: HashMap::new(),
+				pending_responses: Vec::new(),
+				requests: HashMap::new(),
+				next_request_id: 0,
+				request_timeout: DEFAULT_REQUEST_TIMEOUT,
+			})),
+		}
+	}
+
+	/// Adds a node supporting the given notification protocols.
+	///
+	/// The identity of the node is derived from the seed of the network.
+	pub fn add_node(
+		&self,
+		role: ObservedRole,
+		protocols: impl IntoIterator<Item = ProtocolName>,
+	) -> SimulatedNode {
+		let mut inner = self.inner.lock();
+
+		let mut secret = [0u8; 32];
+		inner.rng.fill(&mut secret);
+		let secret = ed25519::SecretKey::from_bytes(&mut secret)
+			.expect("any 32 bytes are a valid ed25519 secret key; qed");
+		let keypair = Keypair::Ed25519(secret.into());
+		let peer_id = keypair.public().to_peer_id();
+
+		inner.nodes.insert(
+			peer_id,
+			Node {
+				role,
+				protocols: protocols.into_iter().collect(),
+				request_handlers: HashMap::new(),
+				event_streams: Vec::new(),
+				sync_event_streams: Vec::new(),
+				reports: Vec::new(),
+			},
+		);
+
+		SimulatedNode { peer_id, keypair: Arc::new(keypair), network: self.clone() }
+	}
+
+	/// Virtual time elapsed since the network was created.
+	pub fn now(&self) -> Duration {
+		self.inner.lock().now
+	}
+
+	/// Sets the configuration of all the links without a specific one.
+	pub fn set_default_link(&self, config: LinkConfig) {
+		self.inner.lock().default_link = config;
+	}
+
+	/// Sets the configuration of the link from `from` to `to`. The opposite direction is not
+	/// affected.
+	pub fn set_link(&self, from: PeerId, to: PeerId, config: LinkConfig) {
+		self.inner.lock().links.insert((from, to), config);
+	}
+
+	/// Sets the time after which the requests sent from now on fail if they didn't get a response.
+	pub fn set_request_timeout(&self, timeout: Duration) {
+		self.inner.lock().request_timeout = timeout;
+	}
+
+	/// Connects two nodes, opening the notification substreams of all the protocols they both
+	/// support. The connection is delayed until the nodes can reach each other if the network is
+	/// partitioned.
+	pub fn connect(&self, a: PeerId, b: PeerId) {
+		let mut inner = self.inner.lock();
+		inner.wanted.insert(pair(a, b));
+		inner.open(pair(a, b));
+	}
+
+	/// Connects every node to every other node.
+	pub fn connect_all(&self) {
+		let peers = self.inner.lock().nodes.keys().cloned().collect::<Vec<_>>();
+		for (i, a) in peers.iter().enumerate() {
+			for b in &peers[i + 1..] {
+				self.connect(*a, *b);
+			}
+		}
+	}
+
+	/// Disconnects two nodes. Messages in flight between them are lost.
+	pub fn disconnect(&self, a: PeerId, b: PeerId) {
+		let mut inner = self.inner.lock();
+		inner.wanted.remove(&pair(a, b));
+		inner.close(pair(a, b));
+	}
+
+	/// Splits the network into groups that can't reach each other. Nodes in no group are
+	/// isolated. Connections between groups are closed and messages in flight between them are
+	/// lost.
+	pub fn partition(&self, groups: &[&[PeerId]]) {
+		let mut inner = self.inner.lock();
+		inner.partition = Some(
+			groups
+				.iter()
+				.enumerate()
+				.flat_map(|(index, group)| group.iter().map(move |peer| (*peer, index)))
+				.collect(),
+		);
+
+		for link in inner.connected.clone() {
+			if !inner.is_reachable(&link.0, &link.1) {
+				inner.close(link);
+			}
+		}
+	}
+
+	/// Ends the partition of the network, reconnecting the nodes that were connected before.
+	pub fn heal(&self) {
+		let mut inner = self.inner.lock();
+		inner.partition = None;
+		for link in inner.wanted.clone() {
+			inner.open(link);
+		}
+	}
+
+	/// Advances the virtual clock by `duration`, delivering the messages due in the meantime in
+	/// order.
+	pub fn advance(&self, duration: Duration) {
+		let mut inner = self.inner.lock();
+		let target = inner.now + duration;
+
+		loop {
+			inner.collect_responses();
+
+			let key = match inner.in_flight.keys().next() {
+				Some(key) if key.0 <= target => *key,
+				_ => break,
+			};
+			let (from, to, message) =
+				inner.in_flight.remove(&key).expect("key was just found in the map; qed");
+			inner.now = key.0;
+			inner.deliver(from, to, message);
+		}
+
+		inner.now = target;
+	}
+
+	/// Number of messages in flight.
+	pub fn in_flight(&self) -> usize {
+		self.inner
+			.lock()
+			.in_flight
+			.values()
+			.filter(|(_, _, message)| !matches!(message, Message::Timeout { .. }))
+			.count()
+	}
+
+	/// Returns the reputation changes reported by `peer_id` so far.
+	pub fn reports(&self, peer_id: &PeerId) -> Vec<(PeerId, ReputationChange)> {
+		self.inner
+			.lock()
+			.nodes
+			.get(peer_id)
+			.map(|node| node.reports.clone())
+			.unwrap_or_default()
+	}
+}
+
+/// Node of a [`SimulatedNetwork`].
+#[derive(Clone)]
+pub struct SimulatedNode {
+	peer_id: PeerId,
+	keypair: Arc<Keypair>,
+	network: SimulatedNetwork,
+}
+
+impl SimulatedNode {
+	/// Returns the network this node belongs to.
+	pub fn network(&self) -> &SimulatedNetwork {
+		&self.network
+	}
+
+	/// Registers the handler of the requests of `protocol` received by this node.
+	pub fn register_request_handler(
+		&self,
+		protocol: ProtocolName,
+		handler: mpsc::Sender<IncomingRequest>,
+	) {
+		let mut inner = self.network.inner.lock();
+		let node = inner.nodes.get_mut(&self.peer_id).expect("nodes are never removed; qed");
+		node.request_handlers.insert(protocol, handler);
+	}
+
+	fn send_notification(
+		&self,
+		target: PeerId,
+		protocol: ProtocolName,
+		message: Vec<u8>,
+	) -> Result<(), NotificationSenderError> {
+		let mut inner = self.network.inner.lock();
+		if !inner.nodes[&self.peer_id].protocols.contains(&protocol) {
+			return Err(NotificationSenderError::BadProtocol)
+		}
+		if !inner
+			.nodes
+			.get(&target)
+			.map_or(false, |node| node.protocols.contains(&protocol))
+		{
+			return Err(NotificationSenderError::Closed)
+		}
+
+		inner
+			.send(self.peer_id, target, Message::Notification { protocol, message })
+			.map_err(|_| NotificationSenderError::Closed)
+	}
+}
+
+impl NetworkPeers for SimulatedNode {
+	fn set_authorized_peers(&self, _peers: HashSet<PeerId>) {}
+
+	fn set_authorized_only(&self, _reserved_only: bool) {}
+
+	fn add_known_address(&self, _peer_id: PeerId, _addr: Multiaddr) {}
+
+	fn report_peer(&self, who: PeerId, cost_benefit: ReputationChange) {
+		let mut inner = self.network.inner.lock();
+		let node = inner.nodes.get_mut(&self.peer_id).expect("nodes are never removed; qed");
+		node.reports.push((who, cost_benefit));
+	}
+
+	fn disconnect_peer(&self, who: PeerId, _protocol: ProtocolName) {
+		// The simulator has a single connection for all the protocols.
+		self.network.inner.lock().close(pair(self.peer_id, who));
+	}
+
+	fn accept_unreserved_peers(&self) {}
+
+	fn deny_unreserved_peers(&self) {}
+
+	fn add_reserved_peer(&self, _peer: MultiaddrWithPeerId) -> Result<(), String> {
+		Ok(())
+	}
+
+	fn remove_reserved_peer(&self, _peer_id: PeerId) {}
+
+	fn set_reserved_peers(
+		&self,
+		_protocol: ProtocolName,
+		_peers: HashSet<Multiaddr>,
+	) -> Result<(), String> {
+		Ok(())
+	}
+
+	fn add_peers_to_reserved_set(
+		&self,
+		_protocol: ProtocolName,
+		_peers: HashSet<Multiaddr>,
+	) -> Result<(), String> {
+		Ok(())
+	}
+
+	fn remove_peers_from_reserved_set(&self, _protocol: ProtocolName, _peers: Vec<PeerId>) {}
+
+	fn add_to_peers_set(
+		&self,
+		_protocol: ProtocolName,
+		_peers: HashSet<Multiaddr>,
+	) -> Result<(), String> {
+		Ok(())
+	}
+
+	fn remove_from_peers_set(&self, _protocol: ProtocolName, _peers: Vec<PeerId>) {}
+
+	fn sync_num_connected(&self) -> usize {
+		let inner = self.network.inner.lock();
+		inner
+			.connected
+			.iter()
+			.filter(|(a, b)| *a == self.peer_id || *b == self.peer_id)
+			.count()
+	}
+}
+
+impl NetworkEventStream for SimulatedNode {
+	fn event_stream(&self, name: &'static str) -> Pin<Box<dyn Stream<Item = Event> + Send>> {
+		let (tx, rx) = tracing_unbounded(name, 100_000);
+		let mut inner = self.network.inner.lock();
+		let node = inner.nodes.get_mut(&self.peer_id).expect("nodes are never removed; qed");
+		node.event_streams.push(tx);
+		Box::pin(rx)
+	}
+}
+
+impl SyncEventStream for SimulatedNode {
+	fn event_stream(&self, name: &'static str) -> Pin<Box<dyn Stream<Item = SyncEvent> + Send>> {
+		let (tx, rx) = tracing_unbounded(name, 100_000);
+		let mut inner = self.network.inner.lock();
+		let node = inner.nodes.get_mut(&self.peer_id).expect("nodes are never removed; qed");
+		node.sync_event_streams.push(tx);
+		Box::pin(rx)
+	}
+}
+
+impl NetworkNotification for SimulatedNode {
+	fn write_notification(&self, target: PeerId, protocol: ProtocolName, message: Vec<u8>) {
+		if let Err(e) = self.send_notification(target, protocol, message) {
+			log::trace!(target: "simulator", "Notification to {} not sent: {}", target, e);
+		}
+	}
+
+	fn notification_sender(
+		&self,
+		target: PeerId,
+		protocol: ProtocolName,
+	) -> Result<Box<dyn NotificationSenderT>, NotificationSenderError> {
+		Ok(Box::new(SimulatedNotificationSender { node: self.clone(), target, protocol }))
+	}
+
+	fn set_notification_handshake(&self, _protocol: ProtocolName, _handshake: Vec<u8>) {}
+}
+
+/// Notification sender of a [`SimulatedNode`]. Always ready, as the simulator has no buffer
+/// limit.
+struct SimulatedNotificationSender {
+	node: SimulatedNode,
+	target: PeerId,
+	protocol: ProtocolName,
+}
+
+#[async_trait::async_trait]
+impl NotificationSenderT for SimulatedNotificationSender {
+	async fn ready(
+		&self,
+	) -> Result<Box<dyn NotificationSenderReady + '_>, NotificationSenderError> {
+		Ok(Box::new(SimulatedNotificationSenderReady { sender: self, sent: false }))
+	}
+}
+
+struct SimulatedNotificationSenderReady<'a> {
+	sender: &'a SimulatedNotificationSender,
+	sent: bool,
+}
+
+impl NotificationSenderReady for SimulatedNotificationSenderReady<'_> {
+	fn send(&mut self, notification: Vec<u8>) -> Result<(), NotificationSenderError> {
+		if std::mem::replace(&mut self.sent, true) {
+			return Err(NotificationSenderError::Closed)
+		}
+
+		let sender = self.sender;
+		sender
+			.node
+			.send_notification(sender.target, sender.protocol.clone(), notification)
+	}
+}
+
+#[async_trait::async_trait]
+impl NetworkRequest for SimulatedNode {
+	async fn request(
+		&self,
+		target: PeerId,
+		protocol: ProtocolName,
+		request: Vec<u8>,
+		connect: IfDisconnected,
+	) -> Result<Vec<u8>, RequestFailure> {
+		let (tx, rx) = oneshot::channel();
+		self.start_request(target, protocol, request, tx, connect);
+		match rx.await {
+			Ok(result) => result,
+			Err(oneshot::Canceled) =>
+				Err(RequestFailure::Network(OutboundFailure::ConnectionClosed)),
+		}
+	}
+
+	fn start_request(
+		&self,
+		target: PeerId,
+		protocol: ProtocolName,
+		request: Vec<u8>,
+		tx: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+		_connect: IfDisconnected,
+	) {
+		self.network
+			.inner
+			.lock()
+			.send_request(self.peer_id, target, protocol, request, tx);
+	}
+}
+
+impl<H, N> NetworkBlock<H, N> for SimulatedNode {
+	fn announce_block(&self, _hash: H, _data: Option<Vec<u8>>) {}
+
+	fn new_best_block_imported(&self, _hash: H, _number: N) {}
+}
+
+impl<H, N> NetworkSyncForkRequest<H, N> for SimulatedNode {
+	fn set_sync_fork_request(&self, _peers: Vec<PeerId>, _hash: H, _number: N) {}
+}
+
+impl NetworkStateInfo for SimulatedNode {
+	fn external_addresses(&self) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn listen_addresses(&self) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn local_peer_id(&self) -> PeerId {
+		self.peer_id
+	}
+}
+
+impl NetworkSigner for SimulatedNode {
+	fn sign_with_local_identity(&self, msg: impl AsRef<[u8]>) -> Result<Signature, SigningError> {
+		Signature::sign_message(msg, &self.keypair)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PROTOCOL: &str = "/test/notifications/1";
+
+	fn add_node(network: &SimulatedNetwork) -> SimulatedNode {
+		network.add_node(ObservedRole::Full, [ProtocolName::from(PROTOCOL)])
+	}
+
+	fn events(node: &SimulatedNode) -> Pin<Box<dyn Stream<Item = Event> + Send>> {
+		NetworkEventStream::event_stream(node, "test")
+	}
+
+	/// Returns the notifications that arrived on `stream` since the last call.
+	fn received(stream: &mut Pin<Box<dyn Stream<Item = Event> + Send>>) -> Vec<(PeerId, Vec<u8>)> {
+		let mut received = Vec::new();
+		while let Some(Some(event)) = stream.next().now_or_never() {
+			if let Event::NotificationsReceived { remote, messages } = event {
+				received
+					.extend(messages.into_iter().map(|(_, message)| (remote, message.to_vec())));
+			}
+		}
+		received
+	}
+
+	#[test]
+	fn messages_are_delivered_after_link_latency() {
+		let network = SimulatedNetwork::new(0);
+		let (a, b, c) = (add_node(&network), add_node(&network), add_node(&network));
+		network.connect_all();
+		network.set_link(
+			a.local_peer_id(),
+			b.local_peer_id(),
+			LinkConfig { latency: Duration::from_millis(100), ..Default::default() },
+		);
+		let (mut b_events, mut c_events) = (events(&b), events(&c));
+
+		a.write_notification(b.local_peer_id(), PROTOCOL.into(), vec![1]);
+		a.write_notification(c.local_peer_id(), PROTOCOL.into(), vec![2]);
+
+		network.advance(Duration::from_millis(50));
+		assert!(received(&mut b_events).is_empty());
+		assert_eq!(received(&mut c_events), vec![(a.local_peer_id(), vec![2])]);
+
+		network.advance(Duration::from_millis(50));
+		assert_eq!(received(&mut b_events), vec![(a.local_peer_id(), vec![1])]);
+		assert_eq!(network.now(), Duration::from_millis(100));
+	}
+
+	#[test]
+	fn partition_drops_messages_until_healed() {
+		let network = SimulatedNetwork::new(0);
+		let (a, b) = (add_node(&network), add_node(&network));
+		network.connect(a.local_peer_id(), b.local_peer_id());
+		let mut b_events = events(&b);
+
+		a.write_notification(b.local_peer_id(), PROTOCOL.into(), vec![1]);
+		network.partition(&[&[a.local_peer_id()], &[b.local_peer_id()]]);
+		a.write_notification(b.local_peer_id(), PROTOCOL.into(), vec![2]);
+		network.advance(Duration::from_secs(1));
+
+		let mut closed = false;
+		while let Some(Some(event)) = b_events.next().now_or_never() {
+			match event {
+				Event::NotificationStreamClosed { remote, .. } => {
+					assert_eq!(remote, a.local_peer_id());
+					closed = true;
+				},
+				Event::NotificationsReceived { .. } => panic!("message crossed the partition"),
+				_ => {},
+			}
+		}
+		assert!(closed);
+
+		network.heal();
+		a.write_notification(b.local_peer_id(), PROTOCOL.into(), vec![3]);
+		network.advance(Duration::from_secs(1));
+		assert_eq!(received(&mut b_events), vec![(a.local_peer_id(), vec![3])]);
+	}
+
+	#[test]
+	fn same_seed_gives_same_deliveries() {
+		let run = |seed| {
+			let network = SimulatedNetwork::new(seed);
+			network.set_default_link(LinkConfig {
+				latency: Duration::from_millis(10),
+				jitter: Duration::from_millis(50),
+				drop_probability: 0.3,
+			});
+			let nodes = (0..4).map(|_| add_node(&network)).collect::<Vec<_>>();
+			network.connect_all();
+			let mut streams = nodes.iter().map(events).collect::<Vec<_>>();
+
+			for (i, node) in nodes.iter().enumerate() {
+				for target in &nodes {
+					if target.local_peer_id() != node.local_peer_id() {
+						node.write_notification(
+							target.local_peer_id(),
+							PROTOCOL.into(),
+							vec![i as u8; 4],
+						);
+					}
+				}
+			}
+			network.advance(Duration::from_secs(1));
+			streams.iter_mut().map(received).collect::<Vec<_>>()
+		};
+
+		assert_eq!(run(42), run(42));
+		assert_ne!(run(42), run(43));
+	}
+
+	#[test]
+	fn requests_are_answered_by_handler() {
+		const REQUESTS: &str = "/test/requests/1";
+
+		let network = SimulatedNetwork::new(0);
+		let (a, b) = (add_node(&network), add_node(&network));
+		network.connect(a.local_peer_id(), b.local_peer_id());
+		let (tx, mut requests) = mpsc::channel(8);
+		b.register_request_handler(REQUESTS.into(), tx);
+
+		let (response_tx, mut response_rx) = oneshot::channel();
+		a.start_request(
+			b.local_peer_id(),
+			REQUESTS.into(),
+			vec![1, 2, 3],
+			response_tx,
+			IfDisconnected::ImmediateError,
+		);
+
+		network.advance(Duration::from_millis(10));
+		let request = requests.try_next().unwrap().unwrap();
+		assert_eq!(request.peer, a.local_peer_id());
+		assert_eq!(request.payload, vec![1, 2, 3]);
+		request
+			.pending_response
+			.send(OutgoingResponse {
+				result: Ok(vec![4, 5]),
+				reputation_changes: vec![ReputationChange::new(10, "good request")],
+				sent_feedback: None,
+			})
+			.unwrap();
+		assert!(response_rx.try_recv().unwrap().is_none());
+
+		network.advance(Duration::from_millis(10));
+		assert_eq!(response_rx.try_recv().unwrap().unwrap().unwrap(), vec![4, 5]);
+		assert_eq!(network.reports(&b.local_peer_id()).len(), 1);
+
+		let (response_tx, mut response_rx) = oneshot::channel();
+		a.start_request(
+			b.local_peer_id(),
+			"/test/unknown/1".into(),
+			Vec::new(),
+			response_tx,
+			IfDisconnected::ImmediateError,
+		);
+		network.advance(Duration::from_millis(10));
+		assert!(matches!(
+			response_rx.try_recv().unwrap(),
+			Some(Err(RequestFailure::UnknownProtocol))
+		));
+	}
+	#[test]
+	fn unanswered_requests_time_out() {
+		const REQUESTS: &str = "/test/requests/1";
+
+		let network = SimulatedNetwork::new(0);
+		network.set_request_timeout(Duration::from_secs(5));
+		let (a, b) = (add_node(&network), add_node(&network));
+		network.connect(a.local_peer_id(), b.local_peer_id());
+		let (tx, mut requests) = mpsc::channel(8);
+		b.register_request_handler(REQUESTS.into(), tx);
+
+		let start_request = || {
+			let (response_tx, response_rx) = oneshot::channel();
+			a.start_request(
+				b.local_peer_id(),
+				REQUESTS.into(),
+				Vec::new(),
+				response_tx,
+				IfDisconnected::ImmediateError,
+			);
+			response_rx
+		};
+
+		// The handler never answers the first request, and the second one is lost.
+		let mut unanswered = start_request();
+		network.set_link(
+			a.local_peer_id(),
+			b.local_peer_id(),
+			LinkConfig { drop_probability: 1.0, ..Default::default() },
+		);
+		let mut lost = start_request();
+
+		network.advance(Duration::from_millis(4999));
+		let _request = requests.try_next().unwrap().unwrap();
+		assert!(requests.try_next().is_err());
+		assert!(unanswered.try_recv().unwrap().is_none());
+		assert!(lost.try_recv().unwrap().is_none());
+
+		network.advance(Duration::from_millis(1));
+		for response_rx in [&mut unanswered, &mut lost] {
+			assert!(matches!(
+				response_rx.try_recv().unwrap(),
+				Some(Err(RequestFailure::Network(OutboundFailure::Timeout)))
+			));
+		}
+	}
+}
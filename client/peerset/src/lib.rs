@@ -31,22 +31,27 @@
 //!
 //! In addition, for each, set, the peerset also holds a list of reserved nodes towards which it
 //! will at all time try to maintain a connection with.
+//!
+//! Reputations and the addresses of reachable peers can optionally be persisted to disk, see
+//! [`PeersetConfig::peer_store`].
 
+mod peer_store;
 mod peersstate;
 
 use futures::{channel::oneshot, prelude::*};
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use serde_json::json;
 use std::{
 	collections::{HashMap, HashSet, VecDeque},
+	path::PathBuf,
 	pin::Pin,
 	task::{Context, Poll},
 	time::{Duration, Instant},
 };
 use wasm_timer::Delay;
 
-pub use libp2p::PeerId;
+pub use libp2p::{Multiaddr, PeerId};
 
 /// We don't accept nodes whose reputation is under this value.
 pub const BANNED_THRESHOLD: i32 = 82 * (i32::MIN / 100);
@@ -55,6 +60,8 @@ const DISCONNECT_REPUTATION_CHANGE: i32 = -256;
 /// Amount of time between the moment we disconnect from a node and the moment we remove it from
 /// the list.
 const FORGET_AFTER: Duration = Duration::from_secs(3600);
+/// Interval at which the peer store, if any, is written to disk.
+const PEER_STORE_SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Debug)]
 enum Action {
@@ -66,6 +73,7 @@ enum Action {
 	AddToPeersSet(SetId, PeerId),
 	RemoveFromPeersSet(SetId, PeerId),
 	PeerReputation(PeerId, oneshot::Sender<i32>),
+	AddKnownAddress(PeerId, Multiaddr),
 }
 
 /// Identifier of a set in the peerset.
@@ -176,6 +184,15 @@ impl PeersetHandle {
 		// The channel can only be closed if the peerset no longer exists.
 		rx.await.map_err(|_| ())
 	}
+
+	/// Records that we successfully dialed the peer at the given address.
+	///
+	/// The address is written to the peer store and reported by
+	/// [`Peerset::persisted_addresses`] after a restart. Has no effect if the peerset was
+	/// created without a peer store.
+	pub fn add_known_address(&self, peer_id: PeerId, address: Multiaddr) {
+		let _ = self.tx.unbounded_send(Action::AddKnownAddress(peer_id, address));
+	}
 }
 
 /// Message that can be sent by the peer set manager (PSM).
@@ -218,6 +235,13 @@ impl From<u64> for IncomingIndex {
 pub struct PeersetConfig {
 	/// List of sets of nodes the peerset manages.
 	pub sets: Vec<SetConfig>,
+
+	/// Path of the file in which reputations and peer addresses are persisted, if any.
+	///
+	/// The file is loaded when the peerset is created and written back periodically and when the
+	/// peerset is dropped, from a background thread. Peers loaded from it with a known address are
+	/// added to the first set, like bootnodes.
+	pub peer_store: Option<PathBuf>,
 }
 
 /// Configuration for a single set of nodes.
@@ -270,6 +294,10 @@ pub struct Peerset {
 	/// Next time to do a periodic call to `alloc_slots` with all sets. This is done once per
 	/// second, to match the period of the reputation updates.
 	next_periodic_alloc_slots: Delay,
+	/// On-disk store of reputations and addresses, if enabled.
+	peer_store: Option<peer_store::PeerStore>,
+	/// Next time to write the peer store to disk.
+	next_peer_store_save: Delay,
 }
 
 impl Peerset {
@@ -297,6 +325,8 @@ impl Peerset {
 				created: now,
 				latest_time_update: now,
 				next_periodic_alloc_slots: Delay::new(Duration::new(0, 0)),
+				peer_store: None,
+				next_peer_store_save: Delay::new(PEER_STORE_SAVE_INTERVAL),
 			}
 		};
 
@@ -314,6 +344,10 @@ impl Peerset {
 			}
 		}
 
+		if let Some(path) = config.peer_store {
+			peerset.restore_peers(path);
+		}
+
 		for set_index in 0..peerset.data.num_sets() {
 			peerset.alloc_slots(SetId(set_index));
 		}
@@ -321,6 +355,58 @@ impl Peerset {
 		(peerset, handle)
 	}
 
+	/// Opens the peer store at `path`, restores the reputations it contains and discovers the
+	/// peers whose address is known.
+	fn restore_peers(&mut self, path: PathBuf) {
+		let (store, reputations) = peer_store::PeerStore::open(path);
+
+		// Reputations don't decay while the node is offline, so that bans survive restarts.
+		for (peer_id, reputation) in reputations {
+			self.data.peer_reputation(peer_id).set_reputation(reputation);
+		}
+
+		if self.data.num_sets() > 0 {
+			let peer_ids = store.addresses().map(|(peer_id, _)| *peer_id).collect::<HashSet<_>>();
+			for peer_id in peer_ids {
+				if let peersstate::Peer::Unknown(entry) = self.data.peer(0, &peer_id) {
+					entry.discover();
+				}
+			}
+		}
+
+		self.peer_store = Some(store);
+	}
+
+	/// Writes the reputations and known addresses to the peer store, if any.
+	fn save_peer_store(&mut self) {
+		if self.peer_store.is_none() {
+			return
+		}
+
+		self.update_time();
+
+		let reputations = self
+			.data
+			.peers()
+			.cloned()
+			.collect::<Vec<_>>()
+			.into_iter()
+			.map(|peer_id| (peer_id, self.data.peer_reputation(peer_id).reputation()))
+			.collect::<Vec<_>>();
+
+		if let Some(store) = &mut self.peer_store {
+			if let Err(e) = store.save(reputations.into_iter()) {
+				warn!(target: "peerset", "Failed to save peer store: {}", e);
+			}
+		}
+	}
+
+	fn on_add_known_address(&mut self, peer_id: PeerId, address: Multiaddr) {
+		if let Some(store) = &mut self.peer_store {
+			store.add_address(peer_id, address);
+		}
+	}
+
 	fn on_add_reserved_peer(&mut self, set_id: SetId, peer_id: PeerId) {
 		let newly_inserted = self.reserved_nodes[set_id.0].0.insert(peer_id);
 		if !newly_inserted {
@@ -717,6 +803,23 @@ impl Peerset {
 	pub fn num_discovered_peers(&self) -> usize {
 		self.data.peers().len()
 	}
+
+	/// Returns the addresses loaded from or recorded in the peer store, if any.
+	///
+	/// These should be passed to the discovery mechanism so that the peers restored from the
+	/// store can be dialed.
+	pub fn persisted_addresses(&self) -> Vec<(PeerId, Multiaddr)> {
+		self.peer_store
+			.iter()
+			.flat_map(|store| store.addresses().map(|(peer_id, addr)| (*peer_id, addr.clone())))
+			.collect()
+	}
+}
+
+impl Drop for Peerset {
+	fn drop(&mut self) {
+		self.save_peer_store();
+	}
 }
 
 impl Stream for Peerset {
@@ -736,6 +839,13 @@ impl Stream for Peerset {
 				}
 			}
 
+			if self.peer_store.is_some() &&
+				Future::poll(Pin::new(&mut self.next_peer_store_save), cx).is_ready()
+			{
+				self.next_peer_store_save = Delay::new(PEER_STORE_SAVE_INTERVAL);
+				self.save_peer_store();
+			}
+
 			let action = match Stream::poll_next(Pin::new(&mut self.rx), cx) {
 				Poll::Pending => return Poll::Pending,
 				Poll::Ready(Some(event)) => event,
@@ -758,6 +868,8 @@ impl Stream for Peerset {
 					self.on_remove_from_peers_set(sets_name, peer_id),
 				Action::PeerReputation(peer_id, pending_response) =>
 					self.on_peer_reputation(peer_id, pending_response),
+				Action::AddKnownAddress(peer_id, address) =>
+					self.on_add_known_address(peer_id, address),
 			}
 		}
 	}
@@ -777,12 +889,17 @@ pub enum DropReason {
 #[cfg(test)]
 mod tests {
 	use super::{
-		IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig, SetId,
-		BANNED_THRESHOLD,
+		IncomingIndex, Message, Multiaddr, Peerset, PeersetConfig, ReputationChange, SetConfig,
+		SetId, BANNED_THRESHOLD,
 	};
 	use futures::prelude::*;
 	use libp2p::PeerId;
-	use std::{pin::Pin, task::Poll, thread, time::Duration};
+	use std::{
+		pin::Pin,
+		task::Poll,
+		thread,
+		time::{Duration, Instant},
+	};
 
 	fn assert_messages(mut peerset: Peerset, messages: Vec<Message>) -> Peerset {
 		for expected_message in messages {
@@ -812,6 +929,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			peer_store: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -845,6 +963,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -876,6 +995,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			peer_store: None,
 		};
 
 		let (mut peerset, _) = Peerset::from_config(config);
@@ -897,6 +1017,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -923,6 +1044,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -966,6 +1088,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_store: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -996,4 +1119,66 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
+
+	#[test]
+	fn reputations_and_addresses_survive_restart() {
+		let dir = tempfile::tempdir().unwrap();
+		let config = || PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				bootnodes: vec![],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			peer_store: Some(dir.path().join("peers.json")),
+		};
+
+		let banned = PeerId::random();
+		let reachable = PeerId::random();
+		let address: Multiaddr = "/ip4/127.0.0.1/tcp/30333".parse().unwrap();
+
+		let (mut peerset, handle) = Peerset::from_config(config());
+		handle.report_peer(banned, ReputationChange::new_fatal(""));
+		handle.add_known_address(reachable, address.clone());
+
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			// We need one polling for the messages to be processed.
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			Poll::Ready(())
+		}));
+
+		// Dropping the peerset writes the store to disk in the background.
+		drop(peerset);
+		let path = dir.path().join("peers.json");
+		let deadline = Instant::now() + Duration::from_secs(10);
+		while !path.exists() {
+			assert!(Instant::now() < deadline, "peer store was not written");
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		// Pretend that the node was offline for an hour, long enough for any ban to have ended
+		// had the reputations kept decaying.
+		let mut stored: serde_json::Value =
+			serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+		let age = |time: &mut serde_json::Value| *time = (time.as_u64().unwrap() - 3600).into();
+		age(&mut stored["saved_at"]);
+		for entry in stored["peers"].as_object_mut().unwrap().values_mut() {
+			age(&mut entry["last_seen"]);
+		}
+		std::fs::write(&path, serde_json::to_vec(&stored).unwrap()).unwrap();
+
+		let (mut peerset, _) = Peerset::from_config(config());
+		assert_eq!(peerset.persisted_addresses(), vec![(reachable, address)]);
+
+		// The reachable peer is dialed right away, and the banned one is still refused.
+		peerset.incoming(SetId::from(0), banned, IncomingIndex(1));
+		assert_messages(
+			peerset,
+			vec![
+				Message::Connect { set_id: SetId::from(0), peer_id: reachable },
+				Message::Reject(IncomingIndex(1)),
+			],
+		);
+	}
 }
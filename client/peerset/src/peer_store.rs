@@ -0,0 +1,226 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Optional on-disk storage of peer reputations and addresses.
+//!
+//! The store is written as a JSON file. Reputations are restored as they were when the store was
+//! saved: they don't decay while the node is offline, so a peer banned before a restart stays
+//! banned for the remainder of its ban once the node is back. Addresses are those at which we
+//! successfully dialed a peer, and are handed to the discovery mechanism at startup so that the
+//! node has good bootstrap candidates besides the configured bootnodes.
+//!
+//! The file is written by a dedicated thread, so that saving the store never blocks the peerset.
+
+use libp2p::{Multiaddr, PeerId};
+use log::{debug, warn};
+use sc_utils::persistence::{unix_time, write_atomically};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::PathBuf, sync::mpsc, thread, time::Duration};
+
+/// Maximum number of peers written to the store.
+const MAX_PERSISTED_PEERS: usize = 1000;
+/// Maximum number of addresses remembered per peer.
+const MAX_ADDRESSES_PER_PEER: usize = 4;
+/// Peers that we haven't seen for this long are dropped from the store.
+const PEER_EXPIRY: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Content of the store file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredPeers {
+	/// UNIX time in seconds at which the store was written.
+	saved_at: u64,
+	/// Peers, indexed by their base58-encoded [`PeerId`].
+	peers: HashMap<String, StoredPeer>,
+}
+
+/// Entry of a single peer in the store file.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPeer {
+	/// Reputation of the peer when the store was written.
+	#[serde(default)]
+	reputation: i32,
+	/// Addresses at which the peer was reachable, most recently seen first.
+	#[serde(default)]
+	addresses: Vec<String>,
+	/// UNIX time in seconds at which the peer was last seen.
+	last_seen: u64,
+}
+
+/// Addresses at which a peer was reachable.
+#[derive(Debug, Default)]
+struct KnownAddresses {
+	/// Addresses, most recently seen first.
+	addresses: Vec<Multiaddr>,
+	/// UNIX time in seconds at which the peer was last seen at one of these addresses.
+	last_seen: u64,
+}
+
+/// On-disk store of peer reputations and addresses.
+#[derive(Debug)]
+pub struct PeerStore {
+	/// Path of the JSON file.
+	path: PathBuf,
+	/// Addresses at which we reached peers, either loaded from disk or reported since.
+	known: HashMap<PeerId, KnownAddresses>,
+	/// Sends the encoded store to the thread writing it to disk.
+	///
+	/// The thread writes the last pending content and exits once the store is dropped.
+	writer: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+impl PeerStore {
+	/// Opens the store at `path` and returns the non-zero reputations it contains.
+	///
+	/// A missing or corrupted file results in an empty store.
+	pub fn open(path: PathBuf) -> (Self, Vec<(PeerId, i32)>) {
+		let mut store = PeerStore { path, known: HashMap::new(), writer: None };
+		let mut reputations = Vec::new();
+
+		let content = match fs::read(&store.path) {
+			Ok(content) => content,
+			Err(e) => {
+				if e.kind() != io::ErrorKind::NotFound {
+					warn!(target: "peerset", "Failed to read peer store {:?}: {}", store.path, e);
+				}
+				return (store, reputations)
+			},
+		};
+
+		let stored = match serde_json::from_slice::<StoredPeers>(&content) {
+			Ok(stored) => stored,
+			Err(e) => {
+				warn!(target: "peerset", "Ignoring corrupted peer store {:?}: {}", store.path, e);
+				return (store, reputations)
+			},
+		};
+
+		let now = unix_time();
+		for (peer_id, entry) in stored.peers {
+			let peer_id = match peer_id.parse::<PeerId>() {
+				Ok(peer_id) => peer_id,
+				Err(_) => {
+					debug!(target: "peerset", "Invalid peer id in peer store: {}", peer_id);
+					continue
+				},
+			};
+
+			if entry.last_seen.saturating_add(PEER_EXPIRY.as_secs()) < now {
+				continue
+			}
+
+			if entry.reputation != 0 {
+				reputations.push((peer_id, entry.reputation));
+			}
+
+			let addresses = entry
+				.addresses
+				.iter()
+				.filter_map(|a| a.parse::<Multiaddr>().ok())
+				.take(MAX_ADDRESSES_PER_PEER)
+				.collect::<Vec<_>>();
+			if !addresses.is_empty() {
+				store
+					.known
+					.insert(peer_id, KnownAddresses { addresses, last_seen: entry.last_seen });
+			}
+		}
+
+		debug!(
+			target: "peerset",
+			"Loaded {} reputations and {} peer addresses from {:?}",
+			reputations.len(),
+			store.known.len(),
+			store.path,
+		);
+
+		(store, reputations)
+	}
+
+	/// Records that `peer_id` was reachable at `address`.
+	pub fn add_address(&mut self, peer_id: PeerId, address: Multiaddr) {
+		let known = self.known.entry(peer_id).or_default();
+		known.addresses.retain(|a| *a != address);
+		known.addresses.insert(0, address);
+		known.addresses.truncate(MAX_ADDRESSES_PER_PEER);
+		known.last_seen = unix_time();
+	}
+
+	/// Returns all the addresses in the store.
+	pub fn addresses(&self) -> impl Iterator<Item = (&PeerId, &Multiaddr)> {
+		self.known
+			.iter()
+			.flat_map(|(peer_id, known)| known.addresses.iter().map(move |a| (peer_id, a)))
+	}
+
+	/// Writes the given reputations and the known addresses to disk in the background.
+	///
+	/// Peers with a reputation of zero and no known address are not written.
+	pub fn save(&mut self, reputations: impl Iterator<Item = (PeerId, i32)>) -> io::Result<()> {
+		let now = unix_time();
+		let mut entries = HashMap::<PeerId, (i32, u64)>::new();
+		for (peer_id, reputation) in reputations.filter(|(_, r)| *r != 0) {
+			entries.insert(peer_id, (reputation, now));
+		}
+		for (peer_id, known) in &self.known {
+			entries.entry(*peer_id).or_insert((0, known.last_seen));
+		}
+
+		// Keep the most recently seen peers if there are too many of them.
+		let mut entries = entries.into_iter().collect::<Vec<_>>();
+		entries.sort_by(|(_, (_, a)), (_, (_, b))| b.cmp(a));
+		entries.truncate(MAX_PERSISTED_PEERS);
+
+		let peers = entries
+			.into_iter()
+			.map(|(peer_id, (reputation, last_seen))| {
+				let addresses = self
+					.known
+					.get(&peer_id)
+					.map(|known| known.addresses.iter().map(|a| a.to_string()).collect())
+					.unwrap_or_default();
+				(peer_id.to_base58(), StoredPeer { reputation, addresses, last_seen })
+			})
+			.collect();
+
+		let stored = StoredPeers { saved_at: now, peers };
+		let content = serde_json::to_vec(&stored)?;
+
+		let writer = match &self.writer {
+			Some(writer) => writer,
+			None => self.writer.insert(spawn_writer(self.path.clone())?),
+		};
+		// The writer only stops if it panicked.
+		writer.send(content).map_err(|_| io::ErrorKind::BrokenPipe.into())
+	}
+}
+
+/// Spawns the thread writing the contents it receives to `path`.
+///
+/// Contents that are superseded while a write is in progress are skipped.
+fn spawn_writer(path: PathBuf) -> io::Result<mpsc::Sender<Vec<u8>>> {
+	let (tx, rx) = mpsc::channel::<Vec<u8>>();
+	thread::Builder::new().name("peer-store-writer".into()).spawn(move || {
+		while let Ok(mut content) = rx.recv() {
+			content = rx.try_iter().last().unwrap_or(content);
+			if let Err(e) = write_atomically(&path, &content) {
+				warn!(target: "peerset", "Failed to save peer store {:?}: {}", path, e);
+			}
+		}
+	})?;
+	Ok(tx)
+}
@@ -86,6 +86,8 @@ const POOL_NO_TAGS: i32 = POOL_INVALID_TX + 9;
 const POOL_INVALID_BLOCK_ID: i32 = POOL_INVALID_TX + 10;
 /// The pool is not accepting future transactions.
 const POOL_FUTURE_TX: i32 = POOL_INVALID_TX + 11;
+/// The transactions providing the same tags were replaced too many times.
+const POOL_TOO_MANY_REPLACEMENTS: i32 = POOL_INVALID_TX + 12;
 
 impl From<Error> for JsonRpseeError {
 	fn from(e: Error) -> Self {
@@ -141,6 +143,11 @@ impl From<Error> for JsonRpseeError {
 				format!("Priority is too low: ({} vs {})", old, new),
 				Some("The transaction has too low priority to replace another transaction already in the pool.")
 			)),
+			Error::Pool(PoolError::TooManyReplacements) => CallError::Custom(ErrorObject::owned(
+				POOL_TOO_MANY_REPLACEMENTS,
+				"Too many replacements",
+				Some("The transaction already in the pool has been replaced too many times.")
+			)),
 			Error::Pool(PoolError::CycleDetected) =>
 				CallError::Custom(ErrorObject::owned(
 				POOL_CYCLE_DETECTED,
@@ -29,6 +29,7 @@ pub use policy::DenyUnsafe;
 pub mod author;
 pub mod chain;
 pub mod child_state;
+pub mod db;
 pub mod dev;
 pub mod offchain;
 pub mod state;
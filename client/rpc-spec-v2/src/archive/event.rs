@@ -0,0 +1,161 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Types returned by the archive methods.
+
+use crate::common::events::StorageResult;
+use serde::{Deserialize, Serialize};
+
+/// The successful result of a runtime call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodResultOk {
+	/// Method was successful.
+	success: bool,
+	/// The result of the method.
+	pub value: String,
+}
+
+/// The error result of a runtime call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodResultErr {
+	/// Method encountered an error.
+	success: bool,
+	/// The error of the method.
+	pub error: String,
+}
+
+/// The result of a runtime call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MethodResult {
+	/// The method was successful.
+	Ok(MethodResultOk),
+	/// The method encountered an error.
+	Err(MethodResultErr),
+}
+
+impl MethodResult {
+	/// Constructs a successful result.
+	pub fn ok(value: impl Into<String>) -> MethodResult {
+		MethodResult::Ok(MethodResultOk { success: true, value: value.into() })
+	}
+
+	/// Constructs an error result.
+	pub fn err(error: impl Into<String>) -> MethodResult {
+		MethodResult::Err(MethodResultErr { success: false, error: error.into() })
+	}
+}
+
+/// The successful result of a storage query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageMethodOk {
+	/// Reported results.
+	pub result: Vec<StorageResult>,
+	/// Number of discarded items.
+	pub discarded_items: usize,
+}
+
+/// The error result of a storage query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageMethodErr {
+	/// The error of the method.
+	pub error: String,
+}
+
+/// The result of a storage query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveStorageResult {
+	/// The storage query was successful.
+	Ok(ArchiveStorageMethodOk),
+	/// The storage query encountered an error.
+	Err(ArchiveStorageMethodErr),
+}
+
+impl ArchiveStorageResult {
+	/// Constructs a successful result.
+	pub fn ok(result: Vec<StorageResult>, discarded_items: usize) -> Self {
+		Self::Ok(ArchiveStorageMethodOk { result, discarded_items })
+	}
+
+	/// Constructs an error result.
+	pub fn err(error: String) -> Self {
+		Self::Err(ArchiveStorageMethodErr { error })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::events::StorageResultType;
+
+	#[test]
+	fn method_result_ok() {
+		let ok = MethodResult::ok("hello");
+
+		let ser = serde_json::to_string(&ok).expect("must serialize");
+		let exp = r#"{"success":true,"value":"hello"}"#;
+		assert_eq!(ser, exp);
+
+		let ok_dec: MethodResult = serde_json::from_str(exp).expect("must deserialize");
+		assert_eq!(ok_dec, ok);
+	}
+
+	#[test]
+	fn method_result_error() {
+		let err = MethodResult::err("hello");
+
+		let ser = serde_json::to_string(&err).expect("must serialize");
+		let exp = r#"{"success":false,"error":"hello"}"#;
+		assert_eq!(ser, exp);
+
+		let err_dec: MethodResult = serde_json::from_str(exp).expect("must deserialize");
+		assert_eq!(err_dec, err);
+	}
+
+	#[test]
+	fn archive_storage_result() {
+		let ok = ArchiveStorageResult::ok(
+			vec![StorageResult {
+				key: "0x1".into(),
+				result: StorageResultType::Value("0x2".into()),
+			}],
+			1,
+		);
+
+		let ser = serde_json::to_string(&ok).expect("must serialize");
+		let exp = r#"{"result":[{"key":"0x1","value":"0x2"}],"discardedItems":1}"#;
+		assert_eq!(ser, exp);
+
+		let ok_dec: ArchiveStorageResult = serde_json::from_str(exp).expect("must deserialize");
+		assert_eq!(ok_dec, ok);
+
+		let err = ArchiveStorageResult::err("hello".into());
+
+		let ser = serde_json::to_string(&err).expect("must serialize");
+		let exp = r#"{"error":"hello"}"#;
+		assert_eq!(ser, exp);
+
+		let err_dec: ArchiveStorageResult = serde_json::from_str(exp).expect("must deserialize");
+		assert_eq!(err_dec, err);
+	}
+}
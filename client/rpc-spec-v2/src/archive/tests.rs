@@ -0,0 +1,396 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::common::events::{
+	PaginatedStorageQuery, StorageQueryType, StorageResult, StorageResultType,
+};
+
+use super::{archive::ArchiveConfig, *};
+
+use assert_matches::assert_matches;
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::error::Error,
+	types::{error::CallError, EmptyServerParams as EmptyParams},
+	RpcModule,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{hexdisplay::HexDisplay, Blake2Hasher, Hasher};
+use std::sync::Arc;
+use substrate_test_runtime::Transfer;
+use substrate_test_runtime_client::{
+	prelude::*, runtime, Backend, BlockBuilderExt, Client, ClientBlockImportExt,
+};
+
+type Header = substrate_test_runtime_client::runtime::Header;
+type Block = substrate_test_runtime_client::runtime::Block;
+const CHAIN_GENESIS: [u8; 32] = [0; 32];
+const INVALID_HASH: [u8; 32] = [1; 32];
+const KEY: &[u8] = b":mock";
+const VALUE: &[u8] = b"hello world";
+const MAX_DESCENDANT_RESPONSES: usize = 3;
+const MAX_QUERIED_ITEMS: usize = 4;
+
+fn setup_api() -> (Arc<Client<Backend>>, RpcModule<Archive<Backend, Block, Client<Backend>>>) {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let client = Arc::new(builder.build());
+
+	let api = Archive::new(
+		client.clone(),
+		backend,
+		CHAIN_GENESIS,
+		ArchiveConfig {
+			max_descendant_responses: MAX_DESCENDANT_RESPONSES,
+			max_queried_items: MAX_QUERIED_ITEMS,
+		},
+	)
+	.into_rpc();
+
+	(client, api)
+}
+
+#[tokio::test]
+async fn archive_genesis() {
+	let (_client, api) = setup_api();
+
+	let genesis: String =
+		api.call("archive_unstable_genesisHash", EmptyParams::new()).await.unwrap();
+	assert_eq!(genesis, format!("0x{}", HexDisplay::from(&CHAIN_GENESIS)));
+}
+
+#[tokio::test]
+async fn archive_body() {
+	let (mut client, api) = setup_api();
+
+	// Invalid block hash.
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+	let body: Option<Vec<String>> =
+		api.call("archive_unstable_body", [invalid_hash]).await.unwrap();
+	assert!(body.is_none());
+
+	// Import a new block with an extrinsic.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder
+		.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		})
+		.unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let expected_tx = hex_string(&block.extrinsics[0].encode());
+
+	let body: Vec<String> = api.call("archive_unstable_body", [block_hash]).await.unwrap();
+	assert_eq!(vec![expected_tx], body);
+}
+
+#[tokio::test]
+async fn archive_header() {
+	let (mut client, api) = setup_api();
+
+	// Invalid block hash.
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+	let header: Option<String> = api.call("archive_unstable_header", [invalid_hash]).await.unwrap();
+	assert!(header.is_none());
+
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let header: String = api.call("archive_unstable_header", [block_hash]).await.unwrap();
+	let bytes = array_bytes::hex2bytes(&header).unwrap();
+	let header: Header = Decode::decode(&mut &bytes[..]).unwrap();
+	assert_eq!(header, block.header);
+}
+
+#[tokio::test]
+async fn archive_finalized_height() {
+	let (client, api) = setup_api();
+
+	let client_height: u64 = client.info().finalized_number;
+
+	let height: u64 =
+		api.call("archive_unstable_finalizedHeight", EmptyParams::new()).await.unwrap();
+
+	assert_eq!(client_height, height);
+}
+
+#[tokio::test]
+async fn archive_hash_by_height() {
+	let (mut client, api) = setup_api();
+
+	// Genesis height.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [0]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", client.genesis_hash())]);
+
+	// Block tree:
+	// genesis -> finalized -> block 2 -> block 3
+	//                      -> block 2b
+	let finalized = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let finalized_hash = finalized.header.hash();
+	client.import(BlockOrigin::Own, finalized.clone()).await.unwrap();
+	client.finalize_block(finalized_hash, None).unwrap();
+
+	let block_2 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_2_hash = block_2.header.hash();
+	client.import(BlockOrigin::Own, block_2.clone()).await.unwrap();
+
+	let block_3 = client
+		.new_block_at(block_2_hash, Default::default(), false)
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	client.import(BlockOrigin::Own, block_3.clone()).await.unwrap();
+
+	// Fork from the finalized block with a different extrinsic.
+	let mut block_builder = client.new_block_at(finalized_hash, Default::default(), false).unwrap();
+	block_builder
+		.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 41,
+			nonce: 0,
+		})
+		.unwrap();
+	let block_2b = block_builder.build().unwrap().block;
+	let block_2b_hash = block_2b.header.hash();
+	client.import(BlockOrigin::Own, block_2b.clone()).await.unwrap();
+
+	// Finalized height.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [1]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", finalized_hash)]);
+
+	// Height with forks.
+	let mut hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [2]).await.unwrap();
+	hashes.sort();
+	let mut expected = vec![format!("{:?}", block_2_hash), format!("{:?}", block_2b_hash)];
+	expected.sort();
+	assert_eq!(hashes, expected);
+
+	// Height without forks.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [3]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", block_3.header.hash())]);
+
+	// Height above the best block.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [4]).await.unwrap();
+	assert!(hashes.is_empty());
+}
+
+#[tokio::test]
+async fn archive_call() {
+	let (mut client, api) = setup_api();
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+
+	// Invalid parameter (non-hex).
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_call",
+			[&invalid_hash, "BabeApi_current_epoch", "0x0"],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 3001 && err.message().contains("Invalid parameter")
+	);
+
+	// Pass an invalid parameters that cannot be decode.
+	let result: MethodResult = api
+		.call("archive_unstable_call", [&invalid_hash, "BabeApi_current_epoch", "0x00"])
+		.await
+		.unwrap();
+	assert_matches!(result, MethodResult::Err(_));
+
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Valid call.
+	let alice_id = AccountKeyring::Alice.to_account_id();
+	// Hex encoded scale encoded bytes representing the call parameters.
+	let call_parameters = hex_string(&alice_id.encode());
+	let result: MethodResult = api
+		.call(
+			"archive_unstable_call",
+			[&block_hash, "AccountNonceApi_account_nonce", &call_parameters],
+		)
+		.await
+		.unwrap();
+	assert_eq!(result, MethodResult::ok("0x0000000000000000"));
+}
+
+#[tokio::test]
+async fn archive_storage_hashes_values() {
+	let (mut client, api) = setup_api();
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":m".to_vec(), Some(b"a".to_vec())).unwrap();
+	builder.push_storage_change(b":mo".to_vec(), Some(b"ab".to_vec())).unwrap();
+	builder.push_storage_change(b":moc".to_vec(), Some(b"abc".to_vec())).unwrap();
+	builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let key = hex_string(&KEY);
+	let items: Vec<PaginatedStorageQuery<String>> = vec![
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: hex_string(b":m"),
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", [serde_json::json!(block_hash), serde_json::json!(items)])
+		.await
+		.unwrap();
+
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(VALUE));
+	match result {
+		ArchiveStorageResult::Ok(result) => {
+			assert_eq!(result.discarded_items, 0);
+			// One value, one hash and three descendants (bounded by the maximum responses).
+			assert_eq!(result.result.len(), 2 + MAX_DESCENDANT_RESPONSES);
+
+			assert_eq!(
+				result.result[0],
+				StorageResult {
+					key: key.clone(),
+					result: StorageResultType::Value(hex_string(&VALUE))
+				}
+			);
+			assert_eq!(
+				result.result[1],
+				StorageResult { key: key.clone(), result: StorageResultType::Hash(expected_hash) }
+			);
+			assert_eq!(
+				result.result[2],
+				StorageResult {
+					key: hex_string(b":m"),
+					result: StorageResultType::Value(hex_string(b"a"))
+				}
+			);
+			assert_eq!(
+				result.result[3],
+				StorageResult {
+					key: hex_string(b":mo"),
+					result: StorageResultType::Value(hex_string(b"ab"))
+				}
+			);
+			assert_eq!(
+				result.result[4],
+				StorageResult {
+					key: hex_string(b":moc"),
+					result: StorageResultType::Value(hex_string(b"abc"))
+				}
+			);
+		},
+		_ => panic!("Unexpected result"),
+	};
+
+	// Resume the descendant iteration after the last reported key.
+	let items: Vec<PaginatedStorageQuery<String>> = vec![PaginatedStorageQuery {
+		key: hex_string(b":m"),
+		query_type: StorageQueryType::DescendantsValues,
+		pagination_start_key: Some(hex_string(b":moc")),
+	}];
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", [serde_json::json!(block_hash), serde_json::json!(items)])
+		.await
+		.unwrap();
+	assert_eq!(
+		result,
+		ArchiveStorageResult::ok(
+			vec![StorageResult {
+				key: key.clone(),
+				result: StorageResultType::Value(hex_string(&VALUE))
+			}],
+			0
+		)
+	);
+}
+
+#[tokio::test]
+async fn archive_storage_discarded_items() {
+	let (mut client, api) = setup_api();
+
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let key = hex_string(&KEY);
+	let items: Vec<PaginatedStorageQuery<String>> = (0..MAX_QUERIED_ITEMS + 2)
+		.map(|_| PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		})
+		.collect();
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", [serde_json::json!(block_hash), serde_json::json!(items)])
+		.await
+		.unwrap();
+	// The key has no value, only the discarded items are reported.
+	assert_eq!(result, ArchiveStorageResult::ok(vec![], 2));
+}
+
+#[tokio::test]
+async fn archive_storage_invalid_pagination_key() {
+	let (_client, api) = setup_api();
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+
+	let items: Vec<PaginatedStorageQuery<String>> = vec![PaginatedStorageQuery {
+		key: hex_string(&KEY),
+		query_type: StorageQueryType::Value,
+		pagination_start_key: Some(hex_string(&KEY)),
+	}];
+
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_storage",
+			[serde_json::json!(invalid_hash), serde_json::json!(items)],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 3001 && err.message().contains("Invalid parameter")
+	);
+}
+
+fn hex_string<Data: AsRef<[u8]>>(data: &Data) -> String {
+	format!("0x{:?}", HexDisplay::from(&data.as_ref()))
+}
@@ -22,10 +22,15 @@ use crate::{
 	chain_head::{
 		api::ChainHeadApiServer,
 		chain_head_follow::ChainHeadFollower,
+		chain_head_storage::ChainHeadStorage,
 		error::Error as ChainHeadRpcError,
-		event::{ChainHeadEvent, ChainHeadResult, ErrorEvent, FollowEvent, NetworkConfig},
+		event::{
+			ChainHeadEvent, ChainHeadResult, ChainHeadStorageEvent, ErrorEvent, FollowEvent,
+			NetworkConfig,
+		},
 		subscription::SubscriptionManagement,
 	},
+	common::events::StorageQuery,
 	SubscriptionTaskExecutor,
 };
 use codec::Encode;
@@ -42,7 +47,7 @@ use sc_client_api::{
 };
 use sp_api::CallApiAt;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
-use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys, traits::CallContext, Bytes};
+use sp_core::{hexdisplay::HexDisplay, traits::CallContext, Bytes};
 use sp_runtime::traits::Block as BlockT;
 use std::{marker::PhantomData, sync::Arc};
 
@@ -62,6 +67,9 @@ pub struct ChainHead<BE, Block: BlockT, Client> {
 	genesis_hash: String,
 	/// The maximum number of pinned blocks allowed per connection.
 	max_pinned_blocks: usize,
+	/// The maximum number of items reported by the `chainHead_storage` before
+	/// pagination is required.
+	operation_max_storage_items: usize,
 	/// Phantom member to pin the block type.
 	_phantom: PhantomData<Block>,
 }
@@ -74,6 +82,7 @@ impl<BE, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 		executor: SubscriptionTaskExecutor,
 		genesis_hash: GenesisHash,
 		max_pinned_blocks: usize,
+		operation_max_storage_items: usize,
 	) -> Self {
 		let genesis_hash = format!("0x{:?}", HexDisplay::from(&genesis_hash.as_ref()));
 
@@ -84,6 +93,7 @@ impl<BE, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 			subscriptions: Arc::new(SubscriptionManagement::new()),
 			genesis_hash,
 			max_pinned_blocks,
+			operation_max_storage_items,
 			_phantom: PhantomData,
 		}
 	}
@@ -272,81 +282,68 @@ where
 		mut sink: SubscriptionSink,
 		follow_subscription: String,
 		hash: Block::Hash,
-		key: String,
-		child_key: Option<String>,
+		items: Vec<StorageQuery<String>>,
+		child_trie: Option<String>,
 		_network_config: Option<NetworkConfig>,
 	) -> SubscriptionResult {
-		let key = StorageKey(parse_hex_param(&mut sink, key)?);
-
-		let child_key = child_key
-			.map(|child_key| parse_hex_param(&mut sink, child_key))
+		// Gain control over parameter parsing and returned error.
+		let items = items
+			.into_iter()
+			.map(|query| {
+				let key = StorageKey(parse_hex_param(&mut sink, query.key)?);
+				Ok(StorageQuery { key, query_type: query.query_type })
+			})
+			.collect::<Result<Vec<_>, SubscriptionEmptyError>>()?;
+
+		let child_trie = child_trie
+			.map(|child_trie| parse_hex_param(&mut sink, child_trie))
 			.transpose()?
 			.map(ChildInfo::new_default_from_vec);
 
-		let client = self.client.clone();
-		let subscriptions = self.subscriptions.clone();
+		let Some(handle) = self.subscriptions.get_subscription(&follow_subscription) else {
+			// Invalid invalid subscription ID.
+			let _ = sink.send(&ChainHeadStorageEvent::Disjoint);
+			return Ok(())
+		};
 
-		let fut = async move {
-			let Some(handle) = subscriptions.get_subscription(&follow_subscription) else {
-				// Invalid invalid subscription ID.
-				let _ = sink.send(&ChainHeadEvent::<String>::Disjoint);
-				return
-			};
+		// Block is not part of the subscription.
+		if !handle.contains_block(&hash) {
+			let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
+			return Ok(())
+		}
 
-			// Block is not part of the subscription.
-			if !handle.contains_block(&hash) {
-				let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
-				return
-			}
+		// The subscription ID of this method is the operation ID used
+		// by `chainHead_continue`.
+		let operation_id = match self.accept_subscription(&mut sink) {
+			Ok(operation_id) => operation_id,
+			Err(err) => {
+				sink.close(ChainHeadRpcError::InvalidSubscriptionID);
+				return Err(err)
+			},
+		};
 
-			// The child key is provided, use the key to query the child trie.
-			if let Some(child_key) = child_key {
-				// The child key must not be prefixed with ":child_storage:" nor
-				// ":child_storage:default:".
-				if well_known_keys::is_default_child_storage_key(child_key.storage_key()) ||
-					well_known_keys::is_child_storage_key(child_key.storage_key())
-				{
-					let _ = sink
-						.send(&ChainHeadEvent::Done(ChainHeadResult { result: None::<String> }));
-					return
-				}
-
-				let res = client
-					.child_storage(hash, &child_key, &key)
-					.map(|result| {
-						let result =
-							result.map(|storage| format!("0x{:?}", HexDisplay::from(&storage.0)));
-						ChainHeadEvent::Done(ChainHeadResult { result })
-					})
-					.unwrap_or_else(|error| {
-						ChainHeadEvent::Error(ErrorEvent { error: error.to_string() })
-					});
-				let _ = sink.send(&res);
-				return
-			}
+		let Some(operation) = handle.register_operation(operation_id.clone()) else {
+			// Registering the operation can only fail if the JsonRPSee
+			// generated a duplicate subscription ID.
+			debug!(
+				target: LOG_TARGET,
+				"[storage][id={:?}] Operation already registered", operation_id
+			);
+			sink.close(ChainHeadRpcError::InvalidSubscriptionID);
+			return Ok(())
+		};
 
-			// The main key must not be prefixed with b":child_storage:" nor
-			// b":child_storage:default:".
-			if well_known_keys::is_default_child_storage_key(&key.0) ||
-				well_known_keys::is_child_storage_key(&key.0)
-			{
-				let _ =
-					sink.send(&ChainHeadEvent::Done(ChainHeadResult { result: None::<String> }));
-				return
-			}
+		let storage_client = ChainHeadStorage::<Client, Block, BE>::new(
+			self.client.clone(),
+			self.operation_max_storage_items,
+		);
 
-			// Main root trie storage query.
-			let res = client
-				.storage(hash, &key)
-				.map(|result| {
-					let result =
-						result.map(|storage| format!("0x{:?}", HexDisplay::from(&storage.0)));
-					ChainHeadEvent::Done(ChainHeadResult { result })
-				})
-				.unwrap_or_else(|error| {
-					ChainHeadEvent::Error(ErrorEvent { error: error.to_string() })
-				});
-			let _ = sink.send(&res);
+		let fut = async move {
+			storage_client
+				.generate_events(sink, hash, items, child_trie, operation_id.clone(), operation)
+				.await;
+
+			handle.unregister_operation(&operation_id);
 		};
 
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
@@ -428,4 +425,35 @@ where
 
 		Ok(())
 	}
+
+	fn chain_head_unstable_continue(
+		&self,
+		follow_subscription: String,
+		operation_id: String,
+	) -> RpcResult<()> {
+		let Some(handle) = self.subscriptions.get_subscription(&follow_subscription) else {
+			// Invalid invalid subscription ID.
+			return Ok(())
+		};
+
+		if !handle.continue_operation(&operation_id) {
+			return Err(ChainHeadRpcError::InvalidOperationID.into())
+		}
+
+		Ok(())
+	}
+
+	fn chain_head_unstable_stop_operation(
+		&self,
+		follow_subscription: String,
+		operation_id: String,
+	) -> RpcResult<()> {
+		let Some(handle) = self.subscriptions.get_subscription(&follow_subscription) else {
+			// Invalid invalid subscription ID.
+			return Ok(())
+		};
+
+		handle.unregister_operation(&operation_id);
+		Ok(())
+	}
 }
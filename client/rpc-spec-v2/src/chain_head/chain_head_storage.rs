@@ -0,0 +1,250 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `chainHead_storage` method.
+
+use std::sync::Arc;
+
+use futures::{future, stream, StreamExt};
+use jsonrpsee::{core::error::SubscriptionClosed, SubscriptionSink};
+use sc_client_api::{Backend, ChildInfo, StorageKey, StorageProvider};
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{
+	chain_head::{
+		event::{ChainHeadStorageEvent, OperationError, OperationId, OperationStorageItems},
+		subscription::RegisteredOperation,
+	},
+	common::{
+		events::{StorageQuery, StorageQueryType, StorageResult},
+		storage::{is_key_queryable, IterQueryType, QueryIter, Storage},
+	},
+};
+
+/// Generates the events of the `chainHead_storage` method.
+pub struct ChainHeadStorage<Client, Block, BE> {
+	/// Storage client.
+	client: Storage<Client, Block, BE>,
+	/// The maximum number of items reported by the `chainHead_storage` before
+	/// waiting for the `chainHead_continue` method.
+	operation_max_storage_items: usize,
+}
+
+impl<Client, Block, BE> ChainHeadStorage<Client, Block, BE> {
+	/// Constructs a new [`ChainHeadStorage`].
+	pub fn new(client: Arc<Client>, operation_max_storage_items: usize) -> Self {
+		Self { client: Storage::new(client), operation_max_storage_items }
+	}
+}
+
+/// The reason the operation stopped producing events.
+enum Interrupted {
+	/// The user dropped the subscription or the `follow` subscription ended.
+	Stopped,
+	/// An error occurred while querying the storage.
+	Error(String),
+}
+
+/// Reports the storage items of one operation, pausing the operation every time
+/// the maximum number of items has been reported.
+struct OperationSink {
+	/// The subscription sink of the `chainHead_storage` method.
+	sink: SubscriptionSink,
+	/// The ID of the operation.
+	operation_id: String,
+	/// Resumes the operation on `chainHead_continue` calls.
+	operation: RegisteredOperation,
+	/// Items that have not been reported yet.
+	items: Vec<StorageResult>,
+	/// The maximum number of items reported at once.
+	max_items: usize,
+}
+
+impl OperationSink {
+	/// The number of items that can be added before the operation must pause.
+	fn remaining(&self) -> usize {
+		self.max_items.saturating_sub(self.items.len())
+	}
+
+	/// Report the buffered items.
+	fn flush(&mut self) -> Result<(), Interrupted> {
+		if self.items.is_empty() {
+			return Ok(())
+		}
+
+		let event = ChainHeadStorageEvent::OperationStorageItems(OperationStorageItems {
+			operation_id: self.operation_id.clone(),
+			items: std::mem::take(&mut self.items),
+		});
+		self.send(&event)
+	}
+
+	/// Buffer the provided items, reporting them and pausing the operation
+	/// once the maximum number of items has been reached.
+	async fn extend(
+		&mut self,
+		items: impl IntoIterator<Item = StorageResult>,
+	) -> Result<(), Interrupted> {
+		self.items.extend(items);
+
+		if self.remaining() > 0 {
+			return Ok(())
+		}
+
+		self.flush()?;
+		self.operation.pause();
+		self.send(&ChainHeadStorageEvent::OperationWaitingForContinue(OperationId {
+			operation_id: self.operation_id.clone(),
+		}))?;
+
+		// Piping an item-less stream into the sink waits for the `chainHead_continue` call,
+		// but returns early if the user unsubscribes or the connection is closed meanwhile.
+		let mut resumed = false;
+		let continued = stream::once(self.operation.wait_for_continue()).filter_map(|continued| {
+			resumed = continued;
+			future::ready(None::<ChainHeadStorageEvent>)
+		});
+		let closed = self.sink.pipe_from_stream(Box::pin(continued)).await;
+
+		// The operation is terminated when it is stopped or the `follow` subscription ends.
+		match closed {
+			SubscriptionClosed::Success if resumed => Ok(()),
+			_ => Err(Interrupted::Stopped),
+		}
+	}
+
+	/// Send one event to the user.
+	fn send(&mut self, event: &ChainHeadStorageEvent) -> Result<(), Interrupted> {
+		match self.sink.send(event) {
+			Ok(true) => Ok(()),
+			_ => Err(Interrupted::Stopped),
+		}
+	}
+}
+
+impl<Client, Block, BE> ChainHeadStorage<Client, Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+	Client: StorageProvider<Block, BE> + 'static,
+{
+	/// Generate the events of the `chainHead_storage` method until all the items
+	/// are reported or the operation is interrupted.
+	pub async fn generate_events(
+		&self,
+		sink: SubscriptionSink,
+		hash: Block::Hash,
+		items: Vec<StorageQuery<StorageKey>>,
+		child_key: Option<ChildInfo>,
+		operation_id: String,
+		registered_operation: RegisteredOperation,
+	) {
+		let mut operation = OperationSink {
+			sink,
+			operation_id: operation_id.clone(),
+			operation: registered_operation,
+			items: Vec::new(),
+			max_items: self.operation_max_storage_items.max(1),
+		};
+
+		let result = self.report_items(&mut operation, hash, items, child_key.as_ref()).await;
+		let result = result.and_then(|()| operation.flush());
+
+		let event = match result {
+			Ok(()) => ChainHeadStorageEvent::OperationStorageDone(OperationId { operation_id }),
+			Err(Interrupted::Error(error)) =>
+				ChainHeadStorageEvent::OperationError(OperationError { operation_id, error }),
+			Err(Interrupted::Stopped) => return,
+		};
+		let _ = operation.sink.send(&event);
+	}
+
+	/// Query the storage for every item, forwarding the results to the operation.
+	async fn report_items(
+		&self,
+		operation: &mut OperationSink,
+		hash: Block::Hash,
+		items: Vec<StorageQuery<StorageKey>>,
+		child_key: Option<&ChildInfo>,
+	) -> Result<(), Interrupted> {
+		// Child tries keyed by a `:child_storage:` prefix are never reported.
+		if child_key.map_or(false, |child_key| !is_key_queryable(child_key.storage_key())) {
+			return Ok(())
+		}
+
+		for item in items {
+			// Keys prefixed by `:child_storage:` are never reported.
+			if child_key.is_none() && !is_key_queryable(&item.key.0) {
+				continue
+			}
+
+			let ty = match item.query_type {
+				StorageQueryType::Value => {
+					let result = self
+						.client
+						.query_value(hash, &item.key, child_key)
+						.map_err(Interrupted::Error)?;
+					operation.extend(result).await?;
+					continue
+				},
+				StorageQueryType::Hash => {
+					let result = self
+						.client
+						.query_hash(hash, &item.key, child_key)
+						.map_err(Interrupted::Error)?;
+					operation.extend(result).await?;
+					continue
+				},
+				StorageQueryType::ClosestDescendantMerkleValue => {
+					let result = self
+						.client
+						.query_merkle_value(hash, &item.key, child_key)
+						.map_err(Interrupted::Error)?;
+					operation.extend(result).await?;
+					continue
+				},
+				StorageQueryType::DescendantsValues => IterQueryType::Value,
+				StorageQueryType::DescendantsHashes => IterQueryType::Hash,
+			};
+
+			// Iterate over the descendants in pages that fit into the
+			// remaining capacity of the operation.
+			let mut pagination_start_key = None;
+			loop {
+				let query = QueryIter {
+					query_key: item.key.clone(),
+					pagination_start_key: pagination_start_key.take(),
+					ty,
+				};
+
+				let (results, next_key) = self
+					.client
+					.query_iter_pagination(query, hash, child_key, operation.remaining())
+					.map_err(Interrupted::Error)?;
+				operation.extend(results).await?;
+
+				match next_key {
+					Some(key) => pagination_start_key = Some(key),
+					None => break,
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -18,6 +18,7 @@
 
 //! The chain head's event returned as json compatible object.
 
+use crate::common::events::StorageResult;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use sp_api::ApiError;
 use sp_version::RuntimeVersion;
@@ -243,9 +244,62 @@ pub enum ChainHeadEvent<T> {
 	Disjoint,
 }
 
+/// The identifier of an ongoing operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationId {
+	/// The operation ID.
+	pub operation_id: String,
+}
+
+/// A batch of storage items produced by the `chainHead_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStorageItems {
+	/// The operation ID.
+	pub operation_id: String,
+	/// The resulting items.
+	pub items: Vec<StorageResult>,
+}
+
+/// The operation could not be processed due to an error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationError {
+	/// The operation ID.
+	pub operation_id: String,
+	/// Reason of the error.
+	pub error: String,
+}
+
+/// The event generated by the `chainHead_storage` method.
+///
+/// The events are generated in the following order:
+/// 1. OperationStorageItems - generated for each batch of reported items.
+/// 2. OperationWaitingForContinue - the server has reached the maximum number
+///      of items that can be reported at once, and it waits for the
+///      `chainHead_unstable_continue` method to resume the operation.
+/// 3. OperationStorageDone - all the queried items have been reported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "event")]
+pub enum ChainHeadStorageEvent {
+	/// A batch of storage items.
+	OperationStorageItems(OperationStorageItems),
+	/// The operation is paused until `chainHead_unstable_continue` is called.
+	OperationWaitingForContinue(OperationId),
+	/// The operation has reported all the storage items.
+	OperationStorageDone(OperationId),
+	/// An error occurred. This is definitive.
+	OperationError(OperationError),
+	/// The provided subscription ID is stale or invalid.
+	Disjoint,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::common::events::StorageResultType;
 
 	#[test]
 	fn follow_initialized_event_no_updates() {
@@ -462,6 +516,66 @@ mod tests {
 		assert_eq!(event_dec, event);
 	}
 
+	#[test]
+	fn chain_head_storage_items_event() {
+		let event = ChainHeadStorageEvent::OperationStorageItems(OperationStorageItems {
+			operation_id: "123".into(),
+			items: vec![StorageResult {
+				key: "0x1".into(),
+				result: StorageResultType::Value("0x123".into()),
+			}],
+		});
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"operationStorageItems","operationId":"123","items":[{"key":"0x1","value":"0x123"}]}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
+	#[test]
+	fn chain_head_storage_waiting_for_continue_event() {
+		let event = ChainHeadStorageEvent::OperationWaitingForContinue(OperationId {
+			operation_id: "123".into(),
+		});
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"operationWaitingForContinue","operationId":"123"}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
+	#[test]
+	fn chain_head_storage_done_event() {
+		let event =
+			ChainHeadStorageEvent::OperationStorageDone(OperationId { operation_id: "123".into() });
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"operationStorageDone","operationId":"123"}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
+	#[test]
+	fn chain_head_storage_error_event() {
+		let event = ChainHeadStorageEvent::OperationError(OperationError {
+			operation_id: "123".into(),
+			error: "reason".into(),
+		});
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"operationError","operationId":"123","error":"reason"}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
 	#[test]
 	fn chain_head_network_config() {
 		let conf = NetworkConfig {
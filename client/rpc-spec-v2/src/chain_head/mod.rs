@@ -33,11 +33,13 @@ pub mod error;
 pub mod event;
 
 mod chain_head_follow;
+mod chain_head_storage;
 mod subscription;
 
 pub use api::ChainHeadApiServer;
 pub use chain_head::ChainHead;
 pub use event::{
-	BestBlockChanged, ChainHeadEvent, ChainHeadResult, ErrorEvent, Finalized, FollowEvent,
-	Initialized, NetworkConfig, NewBlock, RuntimeEvent, RuntimeVersionEvent,
+	BestBlockChanged, ChainHeadEvent, ChainHeadResult, ChainHeadStorageEvent, ErrorEvent,
+	Finalized, FollowEvent, Initialized, NetworkConfig, NewBlock, OperationError, OperationId,
+	OperationStorageItems, RuntimeEvent, RuntimeVersionEvent,
 };
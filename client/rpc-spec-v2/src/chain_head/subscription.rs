@@ -18,13 +18,19 @@
 
 //! Subscription management for tracking subscription IDs to pinned blocks.
 
-use futures::channel::oneshot;
+use futures::{
+	channel::{mpsc, oneshot},
+	StreamExt,
+};
 use parking_lot::RwLock;
 use sp_blockchain::Error;
 use sp_runtime::traits::Block as BlockT;
 use std::{
 	collections::{hash_map::Entry, HashMap, HashSet},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
 };
 
 /// Subscription management error.
@@ -48,6 +54,39 @@ impl From<Error> for SubscriptionManagementError {
 	}
 }
 
+/// The subscription side of an operation that can be resumed by the `chainHead_continue` method.
+struct OperationState {
+	/// Signals the operation to resume.
+	tx_continue: mpsc::Sender<()>,
+	/// Whether the operation is paused and waits for the `chainHead_continue` method.
+	waiting: Arc<AtomicBool>,
+}
+
+/// An operation registered with [`SubscriptionHandle::register_operation`].
+pub struct RegisteredOperation {
+	/// Triggered on each `chainHead_continue` call while the operation is paused.
+	rx_continue: mpsc::Receiver<()>,
+	/// Whether the operation is paused and waits for the `chainHead_continue` method.
+	waiting: Arc<AtomicBool>,
+}
+
+impl RegisteredOperation {
+	/// Pause the operation until the `chainHead_continue` method is called.
+	///
+	/// This must be called before the user is notified that the operation is waiting, otherwise
+	/// a quick `chainHead_continue` call could be rejected.
+	pub fn pause(&self) {
+		self.waiting.store(true, Ordering::Release);
+	}
+
+	/// Wait for the `chainHead_continue` method to resume the paused operation.
+	///
+	/// Returns `false` if the operation was terminated instead.
+	pub async fn wait_for_continue(&mut self) -> bool {
+		self.rx_continue.next().await.is_some()
+	}
+}
+
 /// Inner subscription data structure.
 struct SubscriptionInner<Block: BlockT> {
 	/// The `runtime_updates` parameter flag of the subscription.
@@ -58,6 +97,8 @@ struct SubscriptionInner<Block: BlockT> {
 	blocks: HashSet<Block::Hash>,
 	/// The maximum number of pinned blocks allowed per subscription.
 	max_pinned_blocks: usize,
+	/// The ongoing operations that can be resumed by the `chainHead_continue` method.
+	operations: HashMap<String, OperationState>,
 }
 
 /// Manage the blocks of a specific subscription ID.
@@ -75,6 +116,7 @@ impl<Block: BlockT> SubscriptionHandle<Block> {
 				tx_stop: Some(tx_stop),
 				blocks: HashSet::new(),
 				max_pinned_blocks,
+				operations: HashMap::new(),
 			})),
 		}
 	}
@@ -131,6 +173,46 @@ impl<Block: BlockT> SubscriptionHandle<Block> {
 		let inner = self.inner.read();
 		inner.runtime_updates
 	}
+
+	/// Register a new operation that can be resumed by the `chainHead_continue` method.
+	///
+	/// Returns the operation that is resumed on each `chainHead_continue` call, or
+	/// none if the operation ID was already registered.
+	pub fn register_operation(&self, operation_id: String) -> Option<RegisteredOperation> {
+		let mut inner = self.inner.write();
+
+		if let Entry::Vacant(entry) = inner.operations.entry(operation_id) {
+			let (tx_continue, rx_continue) = mpsc::channel(1);
+			let waiting = Arc::new(AtomicBool::new(false));
+			entry.insert(OperationState { tx_continue, waiting: waiting.clone() });
+			Some(RegisteredOperation { rx_continue, waiting })
+		} else {
+			None
+		}
+	}
+
+	/// Remove the operation ID once the operation has finished, or to stop it.
+	///
+	/// A paused operation is woken up and terminated.
+	pub fn unregister_operation(&self, operation_id: &String) {
+		let mut inner = self.inner.write();
+		inner.operations.remove(operation_id);
+	}
+
+	/// Signal the operation to continue producing events.
+	///
+	/// Returns `false` if the operation ID is not registered for this subscription or
+	/// the operation is not paused.
+	pub fn continue_operation(&self, operation_id: &String) -> bool {
+		let mut inner = self.inner.write();
+
+		let Some(operation) = inner.operations.get_mut(operation_id) else { return false };
+		if !operation.waiting.swap(false, Ordering::AcqRel) {
+			return false
+		}
+
+		operation.tx_continue.try_send(()).is_ok()
+	}
 }
 
 /// Manage block pinning / unpinning for subscription IDs.
@@ -171,9 +253,13 @@ impl<Block: BlockT> SubscriptionManagement<Block> {
 	}
 
 	/// Remove the subscription ID with associated pinned blocks.
+	///
+	/// The ongoing operations of the subscription are terminated.
 	pub fn remove_subscription(&self, subscription_id: &String) {
 		let mut subs = self.inner.write();
-		subs.remove(subscription_id);
+		if let Some(handle) = subs.remove(subscription_id) {
+			handle.inner.write().operations.clear();
+		}
 	}
 
 	/// Obtain the specific subscription handle.
@@ -282,4 +368,43 @@ mod tests {
 		// Exceeded number of pinned blocks.
 		handle.pin_block(hash_2).unwrap_err();
 	}
+
+	#[test]
+	fn subscription_check_operations() {
+		let subs = SubscriptionManagement::<Block>::new();
+
+		let id = "abc".to_string();
+		let (_, handle) = subs.insert_subscription(id.clone(), false, 10).unwrap();
+
+		let operation_id = "op".to_string();
+		// Unknown operations cannot be continued.
+		assert!(!handle.continue_operation(&operation_id));
+
+		let mut operation = handle.register_operation(operation_id.clone()).unwrap();
+		// Operation IDs are unique per subscription.
+		assert!(handle.register_operation(operation_id.clone()).is_none());
+
+		// Operations that are not paused cannot be continued.
+		assert!(!handle.continue_operation(&operation_id));
+		assert_eq!(operation.rx_continue.try_next().ok(), None);
+
+		operation.pause();
+		assert!(handle.continue_operation(&operation_id));
+		assert_eq!(operation.rx_continue.try_next().unwrap(), Some(()));
+		// The operation is resumed only once per pause.
+		assert!(!handle.continue_operation(&operation_id));
+
+		// Unregistering the operation terminates it.
+		operation.pause();
+		handle.unregister_operation(&operation_id);
+		assert!(!handle.continue_operation(&operation_id));
+		assert_eq!(operation.rx_continue.try_next().unwrap(), None);
+
+		// Removing the subscription terminates the operation.
+		let mut operation = handle.register_operation(operation_id.clone()).unwrap();
+		operation.pause();
+		subs.remove_subscription(&id);
+		assert!(!handle.continue_operation(&operation_id));
+		assert_eq!(operation.rx_continue.try_next().unwrap(), None);
+	}
 }
@@ -1,6 +1,7 @@
 use crate::chain_head::test_utils::ChainHeadMockClient;
 
 use super::*;
+use crate::common::events::{StorageQuery, StorageQueryType, StorageResultType};
 use assert_matches::assert_matches;
 use codec::{Decode, Encode};
 use futures::Future;
@@ -17,6 +18,7 @@ use sp_core::{
 	hexdisplay::HexDisplay,
 	storage::well_known_keys::{self, CODE},
 	testing::TaskExecutor,
+	Blake2Hasher, Hasher,
 };
 use sp_version::RuntimeVersion;
 use std::sync::Arc;
@@ -28,6 +30,7 @@ use substrate_test_runtime_client::{
 type Header = substrate_test_runtime_client::runtime::Header;
 type Block = substrate_test_runtime_client::runtime::Block;
 const MAX_PINNED_BLOCKS: usize = 32;
+const MAX_PAGINATION_LIMIT: usize = 5;
 const CHAIN_GENESIS: [u8; 32] = [0; 32];
 const INVALID_HASH: [u8; 32] = [1; 32];
 const KEY: &[u8] = b":mock";
@@ -72,6 +75,7 @@ async fn setup_api() -> (
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -111,6 +115,7 @@ async fn follow_subscription_produces_blocks() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -168,6 +173,7 @@ async fn follow_with_runtime() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -273,6 +279,7 @@ async fn get_genesis() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -457,6 +464,7 @@ async fn call_runtime_without_flag() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -499,23 +507,34 @@ async fn call_runtime_without_flag() {
 }
 
 #[tokio::test]
-async fn get_storage() {
+async fn get_storage_hash() {
 	let (mut client, api, mut block_sub, sub_id, block) = setup_api().await;
 	let block_hash = format!("{:?}", block.header.hash());
 	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
 	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let items = vec![StorageQuery { key: key.clone(), query_type: StorageQueryType::Hash }];
 
 	// Subscription ID is stale the disjoint event is emitted.
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", ["invalid_sub_id", &invalid_hash, &key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[
+				serde_json::json!("invalid_sub_id"),
+				serde_json::json!(invalid_hash),
+				serde_json::json!(items),
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<String> = get_next_event(&mut sub).await;
-	assert_eq!(event, ChainHeadEvent::<String>::Disjoint);
+	let event: ChainHeadStorageEvent = get_next_event(&mut sub).await;
+	assert_eq!(event, ChainHeadStorageEvent::Disjoint);
 
 	// Valid subscription ID with invalid block hash will error.
 	let err = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &invalid_hash, &key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(invalid_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap_err();
 	assert_matches!(err,
@@ -524,11 +543,17 @@ async fn get_storage() {
 
 	// Valid call without storage at the key.
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	// The `Done` event is generated directly since the key does not have any value associated.
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
 
 	// Import a new block with storage changes.
 	let mut builder = client.new_block(Default::default()).unwrap();
@@ -548,24 +573,312 @@ async fn get_storage() {
 	);
 
 	// Valid call with storage at the key.
-	let expected_value = Some(format!("0x{:?}", HexDisplay::from(&VALUE)));
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(&VALUE));
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result == expected_value);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
 
 	// Child value set in `setup_api`.
 	let child_info = format!("0x{:?}", HexDisplay::from(b"child"));
 	let genesis_hash = format!("{:?}", client.genesis_hash());
-	let expected_value = Some(format!("0x{:?}", HexDisplay::from(&CHILD_VALUE)));
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(&CHILD_VALUE));
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			[
+				serde_json::json!(sub_id),
+				serde_json::json!(genesis_hash),
+				serde_json::json!(items),
+				serde_json::json!(child_info),
+			],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+}
+
+#[tokio::test]
+async fn get_storage_value() {
+	let (mut client, api, mut block_sub, sub_id, block) = setup_api().await;
+	let block_hash = format!("{:?}", block.header.hash());
+	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let items = vec![StorageQuery { key: key.clone(), query_type: StorageQueryType::Value }];
+
+	// Valid call without storage at the key.
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+
+	// Import a new block with storage changes.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	// Valid call with storage at the key.
+	let expected_value = format!("0x{:?}", HexDisplay::from(&VALUE));
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Value(expected_value)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+
+	// Child value set in `setup_api`.
+	let child_info = format!("0x{:?}", HexDisplay::from(b"child"));
+	let genesis_hash = format!("{:?}", client.genesis_hash());
+	let expected_value = format!("0x{:?}", HexDisplay::from(&CHILD_VALUE));
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			[
+				serde_json::json!(sub_id),
+				serde_json::json!(genesis_hash),
+				serde_json::json!(items),
+				serde_json::json!(child_info),
+			],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Value(expected_value)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+}
+
+#[tokio::test]
+async fn get_storage_closest_merkle_value() {
+	let (_client, api, _block_sub, sub_id, block) = setup_api().await;
+	let block_hash = format!("{:?}", block.header.hash());
+
+	// The code is always present in the storage of the test runtime.
+	let key = format!("0x{:?}", HexDisplay::from(&CODE));
+	let items = vec![StorageQuery {
+		key: key.clone(),
+		query_type: StorageQueryType::ClosestDescendantMerkleValue,
+	}];
+
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 1 &&
+			res.items[0].key == key &&
+			matches!(res.items[0].result, StorageResultType::ClosestDescendantMerkleValue(_))
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+}
+
+#[tokio::test]
+async fn get_storage_descendants_with_continue() {
+	let (mut client, api, mut block_sub, sub_id, _block) = setup_api().await;
+
+	// Import a new block with more descendants than the pagination limit.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	for i in 0..(MAX_PAGINATION_LIMIT + 2) {
+		let mut key = KEY.to_vec();
+		key.push(i as u8);
+		builder.push_storage_change(key, Some(VALUE.to_vec())).unwrap();
+	}
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let items = vec![StorageQuery { key, query_type: StorageQueryType::DescendantsValues }];
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &genesis_hash, &key, &child_info])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result == expected_value);
+
+	// The first page contains exactly the maximum number of items.
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == MAX_PAGINATION_LIMIT
+	);
+	let operation_id = match get_next_event::<ChainHeadStorageEvent>(&mut sub).await {
+		ChainHeadStorageEvent::OperationWaitingForContinue(res) => res.operation_id,
+		event => panic!("Expected OperationWaitingForContinue, got {:?}", event),
+	};
+
+	// Invalid operation ID.
+	let err = api
+		.call::<_, serde_json::Value>(
+			"chainHead_unstable_continue",
+			[sub_id.as_str(), "invalid_op_id"],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 2005 && err.message() == "Invalid operation ID"
+	);
+
+	// Resume the operation to receive the remaining items.
+	let _res: () = api.call("chainHead_unstable_continue", [&sub_id, &operation_id]).await.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageItems(res) if res.items.len() == 2
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(res) if res.operation_id == operation_id
+	);
+}
+
+#[tokio::test]
+async fn get_storage_stop_paused_operation() {
+	let (mut client, api, mut block_sub, sub_id, _block) = setup_api().await;
+
+	// Import a new block with more descendants than the pagination limit.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	for i in 0..(MAX_PAGINATION_LIMIT + 2) {
+		let mut key = KEY.to_vec();
+		key.push(i as u8);
+		builder.push_storage_change(key, Some(VALUE.to_vec())).unwrap();
+	}
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let items = vec![StorageQuery { key, query_type: StorageQueryType::DescendantsValues }];
+	let start_paused_operation = || async {
+		let mut sub = api
+			.subscribe(
+				"chainHead_unstable_storage",
+				[
+					serde_json::json!(sub_id),
+					serde_json::json!(block_hash),
+					serde_json::json!(items),
+				],
+			)
+			.await
+			.unwrap();
+		assert_matches!(
+			get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+			ChainHeadStorageEvent::OperationStorageItems(_)
+		);
+		let operation_id = match get_next_event::<ChainHeadStorageEvent>(&mut sub).await {
+			ChainHeadStorageEvent::OperationWaitingForContinue(res) => res.operation_id,
+			event => panic!("Expected OperationWaitingForContinue, got {:?}", event),
+		};
+		(sub, operation_id)
+	};
+	// The subscription ends once the operation is terminated.
+	let assert_terminated = |mut sub: RpcSubscription| async move {
+		let event = tokio::time::timeout(
+			std::time::Duration::from_secs(60),
+			sub.next::<ChainHeadStorageEvent>(),
+		)
+		.await
+		.unwrap();
+		assert!(event.is_none());
+	};
+
+	// Stopping the operation terminates it without further events.
+	let (sub, operation_id) = start_paused_operation().await;
+	let _res: () = api
+		.call("chainHead_unstable_stopOperation", [&sub_id, &operation_id])
+		.await
+		.unwrap();
+	let err = api
+		.call::<_, serde_json::Value>("chainHead_unstable_continue", [&sub_id, &operation_id])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 2005 && err.message() == "Invalid operation ID"
+	);
+	assert_terminated(sub).await;
+
+	// Unsubscribing terminates the paused operation.
+	let (sub, operation_id) = start_paused_operation().await;
+	let _res: bool = api.call("chainHead_unstable_stopStorage", [&operation_id]).await.unwrap();
+	assert_terminated(sub).await;
 }
 
 #[tokio::test]
@@ -578,45 +891,79 @@ async fn get_storage_wrong_key() {
 	let mut prefixed_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(&KEY);
 	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	let items = vec![StorageQuery { key: prefixed_key, query_type: StorageQueryType::Value }];
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
 
 	// Key is prefixed by DEFAULT_CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(&KEY);
 	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	let items = vec![StorageQuery { key: prefixed_key, query_type: StorageQueryType::Value }];
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[serde_json::json!(sub_id), serde_json::json!(block_hash), serde_json::json!(items)],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
+
+	let items = vec![StorageQuery { key, query_type: StorageQueryType::Value }];
 
 	// Child key is prefixed by CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(b"child");
 	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[
+				serde_json::json!(sub_id),
+				serde_json::json!(block_hash),
+				serde_json::json!(items),
+				serde_json::json!(prefixed_key),
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
 
 	// Child key is prefixed by DEFAULT_CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(b"child");
 	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			[
+				serde_json::json!(sub_id),
+				serde_json::json!(block_hash),
+				serde_json::json!(items),
+				serde_json::json!(prefixed_key),
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::OperationStorageDone(_)
+	);
 }
 
 #[tokio::test]
@@ -631,6 +978,7 @@ async fn follow_generates_initial_blocks() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -758,6 +1106,7 @@ async fn follow_exceeding_pinned_blocks() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		2,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -808,6 +1157,7 @@ async fn follow_with_unpin() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		2,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -888,6 +1238,7 @@ async fn follow_prune_best_block() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -1044,6 +1395,7 @@ async fn follow_forks_pruned_block() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -1157,6 +1509,7 @@ async fn follow_report_multiple_pruned_block() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
@@ -1341,6 +1694,7 @@ async fn follow_finalized_before_new_block() {
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
 		MAX_PINNED_BLOCKS,
+		MAX_PAGINATION_LIMIT,
 	)
 	.into_rpc();
 
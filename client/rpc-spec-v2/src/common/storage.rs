@@ -0,0 +1,217 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage queries for the RPC-V2 spec.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use sc_client_api::{Backend, ChildInfo, MerkleValue, StorageKey, StorageProvider};
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys};
+use sp_runtime::traits::Block as BlockT;
+
+use super::events::{StorageResult, StorageResultType};
+
+/// Call into the storage of blocks.
+pub struct Storage<Client, Block, BE> {
+	/// Substrate client.
+	client: Arc<Client>,
+	_phandom: PhantomData<(BE, Block)>,
+}
+
+impl<Client, Block, BE> Storage<Client, Block, BE> {
+	/// Constructs a new [`Storage`].
+	pub fn new(client: Arc<Client>) -> Self {
+		Self { client, _phandom: PhantomData }
+	}
+}
+
+/// Query to iterate over storage.
+pub struct QueryIter {
+	/// The key from which the iteration was started.
+	pub query_key: StorageKey,
+	/// The key after which pagination should resume.
+	pub pagination_start_key: Option<StorageKey>,
+	/// The type of the query (either value or hash).
+	pub ty: IterQueryType,
+}
+
+/// The query type of an iteration.
+#[derive(Clone, Copy)]
+pub enum IterQueryType {
+	/// Iterating over (key, value) pairs.
+	Value,
+	/// Iterating over (key, hash) pairs.
+	Hash,
+}
+
+/// The result of making a query call.
+pub type QueryResult = Result<Option<StorageResult>, String>;
+
+/// The result of iterating over keys.
+///
+/// Contains the produced items and the key from which the iteration
+/// should resume, if the descendants were not exhausted.
+pub type QueryIterResult = Result<(Vec<StorageResult>, Option<StorageKey>), String>;
+
+/// Returns `true` if the key is not prefixed by `:child_storage:` or
+/// `:child_storage:default:`.
+///
+/// The RPC-V2 spec does not allow querying the child tries through the main trie keys.
+pub fn is_key_queryable(key: &[u8]) -> bool {
+	!well_known_keys::is_default_child_storage_key(key) &&
+		!well_known_keys::is_child_storage_key(key)
+}
+
+impl<Client, Block, BE> Storage<Client, Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+	Client: StorageProvider<Block, BE> + 'static,
+{
+	/// Fetch the value from storage.
+	pub fn query_value(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_storage(hash, child_key, key)
+		} else {
+			self.client.storage(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_data| StorageResult {
+					key: format!("0x{:?}", HexDisplay::from(&key.0)),
+					result: StorageResultType::Value(format!(
+						"0x{:?}",
+						HexDisplay::from(&storage_data.0)
+					)),
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Fetch the hash of a value from storage.
+	pub fn query_hash(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_storage_hash(hash, child_key, key)
+		} else {
+			self.client.storage_hash(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_hash| StorageResult {
+					key: format!("0x{:?}", HexDisplay::from(&key.0)),
+					result: StorageResultType::Hash(format!(
+						"0x{:?}",
+						HexDisplay::from(&storage_hash.as_ref())
+					)),
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Fetch the closest merkle value.
+	pub fn query_merkle_value(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_closest_merkle_value(hash, child_key, key)
+		} else {
+			self.client.closest_merkle_value(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_data| {
+					let result = match &storage_data {
+						MerkleValue::Node(data) => data.as_slice(),
+						MerkleValue::Hash(hash) => hash.as_ref(),
+					};
+
+					StorageResult {
+						key: format!("0x{:?}", HexDisplay::from(&key.0)),
+						result: StorageResultType::ClosestDescendantMerkleValue(format!(
+							"0x{:?}",
+							HexDisplay::from(&result)
+						)),
+					}
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Iterate over at most `count` descendants of the provided key.
+	///
+	/// The iteration starts after the `pagination_start_key` of the query, if provided.
+	pub fn query_iter_pagination(
+		&self,
+		query: QueryIter,
+		hash: Block::Hash,
+		child_key: Option<&ChildInfo>,
+		count: usize,
+	) -> QueryIterResult {
+		let QueryIter { query_key, pagination_start_key, ty } = query;
+
+		let keys_iter = if let Some(child_key) = child_key {
+			self.client.child_storage_keys(
+				hash,
+				child_key.to_owned(),
+				Some(&query_key),
+				pagination_start_key.as_ref(),
+			)
+		} else {
+			self.client.storage_keys(hash, Some(&query_key), pagination_start_key.as_ref())
+		}
+		.map_err(|err| err.to_string())?;
+
+		let mut result = Vec::with_capacity(count);
+		let mut last_key = None;
+
+		for key in keys_iter {
+			if result.len() == count {
+				// There are more descendants, resume from the last reported key.
+				return Ok((result, last_key))
+			}
+
+			let response = match ty {
+				IterQueryType::Value => self.query_value(hash, &key, child_key),
+				IterQueryType::Hash => self.query_hash(hash, &key, child_key),
+			}?;
+
+			if let Some(value) = response {
+				result.push(value);
+			}
+			last_key = Some(key);
+		}
+
+		Ok((result, None))
+	}
+}
@@ -23,8 +23,10 @@
 #![warn(missing_docs)]
 #![deny(unused_crate_dependencies)]
 
+pub mod archive;
 pub mod chain_head;
 pub mod chain_spec;
+pub mod common;
 pub mod transaction;
 
 /// Task executor that is being used by RPC subscriptions.
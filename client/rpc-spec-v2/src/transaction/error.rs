@@ -21,6 +21,10 @@
 //! Errors are interpreted as transaction events for subscriptions.
 
 use crate::transaction::event::{TransactionError, TransactionEvent};
+use jsonrpsee::{
+	core::Error as RpcError,
+	types::error::{CallError, ErrorObject},
+};
 use sc_transaction_pool_api::error::Error as PoolError;
 use sp_runtime::transaction_validity::InvalidTransaction;
 
@@ -72,6 +76,11 @@ impl<Hash> From<Error> for TransactionEvent<Hash> {
 						old, new
 					),
 				}),
+			Error::Pool(PoolError::TooManyReplacements) =>
+				TransactionEvent::Invalid(TransactionError {
+					error: "The transaction already in the pool has been replaced too many times"
+						.into(),
+				}),
 			Error::Pool(PoolError::CycleDetected) => TransactionEvent::Invalid(TransactionError {
 				error: "The transaction contains a cyclic dependency".into(),
 			}),
@@ -98,3 +107,41 @@ impl<Hash> From<Error> for TransactionEvent<Hash> {
 		}
 	}
 }
+
+/// The transaction broadcast errors.
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorBroadcast {
+	/// The provided operation ID is invalid.
+	#[error("Invalid operation id")]
+	InvalidOperationID,
+	/// The provided extrinsic cannot be decoded.
+	#[error("Extrinsic has invalid format: {0}")]
+	BadFormat(String),
+}
+
+/// Extrinsic has an invalid format.
+///
+/// # Note
+///
+/// This is similar to the old `author` API error code.
+pub(crate) const BAD_FORMAT: i32 = 1001;
+/// The provided operation ID is invalid.
+const INVALID_OPERATION_ID: i32 = 1002;
+
+impl From<ErrorBroadcast> for ErrorObject<'static> {
+	fn from(e: ErrorBroadcast) -> Self {
+		let msg = e.to_string();
+
+		match e {
+			ErrorBroadcast::InvalidOperationID =>
+				ErrorObject::owned(INVALID_OPERATION_ID, msg, None::<()>),
+			ErrorBroadcast::BadFormat(_) => ErrorObject::owned(BAD_FORMAT, msg, None::<()>),
+		}
+	}
+}
+
+impl From<ErrorBroadcast> for RpcError {
+	fn from(e: ErrorBroadcast) -> Self {
+		CallError::Custom(e.into()).into()
+	}
+}
@@ -0,0 +1,124 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use codec::Encode;
+use jsonrpsee::{core::error::Error, types::error::CallError, RpcModule};
+use sc_transaction_pool::{BasicPool, FullChainApi};
+use sc_transaction_pool_api::TransactionPool;
+use sp_core::{hexdisplay::HexDisplay, testing::TaskExecutor};
+use std::{sync::Arc, time::Duration};
+use substrate_test_runtime::Transfer;
+use substrate_test_runtime_client::{prelude::*, runtime::Extrinsic, Backend, Client};
+
+type Block = substrate_test_runtime_client::runtime::Block;
+type FullTransactionPool = BasicPool<FullChainApi<Client<Backend>, Block>, Block>;
+
+fn setup_api(
+	max_ongoing_broadcasts: usize,
+) -> (Arc<FullTransactionPool>, RpcModule<TransactionBroadcast<FullTransactionPool, Client<Backend>>>)
+{
+	let client = Arc::new(TestClientBuilder::new().build());
+	let spawner = TaskExecutor::new();
+	let pool = BasicPool::new_full(Default::default(), true.into(), None, spawner, client.clone());
+
+	let api = TransactionBroadcast::new(
+		client,
+		pool.clone(),
+		Arc::new(TaskExecutor::default()),
+		max_ongoing_broadcasts,
+	)
+	.into_rpc();
+
+	(pool, api)
+}
+
+fn uxt(nonce: u64) -> String {
+	let tx = Transfer {
+		amount: Default::default(),
+		nonce,
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Bob.into(),
+	};
+	let tx: Extrinsic = tx.into_signed_tx();
+	format!("0x{}", HexDisplay::from(&tx.encode()))
+}
+
+#[tokio::test]
+async fn tx_broadcast_enters_pool() {
+	let (pool, api) = setup_api(8);
+
+	let operation_id: Option<String> =
+		api.call("transaction_unstable_broadcast", [uxt(0)]).await.unwrap();
+	let operation_id = operation_id.unwrap();
+	assert_eq!(operation_id.len(), 16);
+	assert!(operation_id.chars().all(|c| c.is_ascii_alphanumeric()));
+
+	tokio::time::timeout(Duration::from_secs(60), async {
+		while pool.status().ready != 1 {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+
+	let _: () = api.call("transaction_unstable_stop", [&operation_id]).await.unwrap();
+
+	// The operation is no longer known.
+	let err = api
+		.call::<_, serde_json::Value>("transaction_unstable_stop", [&operation_id])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 1002 && err.message().contains("Invalid operation id")
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_invalid_format() {
+	let (_pool, api) = setup_api(8);
+
+	let err = api
+		.call::<_, serde_json::Value>("transaction_unstable_broadcast", ["0xdeadbeef"])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 1001 && err.message().contains("Extrinsic has invalid format")
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_is_limited() {
+	let (_pool, api) = setup_api(2);
+
+	let first: Option<String> = api.call("transaction_unstable_broadcast", [uxt(0)]).await.unwrap();
+	let second: Option<String> =
+		api.call("transaction_unstable_broadcast", [uxt(1)]).await.unwrap();
+	let (first, second) = (first.unwrap(), second.unwrap());
+	assert_ne!(first, second);
+
+	// The server cannot handle more broadcasts.
+	let third: Option<String> = api.call("transaction_unstable_broadcast", [uxt(2)]).await.unwrap();
+	assert!(third.is_none());
+
+	// Stopping a broadcast makes room for a new one.
+	let _: () = api.call("transaction_unstable_stop", [&first]).await.unwrap();
+	let third: Option<String> = api.call("transaction_unstable_broadcast", [uxt(2)]).await.unwrap();
+	assert!(third.is_some());
+}
@@ -21,7 +21,7 @@
 use crate::{
 	transaction::{
 		api::TransactionApiServer,
-		error::Error,
+		error::{Error, BAD_FORMAT},
 		event::{
 			TransactionBlock, TransactionBroadcasted, TransactionDropped, TransactionError,
 			TransactionEvent,
@@ -73,14 +73,7 @@ impl<Pool, Client> Transaction<Pool, Client> {
 /// Possibly in the future we could allow opt-in for special treatment
 /// of such transactions, so that the block authors can inject
 /// some unique transactions via RPC and have them included in the pool.
-const TX_SOURCE: TransactionSource = TransactionSource::External;
-
-/// Extrinsic has an invalid format.
-///
-/// # Note
-///
-/// This is similar to the old `author` API error code.
-const BAD_FORMAT: i32 = 1001;
+pub(crate) const TX_SOURCE: TransactionSource = TransactionSource::External;
 
 #[async_trait]
 impl<Pool, Client> TransactionApiServer<BlockHash<Pool>> for Transaction<Pool, Client>
@@ -197,6 +190,9 @@ impl TransactionState {
 			TransactionStatus::Usurped(_) => Some(TransactionEvent::Invalid(TransactionError {
 				error: "Extrinsic was rendered invalid by another extrinsic".into(),
 			})),
+			TransactionStatus::Replaced(_) => Some(TransactionEvent::Invalid(TransactionError {
+				error: "Extrinsic was replaced by another extrinsic with a higher priority".into(),
+			})),
 			TransactionStatus::Dropped => Some(TransactionEvent::Invalid(TransactionError {
 				error: "Extrinsic dropped from the pool due to exceeding limits".into(),
 			})),
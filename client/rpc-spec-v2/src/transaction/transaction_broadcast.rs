@@ -0,0 +1,245 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! API implementation for broadcasting transactions.
+
+use crate::{
+	transaction::{
+		api::TransactionBroadcastApiServer, error::ErrorBroadcast, transaction::TX_SOURCE,
+	},
+	SubscriptionTaskExecutor,
+};
+use codec::Decode;
+use futures::{
+	future::{AbortHandle, Abortable},
+	FutureExt, Stream, StreamExt,
+};
+use jsonrpsee::{
+	core::{id_providers::RandomStringIdProvider, traits::IdProvider, RpcResult},
+	types::SubscriptionId,
+};
+use log::debug;
+use parking_lot::RwLock;
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::{
+	error::{Error as PoolError, IntoPoolError},
+	TransactionFor, TransactionPool, TransactionStatus,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::generic;
+use std::{collections::HashMap, sync::Arc};
+
+/// Log target for the transaction broadcast.
+const LOG_TARGET: &str = "rpc-spec-v2::transaction";
+
+/// An API for transaction broadcast RPC calls.
+pub struct TransactionBroadcast<Pool, Client> {
+	/// Substrate client.
+	client: Arc<Client>,
+	/// Transactions pool.
+	pool: Arc<Pool>,
+	/// Executor to spawn subscriptions.
+	executor: SubscriptionTaskExecutor,
+	/// The brodcast operation IDs.
+	broadcast_ids: Arc<RwLock<HashMap<String, AbortHandle>>>,
+	/// Generates the operation IDs, the same way as the server generates subscription IDs.
+	id_provider: RandomStringIdProvider,
+	/// The maximum number of broadcasts that run at the same time.
+	max_ongoing_broadcasts: usize,
+}
+
+impl<Pool, Client> TransactionBroadcast<Pool, Client> {
+	/// Creates a new [`TransactionBroadcast`].
+	pub fn new(
+		client: Arc<Client>,
+		pool: Arc<Pool>,
+		executor: SubscriptionTaskExecutor,
+		max_ongoing_broadcasts: usize,
+	) -> Self {
+		TransactionBroadcast {
+			client,
+			pool,
+			executor,
+			broadcast_ids: Default::default(),
+			id_provider: RandomStringIdProvider::new(16),
+			max_ongoing_broadcasts,
+		}
+	}
+
+	/// Register a new broadcast under a fresh operation ID.
+	///
+	/// Returns `None` if the maximum number of broadcasts are already running.
+	fn register_broadcast(&self, abort_handle: AbortHandle) -> Option<String> {
+		let mut broadcast_ids = self.broadcast_ids.write();
+		if broadcast_ids.len() >= self.max_ongoing_broadcasts {
+			return None
+		}
+		loop {
+			let id = match self.id_provider.next_id() {
+				SubscriptionId::Str(id) => id.into_owned(),
+				SubscriptionId::Num(id) => id.to_string(),
+			};
+			if !broadcast_ids.contains_key(&id) {
+				broadcast_ids.insert(id.clone(), abort_handle);
+				return Some(id)
+			}
+		}
+	}
+}
+
+/// The outcome of one submission of the transaction to the pool.
+enum SubmitOutcome {
+	/// The transaction was finalized or became invalid, the broadcast is complete.
+	Completed,
+	/// The transaction left the pool without being finalized and must be resubmitted.
+	Resubmit,
+}
+
+impl<Pool, Client> TransactionBroadcastApiServer for TransactionBroadcast<Pool, Client>
+where
+	Pool: TransactionPool + Sync + Send + 'static,
+	Client: HeaderBackend<Pool::Block> + BlockchainEvents<Pool::Block> + Send + Sync + 'static,
+{
+	fn broadcast(&self, bytes: Bytes) -> RpcResult<Option<String>> {
+		let decoded_extrinsic = TransactionFor::<Pool>::decode(&mut &bytes[..])
+			.map_err(|e| ErrorBroadcast::BadFormat(e.to_string()))?;
+
+		let (abort_handle, abort_registration) = AbortHandle::new_pair();
+		let Some(operation_id) = self.register_broadcast(abort_handle) else {
+			debug!(target: LOG_TARGET, "[broadcast] Too many ongoing broadcasts");
+			return Ok(None)
+		};
+
+		let client = self.client.clone();
+		let pool = self.pool.clone();
+		let log_id = operation_id.clone();
+
+		let broadcast_fut = async move {
+			// The new best blocks are used to retry the submission of transactions
+			// that left the pool without being finalized.
+			let mut best_block_import_stream = client
+				.import_notification_stream()
+				.filter_map(|notification| async move {
+					notification.is_new_best.then_some(notification.hash)
+				})
+				.boxed();
+
+			loop {
+				let best_block_hash = client.info().best_hash;
+				let submit = pool
+					.submit_and_watch(
+						&generic::BlockId::hash(best_block_hash),
+						TX_SOURCE,
+						decoded_extrinsic.clone(),
+					)
+					.await;
+
+				let outcome = match submit {
+					Ok(stream) => watch_transaction(stream).await,
+					Err(error) => match error.into_pool_error() {
+						// The transaction will never become valid.
+						Ok(PoolError::InvalidTransaction(_)) => SubmitOutcome::Completed,
+						// The pool rejected the transaction for now (ie temporarily banned,
+						// already imported, pool limits), retry on the next best block.
+						Ok(error) => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Failed to submit transaction: {}",
+								log_id,
+								error
+							);
+							SubmitOutcome::Resubmit
+						},
+						Err(error) => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Failed to verify transaction: {}",
+								log_id,
+								error
+							);
+							SubmitOutcome::Completed
+						},
+					},
+				};
+
+				if let SubmitOutcome::Completed = outcome {
+					debug!(target: LOG_TARGET, "[broadcast][id={:?}] Broadcast completed", log_id);
+					return
+				}
+
+				// Wait for a new best block before resubmitting.
+				if best_block_import_stream.next().await.is_none() {
+					return
+				}
+			}
+		};
+
+		let broadcast_fut = Abortable::new(broadcast_fut, abort_registration);
+
+		let broadcast_ids = self.broadcast_ids.clone();
+		let drop_id = operation_id.clone();
+		let fut = async move {
+			let _ = broadcast_fut.await;
+			// The broadcast has finished or it was aborted.
+			broadcast_ids.write().remove(&drop_id);
+		};
+
+		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+
+		Ok(Some(operation_id))
+	}
+
+	fn stop_broadcast(&self, operation_id: String) -> RpcResult<()> {
+		let Some(abort_handle) = self.broadcast_ids.write().remove(&operation_id) else {
+			return Err(ErrorBroadcast::InvalidOperationID.into())
+		};
+
+		abort_handle.abort();
+
+		Ok(())
+	}
+}
+
+/// Watch the transaction status events until the transaction leaves the pool.
+async fn watch_transaction<Hash, BlockHash>(
+	mut stream: impl Stream<Item = TransactionStatus<Hash, BlockHash>> + Unpin,
+) -> SubmitOutcome {
+	while let Some(event) = stream.next().await {
+		match event {
+			// The transaction is part of the finalized chain.
+			TransactionStatus::Finalized(_) => return SubmitOutcome::Completed,
+			// The transaction can never be included.
+			TransactionStatus::Invalid |
+			TransactionStatus::Usurped(_) |
+			TransactionStatus::Replaced(_) => return SubmitOutcome::Completed,
+			// The transaction was removed from the pool, or the pool stopped tracking it,
+			// before it was finalized.
+			TransactionStatus::Dropped | TransactionStatus::FinalityTimeout(_) =>
+				return SubmitOutcome::Resubmit,
+			TransactionStatus::Ready |
+			TransactionStatus::Future |
+			TransactionStatus::Broadcast(_) |
+			TransactionStatus::InBlock(_) |
+			TransactionStatus::Retracted(_) => (),
+		}
+	}
+
+	// The pool stopped reporting events without a final status.
+	SubmitOutcome::Resubmit
+}
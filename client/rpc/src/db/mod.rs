@@ -0,0 +1,59 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Substrate database API.
+
+#[cfg(test)]
+mod tests;
+
+use jsonrpsee::core::{async_trait, Error as JsonRpseeError, RpcResult};
+use sc_client_api::Backend;
+/// Re-export the API for backward compatibility.
+pub use sc_rpc_api::db::*;
+use sc_rpc_api::{db::error::Error, DenyUnsafe};
+use sp_runtime::traits::Block as BlockT;
+use std::{marker::PhantomData, path::PathBuf, sync::Arc};
+
+/// Database API. All methods are unsafe.
+pub struct Db<Block, BE> {
+	backend: Arc<BE>,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, BE> Db<Block, BE> {
+	/// Create new instance of the database API.
+	pub fn new(backend: Arc<BE>, deny_unsafe: DenyUnsafe) -> Self {
+		Db { backend, deny_unsafe, _phantom: PhantomData }
+	}
+}
+
+#[async_trait]
+impl<Block, BE> DbApiServer for Db<Block, BE>
+where
+	Block: BlockT,
+	BE: Backend<Block> + 'static,
+{
+	fn backup(&self, path: PathBuf) -> RpcResult<()> {
+		self.deny_unsafe.check_if_safe()?;
+
+		log::info!("Writing database backup to {:?}", path);
+		self.backend
+			.checkpoint(&path)
+			.map_err(|e| JsonRpseeError::from(Error::BackupFailed(Box::new(e))))
+	}
+}
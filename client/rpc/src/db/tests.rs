@@ -0,0 +1,51 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use jsonrpsee::types::error::CallError;
+use substrate_test_runtime_client::{
+	runtime::Block, DefaultTestClientBuilderExt, TestClientBuilder,
+};
+
+#[test]
+fn backup_reports_unsupported_backend() {
+	let backend = TestClientBuilder::new().backend();
+	let db = <Db<Block, _>>::new(backend, DenyUnsafe::No);
+
+	// The test backend keeps its database in memory, which can't be checkpointed.
+	assert_matches!(
+		db.backup(PathBuf::from("backup")),
+		Err(JsonRpseeError::Call(CallError::Custom(err))) => {
+			assert_eq!(err.code(), 7001)
+		}
+	);
+}
+
+#[test]
+fn backup_is_considered_unsafe() {
+	let backend = TestClientBuilder::new().backend();
+	let db = <Db<Block, _>>::new(backend, DenyUnsafe::Yes);
+
+	assert_matches!(
+		db.backup(PathBuf::from("backup")),
+		Err(JsonRpseeError::Call(CallError::Custom(err))) => {
+			assert_eq!(err.message(), "RPC call is unsafe to be called externally")
+		}
+	);
+}
@@ -33,6 +33,7 @@ pub use sc_rpc_api::DenyUnsafe;
 
 pub mod author;
 pub mod chain;
+pub mod db;
 pub mod dev;
 pub mod offchain;
 pub mod state;
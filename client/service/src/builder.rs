@@ -31,14 +31,15 @@ use log::info;
 use prometheus_endpoint::Registry;
 use sc_chain_spec::get_extension;
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
+	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, AuxStore, BadBlocks,
 	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider, UsageProvider,
 };
 use sc_client_db::{Backend, DatabaseSettings};
 use sc_consensus::import_queue::ImportQueue;
 use sc_executor::{
 	sp_wasm_interface::HostFunctions, HeapAllocStrategy, NativeElseWasmExecutor,
-	NativeExecutionDispatch, RuntimeVersionOf, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY,
+	NativeExecutionDispatch, PrecompileRuntime, RuntimeVersionOf, WasmExecutor,
+	DEFAULT_HEAP_ALLOC_STRATEGY,
 };
 use sc_keystore::LocalKeystore;
 use sc_network::{config::SyncMode, NetworkService, NetworkStateInfo, NetworkStatusProvider};
@@ -53,12 +54,17 @@ use sc_network_sync::{
 use sc_rpc::{
 	author::AuthorApiServer,
 	chain::ChainApiServer,
+	db::DbApiServer,
 	offchain::OffchainApiServer,
 	state::{ChildStateApiServer, StateApiServer},
 	system::SystemApiServer,
 	DenyUnsafe, SubscriptionTaskExecutor,
 };
-use sc_rpc_spec_v2::{chain_head::ChainHeadApiServer, transaction::TransactionApiServer};
+use sc_rpc_spec_v2::{
+	archive::ArchiveApiServer,
+	chain_head::ChainHeadApiServer,
+	transaction::{TransactionApiServer, TransactionBroadcastApiServer},
+};
 use sc_telemetry::{telemetry, ConnectionMessage, Telemetry, TelemetryHandle, SUBSTRATE_INFO};
 use sc_transaction_pool_api::{MaintainedTransactionPool, TransactionPool};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
@@ -393,6 +399,28 @@ where
 	offchain_workers
 }
 
+/// Spawn a task that compiles the runtime code announced under `:pending_code` ahead of its
+/// enactment.
+///
+/// `executor` should be the executor the client was built with, so that the compiled runtime ends
+/// up in the cache the client is using.
+pub fn spawn_runtime_precompiler<TBl, TBackend, TCl, TExec>(
+	spawn_handle: SpawnTaskHandle,
+	client: Arc<TCl>,
+	executor: TExec,
+) where
+	TBl: BlockT,
+	TBackend: sc_client_api::backend::Backend<TBl> + 'static,
+	TCl: BlockchainEvents<TBl> + StorageProvider<TBl, TBackend> + Send + Sync + 'static,
+	TExec: PrecompileRuntime + Send + 'static,
+{
+	spawn_handle.spawn_blocking(
+		"runtime-precompiler",
+		None,
+		crate::precompile::precompile_pending_code(client, executor),
+	);
+}
+
 /// Spawn the tasks that are required to run a node.
 pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	params: SpawnTasksParams<TBl, TCl, TExPool, TRpc, TBackend>,
@@ -667,19 +695,48 @@ where
 	)
 	.into_rpc();
 
+	// Maximum number of `transaction_unstable_broadcast` operations running at once.
+	const MAX_ONGOING_BROADCASTS: usize = 512;
+	let transaction_broadcast_v2 = sc_rpc_spec_v2::transaction::TransactionBroadcast::new(
+		client.clone(),
+		transaction_pool.clone(),
+		task_executor.clone(),
+		MAX_ONGOING_BROADCASTS,
+	)
+	.into_rpc();
+
 	// Maximum pinned blocks per connection.
 	// This number is large enough to consider immediate blocks,
 	// but it will change to facilitate adequate limits for the pinning API.
 	const MAX_PINNED_BLOCKS: usize = 4096;
+	// Maximum number of storage items reported by `chainHead_storage` before
+	// the client must call `chainHead_continue`.
+	const MAX_STORAGE_ITEMS: usize = 5;
 	let chain_head_v2 = sc_rpc_spec_v2::chain_head::ChainHead::new(
 		client.clone(),
 		backend.clone(),
 		task_executor.clone(),
 		client.info().genesis_hash,
 		MAX_PINNED_BLOCKS,
+		MAX_STORAGE_ITEMS,
 	)
 	.into_rpc();
 
+	// The archive methods are exposed only by nodes that keep the full history of
+	// finalized blocks and their state.
+	let is_archive_node = config.state_pruning.as_ref().map_or(false, |p| p.is_archive()) &&
+		config.blocks_pruning.is_archive();
+	if is_archive_node {
+		let archive_v2 = sc_rpc_spec_v2::archive::Archive::new(
+			client.clone(),
+			backend.clone(),
+			client.info().genesis_hash,
+			Default::default(),
+		)
+		.into_rpc();
+		rpc_api.merge(archive_v2).map_err(|e| Error::Application(e.into()))?;
+	}
+
 	let author = sc_rpc::author::Author::new(
 		client.clone(),
 		transaction_pool,
@@ -691,6 +748,8 @@ where
 
 	let system = sc_rpc::system::System::new(system_info, system_rpc_tx, deny_unsafe).into_rpc();
 
+	let db = sc_rpc::db::Db::<TBl, _>::new(backend.clone(), deny_unsafe).into_rpc();
+
 	if let Some(storage) = backend.offchain_storage() {
 		let offchain = sc_rpc::offchain::Offchain::new(storage, deny_unsafe).into_rpc();
 
@@ -699,12 +758,16 @@ where
 
 	// Part of the RPC v2 spec.
 	rpc_api.merge(transaction_v2).map_err(|e| Error::Application(e.into()))?;
+	rpc_api
+		.merge(transaction_broadcast_v2)
+		.map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(chain_head_v2).map_err(|e| Error::Application(e.into()))?;
 
 	// Part of the old RPC spec.
 	rpc_api.merge(chain).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(author).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(system).map_err(|e| Error::Application(e.into()))?;
+	rpc_api.merge(db).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(state).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(child_state).map_err(|e| Error::Application(e.into()))?;
 	// Additional [`RpcModule`]s defined in the node to fit the specific blockchain
@@ -755,6 +818,7 @@ where
 		+ ProofProvider<TBl>
 		+ HeaderBackend<TBl>
 		+ BlockchainEvents<TBl>
+		+ AuxStore
 		+ 'static,
 	TExPool: TransactionPool<Block = TBl, Hash = <TBl as BlockT>::Hash> + 'static,
 	TImpQu: ImportQueue<TBl> + 'static,
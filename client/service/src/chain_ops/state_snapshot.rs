@@ -0,0 +1,316 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Verifiable state snapshots.
+//!
+//! A snapshot is a stream consisting of a [`StateSnapshotHeader`] followed by a sequence of
+//! compact trie proofs, each of them covering a range of the state of the snapshot block. The
+//! stream is terminated by an encoded `None`. Every chunk is checked against the state root of the
+//! block header when importing, so a snapshot can be fetched from an untrusted source as long as
+//! the block hash is checked against a trusted one, which the import requires.
+//!
+//! The expected block hash is the only root of trust: the finality proof carried by a snapshot is
+//! stored with the block, but not verified, as that would require the authority set of the
+//! snapshot block, which a node bootstrapping from the snapshot does not know.
+
+use crate::error::Error;
+use codec::{Decode, Encode, IoReader as CodecIoReader};
+use futures::{future, prelude::*};
+use log::{info, warn};
+use sc_client_api::{BlockBackend, CompactProof, HeaderBackend, ProofProvider};
+use sc_consensus::{
+	import_queue::{BlockImportError, BlockImportStatus, ImportQueue, IncomingBlock, Link},
+	ImportedState,
+};
+use smallvec::SmallVec;
+use sp_consensus::BlockOrigin;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+	Justifications,
+};
+use std::{
+	collections::HashMap,
+	io::{Read, Write},
+	pin::Pin,
+	sync::Arc,
+	task::Poll,
+};
+
+/// Magic bytes every state snapshot starts with.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"sstn";
+
+/// Version of the snapshot format.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Maximum size of the trie nodes in a single snapshot chunk.
+const CHUNK_SIZE_LIMIT: usize = 8 * 1024 * 1024;
+
+/// Number of chunks between two progress reports.
+const CHUNKS_BETWEEN_UPDATES: u64 = 64;
+
+/// Leading part of a state snapshot.
+#[derive(Encode, Decode)]
+pub struct StateSnapshotHeader<B: BlockT> {
+	/// Hash of the genesis block of the chain the snapshot was taken from.
+	pub genesis_hash: B::Hash,
+	/// Header of the snapshot block.
+	pub header: B::Header,
+	/// Body of the snapshot block, if it was available.
+	pub body: Option<Vec<B::Extrinsic>>,
+	/// Finality proof of the snapshot block, if it was available.
+	pub justifications: Option<Justifications>,
+}
+
+/// Export the state at `hash` as a snapshot into `output`.
+///
+/// The state is written in chunks of at most [`CHUNK_SIZE_LIMIT`] bytes of trie nodes. Each
+/// chunk is verified against the state root of the block before being written.
+pub fn export_state_snapshot<B, C>(
+	client: Arc<C>,
+	hash: B::Hash,
+	mut output: impl Write,
+) -> Result<(), Error>
+where
+	C: HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+	B: BlockT,
+{
+	let header = client
+		.header(hash)?
+		.ok_or_else(|| Error::Other(format!("Unknown block {:?}", hash)))?;
+	let state_root = *header.state_root();
+	let number = *header.number();
+	let justifications = client.justifications(hash)?;
+	if justifications.is_none() {
+		warn!("No finality proof available for block #{} ({:?})", number, hash);
+	}
+
+	output.write_all(&SNAPSHOT_MAGIC)?;
+	output.write_all(&SNAPSHOT_VERSION.encode())?;
+	let snapshot_header = StateSnapshotHeader::<B> {
+		genesis_hash: client.info().genesis_hash,
+		header,
+		body: client.block_body(hash)?,
+		justifications,
+	};
+	output.write_all(&snapshot_header.encode())?;
+
+	let mut start_keys = SmallVec::<[Vec<u8>; 2]>::new();
+	let mut chunks = 0u64;
+	let mut entries = 0u64;
+	loop {
+		let (proof, count) =
+			client.read_proof_collection(hash, start_keys.as_slice(), CHUNK_SIZE_LIMIT)?;
+		let (values, completed) =
+			client.verify_range_proof(state_root, proof.clone(), start_keys.as_slice())?;
+		output.write_all(&Some(proof).encode())?;
+
+		chunks += 1;
+		entries += count as u64;
+		if chunks % CHUNKS_BETWEEN_UPDATES == 0 {
+			info!("#{} exported {} chunks, {} state entries", number, chunks, entries);
+		}
+
+		if completed == 0 {
+			break
+		}
+		if !values.update_last_key(completed, &mut start_keys) {
+			return Err(Error::Other(format!(
+				"Error updating the state cursor at depth {}",
+				completed
+			)))
+		}
+	}
+	output.write_all(&None::<CompactProof>.encode())?;
+	output.flush()?;
+
+	info!(
+		"🎉 Exported state of block #{} ({:?}): {} chunks, {} entries",
+		number, hash, chunks, entries
+	);
+	Ok(())
+}
+
+/// Read and verify a state snapshot, returning the block it should be imported as, together with
+/// its state.
+fn read_state_snapshot<B, C>(
+	client: &C,
+	input: impl Read,
+	expected_hash: B::Hash,
+) -> Result<IncomingBlock<B>, Error>
+where
+	C: HeaderBackend<B> + ProofProvider<B>,
+	B: BlockT,
+{
+	let mut reader = CodecIoReader(input);
+	let decode_err = |what: &str, e: codec::Error| {
+		Error::Other(format!("Failed to decode the snapshot {}: {}", what, e))
+	};
+
+	let magic = <[u8; 4]>::decode(&mut reader).map_err(|e| decode_err("magic", e))?;
+	if magic != SNAPSHOT_MAGIC {
+		return Err(Error::Other("Input is not a state snapshot".into()))
+	}
+	let version = u32::decode(&mut reader).map_err(|e| decode_err("version", e))?;
+	if version != SNAPSHOT_VERSION {
+		return Err(Error::Other(format!("Unsupported state snapshot version {}", version)))
+	}
+
+	let StateSnapshotHeader { genesis_hash, header, body, justifications } =
+		StateSnapshotHeader::<B>::decode(&mut reader).map_err(|e| decode_err("header", e))?;
+	let hash = header.hash();
+	let number = *header.number();
+	let state_root = *header.state_root();
+
+	if genesis_hash != client.info().genesis_hash {
+		return Err(Error::Other(format!(
+			"Snapshot belongs to a different chain with genesis {:?}",
+			genesis_hash
+		)))
+	}
+	if hash != expected_hash {
+		return Err(Error::Other(format!(
+			"Snapshot block {:?} does not match the expected block {:?}",
+			hash, expected_hash
+		)))
+	}
+
+	info!("Importing state of block #{} ({:?})", number, hash);
+	let mut start_keys = SmallVec::<[Vec<u8>; 2]>::new();
+	// Key values of every trie, by their root, and the keys of a child trie in the top trie.
+	let mut state = HashMap::<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>::new();
+	let mut chunks = 0u64;
+	let mut complete = false;
+	while let Some(proof) =
+		Option::<CompactProof>::decode(&mut reader).map_err(|e| decode_err("chunk", e))?
+	{
+		if complete {
+			return Err(Error::Other("Snapshot contains data past the end of the state".into()))
+		}
+		let (values, completed) =
+			client.verify_range_proof(state_root, proof, start_keys.as_slice())?;
+		complete = completed == 0;
+		if !complete && !values.update_last_key(completed, &mut start_keys) {
+			return Err(Error::Other(format!(
+				"Error updating the state cursor at depth {}",
+				completed
+			)))
+		}
+
+		for level in values.0 {
+			let mut key_values = level.key_values;
+			if level.state_root.is_empty() {
+				// Child trie roots are recalculated on import.
+				key_values.retain(|(key, root)| {
+					if well_known_keys::is_child_storage_key(key) {
+						state.entry(root.clone()).or_default().1.push(key.clone());
+						false
+					} else {
+						true
+					}
+				});
+			}
+			state.entry(level.state_root).or_default().0.extend(key_values);
+		}
+
+		chunks += 1;
+		if chunks % CHUNKS_BETWEEN_UPDATES == 0 {
+			info!("#{} imported {} chunks", number, chunks);
+		}
+	}
+	if !complete {
+		return Err(Error::Other("Snapshot ended before the end of the state".into()))
+	}
+
+	Ok(IncomingBlock {
+		hash,
+		header: Some(header),
+		body,
+		indexed_body: None,
+		justifications,
+		origin: None,
+		allow_missing_state: true,
+		import_existing: true,
+		skip_execution: true,
+		state: Some(ImportedState { block: hash, state: state.into() }),
+	})
+}
+
+/// Import a state snapshot read from `input` through the import queue.
+///
+/// The state is verified chunk by chunk and kept in memory, then the snapshot block is imported
+/// with it like a block downloaded by state sync, after which the node can continue syncing from
+/// it. The snapshot is rejected unless it was taken at `expected_hash`.
+pub fn import_state_snapshot<B, IQ, C>(
+	client: Arc<C>,
+	mut import_queue: IQ,
+	input: impl Read + Send + 'static,
+	expected_hash: B::Hash,
+) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
+where
+	C: HeaderBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+	B: BlockT,
+	IQ: ImportQueue<B> + 'static,
+{
+	struct WaitLink {
+		imported: bool,
+		error: Option<String>,
+	}
+
+	impl<B: BlockT> Link<B> for WaitLink {
+		fn blocks_processed(
+			&mut self,
+			imported: usize,
+			_num_expected_blocks: usize,
+			results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+		) {
+			self.imported |= imported > 0;
+			for result in results {
+				if let (Err(err), hash) = result {
+					self.error = Some(format!("Error importing block {:?}: {}", hash, err));
+				}
+			}
+		}
+	}
+
+	async move {
+		let block = read_state_snapshot(&*client, input, expected_hash)?;
+		let hash = block.hash;
+		import_queue.service_ref().import_blocks(BlockOrigin::File, vec![block]);
+
+		let mut link = WaitLink { imported: false, error: None };
+		future::poll_fn(|cx| {
+			import_queue.poll_actions(cx, &mut link);
+			if let Some(err) = link.error.take() {
+				Poll::Ready(Err(Error::Other(err)))
+			} else if link.imported {
+				Poll::Ready(Ok(()))
+			} else {
+				Poll::Pending
+			}
+		})
+		.await?;
+
+		info!(
+			"🎉 Imported state snapshot at {:?}, best is now #{}",
+			hash,
+			client.info().best_number
+		);
+		Ok(())
+	}
+	.boxed()
+}
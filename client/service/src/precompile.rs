@@ -0,0 +1,174 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compiles runtimes announced under [`well_known_keys::PENDING_CODE`] ahead of their enactment.
+//!
+//! Without this the runtime is compiled by the first call into it after `:code` changed, which
+//! stalls the import or the authoring of the first block after every runtime upgrade.
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use log::{debug, warn};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_executor::PrecompileRuntime;
+use sp_core::{
+	storage::{well_known_keys, StorageKey},
+	traits::{RuntimeCode, WrappedRuntimeCode},
+};
+use sp_runtime::traits::{Block as BlockT, Hash, HashFor};
+use sp_state_machine::BasicExternalities;
+use std::sync::Arc;
+
+/// Precompile the pending code of every new best block.
+///
+/// Meant to be run as a blocking task, as compiling a runtime can take several seconds.
+pub(crate) async fn precompile_pending_code<Block, BE, Client, E>(client: Arc<Client>, executor: E)
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: BlockchainEvents<Block> + StorageProvider<Block, BE>,
+	E: PrecompileRuntime,
+{
+	let mut import_notifications = client.import_notification_stream();
+	let mut last_code_hash = None;
+
+	while let Some(notification) = import_notifications.next().await {
+		if notification.is_new_best {
+			precompile_at(&*client, &executor, notification.hash, &mut last_code_hash);
+		}
+	}
+}
+
+/// Precompile the pending code at `hash`, unless it is the one precompiled last.
+fn precompile_at<Block, BE, Client, E>(
+	client: &Client,
+	executor: &E,
+	hash: Block::Hash,
+	last_code_hash: &mut Option<Vec<u8>>,
+) where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: StorageProvider<Block, BE>,
+	E: PrecompileRuntime,
+{
+	let code = match client.storage(hash, &StorageKey(well_known_keys::PENDING_CODE.to_vec())) {
+		Ok(Some(code)) => code.0,
+		Ok(None) => return,
+		Err(err) => {
+			warn!("Failed to read the pending runtime code at {}: {}", hash, err);
+			return
+		},
+	};
+
+	// Runtimes are cached by the hash of `:code`, which is what the code will be stored under
+	// once it is enacted.
+	let code_hash = <HashFor<Block> as Hash>::hash(&code).encode();
+	if last_code_hash.as_ref() == Some(&code_hash) {
+		return
+	}
+
+	let heap_pages = client
+		.storage(hash, &StorageKey(well_known_keys::HEAP_PAGES.to_vec()))
+		.ok()
+		.flatten()
+		.and_then(|d| Decode::decode(&mut &d.0[..]).ok());
+
+	let code_fetcher = WrappedRuntimeCode(code.into());
+	let runtime_code = RuntimeCode { code_fetcher: &code_fetcher, heap_pages, hash: code_hash };
+
+	match executor.precompile_runtime(&mut BasicExternalities::default(), &runtime_code) {
+		Ok(()) => debug!("Precompiled the pending runtime code announced at {}", hash),
+		Err(err) =>
+			warn!("Failed to precompile the pending runtime code announced at {}: {}", hash, err),
+	}
+
+	*last_code_hash = Some(runtime_code.hash);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{executor::block_on, poll};
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use sp_core::traits::Externalities;
+	use std::sync::Mutex;
+	use substrate_test_runtime_client::{prelude::*, runtime::Block};
+
+	/// Records the hashes of the runtimes it is asked to precompile.
+	#[derive(Clone, Default)]
+	struct RecordingExecutor(Arc<Mutex<Vec<Vec<u8>>>>);
+
+	impl PrecompileRuntime for RecordingExecutor {
+		fn precompile_runtime(
+			&self,
+			_: &mut dyn Externalities,
+			runtime_code: &RuntimeCode,
+		) -> sc_executor::error::Result<()> {
+			self.0.lock().unwrap().push(runtime_code.hash.clone());
+			Ok(())
+		}
+	}
+
+	fn code_hash(code: &[u8]) -> Vec<u8> {
+		<HashFor<Block> as Hash>::hash(code).encode()
+	}
+
+	#[test]
+	fn pending_code_is_precompiled_once() {
+		let mut client = Arc::new(
+			TestClientBuilder::new()
+				.add_extra_storage(well_known_keys::PENDING_CODE.to_vec(), b"new code".to_vec())
+				.build(),
+		);
+		let executor = RecordingExecutor::default();
+		let mut task = Box::pin(precompile_pending_code(client.clone(), executor.clone()));
+
+		block_on(async {
+			assert!(poll!(&mut task).is_pending());
+			for pending_code in [None, None, Some(b"newer code".to_vec())] {
+				let mut builder = client.new_block(Default::default()).unwrap();
+				if let Some(code) = pending_code {
+					builder
+						.push_storage_change(well_known_keys::PENDING_CODE.to_vec(), Some(code))
+						.unwrap();
+				}
+				let block = builder.build().unwrap().block;
+				client.import(BlockOrigin::Own, block).await.unwrap();
+				assert!(poll!(&mut task).is_pending());
+			}
+		});
+
+		assert_eq!(
+			*executor.0.lock().unwrap(),
+			vec![code_hash(b"new code"), code_hash(b"newer code")],
+		);
+	}
+
+	#[test]
+	fn nothing_is_precompiled_without_pending_code() {
+		let client = TestClientBuilder::new().build();
+		let executor = RecordingExecutor::default();
+		let mut last_code_hash = None;
+
+		precompile_at(&client, &executor, client.chain_info().best_hash, &mut last_code_hash);
+
+		assert!(executor.0.lock().unwrap().is_empty());
+		assert!(last_code_hash.is_none());
+	}
+}
@@ -40,7 +40,7 @@ use sp_runtime::{
 use sp_state_machine::{
 	backend::Backend as _, ExecutionStrategy, InMemoryBackend, OverlayedChanges, StateMachine,
 };
-use sp_storage::{ChildInfo, StorageKey};
+use sp_storage::{ChildInfo, StorageData, StorageKey};
 use sp_trie::{LayoutV0, TrieConfiguration};
 use std::{collections::HashSet, sync::Arc};
 use substrate_test_runtime::TestAPI;
@@ -2060,3 +2060,85 @@ fn use_dalek_ext_works() {
 		.verify_ed25519(a1.hash(), zero_ed_sig(), zero_ed_pub(), vec![])
 		.unwrap());
 }
+
+#[test]
+fn state_snapshot_export_and_import_works() {
+	struct FinalizingVerifier;
+
+	#[async_trait::async_trait]
+	impl sc_consensus::Verifier<Block> for FinalizingVerifier {
+		async fn verify(
+			&mut self,
+			mut block: BlockImportParams<Block, ()>,
+		) -> Result<BlockImportParams<Block, ()>, String> {
+			block.finalized = true;
+			block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+			Ok(block)
+		}
+	}
+
+	let mut source = substrate_test_runtime_client::new();
+	let mut parent = source.chain_info().genesis_hash;
+	for value in 1..=3u8 {
+		let mut builder = source.new_block_at(parent, Default::default(), false).unwrap();
+		builder.push_storage_change(vec![value], Some(vec![value; 64])).unwrap();
+		let block = builder.build().unwrap().block;
+		parent = block.hash();
+		block_on(source.import(BlockOrigin::Own, block)).unwrap();
+	}
+	ClientExt::finalize_block(&source, parent, Some((TEST_ENGINE_ID, vec![1, 2, 3]))).unwrap();
+
+	let mut snapshot = Vec::new();
+	sc_service::chain_ops::export_state_snapshot(Arc::new(source), parent, &mut snapshot).unwrap();
+
+	let target = Arc::new(substrate_test_runtime_client::new());
+	let import_queue = |client: &Arc<TestClient>| {
+		sc_consensus::BasicQueue::new(
+			FinalizingVerifier,
+			Box::new(client.clone()),
+			None,
+			&TaskExecutor::new(),
+			None,
+		)
+	};
+
+	// A snapshot taken at another block is rejected.
+	assert!(block_on(sc_service::chain_ops::import_state_snapshot(
+		target.clone(),
+		import_queue(&target),
+		std::io::Cursor::new(snapshot.clone()),
+		target.chain_info().genesis_hash,
+	))
+	.is_err());
+
+	// A truncated snapshot is rejected.
+	assert!(block_on(sc_service::chain_ops::import_state_snapshot(
+		target.clone(),
+		import_queue(&target),
+		std::io::Cursor::new(snapshot[..snapshot.len() - 1].to_vec()),
+		parent,
+	))
+	.is_err());
+	assert_eq!(target.chain_info().best_number, 0);
+
+	block_on(sc_service::chain_ops::import_state_snapshot(
+		target.clone(),
+		import_queue(&target),
+		std::io::Cursor::new(snapshot),
+		parent,
+	))
+	.unwrap();
+
+	assert_eq!(target.chain_info().best_hash, parent);
+	assert_eq!(target.chain_info().finalized_hash, parent);
+	assert_eq!(
+		target.justifications(parent).unwrap(),
+		Some(Justifications::from((TEST_ENGINE_ID, vec![1, 2, 3])))
+	);
+	for value in 1..=3u8 {
+		assert_eq!(
+			target.storage(parent, &StorageKey(vec![value])).unwrap(),
+			Some(StorageData(vec![value; 64]))
+		);
+	}
+}
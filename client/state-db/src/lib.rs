@@ -40,13 +40,18 @@
 //! # Pruning.
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each canonicalization until
 //! pruning constraints are satisfied.
+//!
+//! # Periodic snapshots.
+//! See `SnapshotIndex` for details on how the state of every Nth canonical block is kept while
+//! pruning the rest of the history.
 
 mod noncanonical;
 mod pruning;
+mod snapshot;
 #[cfg(test)]
 mod test;
 
-use codec::Codec;
+use codec::{Codec, Decode, Encode};
 use log::trace;
 use noncanonical::NonCanonicalOverlay;
 use parking_lot::RwLock;
@@ -62,6 +67,8 @@ const PRUNING_MODE: &[u8] = b"mode";
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+const PRUNING_MODE_PERIODIC_SNAPSHOTS: &[u8] = b"periodic_snapshots";
+const SNAPSHOT_PERIOD: &[u8] = b"snapshot_period";
 pub(crate) const DEFAULT_MAX_BLOCK_CONSTRAINT: u32 = 256;
 
 /// Database value type.
@@ -145,6 +152,8 @@ pub enum StateDbError {
 	BlockUnavailable,
 	/// Block record is missing from the pruning window
 	BlockMissing,
+	/// Periodic snapshots are requested for a database without reference counting.
+	SnapshotsRequireRefCounting,
 }
 
 impl<E> From<StateDbError> for Error<E> {
@@ -196,6 +205,8 @@ impl fmt::Debug for StateDbError {
 				write!(f, "Trying to get a block record from db while it is not commit to db yet")
 			},
 			Self::BlockMissing => write!(f, "Block record is missing from the pruning window"),
+			Self::SnapshotsRequireRefCounting =>
+				write!(f, "Periodic snapshots require a database with reference counting"),
 		}
 	}
 }
@@ -226,6 +237,16 @@ pub struct Constraints {
 	pub max_blocks: Option<u32>,
 }
 
+/// Periodic snapshot constraints.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SnapshotConstraints {
+	/// Pruning window for the state of the blocks that are not snapshots.
+	pub window: Constraints,
+	/// The state of every canonical block whose number is a multiple of `period` is never
+	/// pruned.
+	pub period: u32,
+}
+
 /// Pruning mode.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum PruningMode {
@@ -235,6 +256,8 @@ pub enum PruningMode {
 	ArchiveAll,
 	/// Canonicalization discards non-canonical nodes. All the canonical nodes are kept in the DB.
 	ArchiveCanonical,
+	/// Maintain a pruning window and keep the state of every Nth canonical block.
+	PeriodicSnapshots(SnapshotConstraints),
 }
 
 impl PruningMode {
@@ -243,11 +266,19 @@ impl PruningMode {
 		PruningMode::Constrained(Constraints { max_blocks: Some(n) })
 	}
 
+	/// Create a mode that keeps given number of blocks and the state of every `period`-th block.
+	pub fn periodic_snapshots(n: u32, period: u32) -> PruningMode {
+		PruningMode::PeriodicSnapshots(SnapshotConstraints {
+			window: Constraints { max_blocks: Some(n) },
+			period,
+		})
+	}
+
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
 	pub fn is_archive(&self) -> bool {
 		match *self {
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => true,
-			PruningMode::Constrained(_) => false,
+			PruningMode::Constrained(_) | PruningMode::PeriodicSnapshots(_) => false,
 		}
 	}
 
@@ -257,17 +288,36 @@ impl PruningMode {
 			PruningMode::ArchiveAll => PRUNING_MODE_ARCHIVE,
 			PruningMode::ArchiveCanonical => PRUNING_MODE_ARCHIVE_CANON,
 			PruningMode::Constrained(_) => PRUNING_MODE_CONSTRAINED,
+			PruningMode::PeriodicSnapshots(_) => PRUNING_MODE_PERIODIC_SNAPSHOTS,
 		}
 	}
 
+	/// Returns the pruning mode for the given id.
+	///
+	/// The snapshot period is not part of the id, it is set to `0` for
+	/// `PruningMode::PeriodicSnapshots`.
 	pub fn from_id(id: &[u8]) -> Option<Self> {
 		match id {
 			PRUNING_MODE_ARCHIVE => Some(Self::ArchiveAll),
 			PRUNING_MODE_ARCHIVE_CANON => Some(Self::ArchiveCanonical),
 			PRUNING_MODE_CONSTRAINED => Some(Self::Constrained(Default::default())),
+			PRUNING_MODE_PERIODIC_SNAPSHOTS => Some(Self::PeriodicSnapshots(SnapshotConstraints {
+				window: Default::default(),
+				period: 0,
+			})),
 			_ => None,
 		}
 	}
+
+	/// Returns the pruning window constraints, if any.
+	fn constraints(&self) -> Option<&Constraints> {
+		match self {
+			PruningMode::Constrained(constraints) |
+			PruningMode::PeriodicSnapshots(SnapshotConstraints { window: constraints, .. }) =>
+				Some(constraints),
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
+		}
+	}
 }
 
 impl Default for PruningMode {
@@ -319,6 +369,17 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
 			PruningMode::Constrained(Constraints { max_blocks }) =>
 				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
+			PruningMode::PeriodicSnapshots(_) if !ref_counting =>
+				return Err(Error::StateDb(StateDbError::SnapshotsRequireRefCounting)),
+			PruningMode::PeriodicSnapshots(SnapshotConstraints {
+				window: Constraints { max_blocks },
+				period,
+			}) => Some(RefWindow::new_with_snapshots(
+				db,
+				max_blocks.unwrap_or(0),
+				ref_counting,
+				Some(period),
+			)?),
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 
@@ -338,7 +399,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 				// write changes immediately
 				Ok(CommitSet { data: changeset, meta: Default::default() })
 			},
-			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical => self
+			PruningMode::Constrained(_) |
+			PruningMode::ArchiveCanonical |
+			PruningMode::PeriodicSnapshots(_) => self
 				.non_canonical
 				.insert(hash, number, parent_hash, changeset)
 				.map_err(Into::into),
@@ -380,7 +443,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn is_pruned(&self, hash: &BlockHash, number: u64) -> IsPruned {
 		match self.mode {
 			PruningMode::ArchiveAll => IsPruned::NotPruned,
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::PeriodicSnapshots(_) => {
 				if self
 					.non_canonical
 					.last_canonicalized_block_number()
@@ -397,6 +462,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 						// We don't know for sure.
 						None => IsPruned::MaybePruned,
 						Some(pruning) => match pruning.have_block(hash, number) {
+							// The state of snapshot blocks is kept, but only for the canonical
+							// block at that height.
+							HaveBlock::No if pruning.is_snapshot(number) => IsPruned::MaybePruned,
 							HaveBlock::No => IsPruned::Pruned,
 							HaveBlock::Yes => IsPruned::NotPruned,
 							HaveBlock::Maybe => IsPruned::MaybePruned,
@@ -408,8 +476,8 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	}
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) -> Result<(), Error<D::Error>> {
-		if let (&mut Some(ref mut pruning), PruningMode::Constrained(constraints)) =
-			(&mut self.pruning, &self.mode)
+		if let (&mut Some(ref mut pruning), Some(constraints)) =
+			(&mut self.pruning, self.mode.constraints())
 		{
 			loop {
 				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
@@ -442,16 +510,18 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn revert_one(&mut self) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.revert_one(),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::PeriodicSnapshots(_) => self.non_canonical.revert_one(),
 		}
 	}
 
 	fn remove(&mut self, hash: &BlockHash) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.remove(hash),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::PeriodicSnapshots(_) => self.non_canonical.remove(hash),
 		}
 	}
 
@@ -461,11 +531,14 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	{
 		match self.mode {
 			PruningMode::ArchiveAll => Ok(()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::PeriodicSnapshots(_) => {
 				let have_block = self.non_canonical.have_block(hash) ||
 					self.pruning.as_ref().map_or_else(
 						|| hint(),
 						|pruning| match pruning.have_block(hash, number) {
+							HaveBlock::No if pruning.is_snapshot(number) => hint(),
 							HaveBlock::No => false,
 							HaveBlock::Yes => true,
 							HaveBlock::Maybe => hint(),
@@ -564,6 +637,12 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 
 			cs.meta.inserted.push((key, value));
 
+			if let PruningMode::PeriodicSnapshots(SnapshotConstraints { period, .. }) =
+				&selected_mode
+			{
+				cs.meta.inserted.push((to_meta_key(SNAPSHOT_PERIOD, &()), period.encode()));
+			}
+
 			cs
 		} else {
 			Default::default()
@@ -660,6 +739,21 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 	}
 }
 
+/// Rewrite the journals of a state database that is moved to a backend storing trie nodes under
+/// different keys, from a database without reference counting to one with it.
+///
+/// `convert_key` is applied to the node keys of the non-canonical blocks and to the node keys
+/// pending deletion by the blocks in the pruning window. Returns the meta changes to apply.
+pub fn convert_journals<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	convert_key: impl Fn(&mut Key),
+) -> Result<CommitSet<Key>, Error<D::Error>> {
+	let mut commit = CommitSet::default();
+	noncanonical::convert_journal::<BlockHash, Key, D>(db, &convert_key, &mut commit)?;
+	pruning::convert_death_rows::<BlockHash, Key, D>(db, &convert_key, &mut commit)?;
+	Ok(commit)
+}
+
 /// The result return by `StateDb::is_pruned`
 #[derive(Debug, PartialEq, Eq)]
 pub enum IsPruned {
@@ -674,7 +768,19 @@ pub enum IsPruned {
 fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, Error<D::Error>> {
 	let meta_key_mode = to_meta_key(PRUNING_MODE, &());
 	if let Some(stored_mode) = db.get_meta(&meta_key_mode).map_err(Error::Db)? {
-		if let Some(mode) = PruningMode::from_id(&stored_mode) {
+		if let Some(PruningMode::PeriodicSnapshots(SnapshotConstraints { window, .. })) =
+			PruningMode::from_id(&stored_mode)
+		{
+			let meta_key_period = to_meta_key(SNAPSHOT_PERIOD, &());
+			let Some(period) = db.get_meta(&meta_key_period).map_err(Error::Db)? else {
+				return Err(StateDbError::Metadata(
+					"SNAPSHOT_PERIOD is missing for the periodic snapshots pruning mode".into(),
+				)
+				.into())
+			};
+			let period = u32::decode(&mut period.as_slice())?;
+			Ok(Some(PruningMode::PeriodicSnapshots(SnapshotConstraints { window, period })))
+		} else if let Some(mode) = PruningMode::from_id(&stored_mode) {
 			Ok(Some(mode))
 		} else {
 			Err(StateDbError::Metadata(format!(
@@ -698,6 +804,9 @@ fn choose_pruning_mode(
 			Ok(PruningMode::ArchiveCanonical),
 		(PruningMode::Constrained(_), PruningMode::Constrained(requested)) =>
 			Ok(PruningMode::Constrained(requested)),
+		(PruningMode::PeriodicSnapshots(stored), PruningMode::PeriodicSnapshots(requested))
+			if stored.period == requested.period =>
+			Ok(PruningMode::PeriodicSnapshots(requested)),
 		(stored, requested) => Err(StateDbError::IncompatiblePruningModes { requested, stored }),
 	}
 }
@@ -706,14 +815,20 @@ fn choose_pruning_mode(
 mod tests {
 	use crate::{
 		test::{make_changeset, make_db, TestDb},
-		Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		Constraints, Error, IsPruned, NodeDb, PruningMode, StateDb, StateDbError,
 	};
 	use sp_core::H256;
 
+	/// Periodic snapshots are only supported with reference counting.
+	fn ref_counting(mode: Option<&PruningMode>) -> bool {
+		matches!(mode, Some(PruningMode::PeriodicSnapshots(_)))
+	}
+
 	fn make_test_db(settings: PruningMode) -> (TestDb, StateDb<H256, H256, TestDb>) {
 		let mut db = make_db(&[91, 921, 922, 93, 94]);
+		let ref_counting = ref_counting(Some(&settings));
 		let (state_db_init, state_db) =
-			StateDb::open(db.clone(), Some(settings), false, true).unwrap();
+			StateDb::open(db.clone(), Some(settings), ref_counting, true).unwrap();
 		db.commit(&state_db_init);
 
 		db.commit(
@@ -847,6 +962,97 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn periodic_snapshots_keep_every_block() {
+		let (db, sdb) = make_test_db(PruningMode::periodic_snapshots(0, 1));
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::MaybePruned);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 91, 921, 922, 93, 94])));
+	}
+
+	#[test]
+	fn periodic_snapshots_prune_between_snapshots() {
+		let (db, sdb) = make_test_db(PruningMode::periodic_snapshots(0, 2));
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::MaybePruned);
+		// Node `1` is inserted by block #1 and removed by block #2, no snapshot references it.
+		assert!(db.data_eq(&make_db(&[21, 3, 91, 921, 922, 93, 94])));
+	}
+
+	#[test]
+	fn periodic_snapshots_require_ref_counting() {
+		let result = StateDb::<H256, H256, TestDb>::open(
+			make_db(&[]),
+			Some(PruningMode::periodic_snapshots(256, 100)),
+			false,
+			true,
+		);
+		assert!(matches!(result, Err(Error::StateDb(StateDbError::SnapshotsRequireRefCounting))));
+	}
+
+	fn convert_key(key: &mut H256) {
+		*key = H256::from_low_u64_be(key.to_low_u64_be() + 1000)
+	}
+
+	#[test]
+	fn convert_journals_works() {
+		let mode = PruningMode::Constrained(Constraints { max_blocks: Some(2) });
+		let (mut db, _) = make_test_db(mode.clone());
+		let commit = crate::convert_journals::<H256, H256, _>(&db, convert_key).unwrap();
+		db.commit(&commit);
+
+		let (_, sdb) =
+			StateDb::<H256, H256, TestDb>::open(db.clone(), Some(mode), false, false).unwrap();
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
+
+		// Pruning block #2 deletes the nodes it removed from the state under their converted keys.
+		let commit = sdb.canonicalize_block(&H256::from_low_u64_be(4)).unwrap();
+		let mut deleted = commit.data.deleted.clone();
+		deleted.sort();
+		assert_eq!(deleted, vec![H256::from_low_u64_be(1001), H256::from_low_u64_be(1921)]);
+		db.commit(&commit);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::Pruned);
+
+		// The node inserted by the non-canonical block #4 is written under its converted key.
+		let node = |n| NodeDb::get(&db, &H256::from_low_u64_be(n)).unwrap();
+		assert_eq!(node(1004), Some(H256::from_low_u64_be(4).as_bytes().to_vec()));
+		assert_eq!(node(4), None);
+	}
+
+	#[test]
+	fn convert_journals_keeps_reinserted_nodes() {
+		// The source database does not count references, unlike the converted one.
+		let mut db = make_db(&[]);
+		let mode = PruningMode::Constrained(Constraints { max_blocks: Some(4) });
+		let (init, sdb) =
+			StateDb::<H256, H256, TestDb>::open(db.clone(), Some(mode.clone()), true, true)
+				.unwrap();
+		db.commit(&init);
+		let mut target = db.clone();
+		let mut import = move |sdb: &StateDb<H256, H256, TestDb>, number, changeset| {
+			let hash = H256::from_low_u64_be(number);
+			let parent = H256::from_low_u64_be(number.saturating_sub(1));
+			target.commit(&sdb.insert_block(&hash, number, &parent, changeset).unwrap());
+			let commit = sdb.canonicalize_block(&hash).unwrap();
+			target.commit(&commit);
+			commit.data.deleted
+		};
+		// Node `10` is removed from the state by block #2 and inserted again by block #3.
+		import(&sdb, 0, make_changeset(&[], &[]));
+		import(&sdb, 1, make_changeset(&[10], &[]));
+		import(&sdb, 2, make_changeset(&[], &[10]));
+		import(&sdb, 3, make_changeset(&[10], &[]));
+
+		let commit = crate::convert_journals::<H256, H256, _>(&db, convert_key).unwrap();
+		db.commit(&commit);
+		let (_, sdb) =
+			StateDb::<H256, H256, TestDb>::open(db.clone(), Some(mode), false, false).unwrap();
+		let deleted = (4..8)
+			.flat_map(|number| import(&sdb, number, make_changeset(&[], &[])))
+			.collect::<Vec<_>>();
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::Pruned);
+		assert!(deleted.is_empty());
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -875,14 +1081,19 @@ mod tests {
 		expected_effective_mode_when_reopenned: Result<PruningMode, ()>,
 	) {
 		let mut db = make_db(&[]);
+		let ref_counting = ref_counting(mode_when_created.as_ref());
 		let (state_db_init, state_db) =
-			StateDb::<H256, H256, TestDb>::open(db.clone(), mode_when_created, false, true)
+			StateDb::<H256, H256, TestDb>::open(db.clone(), mode_when_created, ref_counting, true)
 				.unwrap();
 		db.commit(&state_db_init);
 		std::mem::drop(state_db);
 
-		let state_db_reopen_result =
-			StateDb::<H256, H256, TestDb>::open(db.clone(), mode_when_reopened, false, false);
+		let state_db_reopen_result = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			mode_when_reopened,
+			ref_counting,
+			false,
+		);
 		if let Ok(expected_mode) = expected_effective_mode_when_reopenned {
 			let (state_db_init, state_db_reopened) = state_db_reopen_result.unwrap();
 			db.commit(&state_db_init);
@@ -942,6 +1153,31 @@ mod tests {
 				Some(PruningMode::ArchiveCanonical),
 				Ok(PruningMode::ArchiveCanonical),
 			),
+			(
+				Some(PruningMode::periodic_snapshots(256, 100)),
+				None,
+				Ok(PruningMode::periodic_snapshots(256, 100)),
+			),
+			(
+				Some(PruningMode::periodic_snapshots(256, 100)),
+				Some(PruningMode::periodic_snapshots(128, 100)),
+				Ok(PruningMode::periodic_snapshots(128, 100)),
+			),
+			(
+				Some(PruningMode::periodic_snapshots(256, 100)),
+				Some(PruningMode::periodic_snapshots(256, 50)),
+				Err(()),
+			),
+			(
+				Some(PruningMode::periodic_snapshots(256, 100)),
+				Some(PruningMode::blocks_pruning(256)),
+				Err(()),
+			),
+			(
+				Some(PruningMode::blocks_pruning(256)),
+				Some(PruningMode::periodic_snapshots(256, 100)),
+				Err(()),
+			),
 		] {
 			check_stored_and_requested_mode_compatibility(created, reopened, expected);
 		}
@@ -167,6 +167,39 @@ fn discard_descendants<BlockHash: Hash, Key: Hash>(
 	pinned_children
 }
 
+/// Apply `convert_key` to every node key in the journal of the non-canonical blocks. Adds changes
+/// to `commit`.
+pub(crate) fn convert_journal<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	convert_key: &impl Fn(&mut Key),
+	commit: &mut CommitSet<Key>,
+) -> Result<(), Error<D::Error>> {
+	let Some(last_canonicalized) =
+		db.get_meta(&to_meta_key(LAST_CANONICAL, &())).map_err(Error::Db)?
+	else {
+		return Ok(())
+	};
+	let (_, mut block) = <(BlockHash, u64)>::decode(&mut last_canonicalized.as_slice())?;
+	loop {
+		block += 1;
+		let mut level_len = 0;
+		for index in 0..MAX_BLOCKS_PER_LEVEL {
+			let journal_key = to_journal_key(block, index);
+			if let Some(record) = db.get_meta(&journal_key).map_err(Error::Db)? {
+				let mut record: JournalRecord<BlockHash, Key> =
+					Decode::decode(&mut record.as_slice())?;
+				record.inserted.iter_mut().for_each(|(key, _)| convert_key(key));
+				record.deleted.iter_mut().for_each(convert_key);
+				commit.meta.inserted.push((journal_key, record.encode()));
+				level_len += 1;
+			}
+		}
+		if level_len == 0 {
+			return Ok(())
+		}
+	}
+}
+
 impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 	/// Creates a new instance. Does not expect any metadata to be present in the DB.
 	pub fn new<D: MetaDb>(db: &D) -> Result<NonCanonicalOverlay<BlockHash, Key>, Error<D::Error>> {
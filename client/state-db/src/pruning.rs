@@ -25,8 +25,8 @@
 //! The changes are journaled in the DB.
 
 use crate::{
-	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, Error, Hash, MetaDb, StateDbError,
-	DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
+	noncanonical::LAST_CANONICAL, snapshot::SnapshotIndex, to_meta_key, CommitSet, Error, Hash,
+	MetaDb, StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
 };
 use codec::{Decode, Encode};
 use log::trace;
@@ -35,6 +35,57 @@ use std::collections::{HashMap, HashSet, VecDeque};
 pub(crate) const LAST_PRUNED: &[u8] = b"last_pruned";
 const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
 
+/// Apply `convert_key` to the node keys pending deletion by the blocks in the pruning window,
+/// for a database that counts references. Adds changes to `commit`.
+///
+/// Without reference counting, a node deleted by a block and re-inserted by a later block of the
+/// window is dropped from the death row of the first block when the window is loaded. With
+/// reference counting, only the re-insertions canonicalized after the conversion are counted, so
+/// such nodes are dropped from the converted death rows instead.
+pub(crate) fn convert_death_rows<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	convert_key: &impl Fn(&mut Key),
+	commit: &mut CommitSet<Key>,
+) -> Result<(), Error<D::Error>> {
+	let base = match db.get_meta(&to_meta_key(LAST_PRUNED, &())).map_err(Error::Db)? {
+		Some(buffer) => u64::decode(&mut buffer.as_slice())? + 1,
+		None => 0,
+	};
+	let mut death_rows: Vec<DeathRow<BlockHash, Key>> = Vec::new();
+	let mut death_index: HashMap<Key, usize> = HashMap::new();
+	while let Some(record) = db
+		.get_meta(&to_journal_key(base + death_rows.len() as u64))
+		.map_err(Error::Db)?
+	{
+		let JournalRecord::<BlockHash, Key> { hash, inserted, deleted } =
+			Decode::decode(&mut record.as_slice())?;
+		for key in inserted {
+			if let Some(index) = death_index.remove(&key) {
+				death_rows[index].deleted.remove(&key);
+			}
+		}
+		for key in deleted.iter() {
+			death_index.insert(key.clone(), death_rows.len());
+		}
+		death_rows.push(DeathRow { hash, deleted: deleted.into_iter().collect() });
+	}
+	for (index, DeathRow { hash, deleted }) in death_rows.into_iter().enumerate() {
+		let deleted = deleted
+			.into_iter()
+			.map(|mut key| {
+				convert_key(&mut key);
+				key
+			})
+			.collect();
+		let record = JournalRecord { hash, inserted: Vec::new(), deleted };
+		commit
+			.meta
+			.inserted
+			.push((to_journal_key(base + index as u64), record.encode()));
+	}
+	Ok(())
+}
+
 /// See module documentation.
 pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	/// A queue of blocks keep tracking keys that should be deleted for each block in the
@@ -42,6 +93,8 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	queue: DeathRowQueue<BlockHash, Key, D>,
 	/// Block number that is next to be pruned.
 	base: u64,
+	/// Nodes kept for the periodic state snapshots, if enabled.
+	snapshots: Option<SnapshotIndex<Key>>,
 }
 
 /// `DeathRowQueue` used to keep track of blocks in the pruning window, there are two flavors:
@@ -51,6 +104,8 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 /// 	a few number of blocks in memory and load more blocks on demand
 enum DeathRowQueue<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	Mem {
+		// The backend database
+		db: D,
 		/// A queue of keys that should be deleted for each block in the pruning window.
 		death_rows: VecDeque<DeathRow<BlockHash, Key>>,
 		/// An index that maps each key from `death_rows` to block number.
@@ -72,12 +127,9 @@ enum DeathRowQueue<BlockHash: Hash, Key: Hash, D: MetaDb> {
 
 impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 	/// Return a `DeathRowQueue` that all blocks are keep in memory
-	fn new_mem(db: &D, base: u64) -> Result<DeathRowQueue<BlockHash, Key, D>, Error<D::Error>> {
+	fn new_mem(db: D, base: u64) -> Result<DeathRowQueue<BlockHash, Key, D>, Error<D::Error>> {
 		let mut block = base;
-		let mut queue = DeathRowQueue::<BlockHash, Key, D>::Mem {
-			death_rows: VecDeque::new(),
-			death_index: HashMap::new(),
-		};
+		let mut records = Vec::new();
 		// read the journal
 		trace!(
 			target: LOG_TARGET,
@@ -97,12 +149,20 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 						record.inserted.len(),
 						record.deleted.len(),
 					);
-					queue.import(base, block, record);
+					records.push(record);
 				},
 				None => break,
 			}
 			block += 1;
 		}
+		let mut queue = DeathRowQueue::<BlockHash, Key, D>::Mem {
+			db,
+			death_rows: VecDeque::new(),
+			death_index: HashMap::new(),
+		};
+		for (index, record) in records.into_iter().enumerate() {
+			queue.import(base, base + index as u64, record);
+		}
 		Ok(queue)
 	}
 
@@ -140,7 +200,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 				}
 				*last = Some(num);
 			},
-			DeathRowQueue::Mem { death_rows, death_index } => {
+			DeathRowQueue::Mem { death_rows, death_index, .. } => {
 				// remove all re-inserted keys from death rows
 				for k in inserted {
 					if let Some(block) = death_index.remove(&k) {
@@ -170,7 +230,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 				}
 				Ok(cache.pop_front())
 			},
-			DeathRowQueue::Mem { death_rows, death_index } => match death_rows.pop_front() {
+			DeathRowQueue::Mem { death_rows, death_index, .. } => match death_rows.pop_front() {
 				Some(row) => {
 					for k in row.deleted.iter() {
 						death_index.remove(k);
@@ -219,6 +279,13 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 		}
 	}
 
+	/// Return the backend database
+	fn db(&self) -> &D {
+		match self {
+			DeathRowQueue::DbBacked { db, .. } | DeathRowQueue::Mem { db, .. } => db,
+		}
+	}
+
 	/// Return the number of block in the pruning window
 	fn len(&self, base: u64) -> u64 {
 		match self {
@@ -233,7 +300,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 	) -> Option<(&VecDeque<DeathRow<BlockHash, Key>>, &HashMap<Key, u64>)> {
 		match self {
 			DeathRowQueue::DbBacked { .. } => None,
-			DeathRowQueue::Mem { death_rows, death_index } => Some((death_rows, death_index)),
+			DeathRowQueue::Mem { death_rows, death_index, .. } => Some((death_rows, death_index)),
 		}
 	}
 
@@ -305,6 +372,16 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		db: D,
 		window_size: u32,
 		count_insertions: bool,
+	) -> Result<RefWindow<BlockHash, Key, D>, Error<D::Error>> {
+		Self::new_with_snapshots(db, window_size, count_insertions, None)
+	}
+
+	/// Create a pruning window that never prunes the state of every `snapshot_period`-th block.
+	pub fn new_with_snapshots(
+		db: D,
+		window_size: u32,
+		count_insertions: bool,
+		snapshot_period: Option<u32>,
 	) -> Result<RefWindow<BlockHash, Key, D>, Error<D::Error>> {
 		// the block number of the first block in the queue or the next block number if the queue is
 		// empty
@@ -332,7 +409,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 				);
 			}
 
-			DeathRowQueue::new_mem(&db, base)?
+			DeathRowQueue::new_mem(db, base)?
 		} else {
 			let last = match last_canonicalized_number {
 				Some(last_canonicalized_number) => {
@@ -347,7 +424,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			DeathRowQueue::new_db_backed(db, base, last, window_size)?
 		};
 
-		Ok(RefWindow { queue, base })
+		let snapshots = snapshot_period
+			.map(|period| SnapshotIndex::new(queue.db(), period, base))
+			.transpose()?;
+		Ok(RefWindow { queue, base, snapshots })
 	}
 
 	pub fn window_size(&self) -> u64 {
@@ -368,6 +448,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		Ok(res)
 	}
 
+	/// Check if the state of the given block is kept as a periodic snapshot.
+	pub fn is_snapshot(&self, number: u64) -> bool {
+		self.snapshots.as_ref().map_or(false, |snapshots| snapshots.is_snapshot(number))
+	}
+
 	fn is_empty(&self) -> bool {
 		self.window_size() == 0
 	}
@@ -387,7 +472,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		if let Some(pruned) = self.queue.pop_front(self.base)? {
 			trace!(target: "state-db", "Pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
 			let index = self.base;
-			commit.data.deleted.extend(pruned.deleted.into_iter());
+			match self.snapshots.as_mut() {
+				Some(snapshots) => snapshots.prune(index, pruned.deleted, commit),
+				None => commit.data.deleted.extend(pruned.deleted.into_iter()),
+			}
 			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
 			commit.meta.deleted.push(to_journal_key(self.base));
 			self.base += 1;
@@ -417,6 +505,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		} else {
 			Default::default()
 		};
+		if let Some(snapshots) = self.snapshots.as_mut() {
+			snapshots.note_canonical(number, commit);
+		}
 		let deleted = std::mem::take(&mut commit.data.deleted);
 		let journal_record = JournalRecord { hash: hash.clone(), inserted, deleted };
 		commit.meta.inserted.push((to_journal_key(number), journal_record.encode()));
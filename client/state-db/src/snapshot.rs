@@ -0,0 +1,231 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Periodic state snapshots.
+//!
+//! With [`crate::PruningMode::PeriodicSnapshots`] the state of every canonical block whose number
+//! is a multiple of the snapshot period is never pruned, while the state of all the other blocks
+//! is pruned once it leaves the pruning window.
+//!
+//! Every node inserted by a canonical block is tagged with the number of the first snapshot block
+//! that contains it, unless the node is already tagged. When the pruning window deletes a node
+//! that was removed from the state by block `n`, the node is only deleted from the database if its
+//! tag is at least `n`: the node was inserted and removed between two snapshots and no snapshot
+//! references it. Untagged nodes and nodes with a tag lower than `n` belong to a snapshot and are
+//! kept forever.
+//!
+//! The nodes tagged by a canonical block are stored in a single meta entry per block, committed
+//! together with the pruning journal, and are kept in memory until their snapshot block is pruned.
+//! The tags are then dropped, so a node of a pruned snapshot inserted again is tagged again:
+//! deleting it must only drop the reference added by the new insertion, which is why periodic
+//! snapshots require a database with reference counting.
+
+use crate::{to_meta_key, CommitSet, Error, Hash, MetaDb, LOG_TARGET};
+use codec::{Decode, Encode};
+use log::trace;
+use std::collections::{hash_map::Entry, HashMap};
+
+const SNAPSHOT_TAGS: &[u8] = b"snapshot_tags";
+
+/// Keeps track of the nodes referenced by the periodic state snapshots.
+pub struct SnapshotIndex<Key: Hash> {
+	/// Number of blocks between two snapshots.
+	period: u64,
+	/// Tags of the nodes inserted after the last pruned snapshot block.
+	tags: HashMap<Key, u64>,
+}
+
+impl<Key: Hash> SnapshotIndex<Key> {
+	/// Load the tags needed to prune the blocks starting from `base`.
+	pub fn new<D: MetaDb>(
+		db: &D,
+		period: u32,
+		base: u64,
+	) -> Result<SnapshotIndex<Key>, Error<D::Error>> {
+		let mut index = SnapshotIndex { period: period.max(1) as u64, tags: Default::default() };
+		// Blocks that are already pruned keep their entry until their snapshot block is pruned,
+		// blocks from `base` onwards are all in the pruning window.
+		let mut number = match base.checked_sub(1) {
+			Some(last_pruned) => last_pruned / index.period * index.period + 1,
+			None => 0,
+		};
+		loop {
+			match db.get_meta(&to_tags_key(number)).map_err(Error::Db)? {
+				Some(record) => {
+					let snapshot = index.next_snapshot(number);
+					for key in Vec::<Key>::decode(&mut record.as_slice())? {
+						index.tags.entry(key).or_insert(snapshot);
+					}
+				},
+				None if number >= base => break,
+				None => (),
+			}
+			number += 1;
+		}
+		trace!(target: LOG_TARGET, "Loaded {} snapshot tags", index.tags.len());
+		Ok(index)
+	}
+
+	/// Check if the state of the given block is kept as a snapshot.
+	pub fn is_snapshot(&self, number: u64) -> bool {
+		number % self.period == 0
+	}
+
+	/// Returns the first snapshot block at or after the given block.
+	fn next_snapshot(&self, number: u64) -> u64 {
+		number.saturating_add(self.period - 1) / self.period * self.period
+	}
+
+	/// Tag the nodes inserted by the canonical block `number`. Adds changes to `commit`.
+	pub fn note_canonical(&mut self, number: u64, commit: &mut CommitSet<Key>) {
+		let snapshot = self.next_snapshot(number);
+		let mut tagged = Vec::new();
+		for (key, _) in commit.data.inserted.iter() {
+			if let Entry::Vacant(entry) = self.tags.entry(key.clone()) {
+				entry.insert(snapshot);
+				tagged.push(key.clone());
+			}
+		}
+		commit.meta.inserted.push((to_tags_key(number), tagged.encode()));
+	}
+
+	/// Delete the nodes removed from the state by block `number` that are not referenced by any
+	/// snapshot. Adds changes to `commit`.
+	pub fn prune(
+		&mut self,
+		number: u64,
+		deleted: impl IntoIterator<Item = Key>,
+		commit: &mut CommitSet<Key>,
+	) {
+		let mut kept = 0;
+		for key in deleted {
+			match self.tags.get(&key) {
+				Some(snapshot) if *snapshot >= number => commit.data.deleted.push(key),
+				_ => kept += 1,
+			}
+		}
+		trace!(target: LOG_TARGET, "Kept {} snapshot nodes when pruning #{}", kept, number);
+
+		if self.is_snapshot(number) {
+			// The nodes tagged with this snapshot are never deleted from now on.
+			self.tags.retain(|_, snapshot| *snapshot > number);
+			let first = (number + 1).saturating_sub(self.period);
+			commit.meta.deleted.extend((first..=number).map(to_tags_key));
+		}
+	}
+}
+
+fn to_tags_key(block: u64) -> Vec<u8> {
+	to_meta_key(SNAPSHOT_TAGS, &block)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::SnapshotIndex;
+	use crate::{
+		test::{make_commit, make_db},
+		CommitSet,
+	};
+	use sp_core::H256;
+
+	#[test]
+	fn next_snapshot_rounds_up() {
+		let index = SnapshotIndex::<H256>::new(&make_db(&[]), 10, 0).unwrap();
+		assert_eq!(index.next_snapshot(0), 0);
+		assert_eq!(index.next_snapshot(1), 10);
+		assert_eq!(index.next_snapshot(10), 10);
+		assert_eq!(index.next_snapshot(11), 20);
+		assert!(index.is_snapshot(20));
+		assert!(!index.is_snapshot(21));
+	}
+
+	#[test]
+	fn keeps_nodes_referenced_by_snapshot() {
+		let mut db = make_db(&[]);
+		let mut index = SnapshotIndex::<H256>::new(&db, 10, 0).unwrap();
+
+		// Node 1 is inserted before snapshot #10, node 2 after it.
+		let mut commit = make_commit(&[1], &[]);
+		index.note_canonical(5, &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[2], &[]);
+		index.note_canonical(12, &mut commit);
+		db.commit(&commit);
+
+		// Both are removed from the state by block #15.
+		let mut commit = CommitSet::default();
+		index.prune(15, [H256::from_low_u64_be(1), H256::from_low_u64_be(2)], &mut commit);
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[1])));
+	}
+
+	#[test]
+	fn writes_one_entry_per_block() {
+		let mut db = make_db(&[]);
+		let mut index = SnapshotIndex::<H256>::new(&db, 10, 0).unwrap();
+
+		let mut commit = make_commit(&[1, 2, 3], &[]);
+		index.note_canonical(11, &mut commit);
+		assert_eq!(commit.meta.inserted.len(), 1);
+		db.commit(&commit);
+		// Nodes that are already tagged are not tagged again.
+		let mut commit = make_commit(&[1, 4], &[]);
+		index.note_canonical(12, &mut commit);
+		assert_eq!(commit.meta.inserted.len(), 1);
+		db.commit(&commit);
+		assert_eq!(db.meta_len(), 2);
+
+		// The tags are loaded again on restart.
+		let mut index = SnapshotIndex::<H256>::new(&db, 10, 11).unwrap();
+		assert_eq!(index.tags.len(), 4);
+		let mut commit = CommitSet::default();
+		index.prune(11, [H256::from_low_u64_be(1)], &mut commit);
+		assert_eq!(commit.data.deleted, vec![H256::from_low_u64_be(1)]);
+	}
+
+	#[test]
+	fn drops_tags_once_snapshot_is_pruned() {
+		let mut db = make_db(&[]);
+		let mut index = SnapshotIndex::<H256>::new(&db, 10, 0).unwrap();
+
+		for number in 1..=10 {
+			let mut commit = make_commit(&[number], &[]);
+			index.note_canonical(number, &mut commit);
+			db.commit(&commit);
+		}
+		let mut commit = make_commit(&[11], &[]);
+		index.note_canonical(11, &mut commit);
+		db.commit(&commit);
+
+		// Pruning snapshot #10 drops the tags of the blocks #1 to #10.
+		let mut commit = CommitSet::default();
+		index.prune(10, [], &mut commit);
+		assert_eq!(commit.meta.deleted.len(), 10);
+		db.commit(&commit);
+		assert_eq!(db.meta_len(), 1);
+		assert_eq!(index.tags.len(), 1);
+
+		// A node of snapshot #10 inserted again is tagged again.
+		let mut commit = make_commit(&[5], &[]);
+		index.note_canonical(12, &mut commit);
+		db.commit(&commit);
+		let index = SnapshotIndex::<H256>::new(&db, 10, 11).unwrap();
+		assert_eq!(index.tags.get(&H256::from_low_u64_be(5)), Some(&20));
+		assert_eq!(index.tags.len(), 2);
+	}
+}
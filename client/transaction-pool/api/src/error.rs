@@ -55,6 +55,10 @@ pub enum Error {
 		/// Transaction entering the pool.
 		new: Priority,
 	},
+	/// The transactions providing the same tags were replaced too many times already.
+	#[error("Too many replacements of the transactions providing the same tags")]
+	TooManyReplacements,
+
 	#[error("Transaction with cyclic dependency")]
 	CycleDetected,
 
@@ -70,6 +70,7 @@ impl PoolStatus {
 /// 3. Leaving the pool:
 /// 		- `InBlock`
 /// 		- `Invalid`
+/// 		- `Replaced`
 /// 		- `Usurped`
 /// 		- `Dropped`
 /// 	4. Re-entering the pool:
@@ -123,9 +124,13 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	/// Transaction has been finalized by a finality-gadget, e.g GRANDPA.
 	#[serde(with = "v1_compatible")]
 	Finalized((BlockHash, TxIndex)),
-	/// Transaction has been replaced in the pool, by another transaction
-	/// that provides the same tags. (e.g. same (sender, nonce)).
+	/// Transaction has been usurped by another transaction.
+	///
+	/// Not reported by the pool anymore, replacements are reported as `Replaced`.
 	Usurped(Hash),
+	/// Transaction has been replaced in the pool, by another transaction
+	/// that provides the same tags (e.g. same (sender, nonce)) and has a high enough priority.
+	Replaced(Hash),
 	/// Transaction has been dropped from the pool because of the limit.
 	Dropped,
 	/// Transaction is no longer valid in the current state.
@@ -238,6 +243,25 @@ pub trait TransactionPool: Send + Sync {
 		>,
 	>;
 
+	/// Get an iterator for ready transactions ordered by priority, for a block built on top of
+	/// the block `at` with the given `number`.
+	///
+	/// Pools which track several forks can use the hash to return the transactions valid on the
+	/// right fork. By default this is the same as [`Self::ready_at`].
+	fn ready_at_block(
+		&self,
+		_at: BlockHash<Self>,
+		number: NumberFor<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		self.ready_at(number)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>;
 
@@ -310,6 +334,12 @@ pub enum ChainEvent<B: BlockT> {
 pub trait MaintainedTransactionPool: TransactionPool {
 	/// Perform maintenance
 	async fn maintain(&self, event: ChainEvent<Self::Block>);
+
+	/// Notify the pool about an imported block which did not become the best block.
+	///
+	/// Does nothing by default, pools following every fork can use it to report the
+	/// transactions included on non-best forks.
+	async fn on_block_imported(&self, _hash: BlockHash<Self>) {}
 }
 
 /// Transaction pool interface for submitting local transactions that exposes a
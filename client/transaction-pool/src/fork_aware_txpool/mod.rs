@@ -0,0 +1,1072 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction pool keeping a validated view of its content for every tracked fork.
+//!
+//! [`BasicPool`](crate::BasicPool) validates transactions against the best block only and has
+//! to resubmit retracted and prune enacted transactions on every re-org. [`ForkAwareTxPool`]
+//! instead keeps all submitted transactions in a mempool and validates them against every
+//! tracked fork tip, each in its own view. A view is created when a block is imported, from the
+//! view of its closest ancestor: the transactions included in the blocks in between are pruned
+//! and the remaining ready transactions are revalidated in the background. A view supersedes
+//! the views of the block's ancestors, except for the best one, and it is dropped once finality
+//! prunes its fork. Views are also built on demand for the blocks transactions are submitted at
+//! and for the blocks the ready transactions are requested at.
+
+mod multi_view_listener;
+mod tx_mem_pool;
+mod view;
+mod view_store;
+
+use crate::{
+	api::FullChainApi,
+	error,
+	graph::{self, BlockHash, ExtrinsicFor, ExtrinsicHash, IsValidator, ValidatedTransaction},
+	metrics::MetricsLink as PrometheusMetrics,
+	persistence::{PersistedPool, Persistence},
+	PolledIterator, ReadyIteratorFor, LOG_TARGET,
+};
+use async_trait::async_trait;
+use futures::{
+	channel::{mpsc, oneshot},
+	prelude::*,
+};
+use multi_view_listener::MultiViewListener;
+use parking_lot::Mutex;
+use prometheus_endpoint::Registry as PrometheusRegistry;
+use sc_transaction_pool_api::{
+	error::Error as TxPoolError, ChainEvent, ImportNotificationStream, MaintainedTransactionPool,
+	PoolFuture, PoolStatus, TransactionFor, TransactionPool, TransactionSource,
+	TransactionStatusStreamFor, TxHash,
+};
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
+use sp_blockchain::HashAndNumber;
+use sp_core::traits::SpawnEssentialNamed;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+use std::{
+	collections::{HashMap, HashSet},
+	pin::Pin,
+	sync::{Arc, Weak},
+	time::Instant,
+};
+use tx_mem_pool::TxMemPool;
+use view::View;
+use view_store::ViewStore;
+
+/// Maximum number of views kept at the same time.
+///
+/// When finality stalls, the views at the lowest fork tips are dropped first.
+const MAX_VIEWS: usize = 32;
+
+/// A fork aware transaction pool for a full node.
+pub type FullForkAwarePool<Block, Client> = ForkAwareTxPool<FullChainApi<Client, Block>, Block>;
+
+/// Transactions included in a block, with their index in the block body.
+type IncludedTransactions<ChainApi> = Vec<(ExtrinsicHash<ChainApi>, usize)>;
+
+/// Pending requests for the ready transactions at some block.
+struct ReadyPoll<ChainApi: graph::ChainApi> {
+	/// Requests for a block built on top of the given block, or on top of any block at least as
+	/// high as the given number if no hash is given.
+	pollers: Vec<(
+		Option<BlockHash<ChainApi>>,
+		NumberFor<ChainApi::Block>,
+		oneshot::Sender<ReadyIteratorFor<ChainApi>>,
+	)>,
+}
+
+impl<ChainApi: graph::ChainApi + 'static> ReadyPoll<ChainApi> {
+	fn add(
+		&mut self,
+		at: Option<BlockHash<ChainApi>>,
+		number: NumberFor<ChainApi::Block>,
+	) -> oneshot::Receiver<ReadyIteratorFor<ChainApi>> {
+		let (sender, receiver) = oneshot::channel();
+		self.pollers.push((at, number, sender));
+		receiver
+	}
+
+	/// Answer the requests for the given view. Requests without a block hash are only answered
+	/// by the best view.
+	fn trigger(&mut self, view: &View<ChainApi>, is_best: bool) {
+		let mut idx = 0;
+		while idx < self.pollers.len() {
+			let (at, number, _) = &self.pollers[idx];
+			if at.map_or(is_best && *number <= view.at.number, |at| at == view.at.hash) {
+				let (_, _, sender) = self.pollers.swap_remove(idx);
+				log::debug!(target: LOG_TARGET, "Sending ready signal at block {:?}", view.at.hash);
+				let _ = sender.send(Box::new(view.pool.validated_pool().ready()));
+			} else {
+				idx += 1;
+			}
+		}
+	}
+}
+
+/// Transaction pool validating its content against every tracked fork tip.
+pub struct ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block>,
+{
+	/// The pool itself, for the futures building views after a call returned.
+	this: Weak<Self>,
+	api: Arc<ChainApi>,
+	options: graph::Options,
+	is_validator: Arc<IsValidator>,
+	mempool: Arc<TxMemPool<ChainApi>>,
+	view_store: Arc<ViewStore<ChainApi>>,
+	listener: Arc<MultiViewListener<ChainApi>>,
+	/// Transactions of the mempool included in the non-finalized blocks processed so far.
+	included: Mutex<HashMap<Block::Hash, (NumberFor<Block>, IncludedTransactions<ChainApi>)>>,
+	finalized: Mutex<HashAndNumber<Block>>,
+	ready_poll: Mutex<ReadyPoll<ChainApi>>,
+	/// Queue of the background revalidation of new views, views are revalidated inline if unset.
+	revalidation: Option<TracingUnboundedSender<Weak<View<ChainApi>>>>,
+	import_notification_sinks: Arc<Mutex<Vec<mpsc::Sender<ExtrinsicHash<ChainApi>>>>>,
+	metrics: PrometheusMetrics,
+	persistence: Option<Arc<Persistence>>,
+}
+
+impl<ChainApi, Block> ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block> + 'static,
+{
+	/// Create new fork aware transaction pool with provided api, for tests.
+	pub fn new_test(
+		api: Arc<ChainApi>,
+		best_block: HashAndNumber<Block>,
+		finalized_block: HashAndNumber<Block>,
+	) -> Arc<Self> {
+		Self::new(Default::default(), true.into(), api, None, None, best_block, finalized_block)
+	}
+
+	/// Create new fork aware transaction pool tracking the forks built on top of
+	/// `finalized_block`, starting with a single view at `best_block`.
+	///
+	/// New views are revalidated and the pool content is persisted by tasks spawned with
+	/// `spawner` if given.
+	pub fn new(
+		options: graph::Options,
+		is_validator: IsValidator,
+		api: Arc<ChainApi>,
+		prometheus: Option<&PrometheusRegistry>,
+		spawner: Option<&dyn SpawnEssentialNamed>,
+		best_block: HashAndNumber<Block>,
+		finalized_block: HashAndNumber<Block>,
+	) -> Arc<Self> {
+		let mempool = Arc::new(TxMemPool::new(
+			api.clone(),
+			options.ready.count + options.future.count,
+			options.ready.total_bytes + options.future.total_bytes,
+		));
+		let view_store = Arc::new(ViewStore::new());
+		let import_notification_sinks = Arc::new(Mutex::new(Vec::new()));
+		let revalidation = spawner.map(|spawner| {
+			let (sender, receiver) =
+				tracing_unbounded("mpsc_fork_aware_txpool_revalidation", 100_000);
+			spawner.spawn_essential(
+				"txpool-background",
+				Some("transaction-pool"),
+				revalidate_views(api.clone(), receiver).boxed(),
+			);
+			sender
+		});
+		let persistence = spawner.zip(options.persistence_path.clone()).map(|(spawner, path)| {
+			let persistence = Arc::new(Persistence::new(path));
+			let content = MempoolContent {
+				mempool: mempool.clone(),
+				view_store: view_store.clone(),
+				import_notification_sinks: import_notification_sinks.clone(),
+			};
+			let task_persistence = persistence.clone();
+			spawner.spawn_essential(
+				"txpool-persistence",
+				Some("transaction-pool"),
+				async move { task_persistence.run(&content).await }.boxed(),
+			);
+			persistence
+		});
+		let pool = Arc::new_cyclic(|this| Self {
+			this: this.clone(),
+			api,
+			options,
+			is_validator: Arc::new(is_validator),
+			mempool,
+			view_store,
+			listener: Arc::new(MultiViewListener::new()),
+			included: Default::default(),
+			finalized: Mutex::new(finalized_block),
+			ready_poll: Mutex::new(ReadyPoll { pollers: Vec::new() }),
+			revalidation,
+			import_notification_sinks,
+			metrics: PrometheusMetrics::new(prometheus),
+			persistence,
+		});
+
+		let best_hash = best_block.hash;
+		pool.view_store.insert(Arc::new(pool.create_view(best_block)));
+		pool.view_store.set_best(best_hash);
+		pool
+	}
+
+	/// Get access to the underlying api.
+	pub fn api(&self) -> &ChainApi {
+		&self.api
+	}
+
+	/// Returns the blocks of the currently tracked views, the best one first.
+	pub fn views(&self) -> Vec<HashAndNumber<Block>> {
+		self.view_store.views().iter().map(|view| view.at.clone()).collect()
+	}
+
+	/// Returns the number of transactions known to the pool, whatever fork they are valid on.
+	pub fn mempool_len(&self) -> usize {
+		self.mempool.len()
+	}
+
+	fn create_view(&self, at: HashAndNumber<Block>) -> View<ChainApi> {
+		let is_validator = self.is_validator.clone();
+		let is_validator: Box<dyn Fn() -> bool + Send + Sync> =
+			Box::new(move || is_validator.get());
+		View::new(self.api.clone(), at, self.options.clone(), is_validator.into())
+	}
+
+	/// Add the transactions to the mempool and validate them against every view, building the
+	/// view at `at` first if needed.
+	///
+	/// Transactions rejected by all views are removed from the mempool again.
+	fn submit(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		xts: Vec<ExtrinsicFor<ChainApi>>,
+	) -> PoolFuture<Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>>, ChainApi::Error> {
+		let hash = self.api.block_id_to_hash(at);
+		let at = *at;
+		let pool = self
+			.this
+			.upgrade()
+			.expect("The pool is alive while its methods are called; qed");
+
+		async move {
+			let at = hash?.ok_or_else(|| TxPoolError::InvalidBlockId(format!("{:?}", at)))?;
+			let best = pool.view_store.best_view().map(|view| view.at.hash);
+			if pool.build_view(at, best).await.is_some() {
+				pool.enforce_view_limit();
+			}
+			Ok(submit(
+				&pool.mempool,
+				&pool.view_store,
+				&pool.import_notification_sinks,
+				Some(at),
+				source,
+				xts,
+			)
+			.await)
+		}
+		.boxed()
+	}
+
+	/// Ready transactions of the given view.
+	fn ready_of(view: &View<ChainApi>) -> ReadyIteratorFor<ChainApi> {
+		Box::new(view.pool.validated_pool().ready())
+	}
+
+	/// Wait for a view matching the request to be created.
+	fn poll_ready(
+		&self,
+		at: Option<Block::Hash>,
+		number: NumberFor<Block>,
+	) -> PolledIterator<ChainApi> {
+		// If there are no transactions in the pool, it is fine to return early.
+		if self.mempool.len() == 0 {
+			return async { Box::new(std::iter::empty()) as Box<_> }.boxed()
+		}
+
+		self.ready_poll
+			.lock()
+			.add(at, number)
+			.map(|received| {
+				received.unwrap_or_else(|e| {
+					log::warn!(target: LOG_TARGET, "Error receiving pending set: {:?}", e);
+					Box::new(std::iter::empty())
+				})
+			})
+			.boxed()
+	}
+
+	/// Returns the mempool transactions included in the given block, reporting them as
+	/// `InBlock` the first time the block is seen.
+	async fn included_transactions(
+		&self,
+		block: &HashAndNumber<Block>,
+	) -> IncludedTransactions<ChainApi> {
+		if let Some((_, included)) = self.included.lock().get(&block.hash) {
+			return included.clone()
+		}
+
+		let body = match self.api.block_body(block.hash).await {
+			Ok(Some(body)) => body,
+			Ok(None) => {
+				log::warn!(target: LOG_TARGET, "Missing body of block {:?}", block.hash);
+				Vec::new()
+			},
+			Err(e) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Failed to fetch body of block {:?}: {}",
+					block.hash,
+					e
+				);
+				Vec::new()
+			},
+		};
+		let included = body
+			.iter()
+			.enumerate()
+			.filter_map(|(index, xt)| {
+				let hash = self.api.hash_and_length(xt).0;
+				self.mempool.contains(&hash).then_some((hash, index))
+			})
+			.collect::<Vec<_>>();
+
+		for (hash, index) in &included {
+			self.listener.in_block(hash, block.hash, *index);
+		}
+		self.included.lock().insert(block.hash, (block.number, included.clone()));
+		included
+	}
+
+	/// Returns the view at the given block, creating it if needed.
+	///
+	/// A new view is built from the view of the closest ancestor block: its transactions are
+	/// copied without being validated again and only the ones affected by the blocks in between
+	/// are checked, the remaining ready transactions being revalidated in the background. The
+	/// whole mempool is only validated when no ancestor view is left. Transactions included in
+	/// the blocks in between are reported as `InBlock`.
+	///
+	/// The new view supersedes the views of the block's ancestors, except for `keep`.
+	async fn build_view(
+		&self,
+		hash: Block::Hash,
+		keep: Option<Block::Hash>,
+	) -> Option<Arc<View<ChainApi>>> {
+		if let Some(view) = self.view_store.get(&hash) {
+			return Some(view)
+		}
+
+		let finalized = self.finalized.lock().clone();
+		let tree_route = match self.api.tree_route(finalized.hash, hash) {
+			Ok(tree_route) if tree_route.retracted().is_empty() => tree_route,
+			Ok(_) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Ignoring block {:?} not descending from finalized block {:?}",
+					hash,
+					finalized.hash,
+				);
+				return None
+			},
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Failed to compute tree route to {:?}: {}", hash, e);
+				return None
+			},
+		};
+
+		let mut included_on_fork = HashSet::new();
+		for block in tree_route.enacted() {
+			let included = self.included_transactions(block).await;
+			included_on_fork.extend(included.into_iter().map(|(hash, _)| hash));
+		}
+
+		// The finalized block followed by the blocks up to `hash`.
+		let route = std::iter::once(&finalized).chain(tree_route.enacted()).collect::<Vec<_>>();
+		let origin = route
+			.iter()
+			.enumerate()
+			.rev()
+			.find_map(|(index, block)| Some((index, self.view_store.get(&block.hash)?)));
+
+		// The view is added before its content is imported, so that transactions submitted in
+		// the meantime reach it as well.
+		let at = (*route.last().expect("Route starts with the finalized block; qed")).clone();
+		let view = Arc::new(self.create_view(at));
+		self.view_store.insert(view.clone());
+
+		match origin {
+			Some((index, origin)) => {
+				view.import_from(&origin);
+				for blocks in route[index..].windows(2) {
+					let (parent, block) = (blocks[0].hash, blocks[1].hash);
+					match self.api.block_body(block).await {
+						Ok(Some(body)) => view.prune(block, parent, &body).await,
+						Ok(None) =>
+							log::warn!(target: LOG_TARGET, "Missing body of block {:?}", block),
+						Err(e) => log::warn!(
+							target: LOG_TARGET,
+							"Failed to fetch body of block {:?}: {}",
+							block,
+							e
+						),
+					}
+				}
+				self.revalidate(&view).await;
+			},
+			None => {
+				let transactions = self.mempool.transactions();
+				for source in [
+					TransactionSource::InBlock,
+					TransactionSource::Local,
+					TransactionSource::External,
+				] {
+					let xts = transactions
+						.iter()
+						.filter(|(hash, tx)| {
+							tx.source == source && !included_on_fork.contains(hash)
+						})
+						.map(|(_, tx)| tx.tx.clone())
+						.collect::<Vec<_>>();
+					if !xts.is_empty() {
+						view.submit_many(source, xts).await;
+					}
+				}
+			},
+		}
+
+		let ancestors = route
+			.iter()
+			.map(|block| block.hash)
+			.filter(|block| *block != hash && Some(*block) != keep)
+			.collect::<Vec<_>>();
+		self.view_store.remove(&ancestors);
+		Some(view)
+	}
+
+	/// Validate the ready transactions of a new view again, in the background if possible.
+	async fn revalidate(&self, view: &Arc<View<ChainApi>>) {
+		match &self.revalidation {
+			Some(revalidation) =>
+				if let Err(e) = revalidation.unbounded_send(Arc::downgrade(view)) {
+					log::warn!(target: LOG_TARGET, "Failed to queue view revalidation: {:?}", e);
+				},
+			None => view.revalidate(self.api.clone()).await,
+		}
+	}
+
+	/// Make the view at the given block the best one, creating it if needed.
+	async fn handle_new_best_block(&self, hash: Block::Hash) {
+		let view = match self.build_view(hash, None).await {
+			Some(view) => view,
+			None => return,
+		};
+		self.view_store.set_best(hash);
+		self.enforce_view_limit();
+
+		self.update_watchers();
+		self.ready_poll.lock().trigger(&view, true);
+		self.purge_invalid(self.mempool.transactions().into_iter().map(|(hash, _)| hash));
+	}
+
+	/// Create the view at a block which did not become the best block, so that the
+	/// transactions included on its fork are reported.
+	async fn handle_imported_block(&self, hash: Block::Hash) {
+		let best = self.view_store.best_view().map(|view| view.at.hash);
+		let view = match self.build_view(hash, best).await {
+			Some(view) => view,
+			None => return,
+		};
+		self.enforce_view_limit();
+
+		self.ready_poll.lock().trigger(&view, false);
+	}
+
+	/// Report the transactions of the finalized blocks and prune the forks which can no longer
+	/// become canonical.
+	async fn handle_finalized(&self, hash: Block::Hash) {
+		let previous = self.finalized.lock().clone();
+		let finalized_route = match self.api.tree_route(previous.hash, hash) {
+			Ok(tree_route) => tree_route,
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Failed to compute tree route to {:?}: {}", hash, e);
+				return
+			},
+		};
+		let finalized = match finalized_route.enacted().last() {
+			Some(finalized) => finalized.clone(),
+			None => return,
+		};
+
+		let mut finalized_transactions = Vec::new();
+		for block in finalized_route.enacted() {
+			for (tx, index) in self.included_transactions(block).await {
+				self.listener.finalized(&tx, block.hash, index);
+				finalized_transactions.push(tx);
+			}
+		}
+		self.mempool.remove(&finalized_transactions);
+		*self.finalized.lock() = finalized.clone();
+
+		let is_descendant = |block: Block::Hash| {
+			self.api
+				.tree_route(finalized.hash, block)
+				.map_or(false, |tree_route| tree_route.retracted().is_empty())
+		};
+
+		let stale_views = self
+			.view_store
+			.views()
+			.iter()
+			.map(|view| view.at.hash)
+			.filter(|block| !is_descendant(*block))
+			.collect::<Vec<_>>();
+		self.view_store.remove(&stale_views);
+
+		let finalized_blocks =
+			finalized_route.enacted().iter().map(|block| block.hash).collect::<HashSet<_>>();
+		let tracked_blocks = self
+			.included
+			.lock()
+			.iter()
+			.map(|(hash, (number, _))| (*hash, *number))
+			.collect::<Vec<_>>();
+		for (block, number) in tracked_blocks {
+			if number > finalized.number && is_descendant(block) {
+				continue
+			}
+			let included = self.included.lock().remove(&block);
+			if finalized_blocks.contains(&block) {
+				continue
+			}
+			// The block is on a pruned fork, its transactions are back to pending.
+			for (tx, _) in included.into_iter().flat_map(|(_, included)| included) {
+				if self.mempool.contains(&tx) {
+					self.listener.retracted(&tx, block);
+				}
+			}
+		}
+
+		if self.view_store.is_empty() {
+			self.handle_new_best_block(finalized.hash).await;
+		}
+		self.update_watchers();
+		self.purge_invalid(self.mempool.transactions().into_iter().map(|(hash, _)| hash));
+	}
+
+	/// Drop the views at the lowest fork tips if there are too many.
+	fn enforce_view_limit(&self) {
+		let mut views = self.view_store.views();
+		if views.len() <= MAX_VIEWS {
+			return
+		}
+		// Keep the best view, which is the first one.
+		views[1..].sort_by_key(|view| view.at.number);
+		let excess = views.len() - MAX_VIEWS;
+		let dropped = views[1..].iter().take(excess).map(|view| view.at.hash).collect::<Vec<_>>();
+		log::debug!(target: LOG_TARGET, "Dropping views at {:?}", dropped);
+		self.view_store.remove(&dropped);
+	}
+
+	/// Report the readiness of watched transactions in the best view.
+	fn update_watchers(&self) {
+		let best = match self.view_store.best_view() {
+			Some(best) => best,
+			None => return,
+		};
+		for hash in self.listener.watched() {
+			if let Some(is_ready) = best.is_ready(&hash) {
+				self.listener.set_ready(&hash, is_ready);
+			}
+		}
+	}
+
+	/// Remove the given mempool transactions which are neither in any view nor included in any
+	/// tracked block, reporting them as invalid.
+	fn purge_invalid(&self, candidates: impl IntoIterator<Item = ExtrinsicHash<ChainApi>>) {
+		let included = self
+			.included
+			.lock()
+			.values()
+			.flat_map(|(_, included)| included.iter().map(|(hash, _)| *hash))
+			.collect::<HashSet<_>>();
+		let invalid = candidates
+			.into_iter()
+			.filter(|hash| {
+				self.mempool.contains(hash) &&
+					!included.contains(hash) &&
+					self.view_store.is_ready(hash).is_none()
+			})
+			.collect::<Vec<_>>();
+		if invalid.is_empty() {
+			return
+		}
+
+		log::debug!(target: LOG_TARGET, "Removing {} invalid transactions", invalid.len());
+		self.metrics
+			.report(|metrics| metrics.validations_invalid.inc_by(invalid.len() as u64));
+		for hash in &invalid {
+			self.listener.invalid(hash);
+		}
+		self.mempool.remove(&invalid);
+	}
+}
+
+/// Revalidate the views received from the pool, for as long as the pool exists.
+async fn revalidate_views<ChainApi: graph::ChainApi>(
+	api: Arc<ChainApi>,
+	mut views: TracingUnboundedReceiver<Weak<View<ChainApi>>>,
+) {
+	while let Some(view) = views.next().await {
+		// Views dropped in the meantime are not worth revalidating.
+		if let Some(view) = view.upgrade() {
+			view.revalidate(api.clone()).await;
+		}
+	}
+}
+
+/// Add the transactions to the mempool and validate them against every view.
+///
+/// Errors are taken from the view at `at` if there is one. Transactions rejected by all views are
+/// removed from the mempool again.
+async fn submit<ChainApi: graph::ChainApi>(
+	mempool: &TxMemPool<ChainApi>,
+	view_store: &ViewStore<ChainApi>,
+	sinks: &Mutex<Vec<mpsc::Sender<ExtrinsicHash<ChainApi>>>>,
+	at: Option<BlockHash<ChainApi>>,
+	source: TransactionSource,
+	xts: Vec<ExtrinsicFor<ChainApi>>,
+) -> Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>> {
+	let pushed = mempool.push(source, &xts);
+	let to_submit = xts
+		.into_iter()
+		.zip(pushed.iter())
+		.filter_map(|(xt, pushed)| pushed.is_ok().then_some(xt))
+		.collect::<Vec<_>>();
+	let mut submitted = view_store.submit(at, source, &to_submit).await.into_iter();
+
+	let mut rejected = Vec::new();
+	let results = pushed
+		.into_iter()
+		.map(|pushed| {
+			let hash = pushed?;
+			let result = submitted.next().expect("One result per submitted transaction; qed");
+			match result {
+				Ok(_) if view_store.is_ready(&hash) == Some(true) => notify_import(sinks, hash),
+				Ok(_) => (),
+				Err(_) => rejected.push(hash),
+			}
+			result
+		})
+		.collect();
+	mempool.remove(&rejected);
+	results
+}
+
+/// The content of the pool, as persisted.
+struct MempoolContent<ChainApi: graph::ChainApi> {
+	mempool: Arc<TxMemPool<ChainApi>>,
+	view_store: Arc<ViewStore<ChainApi>>,
+	import_notification_sinks: Arc<Mutex<Vec<mpsc::Sender<ExtrinsicHash<ChainApi>>>>>,
+}
+
+#[async_trait]
+impl<ChainApi: graph::ChainApi> PersistedPool for MempoolContent<ChainApi> {
+	type Extrinsic = ExtrinsicFor<ChainApi>;
+
+	fn transactions(&self) -> Vec<(TransactionSource, Self::Extrinsic)> {
+		self.mempool
+			.transactions()
+			.into_iter()
+			.map(|(_, tx)| (tx.source, tx.tx.clone()))
+			.collect()
+	}
+
+	/// Transactions are validated against every view.
+	async fn resubmit(&self, source: TransactionSource, xts: Vec<Self::Extrinsic>) -> usize {
+		submit(&self.mempool, &self.view_store, &self.import_notification_sinks, None, source, xts)
+			.await
+			.iter()
+			.filter(|result| result.is_ok())
+			.count()
+	}
+}
+
+/// Notify the import notification sinks about a ready transaction.
+fn notify_import<Hash: Copy + std::fmt::Debug>(sinks: &Mutex<Vec<mpsc::Sender<Hash>>>, hash: Hash) {
+	sinks.lock().retain_mut(|sink| match sink.try_send(hash) {
+		Ok(()) => true,
+		Err(e) =>
+			if e.is_full() {
+				log::warn!(
+					target: LOG_TARGET,
+					"[{:?}] Trying to notify an import but the channel is full",
+					hash,
+				);
+				true
+			} else {
+				false
+			},
+	});
+}
+
+impl<ChainApi, Block> TransactionPool for ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: 'static + graph::ChainApi<Block = Block>,
+{
+	type Block = ChainApi::Block;
+	type Hash = ExtrinsicHash<ChainApi>;
+	type InPoolTransaction = graph::base_pool::Transaction<TxHash<Self>, TransactionFor<Self>>;
+	type Error = ChainApi::Error;
+
+	/// Transactions are validated against every view, the view at `at` being built first if
+	/// needed. Errors are taken from that view.
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		self.metrics
+			.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
+
+		self.submit(at, source, xts)
+	}
+
+	/// The transaction is validated against every view, the view at `at` being built first if
+	/// needed. Errors are taken from that view.
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		self.submit(at, source, vec![xt]).map(|results| results?.remove(0)).boxed()
+	}
+
+	/// The transaction is validated against every view, the view at `at` being built first if
+	/// needed. Errors are taken from that view.
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		let submit = self.submit(at, source, vec![xt]);
+		let listener = self.listener.clone();
+		let view_store = self.view_store.clone();
+
+		async move {
+			let hash = submit.await?.remove(0)?;
+			let watcher = listener.create_watcher(hash);
+			if let Some(is_ready) = view_store.is_ready(&hash) {
+				listener.set_ready(&hash, is_ready);
+			}
+
+			Ok(watcher.into_stream().boxed())
+		}
+		.boxed()
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		let mut removed = HashMap::new();
+		for view in self.view_store.views() {
+			for tx in view.pool.validated_pool().remove_invalid(hashes) {
+				removed.entry(tx.hash).or_insert(tx);
+			}
+		}
+
+		let removed_hashes = removed.keys().copied().collect::<Vec<_>>();
+		for hash in &removed_hashes {
+			self.listener.invalid(hash);
+		}
+		self.mempool.remove(hashes);
+		self.mempool.remove(&removed_hashes);
+
+		self.metrics
+			.report(|metrics| metrics.validations_invalid.inc_by(removed.len() as u64));
+		removed.into_values().collect()
+	}
+
+	/// Status of the best view.
+	fn status(&self) -> PoolStatus {
+		self.view_store
+			.best_view()
+			.map_or(PoolStatus { ready: 0, ready_bytes: 0, future: 0, future_bytes: 0 }, |view| {
+				view.pool.validated_pool().status()
+			})
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		const CHANNEL_BUFFER_SIZE: usize = 1024;
+
+		let (sink, stream) = mpsc::channel(CHANNEL_BUFFER_SIZE);
+		self.import_notification_sinks.lock().push(sink);
+		stream
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.api.hash_and_length(xt).0
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.listener.broadcasted(propagations)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.view_store.best_view()?.pool.validated_pool().ready_by_hash(hash)
+	}
+
+	fn ready_at(&self, at: NumberFor<Self::Block>) -> PolledIterator<ChainApi> {
+		let view = self
+			.view_store
+			.view_at_number(at)
+			.or_else(|| self.view_store.best_view().filter(|view| view.at.number >= at));
+		match view {
+			Some(view) => {
+				let iterator = Self::ready_of(&view);
+				async move { iterator }.boxed()
+			},
+			None => self.poll_ready(None, at),
+		}
+	}
+
+	fn ready_at_block(
+		&self,
+		at: Block::Hash,
+		number: NumberFor<Self::Block>,
+	) -> PolledIterator<ChainApi> {
+		if let Some(view) = self.view_store.get(&at) {
+			let iterator = Self::ready_of(&view);
+			return async move { iterator }.boxed()
+		}
+
+		// The view is built from the view of the closest ancestor, which also covers blocks
+		// whose view was superseded by a descendant one. Blocks the pool can't build a view at
+		// yet, e.g. because they are not imported, are waited for, unless they are on a pruned
+		// fork.
+		let pool = self
+			.this
+			.upgrade()
+			.expect("The pool is alive while its methods are called; qed");
+		async move {
+			let best = pool.view_store.best_view().map(|view| view.at.hash);
+			match pool.build_view(at, best).await {
+				Some(view) => {
+					pool.enforce_view_limit();
+					Self::ready_of(&view)
+				},
+				None if number <= pool.finalized.lock().number => {
+					log::debug!(target: LOG_TARGET, "No view at {:?}, below the finalized block", at);
+					Box::new(std::iter::empty())
+				},
+				None => pool.poll_ready(Some(at), number).await,
+			}
+		}
+		.boxed()
+	}
+
+	/// Ready transactions of the best view.
+	fn ready(&self) -> ReadyIteratorFor<ChainApi> {
+		match self.view_store.best_view() {
+			Some(view) => Self::ready_of(&view),
+			None => Box::new(std::iter::empty()),
+		}
+	}
+}
+
+impl<ChainApi, Block> Drop for ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block>,
+{
+	fn drop(&mut self) {
+		if let Some(persistence) = &self.persistence {
+			let transactions = self.mempool.transactions();
+			persistence.save(
+				&transactions
+					.iter()
+					.map(|(_, tx)| (tx.source, tx.tx.clone()))
+					.collect::<Vec<_>>(),
+			);
+		}
+	}
+}
+
+#[async_trait]
+impl<ChainApi, Block> MaintainedTransactionPool for ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: 'static + graph::ChainApi<Block = Block>,
+{
+	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		let start = Instant::now();
+		match event {
+			ChainEvent::NewBestBlock { hash, .. } => self.handle_new_best_block(hash).await,
+			ChainEvent::Finalized { hash, .. } => self.handle_finalized(hash).await,
+		}
+		log::trace!(
+			target: LOG_TARGET,
+			"Maintained {} views and {} transactions in {:?}",
+			self.view_store.len(),
+			self.mempool.len(),
+			start.elapsed(),
+		);
+	}
+
+	async fn on_block_imported(&self, hash: Block::Hash) {
+		self.handle_imported_block(hash).await
+	}
+}
+
+impl<Block, Client> FullForkAwarePool<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sc_client_api::ExecutorProvider<Block>
+		+ sc_client_api::UsageProvider<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	/// Create new fork aware transaction pool for a full node with the provided api.
+	pub fn new_full(
+		options: graph::Options,
+		is_validator: IsValidator,
+		prometheus: Option<&PrometheusRegistry>,
+		spawner: impl SpawnEssentialNamed,
+		client: Arc<Client>,
+	) -> Arc<Self> {
+		let api = Arc::new(FullChainApi::new(client.clone(), prometheus, &spawner));
+		let info = client.usage_info().chain;
+		let pool = Self::new(
+			options,
+			is_validator,
+			api,
+			prometheus,
+			Some(&spawner),
+			HashAndNumber { hash: info.best_hash, number: info.best_number },
+			HashAndNumber { hash: info.finalized_hash, number: info.finalized_number },
+		);
+
+		// make transaction pool available for off-chain runtime calls.
+		client.execution_extensions().register_transaction_pool(&pool);
+
+		pool
+	}
+}
+
+impl<Block, Client> sc_transaction_pool_api::LocalTransactionPool
+	for ForkAwareTxPool<FullChainApi<Client, Block>, Block>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	Client: Send + Sync + 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	type Block = Block;
+	type Hash = ExtrinsicHash<FullChainApi<Client, Block>>;
+	type Error = <FullChainApi<Client, Block> as graph::ChainApi>::Error;
+
+	/// The transaction is validated against every view, `at` is ignored.
+	fn submit_local(
+		&self,
+		_at: &BlockId<Self::Block>,
+		xt: sc_transaction_pool_api::LocalTransactionFor<Self>,
+	) -> Result<Self::Hash, Self::Error> {
+		use sp_runtime::{
+			traits::SaturatedConversion, transaction_validity::TransactionValidityError,
+		};
+
+		let hash = self
+			.mempool
+			.push(TransactionSource::Local, std::slice::from_ref(&xt))
+			.remove(0)?;
+		let bytes = graph::ChainApi::hash_and_length(&*self.api, &xt).1;
+
+		// Errors are taken from the best view, which comes first.
+		let mut result: Option<Result<Self::Hash, Self::Error>> = None;
+		for view in self.view_store.views() {
+			let submitted = self
+				.api
+				.validate_transaction_blocking(
+					&BlockId::Hash(view.at.hash),
+					TransactionSource::Local,
+					xt.clone(),
+				)
+				.and_then(|validity| {
+					validity.map_err(|e| {
+						error::Error::Pool(match e {
+							TransactionValidityError::Invalid(i) =>
+								TxPoolError::InvalidTransaction(i),
+							TransactionValidityError::Unknown(u) =>
+								TxPoolError::UnknownTransaction(u),
+						})
+					})
+				})
+				.and_then(|validity| {
+					let validated = ValidatedTransaction::valid_at(
+						view.at.number.saturated_into::<u64>(),
+						hash,
+						TransactionSource::Local,
+						xt.clone(),
+						bytes,
+						validity,
+					);
+					view.pool.validated_pool().submit(vec![validated]).remove(0)
+				});
+
+			match (result.as_ref(), submitted) {
+				(Some(Ok(_)), _) => (),
+				(_, Ok(hash)) => result = Some(Ok(hash)),
+				(None, Err(e)) => result = Some(Err(e)),
+				(Some(Err(_)), Err(_)) => (),
+			}
+		}
+
+		match result.unwrap_or_else(|| Err(TxPoolError::ImmediatelyDropped.into())) {
+			Ok(hash) => {
+				if self.view_store.is_ready(&hash) == Some(true) {
+					notify_import(&self.import_notification_sinks, hash);
+				}
+				Ok(hash)
+			},
+			Err(e) => {
+				self.mempool.remove(&[hash]);
+				Err(e)
+			},
+		}
+	}
+}
@@ -0,0 +1,135 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Status notifications of transactions tracked across several views.
+
+use crate::graph::{
+	self,
+	watcher::{Sender, Watcher},
+	BlockHash, ExtrinsicHash,
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// State of a single watched transaction.
+struct WatchedTx<ChainApi: graph::ChainApi> {
+	sender: Sender<ExtrinsicHash<ChainApi>, BlockHash<ChainApi>>,
+	/// Last reported readiness, `None` if neither `Ready` nor `Future` was reported since the
+	/// last inclusion.
+	is_ready: Option<bool>,
+}
+
+/// Dispatches status updates to the watchers of transactions.
+///
+/// Views come and go with the forks they track, so the events of watched transactions are
+/// reported from the perspective of the whole pool: `Ready` and `Future` follow the best view,
+/// `InBlock` is reported for each fork including the transaction, and terminal events are only
+/// reported once the transaction is gone from every view.
+pub(crate) struct MultiViewListener<ChainApi: graph::ChainApi> {
+	watched: Mutex<HashMap<ExtrinsicHash<ChainApi>, WatchedTx<ChainApi>>>,
+}
+
+impl<ChainApi: graph::ChainApi> MultiViewListener<ChainApi> {
+	/// Create a new listener.
+	pub fn new() -> Self {
+		Self { watched: Default::default() }
+	}
+
+	/// Start watching the given transaction.
+	pub fn create_watcher(
+		&self,
+		hash: ExtrinsicHash<ChainApi>,
+	) -> Watcher<ExtrinsicHash<ChainApi>, BlockHash<ChainApi>> {
+		self.watched
+			.lock()
+			.entry(hash)
+			.or_insert_with(|| WatchedTx { sender: Default::default(), is_ready: None })
+			.sender
+			.new_watcher(hash)
+	}
+
+	/// Returns the hashes of all watched transactions.
+	pub fn watched(&self) -> Vec<ExtrinsicHash<ChainApi>> {
+		self.watched.lock().keys().copied().collect()
+	}
+
+	/// The transaction is ready (`true`) or in the future queue (`false`) of the best view.
+	///
+	/// Nothing is reported if the readiness did not change.
+	pub fn set_ready(&self, hash: &ExtrinsicHash<ChainApi>, is_ready: bool) {
+		self.notify(hash, |tx| {
+			if tx.is_ready != Some(is_ready) {
+				tx.is_ready = Some(is_ready);
+				if is_ready {
+					tx.sender.ready()
+				} else {
+					tx.sender.future()
+				}
+			}
+		})
+	}
+
+	/// The transaction has been included in the given block.
+	pub fn in_block(
+		&self,
+		hash: &ExtrinsicHash<ChainApi>,
+		block: BlockHash<ChainApi>,
+		index: usize,
+	) {
+		self.notify(hash, |tx| {
+			tx.is_ready = None;
+			tx.sender.in_block(block, index)
+		})
+	}
+
+	/// The block including the transaction has been discarded by finality.
+	pub fn retracted(&self, hash: &ExtrinsicHash<ChainApi>, block: BlockHash<ChainApi>) {
+		self.notify(hash, |tx| tx.sender.retracted(block))
+	}
+
+	/// The block including the transaction has been finalized.
+	pub fn finalized(
+		&self,
+		hash: &ExtrinsicHash<ChainApi>,
+		block: BlockHash<ChainApi>,
+		index: usize,
+	) {
+		self.notify(hash, |tx| tx.sender.finalized(block, index))
+	}
+
+	/// The transaction is not valid on any tracked fork.
+	pub fn invalid(&self, hash: &ExtrinsicHash<ChainApi>) {
+		self.notify(hash, |tx| tx.sender.invalid())
+	}
+
+	/// The transactions have been broadcast to the given peers.
+	pub fn broadcasted(&self, propagated: HashMap<ExtrinsicHash<ChainApi>, Vec<String>>) {
+		for (hash, peers) in propagated {
+			self.notify(&hash, |tx| tx.sender.broadcast(peers))
+		}
+	}
+
+	fn notify(&self, hash: &ExtrinsicHash<ChainApi>, f: impl FnOnce(&mut WatchedTx<ChainApi>)) {
+		let mut watched = self.watched.lock();
+		if let Some(tx) = watched.get_mut(hash) {
+			f(tx);
+			if tx.sender.is_done() {
+				watched.remove(hash);
+			}
+		}
+	}
+}
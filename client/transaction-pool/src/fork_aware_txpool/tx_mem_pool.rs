@@ -0,0 +1,103 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Transactions known to the pool, independently of the fork they are valid on.
+
+use crate::graph::{self, ExtrinsicFor, ExtrinsicHash};
+use parking_lot::RwLock;
+use sc_transaction_pool_api::error;
+use sp_runtime::transaction_validity::TransactionSource;
+use std::{collections::HashMap, sync::Arc};
+
+/// A transaction known to the pool.
+pub(crate) struct TxInMemPool<ChainApi: graph::ChainApi> {
+	/// Where the transaction came from.
+	pub source: TransactionSource,
+	/// The transaction itself.
+	pub tx: ExtrinsicFor<ChainApi>,
+	/// Encoded size of the transaction.
+	pub bytes: usize,
+}
+
+/// All transactions submitted to the pool and not yet finalized or found invalid.
+///
+/// Views are built from this set: every transaction in it is validated against every tracked
+/// fork tip.
+pub(crate) struct TxMemPool<ChainApi: graph::ChainApi> {
+	api: Arc<ChainApi>,
+	transactions: RwLock<HashMap<ExtrinsicHash<ChainApi>, Arc<TxInMemPool<ChainApi>>>>,
+	max_count: usize,
+	max_bytes: usize,
+}
+
+impl<ChainApi: graph::ChainApi> TxMemPool<ChainApi> {
+	/// Create a new mempool holding at most `max_count` transactions of `max_bytes` total size.
+	pub fn new(api: Arc<ChainApi>, max_count: usize, max_bytes: usize) -> Self {
+		Self { api, transactions: Default::default(), max_count, max_bytes }
+	}
+
+	/// Add transactions to the mempool.
+	///
+	/// Transactions which are already known or do not fit are rejected.
+	pub fn push(
+		&self,
+		source: TransactionSource,
+		xts: &[ExtrinsicFor<ChainApi>],
+	) -> Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>> {
+		let mut transactions = self.transactions.write();
+		let mut total_bytes = transactions.values().map(|tx| tx.bytes).sum::<usize>();
+
+		xts.iter()
+			.map(|xt| {
+				let (hash, bytes) = self.api.hash_and_length(xt);
+				if transactions.contains_key(&hash) {
+					return Err(error::Error::AlreadyImported(Box::new(hash)).into())
+				}
+				if transactions.len() >= self.max_count || total_bytes + bytes > self.max_bytes {
+					return Err(error::Error::ImmediatelyDropped.into())
+				}
+
+				total_bytes += bytes;
+				transactions.insert(hash, Arc::new(TxInMemPool { source, tx: xt.clone(), bytes }));
+				Ok(hash)
+			})
+			.collect()
+	}
+
+	/// Remove the given transactions.
+	pub fn remove(&self, hashes: &[ExtrinsicHash<ChainApi>]) {
+		let mut transactions = self.transactions.write();
+		for hash in hashes {
+			transactions.remove(hash);
+		}
+	}
+
+	/// Returns whether the transaction is known.
+	pub fn contains(&self, hash: &ExtrinsicHash<ChainApi>) -> bool {
+		self.transactions.read().contains_key(hash)
+	}
+
+	/// Returns all known transactions.
+	pub fn transactions(&self) -> Vec<(ExtrinsicHash<ChainApi>, Arc<TxInMemPool<ChainApi>>)> {
+		self.transactions.read().iter().map(|(hash, tx)| (*hash, tx.clone())).collect()
+	}
+
+	/// Returns the number of known transactions.
+	pub fn len(&self) -> usize {
+		self.transactions.read().len()
+	}
+}
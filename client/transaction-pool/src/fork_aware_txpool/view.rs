@@ -0,0 +1,107 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! A view of the pool at a single block.
+
+use crate::{
+	graph::{self, BlockHash, ExtrinsicFor, ExtrinsicHash, IsValidator},
+	revalidation, LOG_TARGET,
+};
+use futures::future;
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{generic::BlockId, transaction_validity::TransactionSource};
+use std::sync::Arc;
+
+/// The pool content validated against the state of a single block.
+///
+/// A view is created for every tracked fork tip and holds the ready and future queues a block
+/// built on top of that tip would see.
+pub(crate) struct View<ChainApi: graph::ChainApi> {
+	/// The block the transactions are validated at.
+	pub at: HashAndNumber<ChainApi::Block>,
+	/// Transactions valid at `at`.
+	pub pool: Arc<graph::Pool<ChainApi>>,
+}
+
+impl<ChainApi: graph::ChainApi> View<ChainApi> {
+	/// Create an empty view at the given block.
+	pub fn new(
+		api: Arc<ChainApi>,
+		at: HashAndNumber<ChainApi::Block>,
+		options: graph::Options,
+		is_validator: IsValidator,
+	) -> Self {
+		Self { at, pool: Arc::new(graph::Pool::new(options, is_validator, api)) }
+	}
+
+	/// Validate the given transactions at the view's block and import the valid ones.
+	///
+	/// Results are returned in the order of `xts`.
+	pub async fn submit_many(
+		&self,
+		source: TransactionSource,
+		xts: impl IntoIterator<Item = ExtrinsicFor<ChainApi>>,
+	) -> Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>> {
+		let at = BlockId::Hash(self.at.hash);
+		future::join_all(xts.into_iter().map(|xt| self.pool.submit_one(&at, source, xt))).await
+	}
+
+	/// Import the transactions of another view as they are, without validating them again.
+	///
+	/// Used with the view of an ancestor block, only the transactions affected by the blocks in
+	/// between then need to be checked, see [`View::prune`].
+	pub fn import_from(&self, other: &Self) {
+		self.pool
+			.validated_pool()
+			.submit(other.pool.validated_pool().duplicate_transactions());
+	}
+
+	/// Remove the transactions included in `block` and the ones they make invalid.
+	///
+	/// Only the transactions depending on the included ones are validated again, at `block`.
+	pub async fn prune(
+		&self,
+		block: BlockHash<ChainApi>,
+		parent: BlockHash<ChainApi>,
+		extrinsics: &[ExtrinsicFor<ChainApi>],
+	) {
+		let result =
+			self.pool.prune(&BlockId::Hash(block), &BlockId::Hash(parent), extrinsics).await;
+		if let Err(e) = result {
+			log::warn!(target: LOG_TARGET, "Failed to prune view at {:?}: {}", block, e);
+		}
+	}
+
+	/// Validate the ready transactions again at the view's block, removing the invalid ones.
+	pub async fn revalidate(&self, api: Arc<ChainApi>) {
+		let hashes = self.pool.validated_pool().ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		revalidation::batch_revalidate_at(
+			self.pool.clone(),
+			api,
+			&BlockId::Hash(self.at.hash),
+			self.at.number,
+			hashes,
+		)
+		.await
+	}
+
+	/// Returns whether the transaction is ready (`Some(true)`), in the future queue
+	/// (`Some(false)`) or not part of this view.
+	pub fn is_ready(&self, hash: &ExtrinsicHash<ChainApi>) -> Option<bool> {
+		self.pool.validated_pool().is_ready(hash)
+	}
+}
@@ -0,0 +1,144 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! The set of views tracked by the pool.
+
+use super::view::View;
+use crate::graph::{self, BlockHash, ExtrinsicFor, ExtrinsicHash, NumberFor};
+use futures::future;
+use parking_lot::RwLock;
+use sp_runtime::transaction_validity::TransactionSource;
+use std::{collections::HashMap, sync::Arc};
+
+/// Views of the pool at every tracked fork tip.
+pub(crate) struct ViewStore<ChainApi: graph::ChainApi> {
+	views: RwLock<HashMap<BlockHash<ChainApi>, Arc<View<ChainApi>>>>,
+	/// Block of the view following the best block.
+	best: RwLock<Option<BlockHash<ChainApi>>>,
+}
+
+impl<ChainApi: graph::ChainApi> ViewStore<ChainApi> {
+	/// Create an empty store.
+	pub fn new() -> Self {
+		Self { views: Default::default(), best: Default::default() }
+	}
+
+	/// Add a view, replacing any existing view at the same block.
+	pub fn insert(&self, view: Arc<View<ChainApi>>) {
+		self.views.write().insert(view.at.hash, view);
+	}
+
+	/// Remove the views at the given blocks.
+	pub fn remove(&self, hashes: &[BlockHash<ChainApi>]) {
+		let mut views = self.views.write();
+		for hash in hashes {
+			views.remove(hash);
+		}
+		let mut best = self.best.write();
+		if best.map_or(false, |best| !views.contains_key(&best)) {
+			*best = None;
+		}
+	}
+
+	/// Returns the view at the given block.
+	pub fn get(&self, hash: &BlockHash<ChainApi>) -> Option<Arc<View<ChainApi>>> {
+		self.views.read().get(hash).cloned()
+	}
+
+	/// Returns all views, the best one first.
+	pub fn views(&self) -> Vec<Arc<View<ChainApi>>> {
+		let best = *self.best.read();
+		let mut views = self.views.read().values().cloned().collect::<Vec<_>>();
+		views.sort_by_key(|view| Some(view.at.hash) != best);
+		views
+	}
+
+	/// Returns the number of views.
+	pub fn len(&self) -> usize {
+		self.views.read().len()
+	}
+
+	/// Returns whether there is no view.
+	pub fn is_empty(&self) -> bool {
+		self.views.read().is_empty()
+	}
+
+	/// Mark the view at the given block as the best one.
+	pub fn set_best(&self, hash: BlockHash<ChainApi>) {
+		*self.best.write() = Some(hash);
+	}
+
+	/// Returns the view following the best block.
+	pub fn best_view(&self) -> Option<Arc<View<ChainApi>>> {
+		self.best.read().and_then(|best| self.get(&best))
+	}
+
+	/// Returns a view at the given height, preferring the best one.
+	pub fn view_at_number(&self, number: NumberFor<ChainApi>) -> Option<Arc<View<ChainApi>>> {
+		self.best_view()
+			.filter(|view| view.at.number == number)
+			.or_else(|| self.views.read().values().find(|view| view.at.number == number).cloned())
+	}
+
+	/// Returns whether the transaction is ready (`Some(true)`) or in the future queue
+	/// (`Some(false)`), as seen by the best view if it knows the transaction, or by any other
+	/// view otherwise. Returns `None` if no view knows the transaction.
+	pub fn is_ready(&self, hash: &ExtrinsicHash<ChainApi>) -> Option<bool> {
+		self.views().iter().find_map(|view| view.is_ready(hash))
+	}
+
+	/// Submit transactions to every view.
+	///
+	/// A transaction is accepted if it is accepted by at least one view. Otherwise the error
+	/// reported by the view at `at` is returned, or by the best view if there is none at `at`.
+	pub async fn submit(
+		&self,
+		at: Option<BlockHash<ChainApi>>,
+		source: TransactionSource,
+		xts: &[ExtrinsicFor<ChainApi>],
+	) -> Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>> {
+		let mut views = self.views();
+		// Keeps the best view first otherwise, the sort being stable.
+		views.sort_by_key(|view| Some(view.at.hash) != at);
+		let results = future::join_all(
+			views.iter().map(|view| view.submit_many(source, xts.iter().cloned())),
+		)
+		.await;
+
+		// Errors are taken from the first view.
+		let mut aggregated: Vec<Option<Result<_, _>>> = xts.iter().map(|_| None).collect();
+		for view_results in results {
+			for (result, aggregated) in view_results.into_iter().zip(aggregated.iter_mut()) {
+				match (aggregated.as_ref(), result) {
+					(Some(Ok(_)), _) => (),
+					(_, Ok(hash)) => *aggregated = Some(Ok(hash)),
+					(None, Err(e)) => *aggregated = Some(Err(e)),
+					(Some(Err(_)), Err(_)) => (),
+				}
+			}
+		}
+
+		aggregated
+			.into_iter()
+			.map(|result| {
+				result.unwrap_or_else(|| {
+					Err(sc_transaction_pool_api::error::Error::ImmediatelyDropped.into())
+				})
+			})
+			.collect()
+	}
+}
@@ -33,6 +33,7 @@ use sp_runtime::{
 		TransactionLongevity as Longevity, TransactionPriority as Priority,
 		TransactionSource as Source, TransactionTag as Tag,
 	},
+	Percent,
 };
 
 use super::{
@@ -51,7 +52,7 @@ pub enum Imported<Hash, Ex> {
 		promoted: Vec<Hash>,
 		/// Transactions that failed to be promoted from the Future queue and are now discarded.
 		failed: Vec<Hash>,
-		/// Transactions removed from the Ready pool (replaced).
+		/// Transactions removed from the Ready pool (replaced by the imported one).
 		removed: Vec<Arc<Transaction<Hash, Ex>>>,
 	},
 	/// Transaction was successfully imported to Future queue.
@@ -223,17 +224,18 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
 	fn default() -> Self {
-		Self::new(false)
+		Self::new(false, Default::default())
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash, Ex> {
-	/// Create new pool given reject_future_transactions flag.
-	pub fn new(reject_future_transactions: bool) -> Self {
+	/// Create new pool given reject_future_transactions flag and the rules for replacing ready
+	/// transactions.
+	pub fn new(reject_future_transactions: bool, replacement_policy: ReplacementPolicy) -> Self {
 		Self {
 			reject_future_transactions,
 			future: Default::default(),
-			ready: Default::default(),
+			ready: ReadyTransactions::new(replacement_policy),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
 		}
@@ -530,6 +532,37 @@ impl Limit {
 	}
 }
 
+/// Rules for replacing ready transactions by a transaction providing the same tags.
+///
+/// By default the replacement needs a priority at least 10% higher than the transactions it
+/// replaces, and the transactions of a sender with a given nonce can be replaced at most 10 times.
+#[derive(Debug, Clone)]
+pub struct ReplacementPolicy {
+	/// Minimal priority increase of the replacement, relative to the total priority of the
+	/// transactions it replaces.
+	pub min_priority_bump: Percent,
+	/// Maximal number of times the transactions providing a tag can be replaced, if any.
+	///
+	/// Tags identify the sender and nonce of a transaction, so the count is kept per sender and
+	/// nonce. It is only reset once the tag is pruned, i.e. the nonce got included in a block.
+	pub max_replacements: Option<usize>,
+}
+
+impl Default for ReplacementPolicy {
+	fn default() -> Self {
+		Self { min_priority_bump: Percent::from_percent(10), max_replacements: Some(10) }
+	}
+}
+
+impl ReplacementPolicy {
+	/// Returns the minimal priority required to replace transactions of the given total priority.
+	///
+	/// The replacement always needs a strictly higher priority, even with no relative bump.
+	pub fn min_priority(&self, old: Priority) -> Priority {
+		old.saturating_add(self.min_priority_bump.mul_ceil(old).max(1))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
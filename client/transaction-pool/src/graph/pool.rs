@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use crate::LOG_TARGET;
 use futures::{channel::mpsc::Receiver, Future};
@@ -117,8 +117,25 @@ pub struct Options {
 	pub future: base::Limit,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// Rules for replacing ready transactions.
+	pub replacement_policy: base::ReplacementPolicy,
 	/// How long the extrinsic is banned for.
 	pub ban_time: Duration,
+	/// File the pool content is persisted to, so that it survives restarts.
+	pub persistence_path: Option<PathBuf>,
+	/// Implementation of the pool to use.
+	pub pool_type: TransactionPoolType,
+}
+
+/// Implementation of the transaction pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransactionPoolType {
+	/// Validate transactions against the best block only, see [`BasicPool`](crate::BasicPool).
+	#[default]
+	SingleState,
+	/// Validate transactions against every tracked fork, see
+	/// [`ForkAwareTxPool`](crate::ForkAwareTxPool).
+	ForkAware,
 }
 
 impl Default for Options {
@@ -127,7 +144,10 @@ impl Default for Options {
 			ready: base::Limit { count: 8192, total_bytes: 20 * 1024 * 1024 },
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
 			reject_future_transactions: false,
+			replacement_policy: Default::default(),
 			ban_time: Duration::from_secs(60 * 30),
+			persistence_path: None,
+			pool_type: Default::default(),
 		}
 	}
 }
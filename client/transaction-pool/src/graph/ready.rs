@@ -30,7 +30,7 @@ use serde::Serialize;
 use sp_runtime::{traits::Member, transaction_validity::TransactionTag as Tag};
 
 use super::{
-	base_pool::Transaction,
+	base_pool::{ReplacementPolicy, Transaction},
 	future::WaitingTransaction,
 	tracked_map::{self, TrackedMap},
 };
@@ -98,6 +98,10 @@ impl<Hash: Clone, Ex> Clone for ReadyTx<Hash, Ex> {
 	}
 }
 
+/// Number of replacement counters above which the counters of tags that are no longer provided by
+/// any ready transaction are forgotten.
+const MAX_REPLACEMENT_COUNTERS: usize = 8192;
+
 const HASH_READY: &str = r#"
 Every time transaction is imported its hash is placed in `ready` map and tags in `provided_tags`;
 Every time transaction is removed from the queue we remove the hash from `ready` map and from `provided_tags`;
@@ -118,6 +122,14 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	/// Best transactions that are ready to be included to the block without any other previous
 	/// transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Rules for replacing transactions providing the same tags.
+	replacement_policy: ReplacementPolicy,
+	/// Number of times the transactions providing a tag were replaced.
+	///
+	/// Tags identify the sender and nonce of a transaction (e.g. `(sender, nonce)` in FRAME),
+	/// so this counts the replacements per sender and nonce. Entries survive the removal of
+	/// the transactions and are only dropped once the tag is pruned, i.e. the nonce got included.
+	replacements: HashMap<Tag, usize>,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -128,11 +140,20 @@ impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
 
 impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 	fn default() -> Self {
+		Self::new(Default::default())
+	}
+}
+
+impl<Hash: hash::Hash + Eq, Ex> ReadyTransactions<Hash, Ex> {
+	/// Create an empty queue following the given replacement rules.
+	pub fn new(replacement_policy: ReplacementPolicy) -> Self {
 		Self {
 			insertion_id: Default::default(),
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			replacement_policy,
+			replacements: Default::default(),
 		}
 	}
 }
@@ -333,6 +354,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		let mut to_remove = vec![tag];
 
 		while let Some(tag) = to_remove.pop() {
+			self.replacements.remove(&tag);
 			let res = self
 				.provided_tags
 				.remove(&tag)
@@ -394,6 +416,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 				// mapping.
 				let current_tag = &tag;
 				for tag in &tx.provides {
+					self.replacements.remove(tag);
 					let removed = self.provided_tags.remove(tag);
 					assert_eq!(
 						removed.as_ref(),
@@ -413,7 +436,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	/// Checks if the transaction is providing the same tags as other transactions.
 	///
 	/// In case that's true it determines if the priority of transactions that
-	/// we are about to replace is lower than the priority of the replacement transaction.
+	/// we are about to replace is low enough for the replacement policy, and that the
+	/// transactions providing these tags were not replaced too many times already.
 	/// We remove/replace old transactions in case they have lower priority.
 	///
 	/// In case replacement is successful returns a list of removed transactions
@@ -422,7 +446,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		&mut self,
 		tx: &Transaction<Hash, Ex>,
 	) -> error::Result<(Vec<Arc<Transaction<Hash, Ex>>>, Vec<Hash>)> {
-		let (to_remove, unlocks) = {
+		let (to_remove, unlocks, replacements) = {
 			// check if we are replacing a transaction
 			let replace_hashes = tx
 				.provides
@@ -447,10 +471,22 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			if tx.priority < self.replacement_policy.min_priority(old_priority) {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
+			// bail - the old ones have already been replaced too many times
+			let replacements = tx
+				.provides
+				.iter()
+				.filter_map(|tag| self.replacements.get(tag))
+				.max()
+				.copied()
+				.unwrap_or(0);
+			if replacements >= self.replacement_policy.max_replacements.unwrap_or(usize::MAX) {
+				return Err(error::Error::TooManyReplacements)
+			}
+
 			// construct a list of unlocked transactions
 			let unlocks = {
 				let ready = self.ready.read();
@@ -463,11 +499,19 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 				)
 			};
 
-			(replace_hashes.into_iter().cloned().collect::<Vec<_>>(), unlocks)
+			(replace_hashes.into_iter().cloned().collect::<Vec<_>>(), unlocks, replacements)
 		};
 
 		let new_provides = tx.provides.iter().cloned().collect::<HashSet<_>>();
 		let removed = self.remove_subtree_with_tag_filter(to_remove, Some(new_provides));
+		if self.replacements.len() > MAX_REPLACEMENT_COUNTERS {
+			// only forget about the tags that are not provided by any ready transaction
+			let provided_tags = &self.provided_tags;
+			self.replacements.retain(|tag, _| provided_tags.contains_key(tag));
+		}
+		for tag in &tx.provides {
+			self.replacements.insert(tag.clone(), replacements + 1);
+		}
 
 		Ok((removed, unlocks))
 	}
@@ -678,6 +722,87 @@ mod tests {
 		assert_eq!(ready.get().count(), 3);
 	}
 
+	#[test]
+	fn should_require_minimum_priority_bump_for_replacement() {
+		// given
+		let policy = ReplacementPolicy {
+			min_priority_bump: sp_runtime::Percent::from_percent(50),
+			..Default::default()
+		};
+		let mut ready = ReadyTransactions::new(policy);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 10;
+		import(&mut ready, tx1).unwrap();
+
+		// when
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 14;
+		let err = import(&mut ready, tx2.clone()).unwrap_err();
+		tx2.priority = 15;
+		let replaced = import(&mut ready, tx2).unwrap();
+
+		// then
+		assert!(matches!(err, error::Error::TooLowPriority { old: 10, new: 14 }));
+		assert_eq!(replaced.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+	}
+
+	#[test]
+	fn should_limit_number_of_replacements() {
+		// given
+		let policy = ReplacementPolicy { max_replacements: Some(2), ..Default::default() };
+		let mut ready = ReadyTransactions::new(policy);
+		let with_priority = |id, priority| {
+			let mut tx = tx(id);
+			tx.requires.clear();
+			tx.priority = priority;
+			tx
+		};
+		import(&mut ready, with_priority(1, 10)).unwrap();
+		import(&mut ready, with_priority(2, 20)).unwrap();
+		import(&mut ready, with_priority(3, 30)).unwrap();
+
+		// when
+		let err = import(&mut ready, with_priority(4, 40)).unwrap_err();
+
+		// then
+		assert!(matches!(err, error::Error::TooManyReplacements));
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+
+		// once the tags are pruned the counter starts over
+		ready.prune_tags(vec![3]);
+		assert_eq!(ready.get().count(), 0);
+		import(&mut ready, with_priority(5, 10)).unwrap();
+		import(&mut ready, with_priority(6, 20)).unwrap();
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![6]);
+	}
+
+	#[test]
+	fn should_keep_replacement_count_after_removal() {
+		// given
+		let policy = ReplacementPolicy { max_replacements: Some(1), ..Default::default() };
+		let mut ready = ReadyTransactions::new(policy);
+		let with_priority = |id, priority| {
+			let mut tx = tx(id);
+			tx.requires.clear();
+			tx.priority = priority;
+			tx
+		};
+		import(&mut ready, with_priority(1, 10)).unwrap();
+		import(&mut ready, with_priority(2, 20)).unwrap();
+
+		// when
+		ready.remove_subtree(&[2]);
+		import(&mut ready, with_priority(3, 10)).unwrap();
+		let err = import(&mut ready, with_priority(4, 20)).unwrap_err();
+
+		// then
+		assert!(matches!(err, error::Error::TooManyReplacements));
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+	}
+
 	/// Populate the pool, with a graph that looks like so:
 	///
 	/// tx1 -> tx2 \
@@ -100,6 +100,13 @@ impl From<Box<dyn Fn() -> bool + Send + Sync>> for IsValidator {
 	}
 }
 
+impl IsValidator {
+	/// Returns whether the local node is currently a validator.
+	pub(crate) fn get(&self) -> bool {
+		(self.0)()
+	}
+}
+
 /// Pool that deals with validated transactions.
 pub struct ValidatedPool<B: ChainApi> {
 	api: Arc<B>,
@@ -114,7 +121,10 @@ pub struct ValidatedPool<B: ChainApi> {
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, is_validator: IsValidator, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let base_pool = base::BasePool::new(
+			options.reject_future_transactions,
+			options.replacement_policy.clone(),
+		);
 		let ban_time = options.ban_time;
 		Self {
 			is_validator,
@@ -263,7 +273,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			// run notifications
 			let mut listener = self.listener.write();
 			for h in &removed {
-				listener.dropped(h, None);
+				listener.dropped(h);
 			}
 
 			removed
@@ -423,7 +433,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				match final_status {
 					Status::Future => listener.future(&hash),
 					Status::Ready => listener.ready(&hash, None),
-					Status::Dropped => listener.dropped(&hash, None),
+					Status::Dropped => listener.dropped(&hash),
 					Status::Failed => listener.invalid(&hash),
 				}
 			}
@@ -462,7 +472,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				fire_events(&mut *listener, promoted);
 			}
 			for f in &status.failed {
-				listener.dropped(f, None);
+				listener.dropped(f);
 			}
 		}
 
@@ -625,6 +635,42 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().futures().map(|tx| (tx.hash, tx.data.clone())).collect()
 	}
 
+	/// Returns whether the transaction is in the ready (`Some(true)`) or in the future
+	/// (`Some(false)`) queue, or `None` if it is not in the pool.
+	pub fn is_ready(&self, hash: &ExtrinsicHash<B>) -> Option<bool> {
+		let pool = self.pool.read();
+		if pool.ready_by_hash(hash).is_some() {
+			Some(true)
+		} else if pool.is_imported(hash) {
+			Some(false)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the source and extrinsic of every transaction in the pool.
+	///
+	/// Ready transactions come first, in the order they could be included in a block.
+	pub fn transactions(&self) -> Vec<(TransactionSource, ExtrinsicFor<B>)> {
+		let pool = self.pool.read();
+		pool.ready()
+			.map(|tx| (tx.source, tx.data.clone()))
+			.chain(pool.futures().map(|tx| (tx.source, tx.data.clone())))
+			.collect()
+	}
+
+	/// Returns a copy of every transaction in the pool, keeping their validity.
+	///
+	/// Ready transactions come first, so that submitting the copies to another pool restores
+	/// both queues without calling into the runtime.
+	pub fn duplicate_transactions(&self) -> Vec<ValidatedTransactionFor<B>> {
+		let pool = self.pool.read();
+		pool.ready()
+			.map(|tx| ValidatedTransaction::Valid(tx.duplicate()))
+			.chain(pool.futures().map(|tx| ValidatedTransaction::Valid(tx.duplicate())))
+			.collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
@@ -656,7 +702,7 @@ where
 		base::Imported::Ready { ref promoted, ref failed, ref removed, ref hash } => {
 			listener.ready(hash, None);
 			failed.iter().for_each(|f| listener.invalid(f));
-			removed.iter().for_each(|r| listener.dropped(&r.hash, Some(hash)));
+			removed.iter().for_each(|r| listener.replaced(&r.hash, hash));
 			promoted.iter().for_each(|p| listener.ready(p, None));
 		},
 		base::Imported::Future { ref hash } => listener.future(hash),
@@ -77,9 +77,9 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.send(TransactionStatus::Future)
 	}
 
-	/// Some state change (perhaps another extrinsic was included) rendered this extrinsic invalid.
-	pub fn usurped(&mut self, hash: H) {
-		self.send(TransactionStatus::Usurped(hash));
+	/// Extrinsic has been replaced by the one with given hash.
+	pub fn replaced(&mut self, hash: H) {
+		self.send(TransactionStatus::Replaced(hash));
 		self.is_finalized = true;
 	}
 
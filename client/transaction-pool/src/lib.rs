@@ -25,8 +25,11 @@
 mod api;
 mod enactment_state;
 pub mod error;
+mod fork_aware_txpool;
 mod graph;
 mod metrics;
+mod persistence;
+mod pool_wrapper;
 mod revalidation;
 #[cfg(test)]
 mod tests;
@@ -34,15 +37,14 @@ mod tests;
 pub use crate::api::FullChainApi;
 use async_trait::async_trait;
 use enactment_state::{EnactmentAction, EnactmentState};
-use futures::{
-	channel::oneshot,
-	future::{self, ready},
-	prelude::*,
-};
+pub use fork_aware_txpool::{ForkAwareTxPool, FullForkAwarePool};
+use futures::{channel::oneshot, future, prelude::*};
 pub use graph::{
-	base_pool::Limit as PoolLimit, ChainApi, Options, Pool, Transaction, ValidatedTransaction,
+	base_pool::{Limit as PoolLimit, ReplacementPolicy},
+	ChainApi, Options, Pool, Transaction, TransactionPoolType, ValidatedTransaction,
 };
 use parking_lot::Mutex;
+pub use pool_wrapper::TransactionPoolWrapper;
 use std::{
 	collections::{HashMap, HashSet},
 	pin::Pin,
@@ -62,7 +64,10 @@ use sp_runtime::{
 };
 use std::time::Instant;
 
-use crate::metrics::MetricsLink as PrometheusMetrics;
+use crate::{
+	metrics::MetricsLink as PrometheusMetrics,
+	persistence::{Persistence, PoolAt},
+};
 use prometheus_endpoint::Registry as PrometheusRegistry;
 
 use sp_blockchain::{HashAndNumber, TreeRoute};
@@ -93,6 +98,7 @@ where
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 	enactment_state: Arc<Mutex<EnactmentState<Block>>>,
+	persistence: Option<Arc<Persistence>>,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -180,6 +186,7 @@ where
 					best_block_hash,
 					finalized_hash,
 				))),
+				persistence: None,
 			},
 			background_task,
 		)
@@ -198,6 +205,8 @@ where
 		best_block_hash: Block::Hash,
 		finalized_hash: Block::Hash,
 	) -> Self {
+		let persistence =
+			options.persistence_path.clone().map(|path| Arc::new(Persistence::new(path)));
 		let pool = Arc::new(graph::Pool::new(options, is_validator, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light =>
@@ -213,6 +222,15 @@ where
 			spawner.spawn_essential("txpool-background", Some("transaction-pool"), background_task);
 		}
 
+		if let Some(persistence) = persistence.clone() {
+			let pool = pool.clone();
+			spawner.spawn_essential(
+				"txpool-persistence",
+				Some("transaction-pool"),
+				async move { persistence.run(&PoolAt { pool, at: best_block_hash }).await }.boxed(),
+			);
+		}
+
 		Self {
 			api: pool_api,
 			pool,
@@ -228,6 +246,7 @@ where
 				best_block_hash,
 				finalized_hash,
 			))),
+			persistence,
 		}
 	}
 
@@ -242,6 +261,18 @@ where
 	}
 }
 
+impl<PoolApi, Block> Drop for BasicPool<PoolApi, Block>
+where
+	Block: BlockT,
+	PoolApi: graph::ChainApi<Block = Block>,
+{
+	fn drop(&mut self) {
+		if let Some(persistence) = &self.persistence {
+			persistence.save(&self.pool.validated_pool().transactions());
+		}
+	}
+}
+
 impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 where
 	Block: BlockT,
@@ -781,13 +812,22 @@ where
 	Client: sc_client_api::BlockchainEvents<Block>,
 	Pool: MaintainedTransactionPool<Block = Block>,
 {
+	// Imports of blocks which did not become the best block are only notified.
 	let import_stream = client
 		.import_notification_stream()
-		.filter_map(|n| ready(n.try_into().ok()))
+		.map(|n| {
+			let hash = n.hash;
+			n.try_into().map_err(|_| hash)
+		})
 		.fuse();
-	let finality_stream = client.finality_notification_stream().map(Into::into).fuse();
+	let finality_stream = client.finality_notification_stream().map(|n| Ok(n.into())).fuse();
 
 	futures::stream::select(import_stream, finality_stream)
-		.for_each(|evt| txpool.maintain(evt))
+		.for_each(|evt| async {
+			match evt {
+				Ok(evt) => txpool.maintain(evt).await,
+				Err(hash) => txpool.on_block_imported(hash).await,
+			}
+		})
 		.await
 }
@@ -0,0 +1,242 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Transaction pool persistence.
+//!
+//! The content of the pool is periodically written to disk, and read back when the pool is
+//! created. Restored transactions are revalidated like any other submission, so whatever became
+//! invalid in the meantime is dropped.
+
+use crate::{
+	graph::{BlockHash, ChainApi, ExtrinsicFor, Pool},
+	LOG_TARGET,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::channel::oneshot;
+use futures_timer::Delay;
+use sc_utils::persistence::write_atomically;
+use sp_runtime::{generic::BlockId, transaction_validity::TransactionSource};
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+/// Version of the persisted pool format.
+const PERSISTENCE_VERSION: u32 = 1;
+
+/// Interval at which the pool is written to disk.
+const PERSISTENCE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Persisted pool content: transactions along with their source, SCALE-encoded separately so
+/// that transactions which can no longer be decoded are skipped individually.
+type PersistedTransactions = Vec<(TransactionSource, Vec<u8>)>;
+
+/// A pool whose content can be persisted.
+#[async_trait]
+pub(crate) trait PersistedPool: Send + Sync {
+	/// Transactions of the pool.
+	type Extrinsic: Encode + Decode + Clone + Send + Sync + 'static;
+
+	/// Returns all transactions of the pool, along with their source.
+	fn transactions(&self) -> Vec<(TransactionSource, Self::Extrinsic)>;
+
+	/// Submit persisted transactions of the given source again, returning how many of them were
+	/// accepted.
+	async fn resubmit(&self, source: TransactionSource, xts: Vec<Self::Extrinsic>) -> usize;
+}
+
+/// A [`Pool`] whose persisted transactions are restored at the given block.
+pub(crate) struct PoolAt<Api: ChainApi> {
+	pub pool: Arc<Pool<Api>>,
+	pub at: BlockHash<Api>,
+}
+
+#[async_trait]
+impl<Api: ChainApi> PersistedPool for PoolAt<Api> {
+	type Extrinsic = ExtrinsicFor<Api>;
+
+	fn transactions(&self) -> Vec<(TransactionSource, Self::Extrinsic)> {
+		self.pool.validated_pool().transactions()
+	}
+
+	async fn resubmit(&self, source: TransactionSource, xts: Vec<Self::Extrinsic>) -> usize {
+		match self.pool.submit_at(&BlockId::Hash(self.at), source, xts).await {
+			Ok(results) => results.iter().filter(|r| r.is_ok()).count(),
+			Err(e) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"[{:?}] Error restoring persisted transactions: {}",
+					self.at,
+					e,
+				);
+				0
+			},
+		}
+	}
+}
+
+/// On-disk persistence of the pool content.
+pub(crate) struct Persistence {
+	path: PathBuf,
+	/// Set once the previously persisted transactions have been resubmitted. The pool is not
+	/// written before that, so that a partially restored pool does not overwrite the file.
+	restored: AtomicBool,
+}
+
+impl Persistence {
+	/// Create persistence of the pool into the file at `path`.
+	pub fn new(path: PathBuf) -> Self {
+		// Without a file there is nothing to restore, and the pool can be written right away.
+		let restored = AtomicBool::new(!path.exists());
+		Self { path, restored }
+	}
+
+	/// Write the given transactions of the pool to disk, blocking the current thread.
+	pub fn save<Ex: Encode>(&self, transactions: &[(TransactionSource, Ex)]) {
+		if self.restored.load(Ordering::Acquire) {
+			log_write(&self.path, transactions.len(), write(&self.path, transactions));
+		}
+	}
+
+	/// Resubmit the transactions persisted previously, then keep persisting the pool
+	/// periodically.
+	pub async fn run<P: PersistedPool>(&self, pool: &P) {
+		let path = self.path.clone();
+		let transactions =
+			spawn_blocking(move || read::<P::Extrinsic>(&path)).await.unwrap_or_else(|e| {
+				log::warn!(
+					target: LOG_TARGET,
+					"Failed to read persisted transactions from {}: {}",
+					self.path.display(),
+					e,
+				);
+				Vec::new()
+			});
+		if !transactions.is_empty() {
+			let total = transactions.len();
+			let restored = restore(pool, transactions).await;
+			log::info!(
+				target: LOG_TARGET,
+				"Restored {} of {} transactions from {}",
+				restored,
+				total,
+				self.path.display(),
+			);
+		}
+		self.restored.store(true, Ordering::Release);
+
+		loop {
+			Delay::new(PERSISTENCE_INTERVAL).await;
+			let transactions = pool.transactions();
+			let count = transactions.len();
+			let path = self.path.clone();
+			let written = spawn_blocking(move || write(&path, &transactions)).await;
+			log_write(&self.path, count, written);
+		}
+	}
+}
+
+/// Run `f` on a thread of its own, so that file I/O does not block the executor.
+async fn spawn_blocking<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
+	let (tx, rx) = oneshot::channel();
+	std::thread::spawn(move || {
+		let _ = tx.send(f());
+	});
+	rx.await
+		.expect("The sender is only dropped after sending, unless `f` panics; qed")
+}
+
+fn log_write(path: &Path, count: usize, written: io::Result<()>) {
+	match written {
+		Ok(()) =>
+			log::trace!(target: LOG_TARGET, "Persisted {} transactions to {}", count, path.display()),
+		Err(e) => log::warn!(
+			target: LOG_TARGET,
+			"Failed to persist transactions to {}: {}",
+			path.display(),
+			e,
+		),
+	}
+}
+
+/// Atomically replace the file at `path` with the given transactions.
+fn write<Ex: Encode>(path: &Path, transactions: &[(TransactionSource, Ex)]) -> io::Result<()> {
+	let persisted: PersistedTransactions =
+		transactions.iter().map(|(source, xt)| (*source, xt.encode())).collect();
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	write_atomically(path, &(PERSISTENCE_VERSION, persisted).encode())
+}
+
+/// Read the transactions persisted at `path`, if any.
+fn read<Ex: Decode>(path: &Path) -> io::Result<Vec<(TransactionSource, Ex)>> {
+	let data = match fs::read(path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e),
+	};
+
+	let (version, persisted) = <(u32, PersistedTransactions)>::decode(&mut &data[..])
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+	if version != PERSISTENCE_VERSION {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("unsupported format version {}", version),
+		))
+	}
+
+	Ok(persisted
+		.into_iter()
+		.filter_map(|(source, xt)| match Decode::decode(&mut &xt[..]) {
+			Ok(xt) => Some((source, xt)),
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Skipping undecodable persisted transaction: {}", e);
+				None
+			},
+		})
+		.collect())
+}
+
+/// Resubmit transactions, keeping their original source. Returns how many were accepted by the
+/// pool.
+async fn restore<P: PersistedPool>(
+	pool: &P,
+	transactions: Vec<(TransactionSource, P::Extrinsic)>,
+) -> usize {
+	let mut restored = 0;
+	for source in
+		[TransactionSource::Local, TransactionSource::External, TransactionSource::InBlock]
+	{
+		let xts = transactions
+			.iter()
+			.filter(|(s, _)| *s == source)
+			.map(|(_, xt)| xt.clone())
+			.collect::<Vec<_>>();
+		if !xts.is_empty() {
+			restored += pool.resubmit(source, xts).await;
+		}
+	}
+	restored
+}
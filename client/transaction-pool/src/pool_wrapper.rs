@@ -0,0 +1,260 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction pool of a full node, of the implementation selected in the pool options.
+
+use crate::{
+	graph::{self, ExtrinsicHash, IsValidator, TransactionPoolType},
+	BasicPool, ForkAwareTxPool, FullChainApi, PolledIterator, ReadyIteratorFor, RevalidationType,
+};
+use async_trait::async_trait;
+use prometheus_endpoint::Registry as PrometheusRegistry;
+use sc_transaction_pool_api::{
+	ChainEvent, ImportNotificationStream, LocalTransactionFor, LocalTransactionPool,
+	MaintainedTransactionPool, PoolFuture, PoolStatus, TransactionFor, TransactionPool,
+	TransactionSource, TransactionStatusStreamFor, TxHash,
+};
+use sp_blockchain::HashAndNumber;
+use sp_core::traits::SpawnEssentialNamed;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+
+/// Forward a call to the selected pool.
+macro_rules! dispatch {
+	($self:ident, $pool:ident => $call:expr) => {
+		match $self {
+			Self::SingleState($pool) => $call,
+			Self::ForkAware($pool) => $call,
+		}
+	};
+}
+
+/// A transaction pool for a full node, either a [`BasicPool`] or a [`ForkAwareTxPool`].
+pub enum TransactionPoolWrapper<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	/// Pool validating transactions against the best block only.
+	SingleState(BasicPool<FullChainApi<Client, Block>, Block>),
+	/// Pool validating transactions against every tracked fork.
+	ForkAware(Arc<ForkAwareTxPool<FullChainApi<Client, Block>, Block>>),
+}
+
+impl<Block, Client> TransactionPoolWrapper<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sc_client_api::ExecutorProvider<Block>
+		+ sc_client_api::UsageProvider<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	/// Create the transaction pool selected by [`graph::Options::pool_type`] for a full node.
+	pub fn new_full(
+		options: graph::Options,
+		is_validator: IsValidator,
+		prometheus: Option<&PrometheusRegistry>,
+		spawner: impl SpawnEssentialNamed,
+		client: Arc<Client>,
+	) -> Arc<Self> {
+		let pool_api = Arc::new(FullChainApi::new(client.clone(), prometheus, &spawner));
+		let info = client.usage_info().chain;
+		let pool = match options.pool_type {
+			TransactionPoolType::SingleState =>
+				Self::SingleState(BasicPool::with_revalidation_type(
+					options,
+					is_validator,
+					pool_api,
+					prometheus,
+					RevalidationType::Full,
+					spawner,
+					info.best_number,
+					info.best_hash,
+					info.finalized_hash,
+				)),
+			TransactionPoolType::ForkAware => Self::ForkAware(ForkAwareTxPool::new(
+				options,
+				is_validator,
+				pool_api,
+				prometheus,
+				Some(&spawner),
+				HashAndNumber { hash: info.best_hash, number: info.best_number },
+				HashAndNumber { hash: info.finalized_hash, number: info.finalized_number },
+			)),
+		};
+		let pool = Arc::new(pool);
+
+		// make transaction pool available for off-chain runtime calls.
+		client.execution_extensions().register_transaction_pool(&pool);
+
+		pool
+	}
+}
+
+impl<Block, Client> TransactionPool for TransactionPoolWrapper<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	type Block = Block;
+	type Hash = ExtrinsicHash<FullChainApi<Client, Block>>;
+	type InPoolTransaction = graph::base_pool::Transaction<TxHash<Self>, TransactionFor<Self>>;
+	type Error = <FullChainApi<Client, Block> as graph::ChainApi>::Error;
+
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		dispatch!(self, pool => pool.submit_at(at, source, xts))
+	}
+
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		dispatch!(self, pool => pool.submit_one(at, source, xt))
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		dispatch!(self, pool => pool.submit_and_watch(at, source, xt))
+	}
+
+	fn ready_at(&self, at: NumberFor<Self::Block>) -> PolledIterator<FullChainApi<Client, Block>> {
+		dispatch!(self, pool => pool.ready_at(at))
+	}
+
+	fn ready_at_block(
+		&self,
+		at: Block::Hash,
+		number: NumberFor<Self::Block>,
+	) -> PolledIterator<FullChainApi<Client, Block>> {
+		dispatch!(self, pool => pool.ready_at_block(at, number))
+	}
+
+	fn ready(&self) -> ReadyIteratorFor<FullChainApi<Client, Block>> {
+		dispatch!(self, pool => pool.ready())
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		dispatch!(self, pool => pool.remove_invalid(hashes))
+	}
+
+	fn status(&self) -> PoolStatus {
+		dispatch!(self, pool => pool.status())
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		dispatch!(self, pool => pool.import_notification_stream())
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		dispatch!(self, pool => pool.on_broadcasted(propagations))
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		dispatch!(self, pool => pool.hash_of(xt))
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		dispatch!(self, pool => pool.ready_transaction(hash))
+	}
+}
+
+#[async_trait]
+impl<Block, Client> MaintainedTransactionPool for TransactionPoolWrapper<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		dispatch!(self, pool => pool.maintain(event).await)
+	}
+
+	async fn on_block_imported(&self, hash: Block::Hash) {
+		dispatch!(self, pool => pool.on_block_imported(hash).await)
+	}
+}
+
+impl<Block, Client> LocalTransactionPool for TransactionPoolWrapper<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	type Block = Block;
+	type Hash = ExtrinsicHash<FullChainApi<Client, Block>>;
+	type Error = <FullChainApi<Client, Block> as graph::ChainApi>::Error;
+
+	fn submit_local(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: LocalTransactionFor<Self>,
+	) -> Result<Self::Hash, Self::Error> {
+		dispatch!(self, pool => pool.submit_local(at, xt))
+	}
+}
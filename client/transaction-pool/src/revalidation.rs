@@ -70,13 +70,26 @@ async fn batch_revalidate<Api: ChainApi>(
 	api: Arc<Api>,
 	at: NumberFor<Api>,
 	batch: impl IntoIterator<Item = ExtrinsicHash<Api>>,
+) {
+	batch_revalidate_at(pool, api, &BlockId::Number(at), at, batch).await
+}
+
+/// Revalidate batch of transaction at the given block.
+///
+/// Same as [`batch_revalidate`], for blocks which are not necessarily on the canonical chain.
+pub(crate) async fn batch_revalidate_at<Api: ChainApi>(
+	pool: Arc<Pool<Api>>,
+	api: Arc<Api>,
+	block: &BlockId<Api::Block>,
+	at: NumberFor<Api>,
+	batch: impl IntoIterator<Item = ExtrinsicHash<Api>>,
 ) {
 	let mut invalid_hashes = Vec::new();
 	let mut revalidated = HashMap::new();
 
 	let validation_results = futures::future::join_all(batch.into_iter().filter_map(|ext_hash| {
 		pool.validated_pool().ready_by_hash(&ext_hash).map(|ext| {
-			api.validate_transaction(&BlockId::Number(at), ext.source, ext.data.clone())
+			api.validate_transaction(block, ext.source, ext.data.clone())
 				.map(move |validation_result| (validation_result, ext_hash, ext))
 		})
 	}))
@@ -0,0 +1,290 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests for the fork aware transaction pool.
+
+use futures::executor::block_on;
+use sc_transaction_pool::ForkAwareTxPool;
+use sc_transaction_pool_api::{
+	ChainEvent, MaintainedTransactionPool, TransactionPool, TransactionStatus,
+};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{generic::BlockId, traits::Header as _, transaction_validity::TransactionSource};
+use std::sync::Arc;
+use substrate_test_runtime_client::{
+	runtime::{Block, Hash, Header},
+	AccountKeyring::*,
+};
+use substrate_test_runtime_transaction_pool::{uxt, TestApi};
+
+const SOURCE: TransactionSource = TransactionSource::External;
+
+fn hash_and_number(header: &Header) -> HashAndNumber<Block> {
+	HashAndNumber { hash: header.hash(), number: *header.number() }
+}
+
+/// Create a pool with a single view at `header`, which is also the last finalized block.
+fn create_pool(api: Arc<TestApi>, header: &Header) -> Arc<ForkAwareTxPool<TestApi, Block>> {
+	ForkAwareTxPool::new_test(api, hash_and_number(header), hash_and_number(header))
+}
+
+fn new_best(header: &Header) -> ChainEvent<Block> {
+	ChainEvent::NewBestBlock { hash: header.hash(), tree_route: None }
+}
+
+fn finalized(header: &Header, tree_route: Vec<Hash>) -> ChainEvent<Block> {
+	ChainEvent::Finalized { hash: header.hash(), tree_route: Arc::from(tree_route) }
+}
+
+#[test]
+fn views_follow_forks_until_finalization() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let xt = uxt(Alice, 209);
+	let watcher =
+		block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone())).expect("Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![xt.clone()], true);
+	block_on(pool.maintain(new_best(&b1)));
+	assert_eq!(pool.status().ready, 0);
+
+	// Switching to a fork which does not include the transaction makes it ready again, while
+	// the view of the other fork is kept around.
+	let c1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.maintain(new_best(&c1)));
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.views().len(), 2);
+
+	let ready_on_b1 = block_on(pool.ready_at_block(b1.hash(), *b1.number())).count();
+	assert_eq!(ready_on_b1, 0);
+	let ready_on_c1 = block_on(pool.ready_at_block(c1.hash(), *c1.number()))
+		.map(|tx| tx.data.transfer().nonce)
+		.collect::<Vec<_>>();
+	assert_eq!(ready_on_c1, vec![209]);
+
+	let c2 = api.push_block_with_parent(c1.hash(), vec![xt.clone()], true);
+	block_on(pool.maintain(new_best(&c2)));
+	block_on(pool.maintain(finalized(&c2, vec![c1.hash()])));
+	assert_eq!(pool.views().iter().map(|view| view.hash).collect::<Vec<_>>(), vec![c2.hash()]);
+	assert_eq!(pool.mempool_len(), 0);
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((b1.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((c2.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Finalized((c2.hash(), 0))));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn pruned_fork_retracts_its_transactions() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let xt = uxt(Alice, 209);
+	let watcher =
+		block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone())).expect("Imported");
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![xt.clone()], true);
+	block_on(pool.maintain(new_best(&b1)));
+	let c1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.maintain(new_best(&c1)));
+	block_on(pool.maintain(finalized(&c1, vec![])));
+
+	assert_eq!(pool.views().iter().map(|view| view.hash).collect::<Vec<_>>(), vec![c1.hash()]);
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.mempool_len(), 1);
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((b1.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::Retracted(b1.hash())));
+}
+
+#[test]
+fn transaction_invalid_on_every_view_is_reported() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let xt = uxt(Alice, 209);
+	let watcher =
+		block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone())).expect("Imported");
+	api.add_invalid(&xt);
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.maintain(new_best(&b1)));
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.mempool_len(), 0);
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::Invalid));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn ready_at_block_builds_missing_view() {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, uxt(Alice, 209))).expect("Imported");
+	let b1 = api.push_block_with_parent(a.hash(), vec![], true);
+
+	let ready = block_on(pool.ready_at_block(b1.hash(), *b1.number())).count();
+	assert_eq!(ready, 1);
+	assert!(pool.views().iter().any(|view| view.hash == b1.hash()));
+}
+
+#[test]
+fn ready_at_block_rebuilds_superseded_view() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let xt = uxt(Alice, 209);
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt.clone())).expect("Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, uxt(Alice, 210))).expect("Imported");
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.maintain(new_best(&b1)));
+	api.increment_nonce(Alice.into());
+	let b2 = api.push_block_with_parent(b1.hash(), vec![xt], true);
+	block_on(pool.maintain(new_best(&b2)));
+	assert_eq!(pool.views().iter().map(|view| view.hash).collect::<Vec<_>>(), vec![b2.hash()]);
+
+	let nonces = |at: &Header| {
+		let mut nonces = block_on(pool.ready_at_block(at.hash(), *at.number()))
+			.map(|tx| tx.data.transfer().nonce)
+			.collect::<Vec<_>>();
+		nonces.sort();
+		nonces
+	};
+	assert_eq!(nonces(&b1), vec![209, 210]);
+	assert_eq!(nonces(&b2), vec![210]);
+}
+
+#[test]
+fn submit_at_builds_view_at_block() {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.submit_one(&BlockId::Hash(b1.hash()), SOURCE, uxt(Alice, 209)))
+		.expect("Imported");
+
+	assert!(pool.views().iter().any(|view| view.hash == b1.hash()));
+	let ready = block_on(pool.ready_at_block(b1.hash(), *b1.number())).count();
+	assert_eq!(ready, 1);
+}
+
+#[test]
+fn pool_content_is_restored_after_restart() {
+	let dir = tempfile::tempdir().unwrap();
+	let options = sc_transaction_pool::Options {
+		persistence_path: Some(dir.path().join("txpool")),
+		..Default::default()
+	};
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let create_pool = || {
+		ForkAwareTxPool::new(
+			options.clone(),
+			true.into(),
+			api.clone(),
+			None,
+			Some(&sp_core::testing::TaskExecutor::new()),
+			hash_and_number(&a),
+			hash_and_number(&a),
+		)
+	};
+
+	let pool = create_pool();
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, uxt(Alice, 209))).expect("Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, uxt(Alice, 210))).expect("Imported");
+	drop(pool);
+
+	let pool = create_pool();
+	let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+	while pool.status().ready != 2 {
+		assert!(std::time::Instant::now() < deadline, "transactions were not restored");
+		std::thread::sleep(std::time::Duration::from_millis(10));
+	}
+	assert_eq!(pool.mempool_len(), 2);
+}
+
+#[test]
+fn transaction_included_on_non_best_fork_is_reported() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	let xt = uxt(Alice, 209);
+	let watcher =
+		block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone())).expect("Imported");
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![xt.clone()], true);
+	block_on(pool.maintain(new_best(&b1)));
+	let c1 = api.push_block_with_parent(a.hash(), vec![xt.clone()], false);
+	block_on(pool.on_block_imported(c1.hash()));
+
+	// The view of the imported fork is tracked, the best view is kept.
+	assert_eq!(
+		pool.views().iter().map(|view| view.hash).collect::<Vec<_>>(),
+		vec![b1.hash(), c1.hash()]
+	);
+
+	block_on(pool.maintain(finalized(&b1, vec![])));
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((b1.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((c1.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Finalized((b1.hash(), 0))));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn new_view_reuses_transactions_of_parent_view() {
+	sp_tracing::try_init_simple();
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let a = api.push_block(1, vec![], true);
+	let pool = create_pool(api.clone(), &a);
+
+	// Waits for the transaction with nonce 209, so it is not revalidated with the ready ones.
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, uxt(Alice, 210))).expect("Imported");
+	assert_eq!(api.validation_requests().len(), 1);
+
+	let b1 = api.push_block_with_parent(a.hash(), vec![], true);
+	block_on(pool.maintain(new_best(&b1)));
+
+	assert_eq!(pool.status().future, 1);
+	assert_eq!(api.validation_requests().len(), 1);
+}
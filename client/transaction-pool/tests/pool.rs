@@ -1568,3 +1568,50 @@ fn best_block_after_finalization_does_not_retract() {
 		assert_eq!(stream.next(), None);
 	}
 }
+
+#[test]
+fn pool_content_is_restored_after_restart() {
+	let dir = tempfile::tempdir().unwrap();
+	let options =
+		Options { persistence_path: Some(dir.path().join("txpool")), ..Default::default() };
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	let create_pool = || {
+		BasicPool::with_revalidation_type(
+			options.clone(),
+			true.into(),
+			api.clone(),
+			None,
+			RevalidationType::Light,
+			sp_core::testing::TaskExecutor::new(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		)
+	};
+
+	let pool = create_pool();
+	let included = uxt(Alice, 209);
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, included.clone())).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 211))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), TransactionSource::Local, uxt(Bob, 0))).unwrap();
+	assert_eq!(pool.status().ready, 3);
+	assert_eq!(pool.status().future, 0);
+	drop(pool);
+
+	// Alice's first transaction got included while the node was down, so her next one waits for
+	// the missing nonce.
+	api.increment_nonce(Alice.into());
+	api.add_invalid(&included);
+
+	let pool = create_pool();
+	let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+	while pool.status().ready != 1 || pool.status().future != 1 {
+		assert!(std::time::Instant::now() < deadline, "transactions were not restored");
+		std::thread::sleep(std::time::Duration::from_millis(10));
+	}
+
+	let ready: Vec<_> = pool.ready().map(|tx| (tx.source, tx.data.transfer().from)).collect();
+	assert_eq!(ready, vec![(TransactionSource::Local, Bob.public())]);
+	assert_eq!(pool.pool().validated_pool().futures()[0].1.transfer().nonce, 211);
+}
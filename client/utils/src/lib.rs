@@ -34,5 +34,6 @@ pub mod id_sequence;
 pub mod metrics;
 pub mod mpsc;
 pub mod notification;
+pub mod persistence;
 pub mod pubsub;
 pub mod status_sinks;
@@ -0,0 +1,61 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Helpers for state that is persisted to disk across restarts.
+
+use std::{
+	fs, io,
+	path::Path,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Replaces the content of the file at `path` with `content`.
+///
+/// The content is written to a temporary file next to `path` first, which is then moved over
+/// `path`, so that a crash never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, content)?;
+	fs::rename(&tmp_path, path)
+}
+
+/// Current UNIX time in seconds, used to timestamp persisted entries.
+///
+/// Returns zero if the system clock is set before the UNIX epoch.
+pub fn unix_time() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn write_atomically_replaces_content() {
+		let dir = std::env::temp_dir().join(format!("sc-utils-persistence-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("state.bin");
+
+		write_atomically(&path, b"first").unwrap();
+		write_atomically(&path, b"second").unwrap();
+
+		assert_eq!(fs::read(&path).unwrap(), b"second");
+		assert!(!path.with_extension("tmp").exists());
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -0,0 +1,43 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Aura pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::v1::benchmarks;
+use frame_support::ensure;
+use frame_system::RawOrigin;
+
+benchmarks! {
+	schedule_slot_duration_change {
+		let duration = Pallet::<T>::minimum_slot_duration().saturating_mul(2u32.into());
+		// The benchmarking machinery may advance the block number before running the call.
+		let at = frame_system::Pallet::<T>::block_number() + 10u32.into();
+		// Replacing a pending change is the worst case.
+		PendingSlotDurationChange::<T>::put((at, Pallet::<T>::minimum_slot_duration()));
+	}: _(RawOrigin::Root, duration, at)
+	verify {
+		ensure!(
+			PendingSlotDurationChange::<T>::get() == Some((at, duration)),
+			"Slot duration change was not scheduled."
+		);
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(vec![0, 1, 2, 3]), crate::mock::Test);
+}
@@ -26,10 +26,16 @@
 //!
 //! ## Interface
 //!
+//! ### Dispatchable Functions
+//!
+//! - `schedule_slot_duration_change` - Schedule a change of the slot duration at a given block.
+//!   Root only.
+//!
 //! ### Public Functions
 //!
-//! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module
-//!   configuration.
+//! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration
+//!   and any enacted slot duration change.
+//! - `slot_schedule` - The slot duration along with the slot it applies from.
 //!
 //! ## Related Modules
 //!
@@ -44,19 +50,24 @@ use frame_support::{
 	traits::{DisabledValidators, FindAuthor, Get, OnTimestampSet, OneSessionHandler},
 	BoundedSlice, BoundedVec, ConsensusEngineId, Parameter,
 };
-use sp_consensus_aura::{AuthorityIndex, ConsensusLog, Slot, AURA_ENGINE_ID};
+use sp_consensus_aura::{
+	AuthorityIndex, ConsensusLog, Slot, SlotDuration, SlotSchedule, AURA_ENGINE_ID,
+};
 use sp_runtime::{
 	generic::DigestItem,
-	traits::{IsMember, Member, SaturatedConversion, Saturating, Zero},
+	traits::{IsMember, Member, SaturatedConversion, Saturating},
 	RuntimeAppPublic,
 };
 use sp_std::prelude::*;
 
+mod benchmarking;
 pub mod migrations;
 mod mock;
 mod tests;
+pub mod weights;
 
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 const LOG_TARGET: &str = "runtime::aura";
 
@@ -81,6 +92,9 @@ pub mod pallet {
 		/// Blocks authored by a disabled validator will lead to a panic as part of this module's
 		/// initialization.
 		type DisabledValidators: DisabledValidators;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	#[pallet::pallet]
@@ -88,8 +102,13 @@ pub mod pallet {
 
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
-		fn on_initialize(_: T::BlockNumber) -> Weight {
-			if let Some(new_slot) = Self::current_slot_from_digests() {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let weight = match PendingSlotDurationChange::<T>::get() {
+				Some((at, _)) if at <= n => T::DbWeight::get().reads_writes(3, 2),
+				_ => T::DbWeight::get().reads(1),
+			};
+
+			let slot_weight = if let Some(new_slot) = Self::current_slot_from_digests() {
 				let current_slot = CurrentSlot::<T>::get();
 
 				assert!(current_slot < new_slot, "Slot must increase");
@@ -111,10 +130,55 @@ pub mod pallet {
 				T::DbWeight::get().reads_writes(2, 1)
 			} else {
 				T::DbWeight::get().reads(1)
+			};
+
+			weight.saturating_add(slot_weight)
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			// the change is enacted at the end of the block, so that the block itself is still
+			// checked against the slot duration it was authored with.
+			if let Some((at, duration)) = PendingSlotDurationChange::<T>::get() {
+				if at <= n {
+					PendingSlotDurationChange::<T>::kill();
+					Self::enact_slot_duration_change(duration);
+				}
 			}
 		}
 	}
 
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Schedule a change of the slot duration.
+		///
+		/// The new `duration` applies to the children of block `at`. It replaces any change that
+		/// is still pending. The duration can't be lower than the one derived from the Timestamp
+		/// module's `MinimumPeriod`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::schedule_slot_duration_change())]
+		pub fn schedule_slot_duration_change(
+			origin: OriginFor<T>,
+			duration: T::Moment,
+			at: T::BlockNumber,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(at > frame_system::Pallet::<T>::block_number(), Error::<T>::ChangeNotInFuture);
+			ensure!(duration >= Self::minimum_slot_duration(), Error::<T>::SlotDurationTooShort);
+
+			PendingSlotDurationChange::<T>::put((at, duration));
+
+			Ok(())
+		}
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The slot duration change must be scheduled for a future block.
+		ChangeNotInFuture,
+		/// The slot duration is lower than the minimum allowed by the Timestamp module.
+		SlotDurationTooShort,
+	}
+
 	/// The current authority set.
 	#[pallet::storage]
 	#[pallet::getter(fn authorities)]
@@ -128,6 +192,18 @@ pub mod pallet {
 	#[pallet::getter(fn current_slot)]
 	pub(super) type CurrentSlot<T: Config> = StorageValue<_, Slot, ValueQuery>;
 
+	/// The slot schedule enacted by the last slot duration change.
+	///
+	/// `None` if the slot duration never changed.
+	#[pallet::storage]
+	pub(super) type CurrentSlotSchedule<T: Config> = StorageValue<_, SlotSchedule>;
+
+	/// A scheduled slot duration change: the block at which it is enacted and the new duration.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_slot_duration_change)]
+	pub(super) type PendingSlotDurationChange<T: Config> =
+		StorageValue<_, (T::BlockNumber, T::Moment)>;
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub authorities: Vec<T::AuthorityId>,
@@ -198,12 +274,48 @@ impl<T: Config> Pallet<T> {
 		None
 	}
 
-	/// Determine the Aura slot-duration based on the Timestamp module configuration.
+	/// Determine the Aura slot-duration based on the Timestamp module configuration and any
+	/// enacted slot duration change.
 	pub fn slot_duration() -> T::Moment {
+		match CurrentSlotSchedule::<T>::get() {
+			Some(schedule) => schedule.duration.as_millis().saturated_into(),
+			None => Self::minimum_slot_duration(),
+		}
+	}
+
+	/// The slot schedule that applies to the children of the current block.
+	pub fn slot_schedule() -> SlotSchedule {
+		CurrentSlotSchedule::<T>::get().unwrap_or_else(|| {
+			SlotSchedule::new(SlotDuration::from_millis(
+				Self::minimum_slot_duration().saturated_into(),
+			))
+		})
+	}
+
+	/// The lowest slot duration allowed by the Timestamp module configuration.
+	fn minimum_slot_duration() -> T::Moment {
 		// we double the minimum block-period so each author can always propose within
 		// the majority of its slot.
 		<T as pallet_timestamp::Config>::MinimumPeriod::get().saturating_mul(2u32.into())
 	}
+
+	/// Switch to the given slot duration, starting with the current slot.
+	///
+	/// The new schedule is stored and an aura consensus log is appended to the block's log.
+	fn enact_slot_duration_change(duration: T::Moment) {
+		let schedule = SlotSchedule {
+			duration: SlotDuration::from_millis(duration.saturated_into()),
+			anchor_slot: CurrentSlot::<T>::get(),
+			anchor_timestamp: pallet_timestamp::Pallet::<T>::get().saturated_into(),
+		};
+		CurrentSlotSchedule::<T>::put(schedule);
+
+		let log = DigestItem::Consensus(
+			AURA_ENGINE_ID,
+			ConsensusLog::<T::AuthorityId>::SlotScheduleChange(schedule).encode(),
+		);
+		<frame_system::Pallet<T>>::deposit_log(log);
+	}
 }
 
 impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Pallet<T> {
@@ -300,11 +412,10 @@ impl<T: Config> IsMember<T::AuthorityId> for Pallet<T> {
 
 impl<T: Config> OnTimestampSet<T::Moment> for Pallet<T> {
 	fn on_timestamp_set(moment: T::Moment) {
-		let slot_duration = Self::slot_duration();
-		assert!(!slot_duration.is_zero(), "Aura slot duration cannot be zero.");
+		let schedule = Self::slot_schedule();
+		assert!(schedule.duration.as_millis() != 0, "Aura slot duration cannot be zero.");
 
-		let timestamp_slot = moment / slot_duration;
-		let timestamp_slot = Slot::from(timestamp_slot.saturated_into::<u64>());
+		let timestamp_slot = schedule.slot_at(moment.saturated_into::<u64>().into());
 
 		assert!(
 			CurrentSlot::<T>::get() == timestamp_slot,
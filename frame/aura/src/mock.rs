@@ -42,7 +42,7 @@ frame_support::construct_runtime!(
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
 		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
-		Aura: pallet_aura::{Pallet, Storage, Config<T>},
+		Aura: pallet_aura::{Pallet, Call, Storage, Config<T>},
 	}
 );
 
@@ -106,6 +106,7 @@ impl pallet_aura::Config for Test {
 	type AuthorityId = AuthorityId;
 	type DisabledValidators = MockDisabledValidators;
 	type MaxAuthorities = ConstU32<10>;
+	type WeightInfo = ();
 }
 
 pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
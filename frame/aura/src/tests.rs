@@ -19,11 +19,34 @@
 
 #![cfg(test)]
 
-use crate::mock::{new_test_ext, Aura, MockDisabledValidators, System};
+use crate::{
+	mock::{new_test_ext, Aura, MockDisabledValidators, RuntimeOrigin, System, Test, Timestamp},
+	Error,
+};
 use codec::Encode;
-use frame_support::traits::OnInitialize;
-use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
-use sp_runtime::{Digest, DigestItem};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{OnFinalize, OnInitialize},
+};
+use sp_consensus_aura::{
+	ed25519::AuthorityId, ConsensusLog, Slot, SlotDuration, SlotSchedule, AURA_ENGINE_ID,
+};
+use sp_runtime::{Digest, DigestItem, DispatchError};
+
+fn initialize_block(number: u64, slot: u64, timestamp: u64) {
+	let pre_digest =
+		Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, Slot::from(slot).encode())] };
+
+	System::reset_events();
+	System::initialize(&number, &System::parent_hash(), &pre_digest);
+	Aura::on_initialize(number);
+	Timestamp::set_timestamp(timestamp);
+}
+
+fn finalize_block(number: u64) {
+	Aura::on_finalize(number);
+	Timestamp::on_finalize(number);
+}
 
 #[test]
 fn initial_values() {
@@ -54,3 +77,74 @@ fn disabled_validators_cannot_author_blocks() {
 		Aura::on_initialize(42);
 	});
 }
+
+#[test]
+fn schedule_slot_duration_change_checks_its_arguments() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		System::set_block_number(10);
+
+		assert_noop!(
+			Aura::schedule_slot_duration_change(RuntimeOrigin::signed(1), 6, 20),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Aura::schedule_slot_duration_change(RuntimeOrigin::root(), 6, 10),
+			Error::<Test>::ChangeNotInFuture,
+		);
+		// `MinimumPeriod` is 1, so the slot duration can't be lower than 2.
+		assert_noop!(
+			Aura::schedule_slot_duration_change(RuntimeOrigin::root(), 1, 20),
+			Error::<Test>::SlotDurationTooShort,
+		);
+
+		assert_ok!(Aura::schedule_slot_duration_change(RuntimeOrigin::root(), 6, 20));
+		assert_eq!(Aura::pending_slot_duration_change(), Some((20, 6)));
+	});
+}
+
+#[test]
+fn slot_duration_change_is_enacted_at_the_end_of_the_scheduled_block() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		assert_eq!(Aura::slot_duration(), 2);
+		assert_ok!(Aura::schedule_slot_duration_change(RuntimeOrigin::root(), 6, 2));
+
+		initialize_block(1, 40, 80);
+		finalize_block(1);
+		assert_eq!(Aura::slot_duration(), 2);
+
+		// the scheduled block is still checked against the old slot duration.
+		initialize_block(2, 50, 100);
+		finalize_block(2);
+
+		let schedule = SlotSchedule {
+			duration: SlotDuration::from_millis(6),
+			anchor_slot: 50.into(),
+			anchor_timestamp: 100,
+		};
+		assert_eq!(Aura::slot_duration(), 6);
+		assert_eq!(Aura::slot_schedule(), schedule);
+		assert_eq!(Aura::pending_slot_duration_change(), None);
+		assert!(System::digest().logs.contains(&DigestItem::Consensus(
+			AURA_ENGINE_ID,
+			ConsensusLog::<AuthorityId>::SlotScheduleChange(schedule).encode(),
+		)));
+
+		// 100 / 6 = 16 and 102 / 6 = 17, so the next slot starts at timestamp 102.
+		initialize_block(3, 51, 102);
+		finalize_block(3);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Timestamp slot must match `CurrentSlot`")]
+fn slots_are_checked_against_the_new_slot_duration() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		assert_ok!(Aura::schedule_slot_duration_change(RuntimeOrigin::root(), 6, 1));
+
+		initialize_block(1, 50, 100);
+		finalize_block(1);
+
+		// this would be slot 52 with the old slot duration.
+		initialize_block(2, 52, 104);
+	});
+}
@@ -0,0 +1,55 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights for pallet_aura
+//!
+//! These weights were not generated on reference hardware yet. They account for the storage
+//! accesses of the benchmarks in `benchmarking.rs` on top of a conservative execution time, and
+//! should be replaced by the output of `benchmark pallet --pallet=pallet_aura`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_aura.
+pub trait WeightInfo {
+	fn schedule_slot_duration_change() -> Weight;
+}
+
+/// Weights for pallet_aura using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Aura PendingSlotDurationChange (r:0 w:1)
+	/// Proof: Aura PendingSlotDurationChange (max_values: Some(1), max_size: None, mode: Measured)
+	fn schedule_slot_duration_change() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Aura PendingSlotDurationChange (r:0 w:1)
+	/// Proof: Aura PendingSlotDurationChange (max_values: Some(1), max_size: None, mode: Measured)
+	fn schedule_slot_duration_change() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
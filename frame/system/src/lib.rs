@@ -165,6 +165,20 @@ impl<T: Config> SetCode<T> for () {
 	}
 }
 
+/// Enacts new code `Delay` blocks after it was set, see [`Pallet::schedule_code_upgrade`].
+///
+/// Clients can compile the new code in the meantime, instead of stalling on the first block
+/// after the upgrade.
+pub struct DelayedSetCode<Delay>(PhantomData<Delay>);
+
+impl<T: Config, Delay: Get<T::BlockNumber>> SetCode<T> for DelayedSetCode<Delay> {
+	fn set_code(code: Vec<u8>) -> DispatchResult {
+		let at = <Pallet<T>>::block_number().saturating_add(Delay::get());
+		<Pallet<T>>::schedule_code_upgrade(&code, at);
+		Ok(())
+	}
+}
+
 /// Numeric limits over the ability to add a consumer ref using `inc_consumers`.
 pub trait ConsumerLimits {
 	/// The number of consumers over which `inc_consumers` will cease to work.
@@ -361,6 +375,22 @@ pub mod pallet {
 
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			match PendingCodeAt::<T>::get() {
+				Some(at) if at <= n => {
+					match storage::unhashed::get_raw(well_known_keys::PENDING_CODE) {
+						Some(code) => {
+							let _ = Self::update_code_in_storage(&code);
+						},
+						// the code was removed without cancelling the upgrade.
+						None => PendingCodeAt::<T>::kill(),
+					}
+					T::DbWeight::get().reads_writes(2, 3)
+				},
+				_ => T::DbWeight::get().reads(1),
+			}
+		}
+
 		#[cfg(feature = "std")]
 		fn integrity_test() {
 			sp_io::TestExternalities::default().execute_with(|| {
@@ -630,6 +660,10 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type UpgradedToTripleRefCount<T: Config> = StorageValue<_, bool, ValueQuery>;
 
+	/// Block at which the code under [`well_known_keys::PENDING_CODE`] is enacted.
+	#[pallet::storage]
+	pub(super) type PendingCodeAt<T: Config> = StorageValue<_, T::BlockNumber>;
+
 	/// The execution phase of the block.
 	#[pallet::storage]
 	#[pallet::whitelist_storage]
@@ -1005,11 +1039,27 @@ impl<T: Config> Pallet<T> {
 	/// the storage (for instance in case of parachains).
 	pub fn update_code_in_storage(code: &[u8]) -> DispatchResult {
 		storage::unhashed::put_raw(well_known_keys::CODE, code);
+		Self::cancel_code_upgrade();
 		Self::deposit_log(generic::DigestItem::RuntimeEnvironmentUpdated);
 		Self::deposit_event(Event::CodeUpdated);
 		Ok(())
 	}
 
+	/// Switch to `code` at the beginning of block `at`, replacing any upgrade scheduled before.
+	///
+	/// Until then the code is kept under [`well_known_keys::PENDING_CODE`] only, where clients
+	/// can pick it up to prepare the new runtime ahead of time. No checks are done on `code`.
+	pub fn schedule_code_upgrade(code: &[u8], at: T::BlockNumber) {
+		storage::unhashed::put_raw(well_known_keys::PENDING_CODE, code);
+		PendingCodeAt::<T>::put(at);
+	}
+
+	/// Drop the upgrade scheduled by [`Self::schedule_code_upgrade`], together with its code.
+	pub fn cancel_code_upgrade() {
+		storage::unhashed::kill(well_known_keys::PENDING_CODE);
+		PendingCodeAt::<T>::kill();
+	}
+
 	/// Increment the reference counter on an account.
 	#[deprecated = "Use `inc_consumers` instead"]
 	pub fn inc_ref(who: &T::AccountId) {
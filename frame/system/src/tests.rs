@@ -19,6 +19,7 @@ use crate::*;
 use frame_support::{
 	assert_noop, assert_ok,
 	dispatch::{Pays, PostDispatchInfo, WithPostDispatchInfo},
+	traits::{ConstU64, Hooks},
 };
 use mock::{RuntimeOrigin, *};
 use sp_core::H256;
@@ -628,6 +629,60 @@ fn runtime_upgraded_with_set_storage() {
 	});
 }
 
+#[test]
+fn delayed_set_code_enacts_pending_code() {
+	new_test_ext().execute_with(|| {
+		let code = storage::unhashed::get_raw(well_known_keys::CODE);
+		System::set_block_number(1);
+		<DelayedSetCode<ConstU64<2>> as SetCode<Test>>::set_code(b"new code".to_vec()).unwrap();
+		assert_eq!(
+			storage::unhashed::get_raw(well_known_keys::PENDING_CODE),
+			Some(b"new code".to_vec()),
+		);
+
+		System::on_initialize(2);
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), code);
+
+		System::on_initialize(3);
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), Some(b"new code".to_vec()));
+		assert!(storage::unhashed::get_raw(well_known_keys::PENDING_CODE).is_none());
+		assert!(PendingCodeAt::<Test>::get().is_none());
+		System::assert_last_event(Event::CodeUpdated.into());
+	});
+}
+
+#[test]
+fn cancelled_code_upgrade_is_not_enacted() {
+	new_test_ext().execute_with(|| {
+		let code = storage::unhashed::get_raw(well_known_keys::CODE);
+		System::schedule_code_upgrade(b"new code", 2);
+		System::cancel_code_upgrade();
+		assert!(storage::unhashed::get_raw(well_known_keys::PENDING_CODE).is_none());
+
+		System::on_initialize(2);
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), code);
+
+		// removing the code alone cancels the upgrade as well.
+		System::schedule_code_upgrade(b"new code", 3);
+		storage::unhashed::kill(well_known_keys::PENDING_CODE);
+		System::on_initialize(3);
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), code);
+		assert!(PendingCodeAt::<Test>::get().is_none());
+	});
+}
+
+#[test]
+fn update_code_in_storage_cancels_code_upgrade() {
+	new_test_ext().execute_with(|| {
+		System::schedule_code_upgrade(b"new code", 2);
+
+		System::update_code_in_storage(b"other code").unwrap();
+		assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), Some(b"other code".to_vec()));
+		assert!(storage::unhashed::get_raw(well_known_keys::PENDING_CODE).is_none());
+		assert!(PendingCodeAt::<Test>::get().is_none());
+	});
+}
+
 #[test]
 fn events_not_emitted_during_genesis() {
 	new_test_ext().execute_with(|| {
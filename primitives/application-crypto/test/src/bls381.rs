@@ -0,0 +1,43 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration tests for bls381
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::bls381::AppPair;
+use sp_core::{
+	crypto::{ByteArray, Pair},
+	testing::BLS381,
+};
+use sp_keystore::{testing::MemoryKeystore, Keystore};
+use std::sync::Arc;
+use substrate_test_runtime_client::{
+	runtime::TestAPI, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+};
+
+#[test]
+fn bls381_works_in_runtime() {
+	let keystore = Arc::new(MemoryKeystore::new());
+	let test_client = TestClientBuilder::new().set_keystore(keystore.clone()).build();
+	let (signature, public) = test_client
+		.runtime_api()
+		.test_bls381_crypto(test_client.chain_info().genesis_hash)
+		.expect("Tests `bls381` crypto.");
+
+	let supported_keys = keystore.keys(BLS381).unwrap();
+	assert!(supported_keys.contains(&public.to_raw_vec()));
+	assert!(AppPair::verify(&signature, "bls381", &public));
+}
@@ -17,6 +17,8 @@
 
 //! Integration tests for application crypto
 
+#[cfg(test)]
+mod bls381;
 #[cfg(test)]
 mod ecdsa;
 #[cfg(test)]
@@ -66,6 +66,15 @@ impl InherentDataProvider {
 
 		Self { slot }
 	}
+
+	/// Creates the inherent data provider by calculating the slot from the given
+	/// `timestamp` and slot `schedule`.
+	pub fn from_timestamp_and_slot_schedule(
+		timestamp: sp_timestamp::Timestamp,
+		schedule: &crate::SlotSchedule,
+	) -> Self {
+		Self { slot: schedule.slot_at(timestamp) }
+	}
 }
 
 #[cfg(feature = "std")]
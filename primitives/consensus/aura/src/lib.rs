@@ -19,7 +19,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::{Codec, Decode, Encode};
+use codec::{Codec, Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
 use sp_runtime::ConsensusEngineId;
 use sp_std::vec::Vec;
 
@@ -79,6 +80,42 @@ pub enum ConsensusLog<AuthorityId: Codec> {
 	/// Disable the authority with given index.
 	#[codec(index = 2)]
 	OnDisabled(AuthorityIndex),
+	/// The slot duration has changed, starting with the children of this block.
+	#[codec(index = 3)]
+	SlotScheduleChange(SlotSchedule),
+}
+
+/// The slot duration of Aura together with the point from which it applies.
+///
+/// Slots always start at a multiple of `duration` since the unix epoch. After a duration change
+/// the numbering continues from `anchor_slot`, the slot of the block that enacted the change, so
+/// that slot numbers keep increasing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo)]
+pub struct SlotSchedule {
+	/// The duration of a slot.
+	pub duration: SlotDuration,
+	/// The slot of the block that enacted this schedule.
+	pub anchor_slot: Slot,
+	/// The timestamp of the block that enacted this schedule, in milliseconds.
+	pub anchor_timestamp: u64,
+}
+
+impl SlotSchedule {
+	/// A schedule with the given slot duration that has applied since genesis.
+	pub fn new(duration: SlotDuration) -> Self {
+		Self { duration, anchor_slot: 0.into(), anchor_timestamp: 0 }
+	}
+
+	/// Returns the slot the given `timestamp` falls in.
+	///
+	/// Timestamps before `anchor_timestamp` map to `anchor_slot`.
+	pub fn slot_at(&self, timestamp: sp_timestamp::Timestamp) -> Slot {
+		let duration = self.duration.as_millis();
+		let elapsed =
+			(timestamp.as_millis() / duration).saturating_sub(self.anchor_timestamp / duration);
+
+		self.anchor_slot.saturating_add(elapsed)
+	}
 }
 
 sp_api::decl_runtime_apis! {
@@ -86,10 +123,57 @@ sp_api::decl_runtime_apis! {
 	pub trait AuraApi<AuthorityId: Codec> {
 		/// Returns the slot duration for Aura.
 		///
-		/// Currently, only the value provided by this type at genesis will be used.
+		/// Runtimes that support slot duration changes return the duration currently in effect,
+		/// which may differ from the one at genesis. Slot numbers can then no longer be derived
+		/// from this duration and a timestamp alone: use [`Self::slot_schedule`] instead.
 		fn slot_duration() -> SlotDuration;
 
 		/// Return the current set of authorities.
 		fn authorities() -> Vec<AuthorityId>;
+
+		/// Returns the slot schedule for the children of this block.
+		#[api_version(2)]
+		fn slot_schedule() -> SlotSchedule;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_timestamp::Timestamp;
+
+	#[test]
+	fn slot_schedule_without_anchor_matches_timestamp_slots() {
+		let duration = SlotDuration::from_millis(6000);
+		let schedule = SlotSchedule::new(duration);
+
+		for timestamp in [0, 5999, 6000, 1_685_000_123_456] {
+			let timestamp = Timestamp::new(timestamp);
+			assert_eq!(schedule.slot_at(timestamp), Slot::from_timestamp(timestamp, duration));
+		}
+	}
+
+	#[test]
+	fn slot_schedule_keeps_slots_increasing() {
+		// enacted in slot 100 of a 12s schedule.
+		let anchor_timestamp = 100 * 12_000 + 500;
+		let shorter = SlotSchedule {
+			duration: SlotDuration::from_millis(6000),
+			anchor_slot: 100.into(),
+			anchor_timestamp,
+		};
+		let longer = SlotSchedule { duration: SlotDuration::from_millis(24_000), ..shorter };
+
+		assert_eq!(shorter.slot_at(Timestamp::new(anchor_timestamp)), Slot::from(100));
+		assert_eq!(shorter.slot_at(Timestamp::new(1_206_000)), Slot::from(101));
+		assert_eq!(shorter.slot_at(Timestamp::new(1_211_999)), Slot::from(101));
+		assert_eq!(shorter.slot_at(Timestamp::new(1_212_000)), Slot::from(102));
+
+		assert_eq!(longer.slot_at(Timestamp::new(anchor_timestamp)), Slot::from(100));
+		assert_eq!(longer.slot_at(Timestamp::new(1_223_999)), Slot::from(100));
+		assert_eq!(longer.slot_at(Timestamp::new(1_224_000)), Slot::from(101));
+
+		// timestamps before the anchor don't go back in time.
+		assert_eq!(shorter.slot_at(Timestamp::new(0)), Slot::from(100));
 	}
 }
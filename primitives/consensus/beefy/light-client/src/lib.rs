@@ -0,0 +1,526 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+//! A BEEFY light client.
+//!
+//! The light client follows a chain running BEEFY and `pallet-beefy-mmr` by importing
+//! [`SignedCommitment`]s. Each imported commitment moves the light client to a newer MMR root,
+//! against which proofs of any past MMR leaf can be verified.
+//!
+//! Authority sets are only known by their [`BeefyAuthoritySet`] merkle root, the full list of
+//! authorities has to be supplied alongside every commitment. The next authority set is learned
+//! from the latest MMR leaf of every imported commitment, so the light client has to import at
+//! least one commitment signed by every authority set to keep up with the chain.
+//!
+//! The verification is stateless: the [`LightClientState`] is passed in by the caller, which makes
+//! it possible to share the same logic between a bridge runtime and off-chain relayers.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_consensus_beefy::{
+	crypto::{AuthorityId, Signature},
+	known_payloads,
+	mmr::{BeefyAuthoritySet, BeefyNextAuthoritySet, MmrLeaf},
+	SignedCommitment, ValidatorSetId,
+};
+use sp_mmr_primitives::{mmr_lib, utils::NodesUtils, DataOrHash, FullLeaf, Proof};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Convert, Hash, Keccak256},
+	RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Errors encountered while verifying BEEFY commitments and MMR proofs.
+#[derive(RuntimeDebug, Clone, Copy, PartialEq, Eq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum Error {
+	/// The commitment is not newer than the latest imported one.
+	#[cfg_attr(feature = "std", error("Commitment is not newer than the latest imported one"))]
+	StaleCommitment,
+	/// The commitment is signed by neither the current nor the next authority set.
+	#[cfg_attr(feature = "std", error("Commitment is signed by an unknown validator set"))]
+	UnknownValidatorSet,
+	/// The supplied authorities don't match the authority set merkle root.
+	#[cfg_attr(feature = "std", error("Authorities don't match the authority set root"))]
+	InvalidAuthorities,
+	/// There is not exactly one signature slot per authority.
+	#[cfg_attr(feature = "std", error("Number of signatures doesn't match the authority set"))]
+	InvalidSignatureCount,
+	/// Less than a supermajority of the authorities signed the commitment.
+	#[cfg_attr(feature = "std", error("Not enough valid signatures"))]
+	NotEnoughSignatures,
+	/// The commitment payload carries no MMR root.
+	#[cfg_attr(feature = "std", error("Commitment payload is missing the MMR root"))]
+	MissingMmrRoot,
+	/// The leaf proof is not for the latest leaf of the committed MMR.
+	#[cfg_attr(feature = "std", error("Leaf is not the latest leaf of the committed MMR"))]
+	InvalidLatestLeaf,
+	/// The next authority set in the latest leaf doesn't follow the current one.
+	#[cfg_attr(feature = "std", error("Next authority set doesn't follow the current one"))]
+	InvalidNextAuthoritySet,
+	/// The MMR proof doesn't verify against the MMR root.
+	#[cfg_attr(feature = "std", error("Invalid MMR proof"))]
+	InvalidMmrProof,
+}
+
+/// Everything a light client has to keep between two imported commitments.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug, Clone, PartialEq, Eq)]
+pub struct LightClientState<BlockNumber, MerkleRoot> {
+	/// Block number of the latest imported commitment.
+	pub latest_beefy_block: BlockNumber,
+	/// MMR root committed to by the latest imported commitment.
+	pub latest_mmr_root: MerkleRoot,
+	/// Authority set expected to sign the next commitments.
+	pub current_authority_set: BeefyAuthoritySet<MerkleRoot>,
+	/// Authority set taking over at the next validator set change.
+	pub next_authority_set: BeefyNextAuthoritySet<MerkleRoot>,
+}
+
+/// Returns the number of valid signatures a commitment needs to be accepted.
+///
+/// This is the same supermajority the BEEFY voter requires before finalizing a block.
+pub fn threshold(authorities: usize) -> usize {
+	let faulty = authorities.saturating_sub(1) / 3;
+	authorities - faulty
+}
+
+/// Verify a signed commitment against the current or the next authority set of `state`.
+///
+/// `authorities` is the full, ordered list of authorities of the set that signed the commitment,
+/// they are checked against the set's merkle root, using `C` to turn each authority into a merkle
+/// leaf and `H` as hasher, just like `pallet-beefy-mmr` does.
+///
+/// Returns the MMR root contained in the commitment payload.
+pub fn verify_signed_commitment<H, C, N>(
+	state: &LightClientState<N, H::Output>,
+	signed_commitment: &SignedCommitment<N, Signature>,
+	authorities: &[AuthorityId],
+) -> Result<H::Output, Error>
+where
+	H: Hash,
+	H::Output: PartialOrd,
+	C: Convert<AuthorityId, Vec<u8>>,
+	N: AtLeast32BitUnsigned + Copy + Encode,
+{
+	let commitment = &signed_commitment.commitment;
+	if commitment.block_number <= state.latest_beefy_block {
+		return Err(Error::StaleCommitment)
+	}
+
+	let authority_set = authority_set(state, commitment.validator_set_id)?;
+	if authorities.len() != authority_set.len as usize ||
+		binary_merkle_tree::merkle_root::<H, _>(authorities.iter().cloned().map(C::convert)) !=
+			authority_set.root
+	{
+		return Err(Error::InvalidAuthorities)
+	}
+	if signed_commitment.signatures.len() != authorities.len() {
+		return Err(Error::InvalidSignatureCount)
+	}
+
+	// signatures are ordered like the authorities of the set.
+	let valid_signatures = authorities
+		.iter()
+		.zip(signed_commitment.signatures.iter())
+		.filter(|(id, signature)| {
+			signature.as_ref().map_or(false, |signature| {
+				sp_consensus_beefy::check_commitment_signature::<_, _, Keccak256>(
+					commitment, *id, signature,
+				)
+			})
+		})
+		.count();
+	if valid_signatures < threshold(authorities.len()) {
+		return Err(Error::NotEnoughSignatures)
+	}
+
+	commitment
+		.payload
+		.get_decoded::<H::Output>(&known_payloads::MMR_ROOT_ID)
+		.ok_or(Error::MissingMmrRoot)
+}
+
+/// Verify and import a signed commitment, moving `state` to the committed MMR root.
+///
+/// `latest_leaf` is the MMR leaf added in the committed block and `latest_leaf_proof` its proof
+/// against the committed MMR root, the next authority set is taken from it. When the commitment
+/// is signed by the next authority set, the light client switches over to it.
+///
+/// `state` is left untouched if the commitment can't be imported.
+pub fn import_signed_commitment<H, C, N, BlockHash, ExtraData>(
+	state: &mut LightClientState<N, H::Output>,
+	signed_commitment: &SignedCommitment<N, Signature>,
+	authorities: &[AuthorityId],
+	latest_leaf: &MmrLeaf<N, BlockHash, H::Output, ExtraData>,
+	latest_leaf_proof: &Proof<H::Output>,
+) -> Result<(), Error>
+where
+	H: Hash,
+	H::Output: PartialOrd,
+	C: Convert<AuthorityId, Vec<u8>>,
+	N: AtLeast32BitUnsigned + Copy + Encode,
+	MmrLeaf<N, BlockHash, H::Output, ExtraData>: FullLeaf,
+{
+	let mmr_root = verify_signed_commitment::<H, C, N>(state, signed_commitment, authorities)?;
+	let commitment = &signed_commitment.commitment;
+
+	// the leaf added in block `n` refers to block `n - 1` as its parent.
+	if latest_leaf_proof.leaf_indices != [latest_leaf_proof.leaf_count.saturating_sub(1)] ||
+		latest_leaf.parent_number_and_hash.0.saturating_add(N::one()) != commitment.block_number
+	{
+		return Err(Error::InvalidLatestLeaf)
+	}
+	verify_mmr_leaves::<H, _>(mmr_root, vec![latest_leaf.clone()], latest_leaf_proof.clone())?;
+
+	let current_authority_set = if commitment.validator_set_id == state.next_authority_set.id {
+		state.next_authority_set.clone()
+	} else {
+		state.current_authority_set.clone()
+	};
+	let next_authority_set = latest_leaf.beefy_next_authority_set.clone();
+	if next_authority_set.id != current_authority_set.id.saturating_add(1) {
+		return Err(Error::InvalidNextAuthoritySet)
+	}
+
+	*state = LightClientState {
+		latest_beefy_block: commitment.block_number,
+		latest_mmr_root: mmr_root,
+		current_authority_set,
+		next_authority_set,
+	};
+	Ok(())
+}
+
+/// Verify a proof of `leaves` against `mmr_root`, usually the `latest_mmr_root` of a
+/// [`LightClientState`].
+///
+/// Leaves and inner nodes are hashed with `H`, which has to match the hashing of the MMR pallet.
+pub fn verify_mmr_leaves<H, L>(
+	mmr_root: H::Output,
+	leaves: Vec<L>,
+	proof: Proof<H::Output>,
+) -> Result<(), Error>
+where
+	H: Hash,
+	L: FullLeaf,
+{
+	if leaves.len() != proof.leaf_indices.len() {
+		return Err(Error::InvalidMmrProof)
+	}
+
+	let size = NodesUtils::new(proof.leaf_count).size();
+	let leaves = proof
+		.leaf_indices
+		.into_iter()
+		.map(mmr_lib::leaf_index_to_pos)
+		.zip(leaves.into_iter().map(DataOrHash::Data))
+		.collect();
+	let proof = mmr_lib::MerkleProof::<DataOrHash<H, L>, MmrHasher<H, L>>::new(
+		size,
+		proof.items.into_iter().map(DataOrHash::Hash).collect(),
+	);
+
+	match proof.verify(DataOrHash::Hash(mmr_root), leaves) {
+		Ok(true) => Ok(()),
+		_ => Err(Error::InvalidMmrProof),
+	}
+}
+
+fn authority_set<N, MerkleRoot>(
+	state: &LightClientState<N, MerkleRoot>,
+	id: ValidatorSetId,
+) -> Result<&BeefyAuthoritySet<MerkleRoot>, Error> {
+	if id == state.current_authority_set.id {
+		Ok(&state.current_authority_set)
+	} else if id == state.next_authority_set.id {
+		Ok(&state.next_authority_set)
+	} else {
+		Err(Error::UnknownValidatorSet)
+	}
+}
+
+// Merges MMR nodes the same way `pallet-mmr` does.
+struct MmrHasher<H, L>(PhantomData<(H, L)>);
+
+impl<H: Hash, L: FullLeaf> mmr_lib::Merge for MmrHasher<H, L> {
+	type Item = DataOrHash<H, L>;
+
+	fn merge(left: &Self::Item, right: &Self::Item) -> mmr_lib::Result<Self::Item> {
+		let mut concat = left.hash().as_ref().to_vec();
+		concat.extend_from_slice(right.hash().as_ref());
+
+		Ok(DataOrHash::Hash(<H as Hash>::hash(&concat)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_consensus_beefy::{mmr::MmrLeafVersion, Commitment, Keyring, Payload};
+	use sp_core::{crypto::ByteArray, H256};
+
+	type Leaf = MmrLeaf<u64, H256, H256, Vec<u8>>;
+
+	// uses the compressed public key as merkle leaf.
+	struct RawKey;
+	impl Convert<AuthorityId, Vec<u8>> for RawKey {
+		fn convert(id: AuthorityId) -> Vec<u8> {
+			id.to_raw_vec()
+		}
+	}
+
+	fn authorities(keys: &[Keyring]) -> Vec<AuthorityId> {
+		keys.iter().map(|k| k.public()).collect()
+	}
+
+	fn authority_set(id: ValidatorSetId, keys: &[Keyring]) -> BeefyAuthoritySet<H256> {
+		BeefyAuthoritySet {
+			id,
+			len: keys.len() as u32,
+			root: binary_merkle_tree::merkle_root::<Keccak256, _>(
+				authorities(keys).into_iter().map(RawKey::convert),
+			),
+		}
+	}
+
+	fn leaf(parent_number: u64, next_authority_set: BeefyAuthoritySet<H256>) -> Leaf {
+		MmrLeaf {
+			version: MmrLeafVersion::new(0, 0),
+			parent_number_and_hash: (parent_number, H256::repeat_byte(parent_number as u8)),
+			beefy_next_authority_set: next_authority_set,
+			leaf_extra: vec![],
+		}
+	}
+
+	fn leaf_hash(leaf: &Leaf) -> H256 {
+		Keccak256::hash(&leaf.encode())
+	}
+
+	// an MMR with two leaves is a single perfect tree.
+	fn mmr_root(first: &Leaf, second: &Leaf) -> H256 {
+		Keccak256::hash(&[leaf_hash(first).as_bytes(), leaf_hash(second).as_bytes()].concat())
+	}
+
+	fn signed_commitment(
+		block_number: u64,
+		validator_set_id: ValidatorSetId,
+		mmr_root: H256,
+		signers: &[Option<Keyring>],
+	) -> SignedCommitment<u64, Signature> {
+		let commitment = Commitment {
+			payload: Payload::from_single_entry(known_payloads::MMR_ROOT_ID, mmr_root.encode()),
+			block_number,
+			validator_set_id,
+		};
+		let signatures = signers.iter().map(|k| k.map(|k| k.sign(&commitment.encode()))).collect();
+		SignedCommitment { commitment, signatures }
+	}
+
+	const SET_0: [Keyring; 4] = [Keyring::Alice, Keyring::Bob, Keyring::Charlie, Keyring::Dave];
+	const SET_1: [Keyring; 4] = [Keyring::Eve, Keyring::Ferdie, Keyring::One, Keyring::Two];
+
+	fn initial_state() -> LightClientState<u64, H256> {
+		LightClientState {
+			latest_beefy_block: 0,
+			latest_mmr_root: H256::zero(),
+			current_authority_set: authority_set(0, &SET_0),
+			next_authority_set: authority_set(1, &SET_1),
+		}
+	}
+
+	#[test]
+	fn threshold_is_a_supermajority() {
+		assert_eq!(threshold(1), 1);
+		assert_eq!(threshold(3), 3);
+		assert_eq!(threshold(4), 3);
+		assert_eq!(threshold(10), 7);
+	}
+
+	#[test]
+	fn imports_commitments_and_follows_authority_sets() {
+		let mut state = initial_state();
+		let first = leaf(0, authority_set(1, &SET_1));
+		let second = leaf(1, authority_set(1, &SET_1));
+		let root = mmr_root(&first, &second);
+		let proof = Proof { leaf_indices: vec![1], leaf_count: 2, items: vec![leaf_hash(&first)] };
+
+		// a single missing signature is fine.
+		let signers = [Some(Keyring::Alice), None, Some(Keyring::Charlie), Some(Keyring::Dave)];
+		let commitment = signed_commitment(2, 0, root, &signers);
+		import_signed_commitment::<Keccak256, RawKey, _, _, _>(
+			&mut state,
+			&commitment,
+			&authorities(&SET_0),
+			&second,
+			&proof,
+		)
+		.unwrap();
+		assert_eq!(state.latest_beefy_block, 2);
+		assert_eq!(state.latest_mmr_root, root);
+		assert_eq!(state.current_authority_set, authority_set(0, &SET_0));
+
+		// the next set takes over, the leaf announces the one after it.
+		let set_2 = authority_set(2, &SET_0);
+		let third = leaf(2, set_2.clone());
+		let fourth = leaf(3, set_2.clone());
+		let root = mmr_root(&third, &fourth);
+		let proof = Proof { leaf_indices: vec![1], leaf_count: 2, items: vec![leaf_hash(&third)] };
+		let signers = SET_1.map(Some);
+		let commitment = signed_commitment(4, 1, root, &signers);
+		import_signed_commitment::<Keccak256, RawKey, _, _, _>(
+			&mut state,
+			&commitment,
+			&authorities(&SET_1),
+			&fourth,
+			&proof,
+		)
+		.unwrap();
+		assert_eq!(state.latest_beefy_block, 4);
+		assert_eq!(state.current_authority_set, authority_set(1, &SET_1));
+		assert_eq!(state.next_authority_set, set_2);
+
+		// the old set is gone.
+		let commitment = signed_commitment(5, 0, root, &SET_0.map(Some));
+		assert_eq!(
+			verify_signed_commitment::<Keccak256, RawKey, _>(
+				&state,
+				&commitment,
+				&authorities(&SET_0)
+			),
+			Err(Error::UnknownValidatorSet),
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_commitments() {
+		let state = initial_state();
+		let root = H256::repeat_byte(1);
+		let verify = |commitment: &SignedCommitment<u64, Signature>, keys: &[Keyring]| {
+			verify_signed_commitment::<Keccak256, RawKey, _>(&state, commitment, &authorities(keys))
+		};
+
+		let all = SET_0.map(Some);
+		assert_eq!(verify(&signed_commitment(1, 0, root, &all), &SET_0), Ok(root));
+		assert_eq!(
+			verify(&signed_commitment(0, 0, root, &all), &SET_0),
+			Err(Error::StaleCommitment)
+		);
+		assert_eq!(
+			verify(&signed_commitment(1, 0, root, &all), &SET_1),
+			Err(Error::InvalidAuthorities)
+		);
+		assert_eq!(
+			verify(&signed_commitment(1, 0, root, &all[..3]), &SET_0),
+			Err(Error::InvalidSignatureCount)
+		);
+
+		let two_missing = [Some(Keyring::Alice), None, None, Some(Keyring::Dave)];
+		assert_eq!(
+			verify(&signed_commitment(1, 0, root, &two_missing), &SET_0),
+			Err(Error::NotEnoughSignatures)
+		);
+		// signatures in the wrong slots don't count.
+		let shuffled = [Some(Keyring::Bob), Some(Keyring::Alice), Some(Keyring::Charlie), None];
+		assert_eq!(
+			verify(&signed_commitment(1, 0, root, &shuffled), &SET_0),
+			Err(Error::NotEnoughSignatures)
+		);
+
+		let mut commitment = signed_commitment(1, 0, root, &all);
+		commitment.commitment.payload = Payload::from_single_entry(*b"xx", vec![]);
+		commitment.signatures =
+			SET_0.iter().map(|k| Some(k.sign(&commitment.commitment.encode()))).collect();
+		assert_eq!(verify(&commitment, &SET_0), Err(Error::MissingMmrRoot));
+	}
+
+	#[test]
+	fn rejects_wrong_latest_leaf() {
+		let first = leaf(0, authority_set(1, &SET_1));
+		let second = leaf(1, authority_set(1, &SET_1));
+		let root = mmr_root(&first, &second);
+		let commitment = signed_commitment(2, 0, root, &SET_0.map(Some));
+		let import = |leaf: &Leaf, proof: &Proof<H256>| {
+			let mut state = initial_state();
+			let result = import_signed_commitment::<Keccak256, RawKey, _, _, _>(
+				&mut state,
+				&commitment,
+				&authorities(&SET_0),
+				leaf,
+				proof,
+			);
+			// failed imports leave the state alone.
+			assert_eq!(state, initial_state());
+			result
+		};
+
+		// not the latest leaf.
+		let proof = Proof { leaf_indices: vec![0], leaf_count: 2, items: vec![leaf_hash(&second)] };
+		assert_eq!(import(&first, &proof), Err(Error::InvalidLatestLeaf));
+
+		// not part of the committed MMR.
+		let other = leaf(1, authority_set(1, &SET_0));
+		let proof = Proof { leaf_indices: vec![1], leaf_count: 2, items: vec![leaf_hash(&first)] };
+		assert_eq!(import(&other, &proof), Err(Error::InvalidMmrProof));
+
+		// announces a set that doesn't follow the current one.
+		let first = leaf(0, authority_set(3, &SET_1));
+		let second = leaf(1, authority_set(3, &SET_1));
+		let commitment = signed_commitment(2, 0, mmr_root(&first, &second), &SET_0.map(Some));
+		let proof = Proof { leaf_indices: vec![1], leaf_count: 2, items: vec![leaf_hash(&first)] };
+		let mut state = initial_state();
+		assert_eq!(
+			import_signed_commitment::<Keccak256, RawKey, _, _, _>(
+				&mut state,
+				&commitment,
+				&authorities(&SET_0),
+				&second,
+				&proof,
+			),
+			Err(Error::InvalidNextAuthoritySet)
+		);
+		assert_eq!(state, initial_state());
+	}
+
+	#[test]
+	fn verifies_mmr_leaf_proofs() {
+		let first = leaf(0, authority_set(1, &SET_1));
+		let second = leaf(1, authority_set(1, &SET_1));
+		let root = mmr_root(&first, &second);
+
+		let proof = Proof { leaf_indices: vec![0], leaf_count: 2, items: vec![leaf_hash(&second)] };
+		assert_eq!(
+			verify_mmr_leaves::<Keccak256, _>(root, vec![first.clone()], proof.clone()),
+			Ok(())
+		);
+		assert_eq!(
+			verify_mmr_leaves::<Keccak256, _>(root, vec![second.clone()], proof.clone()),
+			Err(Error::InvalidMmrProof)
+		);
+		assert_eq!(
+			verify_mmr_leaves::<Keccak256, _>(root, vec![first.clone(), second.clone()], proof),
+			Err(Error::InvalidMmrProof)
+		);
+
+		// opaque leaves, as handed out by the MMR RPC, work just as well.
+		let proof = Proof { leaf_indices: vec![0], leaf_count: 2, items: vec![leaf_hash(&second)] };
+		let opaque = sp_mmr_primitives::OpaqueLeaf::from_leaf(&first);
+		assert_eq!(verify_mmr_leaves::<Keccak256, _>(root, vec![opaque], proof), Ok(()));
+	}
+}
@@ -23,6 +23,8 @@ use codec::Decode;
 use sp_runtime::ConsensusEngineId;
 use sp_std::vec::Vec;
 
+pub mod lwma;
+
 /// The `ConsensusEngineId` of PoW.
 pub const POW_ENGINE_ID: ConsensusEngineId = [b'p', b'o', b'w', b'_'];
 
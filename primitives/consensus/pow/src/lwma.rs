@@ -0,0 +1,134 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Linearly weighted moving average (LWMA) difficulty adjustment.
+//!
+//! The next difficulty is the average difficulty of the last `N` blocks, scaled by how far their
+//! solve times were off the target. Recent solve times are weighted more than older ones, which
+//! makes the adjustment react quickly to hashrate changes without oscillating.
+//!
+//! The algorithm is pure, so it can run in the runtime (e.g. to implement
+//! [`DifficultyApi`](crate::DifficultyApi)) as well as on the client.
+
+use sp_core::U256;
+
+/// Solve times are clamped to this many target block times, so that a single block with a bogus
+/// timestamp can't move the difficulty too much.
+pub const MAX_SOLVE_TIME_FACTOR: u64 = 6;
+
+/// Compute the difficulty of the next block from `(timestamp, difficulty)` pairs of the most
+/// recent blocks, ordered from the oldest to the newest.
+///
+/// `N + 1` blocks yield a window of `N` solve times, the difficulty of the oldest block is only
+/// used for its timestamp. Returns `None` if fewer than two blocks are given.
+///
+/// Timestamps and `target_block_time` must use the same unit.
+pub fn next_difficulty(blocks: &[(u64, U256)], target_block_time: u64) -> Option<U256> {
+	if blocks.len() < 2 {
+		return None
+	}
+
+	let target_block_time = target_block_time.max(1);
+	let max_solve_time = target_block_time.saturating_mul(MAX_SOLVE_TIME_FACTOR);
+	let window = (blocks.len() - 1) as u64;
+
+	let mut weighted_solve_times = U256::zero();
+	let mut total_difficulty = U256::zero();
+	for (weight, pair) in (1u64..).zip(blocks.windows(2)) {
+		let (previous_timestamp, _) = pair[0];
+		let (timestamp, difficulty) = pair[1];
+		let solve_time = timestamp.saturating_sub(previous_timestamp).clamp(1, max_solve_time);
+
+		weighted_solve_times += U256::from(solve_time) * U256::from(weight);
+		total_difficulty = total_difficulty.saturating_add(difficulty);
+	}
+
+	// average difficulty * target / weighted average solve time, where the weights add up to
+	// `window * (window + 1) / 2`.
+	let next = total_difficulty
+		.saturating_mul(U256::from(target_block_time))
+		.saturating_mul(U256::from(window + 1)) /
+		(weighted_solve_times * U256::from(2u8));
+
+	Some(next.max(U256::one()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TARGET: u64 = 6_000;
+
+	fn blocks(solve_times: &[u64], difficulty: u64) -> Vec<(u64, U256)> {
+		let mut timestamp = 1_000_000;
+		let mut blocks = vec![(timestamp, U256::from(difficulty))];
+		for solve_time in solve_times {
+			timestamp += solve_time;
+			blocks.push((timestamp, U256::from(difficulty)));
+		}
+		blocks
+	}
+
+	#[test]
+	fn needs_two_blocks() {
+		assert_eq!(next_difficulty(&[], TARGET), None);
+		assert_eq!(next_difficulty(&blocks(&[], 1_000), TARGET), None);
+	}
+
+	#[test]
+	fn keeps_difficulty_on_target() {
+		let blocks = blocks(&[TARGET; 60], 1_000_000);
+		assert_eq!(next_difficulty(&blocks, TARGET), Some(U256::from(1_000_000)));
+	}
+
+	#[test]
+	fn follows_solve_times() {
+		let fast = blocks(&[TARGET / 2; 60], 1_000_000);
+		assert_eq!(next_difficulty(&fast, TARGET), Some(U256::from(2_000_000)));
+
+		let slow = blocks(&[TARGET * 2; 60], 1_000_000);
+		assert_eq!(next_difficulty(&slow, TARGET), Some(U256::from(500_000)));
+	}
+
+	#[test]
+	fn weights_recent_blocks_more() {
+		// the same solve times, once slow blocks at the start and once at the end.
+		let mut solve_times = vec![TARGET * 2; 30];
+		solve_times.extend([TARGET; 30]);
+		let slow_then_fast = next_difficulty(&blocks(&solve_times, 1_000_000), TARGET).unwrap();
+		solve_times.reverse();
+		let fast_then_slow = next_difficulty(&blocks(&solve_times, 1_000_000), TARGET).unwrap();
+
+		assert!(slow_then_fast > fast_then_slow);
+		assert!(slow_then_fast < U256::from(1_000_000));
+	}
+
+	#[test]
+	fn clamps_solve_times() {
+		// a block "mined" hours later counts as six target block times.
+		let mut solve_times = vec![TARGET; 59];
+		solve_times.push(TARGET * 1_000);
+		let clamped = next_difficulty(&blocks(&solve_times, 1_000_000), TARGET).unwrap();
+		solve_times[59] = TARGET * MAX_SOLVE_TIME_FACTOR;
+		assert_eq!(next_difficulty(&blocks(&solve_times, 1_000_000), TARGET), Some(clamped));
+
+		// and out of order timestamps as a minimal solve time.
+		let mut blocks = blocks(&[TARGET; 2], 1);
+		blocks[2].0 = 0;
+		assert_eq!(next_difficulty(&blocks, TARGET), Some(U256::from(2)));
+	}
+}
@@ -0,0 +1,668 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simple BLS12-381 API.
+//!
+//! Follows the minimal-public-key-size variant of the IETF BLS signature scheme with proofs of
+//! possession: public keys are points of G1 (48 bytes) and signatures are points of G2
+//! (96 bytes).
+//!
+//! Signatures of the same message by several keys can be aggregated into a single signature of
+//! the same size, and verified at once against the public keys of the signers. This is only
+//! secure if every signer proved that it knows its secret key, see
+//! [`Pair::proof_of_possession`], as a forged public key could otherwise cancel out the others.
+//! Aggregated signatures are hence only verified against [`ProvenPublic`] keys.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime_interface::pass_by::PassByInner;
+
+#[cfg(feature = "std")]
+use crate::crypto::Ss58Codec;
+use crate::crypto::{
+	ByteArray, CryptoType, CryptoTypeId, Derive, Public as TraitPublic, UncheckedFrom,
+};
+#[cfg(feature = "full_crypto")]
+use crate::crypto::{DeriveError, DeriveJunction, Pair as TraitPair, SecretStringError};
+#[cfg(feature = "std")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "full_crypto")]
+use sp_std::vec::Vec;
+#[cfg(feature = "full_crypto")]
+use w3f_bls::{
+	Keypair, Message, PublicKey as BlsPublic, SecretKey, SerializableToBytes,
+	Signature as BlsSignature, ZBLS,
+};
+
+/// An identifier used to match public keys against BLS12-381 keys
+pub const CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"bls8");
+
+/// Size of a serialized public key.
+pub const PUBLIC_KEY_SERIALIZED_SIZE: usize = 48;
+
+/// Size of a serialized signature.
+pub const SIGNATURE_SERIALIZED_SIZE: usize = 96;
+
+/// Signing context of message signatures.
+#[cfg(feature = "full_crypto")]
+const SIGNATURE_CONTEXT: &[u8] = b"substrate-bls-sig";
+
+/// Signing context of proofs of possession.
+#[cfg(feature = "full_crypto")]
+const PROOF_OF_POSSESSION_CONTEXT: &[u8] = b"substrate-bls-pop";
+
+/// A secret seed, from which the secret key is derived.
+///
+/// We need it as a different type because `Seed` is expected to be AsRef<[u8]>.
+#[cfg(feature = "full_crypto")]
+type Seed = [u8; 32];
+
+/// A BLS12-381 public key, in compressed form.
+#[cfg_attr(feature = "full_crypto", derive(Hash))]
+#[derive(
+	Clone,
+	Copy,
+	Encode,
+	Decode,
+	PassByInner,
+	MaxEncodedLen,
+	TypeInfo,
+	Eq,
+	PartialEq,
+	PartialOrd,
+	Ord,
+)]
+pub struct Public(pub [u8; PUBLIC_KEY_SERIALIZED_SIZE]);
+
+impl Public {
+	/// A new instance from the given 48-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	pub fn from_raw(data: [u8; PUBLIC_KEY_SERIALIZED_SIZE]) -> Self {
+		Self(data)
+	}
+
+	/// Verify that `proof` proves the possession of the secret key of this public key.
+	#[cfg(feature = "full_crypto")]
+	pub fn verify_proof_of_possession(&self, proof: &Signature) -> bool {
+		verify_in_context(proof, &self.0, self, PROOF_OF_POSSESSION_CONTEXT)
+	}
+
+	/// Turn this public key into a [`ProvenPublic`] if `proof` is a valid proof of possession.
+	#[cfg(feature = "full_crypto")]
+	pub fn prove(self, proof: &Signature) -> Option<ProvenPublic> {
+		self.verify_proof_of_possession(proof).then_some(ProvenPublic(self))
+	}
+
+	/// Decode the curve point of this public key.
+	///
+	/// Returns `None` for invalid points and for the identity, which would verify any signature
+	/// of the identity.
+	#[cfg(feature = "full_crypto")]
+	fn to_point(&self) -> Option<BlsPublic<ZBLS>> {
+		let public = BlsPublic::<ZBLS>::from_bytes(&self.0).ok()?;
+		(public != BlsPublic(Default::default())).then_some(public)
+	}
+}
+
+impl ByteArray for Public {
+	const LEN: usize = PUBLIC_KEY_SERIALIZED_SIZE;
+}
+
+impl TraitPublic for Public {}
+
+impl Derive for Public {}
+
+impl AsRef<[u8]> for Public {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Public {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl TryFrom<&[u8]> for Public {
+	type Error = ();
+
+	fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+		if data.len() != Self::LEN {
+			return Err(())
+		}
+		let mut r = [0u8; Self::LEN];
+		r.copy_from_slice(data);
+		Ok(Self::unchecked_from(r))
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl From<Pair> for Public {
+	fn from(x: Pair) -> Self {
+		x.public()
+	}
+}
+
+impl UncheckedFrom<[u8; PUBLIC_KEY_SERIALIZED_SIZE]> for Public {
+	fn unchecked_from(x: [u8; PUBLIC_KEY_SERIALIZED_SIZE]) -> Self {
+		Public(x)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Public {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.to_ss58check())
+	}
+}
+
+impl sp_std::fmt::Debug for Public {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let s = self.to_ss58check();
+		write!(f, "{} ({}...)", crate::hexdisplay::HexDisplay::from(&self.as_ref()), &s[0..8])
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Public {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Public {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		Public::from_ss58check(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+/// A BLS12-381 public key whose proof of possession has been verified.
+///
+/// Only such keys can be used to verify aggregated signatures. Decoding does not verify the proof
+/// again, so this type must not be accepted from untrusted input: decode a [`Public`] and
+/// [`prove`](Public::prove) it instead.
+#[cfg_attr(feature = "full_crypto", derive(Hash))]
+#[derive(
+	Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, Eq, PartialEq, PartialOrd, Ord, Debug,
+)]
+pub struct ProvenPublic(Public);
+
+impl ProvenPublic {
+	/// The public key.
+	pub fn public(&self) -> &Public {
+		&self.0
+	}
+}
+
+impl From<ProvenPublic> for Public {
+	fn from(x: ProvenPublic) -> Self {
+		x.0
+	}
+}
+
+impl AsRef<Public> for ProvenPublic {
+	fn as_ref(&self) -> &Public {
+		&self.0
+	}
+}
+
+/// Assume that the proof of possession of the given public key has been verified.
+///
+/// NOTE: Only use it if you verified the proof by other means, e.g. with the
+/// `bls381_verify_proof_of_possession` host function.
+impl UncheckedFrom<Public> for ProvenPublic {
+	fn unchecked_from(x: Public) -> Self {
+		ProvenPublic(x)
+	}
+}
+
+/// A BLS12-381 signature, in compressed form.
+///
+/// Aggregated signatures have the same type as individual ones.
+#[cfg_attr(feature = "full_crypto", derive(Hash))]
+#[derive(Clone, Encode, Decode, MaxEncodedLen, PassByInner, TypeInfo, PartialEq, Eq)]
+pub struct Signature(pub [u8; SIGNATURE_SERIALIZED_SIZE]);
+
+impl TryFrom<&[u8]> for Signature {
+	type Error = ();
+
+	fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+		if data.len() == SIGNATURE_SERIALIZED_SIZE {
+			let mut inner = [0u8; SIGNATURE_SERIALIZED_SIZE];
+			inner.copy_from_slice(data);
+			Ok(Signature(inner))
+		} else {
+			Err(())
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Signature {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&array_bytes::bytes2hex("", self.as_ref()))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Signature {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let signature_hex = array_bytes::hex2bytes(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))?;
+		Signature::try_from(signature_hex.as_ref())
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+impl Default for Signature {
+	fn default() -> Self {
+		Signature([0u8; SIGNATURE_SERIALIZED_SIZE])
+	}
+}
+
+impl From<Signature> for [u8; SIGNATURE_SERIALIZED_SIZE] {
+	fn from(v: Signature) -> [u8; SIGNATURE_SERIALIZED_SIZE] {
+		v.0
+	}
+}
+
+impl AsRef<[u8]> for Signature {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Signature {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl sp_std::fmt::Debug for Signature {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "{}", crate::hexdisplay::HexDisplay::from(&self.0))
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl UncheckedFrom<[u8; SIGNATURE_SERIALIZED_SIZE]> for Signature {
+	fn unchecked_from(data: [u8; SIGNATURE_SERIALIZED_SIZE]) -> Signature {
+		Signature(data)
+	}
+}
+
+impl Signature {
+	/// A new instance from the given 96-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_raw(data: [u8; SIGNATURE_SERIALIZED_SIZE]) -> Signature {
+		Signature(data)
+	}
+
+	/// Aggregate signatures into a single one.
+	///
+	/// Returns `None` if `signatures` is empty or if any of them is not a valid point.
+	#[cfg(feature = "full_crypto")]
+	pub fn aggregate(signatures: &[Signature]) -> Option<Signature> {
+		let (first, rest) = signatures.split_first()?;
+		let mut aggregate = first.to_point()?;
+		for signature in rest {
+			aggregate.0 += signature.to_point()?.0;
+		}
+		Some(aggregate.into())
+	}
+
+	/// Decode the curve point of this signature.
+	#[cfg(feature = "full_crypto")]
+	fn to_point(&self) -> Option<BlsSignature<ZBLS>> {
+		BlsSignature::<ZBLS>::from_bytes(&self.0).ok()
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl From<BlsSignature<ZBLS>> for Signature {
+	fn from(signature: BlsSignature<ZBLS>) -> Signature {
+		let mut r = [0u8; SIGNATURE_SERIALIZED_SIZE];
+		r.copy_from_slice(&signature.to_bytes());
+		Signature(r)
+	}
+}
+
+/// Verify `signature` of `message` by `public`, in the given signing context.
+#[cfg(feature = "full_crypto")]
+fn verify_in_context(
+	signature: &Signature,
+	message: &[u8],
+	public: &Public,
+	context: &[u8],
+) -> bool {
+	match (signature.to_point(), public.to_point()) {
+		(Some(signature), Some(public)) =>
+			signature.verify(&Message::new(context, message), &public),
+		_ => false,
+	}
+}
+
+/// Derive a single hard junction.
+#[cfg(feature = "full_crypto")]
+fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
+	("BLS12381HDKD", secret_seed, cc).using_encoded(sp_core_hashing::blake2_256)
+}
+
+/// A key pair.
+#[cfg(feature = "full_crypto")]
+#[derive(Clone)]
+pub struct Pair {
+	public: Public,
+	keypair: Keypair<ZBLS>,
+	/// Seed the secret key was generated from. Kept around for derivation.
+	seed: Seed,
+}
+
+#[cfg(feature = "full_crypto")]
+impl TraitPair for Pair {
+	type Public = Public;
+	type Seed = Seed;
+	type Signature = Signature;
+
+	/// Make a new key pair from secret seed material. The slice must be 32 bytes long or it
+	/// will return `None`.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed_slice(seed_slice: &[u8]) -> Result<Pair, SecretStringError> {
+		let seed: Seed = seed_slice.try_into().map_err(|_| SecretStringError::InvalidSeedLength)?;
+		let secret = SecretKey::<ZBLS>::from_seed(&seed);
+		let keypair = Keypair { public: secret.into_public(), secret };
+		let public = Public::try_from(&keypair.public.to_bytes()[..])
+			.map_err(|_| SecretStringError::InvalidSeed)?;
+		Ok(Pair { public, keypair, seed })
+	}
+
+	/// Derive a child key from a series of given junctions.
+	fn derive<Iter: Iterator<Item = DeriveJunction>>(
+		&self,
+		path: Iter,
+		_seed: Option<Seed>,
+	) -> Result<(Pair, Option<Seed>), DeriveError> {
+		let mut acc = self.seed;
+		for j in path {
+			match j {
+				DeriveJunction::Soft(_cc) => return Err(DeriveError::SoftKeyInPath),
+				DeriveJunction::Hard(cc) => acc = derive_hard_junction(&acc, &cc),
+			}
+		}
+		Ok((Self::from_seed(&acc), Some(acc)))
+	}
+
+	/// Get the public key.
+	fn public(&self) -> Public {
+		self.public
+	}
+
+	/// Sign a message.
+	fn sign(&self, message: &[u8]) -> Signature {
+		self.sign_in_context(message, SIGNATURE_CONTEXT)
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	fn verify<M: AsRef<[u8]>>(sig: &Self::Signature, message: M, public: &Self::Public) -> bool {
+		verify_in_context(sig, message.as_ref(), public, SIGNATURE_CONTEXT)
+	}
+
+	/// Return a vec filled with raw data.
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.seed.to_vec()
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl Pair {
+	/// Get the seed for this key.
+	pub fn seed(&self) -> Seed {
+		self.seed
+	}
+
+	/// Prove the possession of the secret key, by signing the public key.
+	///
+	/// Public keys must come with a valid proof before their signatures can be aggregated.
+	pub fn proof_of_possession(&self) -> Signature {
+		self.sign_in_context(&self.public.0, PROOF_OF_POSSESSION_CONTEXT)
+	}
+
+	/// Verify an aggregated signature of `message` by all of `publics`.
+	///
+	/// Returns `false` if `publics` is empty.
+	pub fn verify_aggregate<M: AsRef<[u8]>>(
+		aggregate: &Signature,
+		message: M,
+		publics: &[ProvenPublic],
+	) -> bool {
+		let Some((first, rest)) = publics.split_first() else { return false };
+		let Some(mut public) = first.0.to_point() else { return false };
+		for other in rest {
+			match other.0.to_point() {
+				Some(other) => public.0 += other.0,
+				None => return false,
+			}
+		}
+
+		match aggregate.to_point() {
+			Some(aggregate) =>
+				aggregate.verify(&Message::new(SIGNATURE_CONTEXT, message.as_ref()), &public),
+			None => false,
+		}
+	}
+
+	/// Sign `message` in the given signing context.
+	fn sign_in_context(&self, message: &[u8], context: &[u8]) -> Signature {
+		// Signing mutates the split secret key, so sign with a copy of it.
+		self.keypair.clone().sign(&Message::new(context, message)).into()
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl Drop for Pair {
+	fn drop(&mut self) {
+		use zeroize::Zeroize;
+		self.seed.zeroize();
+	}
+}
+
+impl CryptoType for Public {
+	#[cfg(feature = "full_crypto")]
+	type Pair = Pair;
+}
+
+impl CryptoType for Signature {
+	#[cfg(feature = "full_crypto")]
+	type Pair = Pair;
+}
+
+#[cfg(feature = "full_crypto")]
+impl CryptoType for Pair {
+	type Pair = Pair;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::crypto::DEV_PHRASE;
+
+	#[test]
+	fn default_phrase_should_be_used() {
+		assert_eq!(
+			Pair::from_string("//Alice///password", None).unwrap().public(),
+			Pair::from_string(&format!("{}//Alice", DEV_PHRASE), Some("password"))
+				.unwrap()
+				.public(),
+		);
+	}
+
+	#[test]
+	fn seed_and_derive_should_work() {
+		let seed = array_bytes::hex2array_unchecked(
+			"9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+		);
+		let pair = Pair::from_seed(&seed);
+		assert_eq!(pair.seed(), seed);
+
+		let path = vec![DeriveJunction::Hard([0u8; 32])];
+		let derived = pair.derive(path.clone().into_iter(), None).ok().unwrap();
+		assert_eq!(derived.1, Some(derive_hard_junction(&seed, &[0u8; 32])));
+		assert_eq!(derived.0.public(), Pair::from_seed(&derived.1.unwrap()).public());
+		assert_eq!(
+			derived.0.public(),
+			Pair::from_seed(&seed).derive(path.into_iter(), None).unwrap().0.public()
+		);
+
+		let soft = vec![DeriveJunction::soft(1)];
+		assert!(pair.derive(soft.into_iter(), None).is_err());
+	}
+
+	#[test]
+	fn generated_pair_should_work() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(!Pair::verify(&signature, b"Something else", &public));
+		assert!(!Pair::verify(&signature, &message[..], &Pair::generate().0.public()));
+		assert!(!Pair::verify(&Signature::default(), &message[..], &public));
+	}
+
+	#[test]
+	fn generate_with_phrase_recovery_possible() {
+		let (pair1, phrase, _) = Pair::generate_with_phrase(None);
+		let (pair2, _) = Pair::from_phrase(&phrase, None).unwrap();
+
+		assert_eq!(pair1.public(), pair2.public());
+	}
+
+	#[test]
+	fn proof_of_possession_should_work() {
+		let pair = Pair::from_string("//Alice", None).unwrap();
+		let other = Pair::from_string("//Bob", None).unwrap();
+		let proof = pair.proof_of_possession();
+
+		assert!(pair.public().verify_proof_of_possession(&proof));
+		assert!(!other.public().verify_proof_of_possession(&proof));
+		// A regular signature of the public key is not a proof of possession.
+		assert!(!pair.public().verify_proof_of_possession(&pair.sign(pair.public().as_ref())));
+
+		assert_eq!(pair.public().prove(&proof).map(|proven| *proven.public()), Some(pair.public()));
+		assert!(other.public().prove(&proof).is_none());
+	}
+
+	#[test]
+	fn identity_public_key_should_be_rejected() {
+		let mut identity = [0u8; PUBLIC_KEY_SERIALIZED_SIZE];
+		identity[0] = 0xc0;
+		let identity = Public::from_raw(identity);
+		let mut signature = [0u8; SIGNATURE_SERIALIZED_SIZE];
+		signature[0] = 0xc0;
+		let signature = Signature::from_raw(signature);
+
+		assert!(!Pair::verify(&signature, b"anything", &identity));
+		assert!(!identity.verify_proof_of_possession(&signature));
+	}
+
+	#[test]
+	fn aggregated_signature_should_work() {
+		let pairs = ["//Alice", "//Bob", "//Charlie"]
+			.iter()
+			.map(|suri| Pair::from_string(suri, None).unwrap())
+			.collect::<Vec<_>>();
+		let publics = pairs
+			.iter()
+			.map(|pair| pair.public().prove(&pair.proof_of_possession()).unwrap())
+			.collect::<Vec<_>>();
+		let message = b"commitment";
+
+		let signatures = pairs.iter().map(|pair| pair.sign(message)).collect::<Vec<_>>();
+		let aggregate = Signature::aggregate(&signatures).unwrap();
+
+		assert!(Pair::verify_aggregate(&aggregate, message, &publics));
+		assert!(!Pair::verify_aggregate(&aggregate, b"other commitment", &publics));
+		assert!(!Pair::verify_aggregate(&aggregate, message, &publics[..2]));
+		assert!(!Pair::verify_aggregate(&aggregate, message, &[]));
+
+		// A single signature is its own aggregate.
+		let single = Signature::aggregate(&signatures[..1]).unwrap();
+		assert_eq!(single, signatures[0]);
+		assert!(Signature::aggregate(&[]).is_none());
+	}
+
+	#[test]
+	fn ss58check_roundtrip_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let public = pair.public();
+		let s = public.to_ss58check();
+		let cmp = Public::from_ss58check(&s).unwrap();
+		assert_eq!(cmp, public);
+	}
+
+	#[test]
+	fn signature_serialization_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		let serialized_signature = serde_json::to_string(&signature).unwrap();
+		// Signature is 96 bytes, so 192 chars + 2 quote chars
+		assert_eq!(serialized_signature.len(), 194);
+		let signature = serde_json::from_str(&serialized_signature).unwrap();
+		assert!(Pair::verify(&signature, &message[..], &pair.public()));
+	}
+
+	#[test]
+	fn encode_and_decode_public_key_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let public = pair.public();
+		let encoded = public.encode();
+		assert_eq!(encoded.len(), PUBLIC_KEY_SERIALIZED_SIZE);
+		assert_eq!(Public::decode(&mut &encoded[..]).unwrap(), public);
+	}
+}
@@ -17,10 +17,17 @@
 
 /// A wrapper around `kvdb::Database` that implements `sp_database::Database` trait
 use ::kvdb::{DBTransaction, KeyValueDB};
+use std::path::Path;
 
 use crate::{error, Change, ColumnId, Database, Transaction};
 
-struct DbAdapter<D: KeyValueDB + 'static>(D);
+/// Writes a checkpoint of the open database to a new path.
+type CreateCheckpoint = Box<dyn Fn(&Path) -> std::io::Result<()> + Send + Sync>;
+
+struct DbAdapter<D: KeyValueDB + 'static> {
+	db: D,
+	checkpoint: Option<CreateCheckpoint>,
+}
 
 fn handle_err<T>(result: std::io::Result<T>) -> T {
 	match result {
@@ -37,7 +44,26 @@ where
 	D: KeyValueDB + 'static,
 	H: Clone + AsRef<[u8]>,
 {
-	std::sync::Arc::new(DbAdapter(db))
+	std::sync::Arc::new(DbAdapter { db, checkpoint: None })
+}
+
+/// Wrap RocksDb database into a trait object that implements `sp_database::Database` and
+/// supports `Database::checkpoint`.
+///
+/// `create_checkpoint` writes a checkpoint of `db` to the given path while `db` stays open.
+pub fn as_database_with_checkpoint<D, H>(
+	db: D,
+	create_checkpoint: impl Fn(&Path) -> std::io::Result<()> + Send + Sync + 'static,
+) -> std::sync::Arc<dyn Database<H>>
+where
+	D: KeyValueDB + 'static,
+	H: Clone + AsRef<[u8]>,
+{
+	std::sync::Arc::new(DbAdapter { db, checkpoint: Some(Box::new(create_checkpoint)) })
+}
+
+fn to_db_err(e: std::io::Error) -> error::DatabaseError {
+	error::DatabaseError(Box::new(e))
 }
 
 impl<D: KeyValueDB> DbAdapter<D> {
@@ -46,7 +72,7 @@ impl<D: KeyValueDB> DbAdapter<D> {
 		// Add a key suffix for the counter
 		let mut counter_key = key.to_vec();
 		counter_key.push(0);
-		Ok(match self.0.get(col, &counter_key).map_err(|e| error::DatabaseError(Box::new(e)))? {
+		Ok(match self.db.get(col, &counter_key).map_err(to_db_err)? {
 			Some(data) => {
 				let mut counter_data = [0; 4];
 				if data.len() != 4 {
@@ -105,14 +131,31 @@ impl<D: KeyValueDB, H: Clone + AsRef<[u8]>> Database<H> for DbAdapter<D> {
 				},
 			}
 		}
-		self.0.write(tx).map_err(|e| error::DatabaseError(Box::new(e)))
+		self.db.write(tx).map_err(to_db_err)
 	}
 
 	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
-		handle_err(self.0.get(col, key))
+		handle_err(self.db.get(col, key))
 	}
 
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
-		handle_err(self.0.has_key(col, key))
+		handle_err(self.db.has_key(col, key))
+	}
+
+	fn checkpoint(&self, path: &Path) -> error::Result<()> {
+		let Some(checkpoint) = &self.checkpoint else {
+			return Err(to_db_err(std::io::Error::new(
+				std::io::ErrorKind::Unsupported,
+				"Database does not support checkpoints",
+			)))
+		};
+		if path.exists() {
+			return Err(to_db_err(std::io::Error::new(
+				std::io::ErrorKind::AlreadyExists,
+				format!("Checkpoint path {} already exists", path.display()),
+			)))
+		}
+
+		checkpoint(path).map_err(to_db_err)
 	}
 }
@@ -21,7 +21,7 @@ pub mod error;
 mod kvdb;
 mod mem;
 
-pub use crate::kvdb::as_database;
+pub use crate::kvdb::{as_database, as_database_with_checkpoint};
 pub use mem::MemDb;
 
 /// An identifier for a column.
@@ -115,6 +115,19 @@ pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 	///
 	/// Not all database implementations use a prefix for keys, so this function may be a noop.
 	fn sanitize_key(&self, _key: &mut Vec<u8>) {}
+
+	/// Write a consistent copy of the database to `path` while it stays open.
+	///
+	/// The copy reflects the database as of a single committed transaction and can be opened
+	/// like any other database of the same kind. `path` must not exist yet.
+	///
+	/// Not all database implementations support checkpoints; by default this returns an error.
+	fn checkpoint(&self, _path: &std::path::Path) -> error::Result<()> {
+		Err(error::DatabaseError(Box::new(std::io::Error::new(
+			std::io::ErrorKind::Unsupported,
+			"Database does not support checkpoints",
+		))))
+	}
 }
 
 impl<H> std::fmt::Debug for dyn Database<H> {
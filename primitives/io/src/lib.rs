@@ -44,6 +44,8 @@ use sp_core::{
 #[cfg(feature = "std")]
 use sp_keystore::KeystoreExt;
 
+#[cfg(feature = "bls-experimental")]
+use sp_core::bls381;
 use sp_core::{
 	crypto::KeyTypeId,
 	ecdsa, ed25519,
@@ -1046,6 +1048,78 @@ pub trait Crypto {
 		res
 	}
 
+	/// Returns all `bls381` public keys for the given key id from the keystore.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_public_keys(&mut self, id: KeyTypeId) -> Vec<bls381::Public> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.bls381_public_keys(id)
+	}
+
+	/// Generate a `bls381` key for the given key type using an optional `seed` and
+	/// store it in the keystore.
+	///
+	/// The `seed` needs to be a valid utf8.
+	///
+	/// Returns the public key.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_generate(&mut self, id: KeyTypeId, seed: Option<Vec<u8>>) -> bls381::Public {
+		let seed = seed.as_ref().map(|s| std::str::from_utf8(s).expect("Seed is valid utf8!"));
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.bls381_generate_new(id, seed)
+			.expect("`bls381_generate` failed")
+	}
+
+	/// Sign the given `msg` with the `bls381` key that corresponds to the given public key and
+	/// key type in the keystore.
+	///
+	/// Returns the signature.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_sign(
+		&mut self,
+		id: KeyTypeId,
+		pub_key: &bls381::Public,
+		msg: &[u8],
+	) -> Option<bls381::Signature> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.bls381_sign(id, pub_key, msg)
+			.ok()
+			.flatten()
+	}
+
+	/// Verify `bls381` signature.
+	///
+	/// Returns `true` when the verification was successful.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_verify(sig: &bls381::Signature, msg: &[u8], pub_key: &bls381::Public) -> bool {
+		bls381::Pair::verify(sig, msg, pub_key)
+	}
+
+	/// Verify that `proof` proves the possession of the secret key of the `bls381` `pub_key`.
+	///
+	/// Returns `true` when the verification was successful.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_verify_proof_of_possession(
+		proof: &bls381::Signature,
+		pub_key: &bls381::Public,
+	) -> bool {
+		pub_key.verify_proof_of_possession(proof)
+	}
+
+	/// Verify a `bls381` signature of `msg` aggregated from the signatures of all `pub_keys`.
+	///
+	/// Returns `true` when the verification was successful.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_verify_aggregate(
+		sig: &bls381::Signature,
+		msg: &[u8],
+		pub_keys: &[bls381::ProvenPublic],
+	) -> bool {
+		bls381::Pair::verify_aggregate(sig, msg, pub_keys)
+	}
+
 	/// Verify and recover a SECP256k1 ECDSA signature.
 	///
 	/// - `sig` is passed in RSV format. V should be either `0/1` or `27/28`.
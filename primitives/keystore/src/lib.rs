@@ -19,6 +19,8 @@
 
 pub mod testing;
 
+#[cfg(feature = "bls-experimental")]
+use sp_core::bls381;
 use sp_core::{
 	crypto::{ByteArray, CryptoTypeId, KeyTypeId},
 	ecdsa, ed25519, sr25519,
@@ -160,6 +162,46 @@ pub trait Keystore: Send + Sync {
 		msg: &[u8; 32],
 	) -> Result<Option<ecdsa::Signature>, Error>;
 
+	/// Returns all bls12-381 public keys for the given key type.
+	///
+	/// Keystores that don't store bls12-381 keys can rely on the default implementations, which
+	/// report no keys and refuse to generate or sign with them.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_public_keys(&self, _key_type: KeyTypeId) -> Vec<bls381::Public> {
+		Vec::new()
+	}
+
+	/// Generate a new bls12-381 key pair for the given key type and an optional seed.
+	///
+	/// Returns a `bls381::Public` key of the generated key pair or an `Err` if
+	/// something failed during key generation.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		_seed: Option<&str>,
+	) -> Result<bls381::Public, Error> {
+		Err(Error::KeyNotSupported(key_type))
+	}
+
+	/// Generate a bls12-381 signature for a given message.
+	///
+	/// Receives [`KeyTypeId`] and a [`bls381::Public`] key to be able to map
+	/// them to a private key that exists in the keystore.
+	///
+	/// Returns a [`bls381::Signature`] or `None` in case the given `key_type`
+	/// and `public` combination doesn't exist in the keystore.
+	/// An `Err` will be returned if generating the signature itself failed.
+	#[cfg(feature = "bls-experimental")]
+	fn bls381_sign(
+		&self,
+		key_type: KeyTypeId,
+		_public: &bls381::Public,
+		_msg: &[u8],
+	) -> Result<Option<bls381::Signature>, Error> {
+		Err(Error::KeyNotSupported(key_type))
+	}
+
 	/// Insert a new secret key.
 	fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()>;
 
@@ -178,7 +220,8 @@ pub trait Keystore: Send + Sync {
 	///
 	/// The message is signed using the cryptographic primitive specified by `crypto_id`.
 	///
-	/// Schemes supported by the default trait implementation: sr25519, ed25519 and ecdsa.
+	/// Schemes supported by the default trait implementation: sr25519, ed25519, ecdsa and
+	/// bls12-381.
 	/// To support more schemes you can overwrite this method.
 	///
 	/// Returns the SCALE encoded signature if key is found and supported, `None` if the key doesn't
@@ -208,6 +251,12 @@ pub trait Keystore: Send + Sync {
 					.map_err(|_| Error::ValidationError("Invalid public key format".into()))?;
 				self.ecdsa_sign(id, &public, msg)?.map(|s| s.encode())
 			},
+			#[cfg(feature = "bls-experimental")]
+			bls381::CRYPTO_ID => {
+				let public = bls381::Public::from_slice(public)
+					.map_err(|_| Error::ValidationError("Invalid public key format".into()))?;
+				self.bls381_sign(id, &public, msg)?.map(|s| s.encode())
+			},
 			_ => return Err(Error::KeyNotSupported(id)),
 		};
 		Ok(signature)
@@ -23,10 +23,11 @@
 
 use crate::utils::{
 	create_exchangeable_host_function_ident, create_function_ident_with_version,
-	create_host_function_ident, generate_crate_access, get_function_argument_names,
-	get_function_argument_names_and_types_without_ref, get_function_argument_types,
-	get_function_argument_types_ref_and_mut, get_function_argument_types_without_ref,
-	get_function_arguments, get_runtime_interface, RuntimeInterfaceFunction,
+	create_host_function_ident, generate_crate_access, get_cfg_attributes,
+	get_function_argument_names, get_function_argument_names_and_types_without_ref,
+	get_function_argument_types, get_function_argument_types_ref_and_mut,
+	get_function_argument_types_without_ref, get_function_arguments, get_runtime_interface,
+	RuntimeInterfaceFunction,
 };
 
 use syn::{
@@ -96,6 +97,7 @@ fn generate_extern_host_function(
 		method.sig.ident,
 	);
 	let return_value = &method.sig.output;
+	let cfg_attrs = get_cfg_attributes(method);
 
 	let ffi_return_value = match method.sig.output {
 		ReturnType::Default => quote!(),
@@ -112,6 +114,7 @@ fn generate_extern_host_function(
 	};
 
 	Ok(quote! {
+		#( #cfg_attrs )*
 		#[doc = #doc_string]
 		pub fn #function ( #( #args ),* ) #return_value {
 			extern "C" {
@@ -143,9 +146,11 @@ fn generate_exchangeable_host_function(method: &TraitItemFn) -> Result<TokenStre
 	let exchangeable_function = create_exchangeable_host_function_ident(&method.sig.ident);
 	let doc_string = format!(" Exchangeable host function used by [`{}`].", method.sig.ident);
 	let output = &method.sig.output;
+	let cfg_attrs = get_cfg_attributes(method);
 
 	Ok(quote! {
 		#[cfg(not(feature = "std"))]
+		#( #cfg_attrs )*
 		#[allow(non_upper_case_globals)]
 		#[doc = #doc_string]
 		pub static #exchangeable_function : #crate_::wasm::ExchangeableFunction<
@@ -163,13 +168,17 @@ fn generate_host_functions_struct(
 	let crate_ = generate_crate_access();
 
 	let mut host_function_impls = Vec::new();
-	let mut host_function_names = Vec::new();
+	let mut append_host_function_bodies = Vec::new();
 	let mut register_bodies = Vec::new();
 	for (version, method) in get_runtime_interface(trait_def)?.all_versions() {
 		let (implementation, name, register_body) =
 			generate_host_function_implementation(&trait_def.ident, method, version, is_wasm_only)?;
+		let cfg_attrs = get_cfg_attributes(method);
 		host_function_impls.push(implementation);
-		host_function_names.push(name);
+		append_host_function_bodies.push(quote! {
+			#( #cfg_attrs )*
+			host_functions.push(&#name as &dyn #crate_::sp_wasm_interface::Function);
+		});
 		register_bodies.push(register_body);
 	}
 
@@ -183,7 +192,9 @@ fn generate_host_functions_struct(
 		#[cfg(feature = "std")]
 		impl #crate_::sp_wasm_interface::HostFunctions for HostFunctions {
 			fn host_functions() -> Vec<&'static dyn #crate_::sp_wasm_interface::Function> {
-				vec![ #( &#host_function_names as &dyn #crate_::sp_wasm_interface::Function ),* ]
+				let mut host_functions = Vec::new();
+				#(#append_host_function_bodies)*
+				host_functions
 			}
 
 			#crate_::sp_wasm_interface::if_wasmtime_is_enabled! {
@@ -323,11 +334,14 @@ fn generate_host_function_implementation(
 		});
 	}
 
+	let cfg_attrs = get_cfg_attributes(method);
 	let implementation = quote! {
 		#[cfg(feature = "std")]
+		#( #cfg_attrs )*
 		struct #struct_name;
 
 		#[cfg(feature = "std")]
+		#( #cfg_attrs )*
 		impl #struct_name {
 			fn call(
 				__function_context__: &mut dyn #crate_::sp_wasm_interface::FunctionContext,
@@ -342,6 +356,7 @@ fn generate_host_function_implementation(
 		}
 
 		#[cfg(feature = "std")]
+		#( #cfg_attrs )*
 		impl #crate_::sp_wasm_interface::Function for #struct_name {
 			fn name(&self) -> &str {
 				#name
@@ -368,6 +383,7 @@ fn generate_host_function_implementation(
 	};
 
 	let register_body = quote! {
+		#( #cfg_attrs )*
 		registry.register_static(
 			#crate_::sp_wasm_interface::Function::name(&#struct_name),
 			|mut caller: #crate_::sp_wasm_interface::wasmtime::Caller<T::State>, #(#ffi_args_prototype),*|
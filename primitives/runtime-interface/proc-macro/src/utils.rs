@@ -20,8 +20,8 @@
 use proc_macro2::{Span, TokenStream};
 
 use syn::{
-	parse::Parse, parse_quote, spanned::Spanned, token, Error, FnArg, Ident, ItemTrait, LitInt,
-	Pat, PatType, Result, Signature, TraitItem, TraitItemFn, Type,
+	parse::Parse, parse_quote, spanned::Spanned, token, Attribute, Error, FnArg, Ident, ItemTrait,
+	LitInt, Pat, PatType, Result, Signature, TraitItem, TraitItemFn, Type,
 };
 
 use proc_macro_crate::{crate_name, FoundCrate};
@@ -321,6 +321,12 @@ impl Parse for VersionAttribute {
 	}
 }
 
+/// Returns the `cfg` attributes of the given function, which must be forwarded to everything
+/// generated for it.
+pub fn get_cfg_attributes(item: &TraitItemFn) -> Vec<Attribute> {
+	item.attrs.iter().filter(|attr| attr.path().is_ident("cfg")).cloned().collect()
+}
+
 /// Return [`VersionAttribute`], if present.
 fn get_item_version(item: &TraitItemFn) -> Result<Option<VersionAttribute>> {
 	item.attrs
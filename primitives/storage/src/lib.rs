@@ -194,6 +194,12 @@ pub mod well_known_keys {
 	/// Stored as a raw byte vector. Required by substrate.
 	pub const CODE: &[u8] = b":code";
 
+	/// Wasm code the runtime is going to switch to in the future.
+	///
+	/// Stored as a raw byte vector. The client may use it to prepare the new runtime ahead of
+	/// time, but it is never executed from this key.
+	pub const PENDING_CODE: &[u8] = b":pending_code";
+
 	/// Number of wasm linear memory pages required for execution of the runtime.
 	///
 	/// The type of this value is encoded `u64`.
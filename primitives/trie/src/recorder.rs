@@ -356,6 +356,17 @@ impl<H: Hasher, I: DerefMut<Target = RecorderInner<H::Out>>> trie_db::TrieRecord
 
 				self.update_recorded_keys(full_key, RecordedForKey::Value);
 			},
+			TrieAccess::InlineValue { full_key } => {
+				tracing::trace!(
+					target: LOG_TARGET,
+					key = ?sp_core::hexdisplay::HexDisplay::from(&full_key),
+					"Recorded inline value access for key",
+				);
+
+				// The value is stored inline in a node and we already recorded all trie nodes
+				// up to and including that node.
+				self.update_recorded_keys(full_key, RecordedForKey::Value);
+			},
 			TrieAccess::Hash { full_key } => {
 				tracing::trace!(
 					target: LOG_TARGET,
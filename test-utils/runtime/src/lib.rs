@@ -27,7 +27,7 @@ use codec::{Decode, Encode, Error, Input, MaxEncodedLen};
 use scale_info::TypeInfo;
 use sp_std::{marker::PhantomData, prelude::*};
 
-use sp_application_crypto::{ecdsa, ed25519, sr25519, RuntimeAppPublic};
+use sp_application_crypto::{bls381, ecdsa, ed25519, sr25519, RuntimeAppPublic};
 use sp_core::{OpaqueMetadata, RuntimeDebug};
 use sp_trie::{
 	trie_types::{TrieDBBuilder, TrieDBMutBuilderV1},
@@ -377,6 +377,10 @@ cfg_if! {
 				///
 				/// Returns the signature generated for the message `ecdsa`.
 				fn test_ecdsa_crypto() -> (ecdsa::AppSignature, ecdsa::AppPublic);
+				/// Test that `bls381` crypto works in the runtime.
+				///
+				/// Returns the signature generated for the message `bls381`.
+				fn test_bls381_crypto() -> (bls381::AppSignature, bls381::AppPublic);
 				/// Run various tests against storage.
 				fn test_storage();
 				/// Check a witness.
@@ -429,6 +433,10 @@ cfg_if! {
 				///
 				/// Returns the signature generated for the message `ecdsa`.
 				fn test_ecdsa_crypto() -> (ecdsa::AppSignature, ecdsa::AppPublic);
+				/// Test that `bls381` crypto works in the runtime.
+				///
+				/// Returns the signature generated for the message `bls381`.
+				fn test_bls381_crypto() -> (bls381::AppSignature, bls381::AppPublic);
 				/// Run various tests against storage.
 				fn test_storage();
 				/// Check a witness.
@@ -854,6 +862,10 @@ cfg_if! {
 					test_ecdsa_crypto()
 				}
 
+				fn test_bls381_crypto() -> (bls381::AppSignature, bls381::AppPublic) {
+					test_bls381_crypto()
+				}
+
 				fn test_storage() {
 					test_read_storage();
 					test_read_child_storage();
@@ -1158,6 +1170,10 @@ cfg_if! {
 					test_ecdsa_crypto()
 				}
 
+				fn test_bls381_crypto() -> (bls381::AppSignature, bls381::AppPublic) {
+					test_bls381_crypto()
+				}
+
 				fn test_storage() {
 					test_read_storage();
 					test_read_child_storage();
@@ -1310,6 +1326,23 @@ fn test_ecdsa_crypto() -> (ecdsa::AppSignature, ecdsa::AppPublic) {
 	(signature, public0)
 }
 
+fn test_bls381_crypto() -> (bls381::AppSignature, bls381::AppPublic) {
+	let public0 = bls381::AppPublic::generate_pair(None);
+	let public1 = bls381::AppPublic::generate_pair(None);
+	let public2 = bls381::AppPublic::generate_pair(None);
+
+	let all = bls381::AppPublic::all();
+	assert!(all.contains(&public0));
+	assert!(all.contains(&public1));
+	assert!(all.contains(&public2));
+
+	let signature = public0.sign(&"bls381").expect("Generates a valid `bls381` signature.");
+
+	assert!(public0.verify(&"bls381", &signature));
+	assert!(!public1.verify(&"bls381", &signature));
+	(signature, public0)
+}
+
 fn test_read_storage() {
 	const KEY: &[u8] = b":read_storage";
 	sp_io::storage::set(KEY, b"test");